@@ -1,7 +1,13 @@
 use lsp_types::{Position, Range};
-use std::fmt::Write;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
 
-use crate::types::{ServerStartType, TestCase, TestSetupError, TestSetupResult, TestType};
+use crate::{
+    run_timeout,
+    types::{RequestMeta, ServerStartType, TestCase, TestSetupError, TestSetupResult, TestType},
+};
 
 /// Construct the contents of an `init.lua` file to test an lsp request corresponding
 /// to `test_type`.
@@ -11,6 +17,9 @@ pub fn get_init_dot_lua(
     replacements: &mut Vec<LuaReplacement>,
 ) -> TestSetupResult<String> {
     replacements.extend(get_standard_replacements(test_case, test_type)?);
+    if !test_type.is_resolve() {
+        replacements.extend(request_meta_replacements(&test_case.request_meta));
+    }
     let mut raw_init = include_str!("lua_templates/helpers.lua").to_string();
     raw_init.push_str(match test_type {
         TestType::PublishDiagnostics => include_str!("lua_templates/diagnostic_autocmd.lua"),
@@ -20,13 +29,25 @@ pub fn get_init_dot_lua(
         TestType::SemanticTokensFullDelta => {
             include_str!("lua_templates/semantic_tokens_full_delta_action.lua")
         }
+        TestType::CodeLensThenExecute => {
+            include_str!("lua_templates/code_lens_then_execute_action.lua")
+        }
+        TestType::SignatureHelpRetrigger => {
+            include_str!("lua_templates/signature_help_retrigger_action.lua")
+        }
+        TestType::LogMessage => include_str!("lua_templates/log_message_action.lua"),
+        TestType::Progress => include_str!("lua_templates/progress_action.lua"),
         _ => include_str!("lua_templates/request_action.lua"),
     });
     raw_init.push_str(include_str!("lua_templates/attach.lua"));
     // This is how we get neovim to actually invoke the action to be tested
     raw_init = match test_type {
-        // Diagnostics are handled via an autocmd, no need to hook into `$/progress`
-        TestType::PublishDiagnostics => raw_init.replace("LSP_ACTION", ""),
+        // Diagnostics are handled via an autocmd, no need to hook into `$/progress`.
+        // We only note when the server attached to measure the diagnostics' latency
+        TestType::PublishDiagnostics => raw_init.replace("LSP_ACTION", "mark_attached(client)"),
+        // Progress notifications are recorded via a global handler, so that any sent
+        // before the server attaches are captured as well
+        TestType::Progress => raw_init.replace("LSP_ACTION", ""),
         _ => raw_init.replace("LSP_ACTION", &invoke_lsp_action(&test_case.start_type)),
     };
     let replacement_set = LuaDocumentReplacement::new(replacements);
@@ -40,44 +61,78 @@ fn get_standard_replacements(
     test_case: &TestCase,
     test_type: TestType,
 ) -> TestSetupResult<Vec<LuaReplacement>> {
-    let mut replacements = Vec::with_capacity(14);
+    let mut replacements = Vec::with_capacity(24);
     let results_file_path = test_case.get_results_file_path()?;
-    let root_path = test_case.get_lspresso_dir()?;
+    // Collect the components to drop the trailing separator
+    let project_path: PathBuf = test_case.get_source_file_path("")?.components().collect();
     let error_path = test_case.get_error_file_path()?;
     let log_path = test_case.get_log_file_path()?;
     let empty_path = test_case.get_empty_file_path()?;
     let benchmark_path = test_case.get_benchmark_file_path()?;
-    let source_extension = test_case
-        .source_file
-        .path
+    // The server attaches to the file the request targets
+    let target_path = &test_case.target_file().path;
+    let source_extension = target_path
         .extension()
         .ok_or_else(|| {
             // TODO: use `.unwrap_or("*")` here somehow instead to cover files without extensions?
-            TestSetupError::MissingFileExtension(
-                test_case.source_file.path.to_string_lossy().to_string(),
-            )
+            TestSetupError::MissingFileExtension(target_path.to_string_lossy().to_string())
         })?
         .to_str()
         .ok_or_else(|| {
-            TestSetupError::InvalidFileExtension(
-                test_case.source_file.path.to_string_lossy().to_string(),
-            )
+            TestSetupError::InvalidFileExtension(target_path.to_string_lossy().to_string())
         })?;
     replacements.push(LuaReplacement::Other {
         from: "REQUEST_METHOD",
         to: test_type.to_string(),
     });
+    // Overridden by `test_publish_diagnostics_for_file`
+    replacements.push(LuaReplacement::Other {
+        from: "DIAGNOSTICS_FILE",
+        to: "nil".to_string(),
+    });
+    // Overridden by `test_diagnostics_cleared`
+    replacements.push(LuaReplacement::Other {
+        from: "FIX_EDITS",
+        to: "nil".to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "FIX_SETTLE_MS",
+        to: "nil".to_string(),
+    });
+    // Overridden by `test_diagnostics_latency`
+    replacements.push(LuaReplacement::Other {
+        from: "LATENCY_PATH",
+        to: "nil".to_string(),
+    });
+    // Overridden by `test_diagnostic_unchanged`
+    replacements.push(LuaReplacement::Other {
+        from: "REUSE_RESULT_ID",
+        to: "false".to_string(),
+    });
     replacements.push(LuaReplacement::Other {
         from: "RESULTS_FILE",
         to: results_file_path.to_str().unwrap().to_string(),
     });
+    // Expanded before `EXECUTABLE_PATH`, which it may contain
+    replacements.push(LuaReplacement::Other {
+        from: "SERVER_CMD",
+        to: test_case.pool_socket.as_ref().map_or_else(
+            || "{ 'EXECUTABLE_PATH' }".to_string(),
+            |socket| format!("vim.lsp.rpc.connect('{}')", socket.to_str().unwrap()),
+        ),
+    });
     replacements.push(LuaReplacement::Other {
         from: "EXECUTABLE_PATH",
         to: test_case.executable_path.to_str().unwrap().to_string(),
     });
+    // Expanded before `PROJECT_PATH`, which it may contain
+    replacements.push(LuaReplacement::Other {
+        from: "ROOT_DIR",
+        to: root_dir(test_case, &project_path),
+    });
     replacements.push(LuaReplacement::Other {
-        from: "ROOT_PATH",
-        to: root_path.to_str().unwrap().to_string(),
+        from: "PROJECT_PATH",
+        to: project_path.to_str().unwrap().to_string(),
     });
     replacements.push(LuaReplacement::Other {
         from: "ERROR_PATH",
@@ -127,14 +182,307 @@ fn get_standard_replacements(
     });
     replacements.push(LuaReplacement::Other {
         from: "TIMEOUT_MS",
-        to: test_case.timeout.as_millis().to_string(),
+        to: run_timeout(test_case).as_millis().to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "TRACE_PATH",
+        to: test_case
+            .get_trace_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "SETTLE_MS",
+        to: test_case.settle.map_or_else(
+            || "nil".to_string(),
+            |settle| settle.as_millis().to_string(),
+        ),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "NOISE_PATH",
+        to: test_case
+            .get_noise_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "RESPONSE_ERROR_FILE",
+        to: test_case
+            .get_response_error_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "MESSAGES_PATH",
+        to: test_case
+            .get_messages_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "CAPABILITIES_PATH",
+        to: test_case
+            .get_server_capabilities_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "CURSOR_POSITION_PATH",
+        to: test_case
+            .get_initial_cursor_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "SERVER_INFO_PATH",
+        to: test_case
+            .get_server_info_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "ATTACHED_PATH",
+        to: test_case
+            .get_attached_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "OUTGOING_PATH",
+        to: test_case
+            .get_outgoing_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "OBSERVED_TOKENS_PATH",
+        to: test_case
+            .get_progress_tokens_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "INITIAL_DIAGNOSTICS_PATH",
+        to: test_case
+            .get_initial_diagnostics_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "INITIAL_SIGNATURE_HELP_PATH",
+        to: test_case
+            .get_initial_signature_help_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "BUFFER_CHANGES_PATH",
+        to: test_case
+            .get_buffer_changes_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "ASSERT_BUFFER_UNCHANGED",
+        to: test_case.assert_buffer_unchanged.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "INITIAL_CURSOR",
+        to: test_case.initial_cursor.map_or_else(
+            || "nil".to_string(),
+            |pos| format!("{{ {}, {} }}", pos.line + 1, pos.character),
+        ),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "SYNC_KIND",
+        to: test_case.sync_kind.map_or_else(
+            || "nil".to_string(),
+            |kind| serde_json::to_string(&kind).expect("JSON serialization of `sync_kind` failed"),
+        ),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "COUNTED_REQUESTS_PATH",
+        to: test_case
+            .get_server_requests_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "COUNTED_METHODS",
+        to: lua_string_list(
+            &test_case
+                .expected_server_requests
+                .iter()
+                .map(|(method, _)| method.clone())
+                .collect::<Vec<_>>(),
+        ),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "TIMING_PATH",
+        to: test_case
+            .get_timing_file_path()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "RECORD_TIMING",
+        to: test_case.record_timing.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "CAPTURE_OUTGOING",
+        to: test_case.capture_outgoing.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "CREATE_ON_DISK",
+        to: test_case.create_on_disk.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "DELETE_ON_DISK",
+        to: test_case.delete_on_disk.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "RENAME_ON_DISK",
+        to: test_case.rename_on_disk.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "DETECT_NOISE",
+        to: test_case.detect_stdout_noise.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "VIEWPORT_ROWS",
+        to: test_case.viewport.0.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "VIEWPORT_COLS",
+        to: test_case.viewport.1.to_string(),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "ATTACH_FILETYPES",
+        to: lua_string_list(&test_case.attach_filetypes),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "PREOPEN_FILES",
+        to: lua_string_list(
+            &test_case
+                .preopen
+                .iter()
+                .map(|path| {
+                    test_case
+                        .get_source_file_path(path)
+                        .map(|full_path| full_path.to_string_lossy().to_string())
+                })
+                .collect::<std::io::Result<Vec<_>>>()?,
+        ),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "WATCH_GLOBS",
+        to: lua_string_list(&test_case.watch_globs),
+    });
+    replacements.push(LuaReplacement::Other {
+        from: "TRACE_LEVEL",
+        to: if test_case.trace { "verbose" } else { "off" }.to_string(),
+    });
+    // Applied last, so that the user-provided responses aren't subject to any of the
+    // other replacements
+    replacements.push(LuaReplacement::Other {
+        from: "SERVER_REQUEST_HANDLERS",
+        to: serde_json::to_string(&test_case.server_request_handlers)
+            .expect("JSON serialization of `server_request_handlers` failed"),
     });
     Ok(replacements)
 }
 
+/// Inserts each of the fields set in `meta` into the request's params
+fn request_meta_replacements(meta: &RequestMeta) -> Vec<LuaReplacement> {
+    let mut replacements = Vec::new();
+    let mut push_field = |name: &'static str, value: Option<String>| {
+        if let Some(json) = value {
+            replacements.push(LuaReplacement::ParamDirect { name, json });
+        }
+    };
+    push_field(
+        "workDoneToken",
+        meta.work_done_token.as_ref().map(|token| {
+            serde_json::to_string(token).expect("JSON serialization of `workDoneToken` failed")
+        }),
+    );
+    push_field(
+        "partialResultToken",
+        meta.partial_result_token.as_ref().map(|token| {
+            serde_json::to_string(token).expect("JSON serialization of `partialResultToken` failed")
+        }),
+    );
+    push_field(
+        "trace",
+        meta.trace.map(|trace| {
+            serde_json::to_string(&trace).expect("JSON serialization of `trace` failed")
+        }),
+    );
+    replacements
+}
+
+/// Renders the Lua expression for the root directory advertised to the server
+fn root_dir(test_case: &TestCase, project_path: &Path) -> String {
+    if let Some(root) = &test_case.root_uri {
+        let root: PathBuf = project_path.join(root).components().collect();
+        return format!("'{}'", root.to_str().unwrap());
+    }
+    if test_case.root_markers.is_empty() {
+        return "'PROJECT_PATH'".to_string();
+    }
+    format!(
+        "vim.fs.root(ev.buf, {}) or 'PROJECT_PATH'",
+        lua_string_list(&test_case.root_markers)
+    )
+}
+
+/// Renders `items` as a Lua list of single-quoted strings
+fn lua_string_list(items: &[String]) -> String {
+    format!(
+        "{{ {} }}",
+        items
+            .iter()
+            .map(|item| format!("'{}'", item.replace('\\', "\\\\").replace('\'', "\\'")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
 fn progress_threshold(start_type: &ServerStartType) -> String {
     match start_type {
-        ServerStartType::Simple => "1".to_string(),
+        ServerStartType::Simple
+        | ServerStartType::SimpleWithDelay(_)
+        | ServerStartType::AfterDidOpen => "1".to_string(),
         ServerStartType::Progress(threshold, _) => threshold.to_string(),
     }
 }
@@ -148,10 +496,40 @@ fn invoke_lsp_action(start_type: &ServerStartType) -> String {
         ServerStartType::Simple => {
             format!("check_progress_result()\n{}vim.cmd('qa!')", " ".repeat(16))
         }
+        // Pause the timeout while waiting, so that the delay doesn't count against it,
+        // then resume it with whatever time was left. As in the simple case, the test
+        // ends after the first try
+        ServerStartType::SimpleWithDelay(delay) => format!(
+            r"local remaining_ms = TIMEOUT_MS
+                if timer then
+                    remaining_ms = timer:get_due_in()
+                    timer:stop()
+                end
+                vim.defer_fn(function()
+                    if timer then
+                        timer:start(remaining_ms, 0, vim.schedule_wrap(timeout_exit))
+                    end
+                    check_progress_result()
+                    vim.cmd('qa!')
+                end, {})",
+            delay.as_millis()
+        ),
+        // Poll until the tested method is supported, accounting for providers that
+        // are (dynamically) registered after the initial `textDocument/didOpen`
+        ServerStartType::AfterDidOpen => r"poll_timer = vim.uv.new_timer()
+                poll_timer:start(0, 10, vim.schedule_wrap(function()
+                    if client and client:supports_method('REQUEST_METHOD', 0) then
+                        close_poll_timer()
+                        check_progress_result()
+                    end
+                end))"
+            .to_string(),
         // Hook into `$/progress` messages
         ServerStartType::Progress(_, token_name) => {
             format!(
-                r#"vim.lsp.handlers["$/progress"] = function(_, result, _)
+                r#"track_progress_tokens()
+                vim.lsp.handlers["$/progress"] = function(_, result, _)
+                    record_progress_token(result.token)
                     if client then
                         if result.value.kind == "end" and result.token == "{token_name}" then
                             client.initialized = true
@@ -178,6 +556,10 @@ pub enum LuaReplacement {
     },
     /// Equivalent of `range = { start = { line = <start-line>, character = <start-character> }, ["end"] = { line = <end-line>, character = <end-character> } }`
     ParamRange(Range),
+    /// A `range` spanning the lines visible in the current window (see
+    /// [`TestCase::viewport`]), computed by Neovim when the request is issued. As it
+    /// isn't known ahead of time, it's omitted from [`Self::to_json_params`].
+    ParamVisibleRange,
     /// An object that is converted to JSON in order to pass to the lua side. This
     /// object can be inserted directly into `params`.
     ParamDirect { name: &'static str, json: String },
@@ -189,6 +571,11 @@ pub enum LuaReplacement {
         fields: Vec<&'static str>,
         json: String,
     },
+    /// An object that is converted to JSON in order to pass to the lua side. After
+    /// conversion from JSON into a lua table, every one of its fields is inserted
+    /// into `params` individually. Useful when the shape of `params` isn't known
+    /// ahead of time.
+    ParamDestructureAll { json: String },
     /// An object that needs to contain other nested objects. Each replacement in
     /// `fields` is stored in a parent object `name`.
     ParamNested {
@@ -201,6 +588,82 @@ pub enum LuaReplacement {
 }
 
 impl LuaReplacement {
+    /// Builds the params object described by `replacements`, independent of the Lua
+    /// template, i.e. to check the request captured with `capture_outgoing` against.
+    /// `Other` replacements aren't part of the params, and are skipped.
+    ///
+    /// The `textDocument` identifier depends on the buffer the request is issued from,
+    /// so `ParamTextDocument` inserts an empty object for the caller to fill in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the JSON of a `ParamDirect`, `ParamDestructure`, or
+    /// `ParamDestructureAll` replacement is invalid
+    pub fn to_json_params(replacements: &[Self]) -> serde_json::Value {
+        let mut params = serde_json::Map::new();
+        for replacement in replacements {
+            replacement.insert_json_params(&mut params);
+        }
+        serde_json::Value::Object(params)
+    }
+
+    fn insert_json_params(&self, params: &mut serde_json::Map<String, serde_json::Value>) {
+        let parse = |json: &str| -> serde_json::Value {
+            serde_json::from_str(json).expect("Invalid JSON in replacement")
+        };
+        match self {
+            Self::ParamTextDocument => {
+                params.insert("textDocument".to_string(), serde_json::json!({}));
+            }
+            Self::ParamPosition { pos, name } => {
+                params.insert(
+                    name.unwrap_or("position").to_string(),
+                    serde_json::json!({ "line": pos.line, "character": pos.character }),
+                );
+            }
+            Self::ParamRange(range) => {
+                let range = Self::ParamNested {
+                    name: "range",
+                    fields: vec![
+                        Self::ParamPosition {
+                            pos: range.start,
+                            name: Some("start"),
+                        },
+                        Self::ParamPosition {
+                            pos: range.end,
+                            name: Some("end"),
+                        },
+                    ],
+                };
+                range.insert_json_params(params);
+            }
+            Self::ParamDirect { name, json } => {
+                params.insert((*name).to_string(), parse(json));
+            }
+            Self::ParamDestructure { fields, json, .. } => {
+                let value = parse(json);
+                for field in fields {
+                    if let Some(field_value) = value.get(field) {
+                        params.insert((*field).to_string(), field_value.clone());
+                    }
+                }
+            }
+            Self::ParamDestructureAll { json } => {
+                if let serde_json::Value::Object(all_params) = parse(json) {
+                    params.extend(all_params);
+                }
+            }
+            Self::ParamNested { name, fields } => {
+                let mut nested = serde_json::Map::new();
+                for field in fields {
+                    field.insert_json_params(&mut nested);
+                }
+                params.insert((*name).to_string(), serde_json::Value::Object(nested));
+            }
+            Self::ParamVisibleRange | Self::Other { .. } => {}
+        }
+    }
+
     fn perform_replacement(&self, doc: &mut LuaDocumentReplacement, parent_name: Option<&str>) {
         let parent_name = parent_name.unwrap_or("params");
         match self {
@@ -238,6 +701,19 @@ impl LuaReplacement {
                 };
                 range.perform_replacement(doc, Some(parent_name));
             }
+            // From the first visible line up to the start of the line after the last,
+            // like Neovim's own whole-buffer inlay hint requests
+            Self::ParamVisibleRange => {
+                writeln!(
+                    &mut doc.params,
+                    "\tassert(not {parent_name}['range'], \"{parent_name}['range'] already set\")
+\t{parent_name}['range'] = {{
+\t\tstart = {{ line = vim.fn.line('w0') - 1, character = 0 }},
+\t\t['end'] = {{ line = vim.fn.line('w$'), character = 0 }},
+\t}}"
+                )
+                .unwrap();
+            }
             Self::ParamDirect { name, json } => {
                 writeln!(
                     &mut doc.params,
@@ -258,6 +734,17 @@ impl LuaReplacement {
                     .unwrap();
                 }
             }
+            Self::ParamDestructureAll { json } => {
+                writeln!(
+                    &mut doc.params,
+                    "\tlocal all_params = vim.json.decode([[\n{json}\n]])
+\tfor field, value in pairs(all_params) do
+\t\tassert(not {parent_name}[field], \"{parent_name}['\" .. field .. \"'] already set\")
+\t\t{parent_name}[field] = value
+\tend"
+                )
+                .unwrap();
+            }
             Self::ParamNested { name, fields } => {
                 writeln!(
                     &mut doc.params,
@@ -306,9 +793,63 @@ impl LuaDocumentReplacement {
 
 #[cfg(test)]
 mod test {
-    use lsp_types::{CodeLens, Position, Range};
+    use lsp_types::{CodeLens, Position, ProgressToken, Range, TraceValue};
+
+    use crate::types::RequestMeta;
+
+    use super::{LuaDocumentReplacement, LuaReplacement, request_meta_replacements};
+
+    #[test]
+    fn json_params() {
+        let replacements = vec![
+            LuaReplacement::ParamTextDocument,
+            LuaReplacement::ParamPosition {
+                pos: Position::new(1, 2),
+                name: None,
+            },
+            LuaReplacement::ParamRange(Range::new(Position::new(0, 0), Position::new(3, 4))),
+            LuaReplacement::ParamDirect {
+                name: "context",
+                json: r#"{ "includeDeclaration": true }"#.to_string(),
+            },
+            LuaReplacement::ParamDestructure {
+                name: "options",
+                fields: vec!["tabSize", "missing"],
+                json: r#"{ "tabSize": 4, "insertSpaces": true }"#.to_string(),
+            },
+            LuaReplacement::ParamNested {
+                name: "item",
+                fields: vec![LuaReplacement::ParamDirect {
+                    name: "data",
+                    json: "null".to_string(),
+                }],
+            },
+            LuaReplacement::Other {
+                from: "REQUEST_METHOD",
+                to: "textDocument/hover".to_string(),
+            },
+        ];
+        let expected = serde_json::json!({
+            "textDocument": {},
+            "position": { "line": 1, "character": 2 },
+            "range": {
+                "start": { "line": 0, "character": 0 },
+                "end": { "line": 3, "character": 4 },
+            },
+            "context": { "includeDeclaration": true },
+            "tabSize": 4,
+            "item": { "data": null },
+        });
+        assert_eq!(expected, LuaReplacement::to_json_params(&replacements));
 
-    use super::{LuaDocumentReplacement, LuaReplacement};
+        let all = [LuaReplacement::ParamDestructureAll {
+            json: r#"{ "command": "run", "arguments": [] }"#.to_string(),
+        }];
+        assert_eq!(
+            serde_json::json!({ "command": "run", "arguments": [] }),
+            LuaReplacement::to_json_params(&all)
+        );
+    }
 
     #[test]
     fn text_document_param() {
@@ -401,6 +942,27 @@ mod test {
         assert!(doc_repl.raw.is_empty());
     }
 
+    #[test]
+    fn param_destructure_all() {
+        let params_json = serde_json::to_string_pretty(&serde_json::json!({
+            "settings": { "enable": true },
+        }))
+        .expect("JSON serialization of params failed");
+        let replacements = vec![LuaReplacement::ParamDestructureAll {
+            json: params_json.clone(),
+        }];
+        let doc_repl = LuaDocumentReplacement::new(&replacements);
+        let expected = format!(
+            "\tlocal all_params = vim.json.decode([[\n{params_json}\n]])
+\tfor field, value in pairs(all_params) do
+\t\tassert(not params[field], \"params['\" .. field .. \"'] already set\")
+\t\tparams[field] = value
+\tend\n"
+        );
+        assert_eq!(expected, doc_repl.params);
+        assert!(doc_repl.raw.is_empty());
+    }
+
     #[test]
     fn param_nested() {
         let include_decl_json = serde_json::to_string_pretty(&true)
@@ -425,6 +987,26 @@ mod test {
         assert!(doc_repl.raw.is_empty());
     }
 
+    #[test]
+    fn request_meta() {
+        assert!(request_meta_replacements(&RequestMeta::default()).is_empty());
+
+        let meta = RequestMeta {
+            work_done_token: Some(ProgressToken::String("token".to_string())),
+            partial_result_token: None,
+            trace: Some(TraceValue::Verbose),
+        };
+        let doc_repl = LuaDocumentReplacement::new(&request_meta_replacements(&meta));
+        let expected = "\tlocal workDoneToken_json = [[\n\"token\"\n]]
+\tassert(not params['workDoneToken'], \"params['workDoneToken'] already set\")
+\tparams['workDoneToken'] = vim.json.decode(workDoneToken_json)
+\tlocal trace_json = [[\n\"verbose\"\n]]
+\tassert(not params['trace'], \"params['trace'] already set\")
+\tparams['trace'] = vim.json.decode(trace_json)\n";
+        assert_eq!(expected, doc_repl.params);
+        assert!(doc_repl.raw.is_empty());
+    }
+
     #[test]
     fn other() {
         let command_str = "\"rust-analyzer.runSingle\",
@@ -1,17 +1,28 @@
 mod init_dot_lua;
+#[cfg(feature = "tokio")]
+pub mod lspresso_async;
+#[cfg(feature = "criterion")]
+pub mod lspresso_criterion;
+#[cfg(all(feature = "pool", unix))]
+pub mod pool;
 pub mod types;
 
+pub use types::formatting::FormattingOptionsBuilder;
+pub use types::signature_help::SignatureHelpContextBuilder;
+
 use init_dot_lua::LuaReplacement;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, CodeAction,
     CodeActionContext, CodeActionResponse, CodeLens, Color, ColorInformation, ColorPresentation,
-    CompletionItem, CompletionResponse, CreateFilesParams, DeleteFilesParams, Diagnostic,
-    DocumentDiagnosticReport, DocumentHighlight, DocumentLink, DocumentSymbolResponse,
+    CompletionContext, CompletionItem, CompletionResponse, CreateFilesParams, DeleteFilesParams,
+    Diagnostic, DocumentDiagnosticReport, DocumentHighlight, DocumentLink, DocumentSymbolResponse,
     FoldingRange, FormattingOptions, GotoDefinitionResponse, Hover, InlayHint, LinkedEditingRanges,
     Location, Moniker, OneOf, Position, PrepareRenameResponse, PreviousResultId, Range,
-    RelatedFullDocumentDiagnosticReport, RenameFilesParams, SelectionRange,
-    SemanticTokensFullDeltaResult, SemanticTokensRangeResult, SemanticTokensResult, SignatureHelp,
-    SignatureHelpContext, SymbolKind, TextEdit, TypeHierarchyItem, Uri, WorkspaceDiagnosticReport,
+    RelatedFullDocumentDiagnosticReport, RelatedUnchangedDocumentDiagnosticReport,
+    RenameFilesParams, SelectionRange, SemanticTokensFullDeltaResult, SemanticTokensRangeResult,
+    SemanticTokensResult, ServerInfo, SignatureHelp, SignatureHelpContext,
+    SignatureHelpTriggerKind, SymbolKind, TextEdit, TypeHierarchyItem,
+    UnchangedDocumentDiagnosticReport, Uri, WorkDoneProgress, WorkspaceDiagnosticReport,
     WorkspaceEdit, WorkspaceSymbol, WorkspaceSymbolResponse,
     request::{GotoDeclarationResponse, GotoImplementationResponse, GotoTypeDefinitionResponse},
 };
@@ -33,19 +44,28 @@ use serde_json::Value;
 use types::ServerStartType;
 
 use std::{
-    collections::HashMap,
+    collections::BTreeSet,
     fs,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
     str::FromStr as _,
     sync::{Arc, Condvar, Mutex, OnceLock},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use types::{
-    ApproximateEq, BenchmarkConfig, BenchmarkError, CleanResponse, EndCondition,
-    ResponseMismatchError, StateOrResponse, TestCase, TestError, TestExecutionError,
-    TestExecutionResult, TestResult, TestType, TimeoutError, to_parent_err_type,
+    ApproximateEq, BenchmarkConfig, BenchmarkError, BenchmarkStats, CleanResponse, EndCondition,
+    Expected, NvimVersionsError, PositionBenchmarkStats, ResponseMismatchError, StateOrResponse,
+    TestCase, TestError, TestExecutionError, TestExecutionResult, TestResult, TestSetupError,
+    TestSetupResult, TestType, TimeoutError, TriggerKind, Utf8Policy,
+    code_lens::CodeLensExecution,
+    compare::{self, Comparator},
+    deserialization_error_message,
+    formatting::EditsAndState,
+    from_str_stripping_invalid,
+    log_message::{LogMatcher, LogTrigger},
+    progress::check_progress_percentages,
+    scale_duration, timeout_scale, to_parent_err_type,
 };
 
 /// Intended to be used as a wrapper for `lspresso-shot` testing functions. If the
@@ -71,15 +91,225 @@ macro_rules! lspresso_shot {
 /// It looks like this value needs to be 1, so we could replace the `u32` with a `bool`,
 /// but I'll leave it as is for now in case I come up with some other workaround
 static RUNNER_LIMIT: u32 = 1;
-static RUNNER_COUNT: OnceLock<Arc<(Mutex<u32>, Condvar)>> = OnceLock::new();
+static RUNNER_COUNT: OnceLock<Arc<(Mutex<u32>, Condvar, Instant)>> = OnceLock::new();
 
-fn get_runner_count() -> Arc<(Mutex<u32>, Condvar)> {
+/// Returns the state shared by every runner: the number of running tests, the condition
+/// variable notified when one finishes, and when the first test started running
+fn get_runner_count() -> Arc<(Mutex<u32>, Condvar, Instant)> {
     #[allow(clippy::mutex_integer)]
     RUNNER_COUNT
-        .get_or_init(|| Arc::new((Mutex::new(0), Condvar::new())))
+        .get_or_init(|| Arc::new((Mutex::new(0), Condvar::new(), Instant::now())))
         .clone()
 }
 
+/// Reads the suite deadline from `LSPRESSO_SUITE_DEADLINE`, see [`parse_suite_deadline`]
+fn suite_deadline() -> Option<Duration> {
+    std::env::var("LSPRESSO_SUITE_DEADLINE")
+        .ok()
+        .and_then(|secs| parse_suite_deadline(&secs))
+}
+
+/// Parses a suite deadline given as a number of seconds. Returns `None` if `secs`
+/// isn't a positive, finite float.
+fn parse_suite_deadline(secs: &str) -> Option<Duration> {
+    secs.trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Fails with [`TestExecutionError::SuiteDeadlineExceeded`] if `deadline` has passed
+/// since `suite_start`. Checked by every runner before it starts a test, so that a hung
+/// suite aborts its remaining tests rather than running each one to its timeout.
+fn check_suite_deadline(
+    test_case: &TestCase,
+    suite_start: Instant,
+    deadline: Option<Duration>,
+) -> TestExecutionResult<()> {
+    match deadline {
+        Some(deadline) if suite_start.elapsed() >= deadline => {
+            Err(TestExecutionError::SuiteDeadlineExceeded {
+                test_id: test_case.test_id.clone(),
+                deadline,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns the timeout applied to `test_case`'s run: its effective timeout, capped at
+/// the time remaining before the suite deadline, if one is set. This keeps the last
+/// test started before the deadline from running past it.
+pub(crate) fn run_timeout(test_case: &TestCase) -> Duration {
+    let (_, _, suite_start) = &*get_runner_count();
+    cap_timeout(
+        test_case.effective_timeout(),
+        *suite_start,
+        suite_deadline(),
+    )
+}
+
+/// Caps `timeout` at the time remaining before `deadline` passes, measured from
+/// `suite_start`
+fn cap_timeout(timeout: Duration, suite_start: Instant, deadline: Option<Duration>) -> Duration {
+    deadline.map_or(timeout, |deadline| {
+        timeout.min(deadline.saturating_sub(suite_start.elapsed()))
+    })
+}
+
+/// Holds a spawned neovim process, killing its process group (which includes the
+/// server) when dropped if `kill_on_drop` is set. This covers every way of leaving
+/// `run_test`, including early returns and panics, not just the timeout path, and a
+/// server that outlived neovim.
+///
+/// Once neovim has been waited on its pid may be reused, so its process group is only
+/// signalled while it's unreaped. See [`ChildGuard::has_exited`].
+struct ChildGuard {
+    child: std::process::Child,
+    kill_on_drop: bool,
+    /// Whether `child` leads its own process group, see [`nvim_leads_group`]
+    leads_group: bool,
+    reaped: bool,
+}
+
+impl ChildGuard {
+    const fn new(child: std::process::Child, kill_on_drop: bool, leads_group: bool) -> Self {
+        Self {
+            child,
+            kill_on_drop,
+            leads_group,
+            reaped: false,
+        }
+    }
+
+    /// Indicates whether the child has exited. Where possible (on Linux), an exited
+    /// child is left unreaped, so that its pid (and so the process group id) can't be
+    /// reused before the group is killed on drop.
+    fn has_exited(&mut self) -> std::io::Result<bool> {
+        if let Some(exited) = exited_unreaped(self.child.id()) {
+            return Ok(exited);
+        }
+        let exited = self.child.try_wait()?.is_some();
+        self.reaped |= exited;
+        Ok(exited)
+    }
+}
+
+impl Drop for ChildGuard {
+    fn drop(&mut self) {
+        if self.reaped {
+            return;
+        }
+        if !self.kill_on_drop {
+            // Reap the child if it's exited, leaving it be otherwise
+            _ = self.child.try_wait();
+            return;
+        }
+        if self.leads_group {
+            kill_process_group(self.child.id());
+        } else {
+            _ = self.child.kill();
+        }
+        _ = self.child.wait();
+    }
+}
+
+/// Indicates whether the child process `pid` has exited without reaping it, or `None`
+/// if this can't be determined. An exited, unreaped child is a zombie.
+#[cfg(target_os = "linux")]
+pub(crate) fn exited_unreaped(pid: u32) -> Option<bool> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The state follows the parenthesized command name, which may contain spaces
+    let (_, fields) = stat.rsplit_once(')')?;
+    Some(fields.trim_start().starts_with('Z'))
+}
+
+/// Indicates whether the child process `pid` has exited without reaping it, or `None`
+/// if this can't be determined
+#[cfg(not(target_os = "linux"))]
+pub(crate) const fn exited_unreaped(_pid: u32) -> Option<bool> {
+    None
+}
+
+/// Indicates whether neovim is spawned as the leader of its own process group. A
+/// background process group is stopped (via `SIGTTIN`/`SIGTTOU`) when it accesses the
+/// terminal, so neovim remains in the foreground group when its stdio is inherited.
+const fn nvim_leads_group(test_case: &TestCase) -> bool {
+    cfg!(unix) && !test_case.inherit_stdio
+}
+
+/// Kills the process group led by `pgid`, i.e. a neovim process spawned via
+/// [`nvim_command`] along with the server it spawned. Once every process in the
+/// group has exited, this is a no-op.
+#[cfg(unix)]
+pub(crate) fn kill_process_group(pgid: u32) {
+    _ = Command::new("kill")
+        .arg("-KILL")
+        .arg("--")
+        .arg(format!("-{pgid}"))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+/// Kills the process group led by `pgid`
+#[cfg(not(unix))]
+pub(crate) const fn kill_process_group(_pgid: u32) {}
+
+/// Panics if any processes named `server_name` are still running. Intended to be
+/// called once a suite's tests have finished, to catch server processes that were
+/// never torn down. Processes are matched on the file name of their executable, i.e.
+/// `rust-analyzer` matches `/usr/bin/rust-analyzer --stdio`.
+///
+/// # Panics
+///
+/// Panics if any matching processes are found
+pub fn assert_no_orphans(server_name: &str) {
+    let orphans = find_processes(server_name);
+    assert!(
+        orphans.is_empty(),
+        "Found {} orphaned `{server_name}` process(es): {orphans:?}",
+        orphans.len()
+    );
+}
+
+/// Returns the pids of the running processes whose executable is named `name`
+#[cfg(target_os = "linux")]
+fn find_processes(name: &str) -> Vec<u32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| {
+            fs::read(format!("/proc/{pid}/cmdline")).is_ok_and(|cmdline| {
+                let exe = cmdline.split(|b| *b == 0).next().unwrap_or_default();
+                Path::new(&*String::from_utf8_lossy(exe))
+                    .file_name()
+                    .is_some_and(|file_name| file_name == name)
+            })
+        })
+        .collect()
+}
+
+/// Returns the pids of the running processes whose executable is named `name`
+#[cfg(not(target_os = "linux"))]
+fn find_processes(name: &str) -> Vec<u32> {
+    Command::new("pgrep")
+        .arg("-x")
+        .arg(name)
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.trim().parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Helper struct to automatically decrement `n_jobs` when dropped.
 struct RunnerGuard<'a> {
     lock: &'a Mutex<u32>,
@@ -99,6 +329,21 @@ impl<'a> RunnerGuard<'a> {
 
         Self { lock, cvar }
     }
+
+    /// Non-blocking version of `RunnerGuard::new`, returning `None` if the runner
+    /// limit has already been reached
+    #[cfg(feature = "tokio")]
+    fn try_new(lock: &'a Mutex<u32>, cvar: &'a Condvar) -> Option<Self> {
+        let mut n_jobs = lock.lock().expect("Mutex poisoned");
+        if *n_jobs >= RUNNER_LIMIT {
+            return None;
+        }
+
+        *n_jobs += 1;
+        drop(n_jobs);
+
+        Some(Self { lock, cvar })
+    }
 }
 
 impl Drop for RunnerGuard<'_> {
@@ -114,34 +359,176 @@ impl Drop for RunnerGuard<'_> {
 /// `T` is the type of the expected results.
 ///
 /// Note that even if a given request doesn't support an `Option` response, `expected`
-/// is always an `Option` here. For these cases, the expected result should be passed
-/// as `Some(expected)` unconditionally in the caller
+/// is always an [`Expected`] here. For these cases, the expected result should be passed
+/// as `Expected::Value(expected)` unconditionally in the caller
 #[allow(clippy::needless_pass_by_value)]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(test_id = %test_case.test_id, test_type = %test_type)
+    )
+)]
 fn collect_results<T>(
     test_case: &TestCase,
     test_type: TestType,
     replacements: &mut Vec<LuaReplacement>,
-    expected: Option<&T>,
-    cmp: Option<impl Fn(&T, &T, &TestCase) -> bool>,
+    expected: Expected<'_, T>,
+    cmp: Option<Comparator<T>>,
 ) -> TestResult<(), T>
 where
-    T: Clone + serde::de::DeserializeOwned + std::fmt::Debug + CleanResponse + ApproximateEq,
+    T: Clone
+        + serde::de::DeserializeOwned
+        + serde::Serialize
+        + std::fmt::Debug
+        + CleanResponse
+        + ApproximateEq,
+{
+    if test_case.nvim_paths.is_empty() {
+        return collect_results_once(test_case, test_type, replacements, expected, cmp.as_ref());
+    }
+
+    let results = nvim_version_cases(test_case)
+        .into_iter()
+        .map(|version_case| {
+            let result = collect_results_once(
+                &version_case,
+                test_type,
+                &mut replacements.clone(),
+                expected,
+                cmp.as_ref(),
+            );
+            (version_case.nvim_path, result)
+        })
+        .collect();
+    nvim_versions_result(test_case, results)
+}
+
+/// Returns a copy of `test_case` for each of its `nvim_paths`, run against that Neovim
+/// executable alone. Each copy reuses the same test directory, so they must be run in
+/// turn.
+pub(crate) fn nvim_version_cases(test_case: &TestCase) -> Vec<TestCase> {
+    test_case
+        .nvim_paths
+        .iter()
+        .map(|nvim_path| TestCase {
+            nvim_path: nvim_path.clone(),
+            nvim_paths: Vec::new(),
+            ..test_case.clone()
+        })
+        .collect()
+}
+
+/// Combines the results of running a test against each of `test_case.nvim_paths`, see
+/// [`nvim_version_cases`]. Passes only if every run passed.
+pub(crate) fn nvim_versions_result<T>(
+    test_case: &TestCase,
+    results: Vec<(PathBuf, TestResult<(), T>)>,
+) -> TestResult<(), T> {
+    if results.iter().all(|(_, result)| result.is_ok()) {
+        return Ok(());
+    }
+    Err(TestError::NvimVersions(NvimVersionsError {
+        test_id: test_case.test_id.clone(),
+        results,
+    }))
+}
+
+/// Performs a single run of the test with `test_case.nvim_path`, see [`collect_results`]
+#[allow(clippy::needless_pass_by_value)]
+fn collect_results_once<T>(
+    test_case: &TestCase,
+    test_type: TestType,
+    replacements: &mut Vec<LuaReplacement>,
+    expected: Expected<'_, T>,
+    cmp: Option<&Comparator<T>>,
+) -> TestResult<(), T>
+where
+    T: Clone
+        + serde::de::DeserializeOwned
+        + serde::Serialize
+        + std::fmt::Debug
+        + CleanResponse
+        + ApproximateEq,
+{
+    let source_path = prepare_test(test_case, test_type, replacements)?;
+    let run_result = run_test(test_case, &source_path);
+    if test_case.golden.is_some() {
+        return evaluate_golden(test_case, test_type, run_result, cmp);
+    }
+    evaluate_results(test_case, run_result, expected, cmp)
+}
+
+/// Validates `test_case` and writes out its test directory, returning the path
+/// to the source file to be opened
+fn prepare_test(
+    test_case: &TestCase,
+    test_type: TestType,
+    replacements: &mut Vec<LuaReplacement>,
+) -> TestSetupResult<PathBuf> {
+    test_case.validate()?;
+    // For these test types, the range refers to the target file's contents
+    if matches!(
+        test_type,
+        TestType::CodeAction
+            | TestType::InlayHint
+            | TestType::RangeFormatting
+            | TestType::SemanticTokensRange
+    ) {
+        for replacement in replacements.iter() {
+            if let LuaReplacement::ParamRange(range) = replacement {
+                test_case.validate_range(*range)?;
+            }
+        }
+    }
+    test_case.create_test(test_type, replacements)
+}
+
+/// Reads the results of a finished test run and compares them to `expected`.
+/// `run_result` is the outcome of running Neovim for the test
+#[allow(clippy::needless_pass_by_value)]
+fn evaluate_results<T>(
+    test_case: &TestCase,
+    run_result: TestExecutionResult<()>,
+    expected: Expected<'_, T>,
+    cmp: Option<&Comparator<T>>,
+) -> TestResult<(), T>
+where
+    T: Clone
+        + serde::de::DeserializeOwned
+        + serde::Serialize
+        + std::fmt::Debug
+        + CleanResponse
+        + ApproximateEq,
 {
     let get_results = |path: &Path| -> TestExecutionResult<T> {
-        let raw_results = String::from_utf8(
-            fs::read(path)
-                .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?,
-        )
-        .map_err(|e| TestExecutionError::Utf8(test_case.test_id.clone(), e.to_string()))?;
-        let raw_resp: T = serde_json::from_str(&raw_results).map_err(|e| {
-            TestExecutionError::Serialization(test_case.test_id.clone(), e.to_string())
-        })?;
-        let cleaned = raw_resp.clean_response(test_case)?;
-        Ok(cleaned)
+        parse_results(test_case, &read_raw_results(test_case, path)?)
     };
-    test_case.validate()?;
-    let source_path = test_case.create_test(test_type, replacements)?;
-    run_test(test_case, &source_path)?;
+    let is_match = |exp: &T, actual: &T| -> Result<(), Option<String>> {
+        match cmp {
+            Some(cmp) => cmp.compare(exp, actual, test_case),
+            None if T::approx_eq(exp, actual)
+                || compare::within_float_tolerance(test_case, exp, actual) =>
+            {
+                Ok(())
+            }
+            None => Err(None),
+        }
+    };
+    // The closest representation of `[]` in `T`, if there is one
+    let empty_list = || serde_json::from_value::<T>(Value::Array(Vec::new())).ok();
+    // Stdout noise typically causes a timeout, so report it first if present
+    if test_case.detect_stdout_noise {
+        check_stdout_noise(test_case)?;
+    }
+    run_result?;
+    if test_case.assert_buffer_unchanged {
+        check_buffer_unchanged(test_case)?;
+    }
+    if !test_case.expected_server_requests.is_empty() {
+        check_server_requests(test_case)?;
+    }
 
     let empty_result_path = test_case
         .get_empty_file_path()
@@ -155,89 +542,437 @@ where
         empty_result_path.exists(),
         results_file_path.exists(),
     ) {
+        // Invariant: `results.json` and `empty` should never both exist
+        (_, true, true) => unreachable!(),
+        // No results
+        (_, false, false) => Err(no_results_error(test_case))?,
         // Expected and got empty results
-        (None, true, false) => Ok(()),
+        (Expected::Null, true, false) => Ok(()),
         // Expected empty results, got some
-        (None, false, true) => {
+        (Expected::Null, false, true) => {
             // NOTE: We may need to handle deserialization errors here
             let results: T = get_results(&results_file_path)?;
             Err(TestError::ResponseMismatch(ResponseMismatchError {
                 test_id: test_case.test_id.clone(),
                 expected: None,
                 actual: Some(results),
+                reason: None,
             }))?
         }
-        // Invariant: `results.json` and `empty` should never both exist
-        (_, true, true) => unreachable!(),
-        // No results
-        (_, false, false) => Err(TestExecutionError::NoResults(test_case.test_id.clone()))?,
+        // Expected an empty list, got a null response
+        (Expected::EmptyList, true, false) => {
+            Err(TestError::ResponseMismatch(ResponseMismatchError {
+                test_id: test_case.test_id.clone(),
+                expected: empty_list(),
+                actual: None,
+                reason: None,
+            }))?
+        }
+        // Expected an empty list, got some results
+        (Expected::EmptyList, false, true) => {
+            let raw_results = read_raw_results(test_case, &results_file_path)?;
+            let is_empty_list = serde_json::from_str::<Value>(&raw_results)
+                .is_ok_and(|value| value.as_array().is_some_and(Vec::is_empty));
+            if !is_empty_list {
+                Err(ResponseMismatchError {
+                    test_id: test_case.test_id.clone(),
+                    expected: empty_list(),
+                    actual: Some(parse_results(test_case, &raw_results)?),
+                    reason: None,
+                })?;
+            }
+            Ok(())
+        }
         // Expected some results, got none
-        (Some(_), true, false) => Err(TestError::ResponseMismatch(ResponseMismatchError {
-            test_id: test_case.test_id.clone(),
-            expected: expected.cloned(),
-            actual: None,
-        }))?,
+        (Expected::Value(exp), true, false) => {
+            Err(TestError::ResponseMismatch(ResponseMismatchError {
+                test_id: test_case.test_id.clone(),
+                expected: Some(normalize_response(test_case, exp.clone())?),
+                actual: None,
+                reason: None,
+            }))?
+        }
         // Expected and got some results
-        (Some(exp), false, true) => {
+        (Expected::Value(exp), false, true) => {
+            let exp = normalize_response(test_case, exp.clone())?;
             let actual: T = get_results(&results_file_path)?;
-            if !cmp.as_ref().map_or_else(
-                || T::approx_eq(exp, &actual),
-                |cmp_fn| cmp_fn(exp, &actual, test_case),
-            ) {
+            if let Err(reason) = is_match(&exp, &actual) {
                 Err(ResponseMismatchError {
                     test_id: test_case.test_id.clone(),
-                    expected: Some((*exp).clone()),
+                    expected: Some(exp),
                     actual: Some(actual),
+                    reason,
                 })?;
             }
             Ok(())
         }
+        // Expected one of several results, got none
+        (Expected::AnyOf(alternatives), true, false) => {
+            let alternatives = alternatives
+                .iter()
+                .map(|exp| normalize_response(test_case, exp.clone()))
+                .collect::<TestExecutionResult<Vec<T>>>()?;
+            Err(TestError::ResponseMismatch(ResponseMismatchError {
+                test_id: test_case.test_id.clone(),
+                expected: alternatives.first().cloned(),
+                actual: None,
+                reason: Some(describe_alternatives(&alternatives, &[])),
+            }))?
+        }
+        // Expected one of several results, got some results
+        (Expected::AnyOf(alternatives), false, true) => {
+            let alternatives = alternatives
+                .iter()
+                .map(|exp| normalize_response(test_case, exp.clone()))
+                .collect::<TestExecutionResult<Vec<T>>>()?;
+            let actual: T = get_results(&results_file_path)?;
+            let mut rejections = Vec::with_capacity(alternatives.len());
+            for exp in &alternatives {
+                match is_match(exp, &actual) {
+                    Ok(()) => return Ok(()),
+                    Err(reason) => rejections.push(reason),
+                }
+            }
+            Err(ResponseMismatchError {
+                test_id: test_case.test_id.clone(),
+                expected: alternatives.first().cloned(),
+                actual: Some(actual),
+                reason: Some(describe_alternatives(&alternatives, &rejections)),
+            })?
+        }
+        // Expected a raw JSON response, got none
+        (Expected::Json(exp), true, false) => compare_json(test_case, exp, None),
+        // Expected a raw JSON response, got some results
+        (Expected::Json(exp), false, true) => {
+            let raw_results = read_raw_results(test_case, &results_file_path)?;
+            compare_json(test_case, exp, Some(&raw_results))
+        }
+        // Expected results satisfying a predicate, got none
+        (Expected::Predicate(_), true, false) => {
+            Err(TestError::ResponseMismatch(ResponseMismatchError {
+                test_id: test_case.test_id.clone(),
+                expected: None,
+                actual: None,
+                reason: None,
+            }))?
+        }
+        // Expected results satisfying a predicate, got some results
+        (Expected::Predicate(predicate), false, true) => {
+            let actual: T = get_results(&results_file_path)?;
+            if let Err(reason) = predicate(&actual) {
+                Err(ResponseMismatchError {
+                    test_id: test_case.test_id.clone(),
+                    expected: None,
+                    actual: Some(actual),
+                    reason: Some(reason),
+                })?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Compares the JSON form of the server's response against `expected`, without
+/// deserializing it as `T`. A missing response is treated as `null`. Numbers are
+/// compared within the test case's `float_tolerance`.
+fn compare_json<T>(
+    test_case: &TestCase,
+    expected: &Value,
+    raw_results: Option<&str>,
+) -> TestResult<(), T> {
+    let expected = normalize_response(test_case, expected.clone().clean_response(test_case)?)?;
+    let actual: Value = match raw_results {
+        Some(raw_results) => parse_results(test_case, raw_results)?,
+        None => Value::Null,
+    };
+    if expected != actual && !compare::within_float_tolerance(test_case, &expected, &actual) {
+        Err(TestError::JsonMismatch(ResponseMismatchError {
+            test_id: test_case.test_id.clone(),
+            expected: Some(expected),
+            actual: Some(actual),
+            reason: None,
+        }))?;
+    }
+    Ok(())
+}
+
+/// Builds the error for a run that wrote no results, distinguishing a server that
+/// never attached to the source file from one that attached but never responded
+fn no_results_error(test_case: &TestCase) -> TestExecutionError {
+    if test_case.did_server_attach() {
+        TestExecutionError::NoResults(test_case.test_id.clone())
+    } else {
+        TestExecutionError::ServerNeverAttached(test_case.test_id.clone())
+    }
+}
+
+/// Rejects a test case with a golden directory set, for `function`s that don't compare
+/// a single response and so can't be checked against a golden file
+fn reject_golden(test_case: &TestCase, function: &'static str) -> TestSetupResult<()> {
+    if test_case.golden.is_some() {
+        Err(TestSetupError::GoldenUnsupported(function))?;
+    }
+    Ok(())
+}
+
+/// Builds the mismatch reason for an [`Expected::AnyOf`] that none of the server's
+/// response matched, listing every alternative along with the comparator's reason for
+/// rejecting it, if one was given
+fn describe_alternatives<T: std::fmt::Debug>(
+    alternatives: &[T],
+    rejections: &[Option<String>],
+) -> String {
+    let mut description = format!(
+        "None of the {} acceptable alternatives matched (the comparison below is against the first):",
+        alternatives.len()
+    );
+    for (i, alternative) in alternatives.iter().enumerate() {
+        description.push_str(&format!("\n  [{i}]: {alternative:?}"));
+        if let Some(Some(reason)) = rejections.get(i) {
+            description.push_str(&format!("\n    Rejected: {reason}"));
+        }
     }
+    description
+}
+
+/// Runs the test without comparing its results to an expected value, returning the
+/// server's response. Used by tests that chain several requests together, where the
+/// parameters of one request depend on the response to another
+fn fetch_results<T>(
+    test_case: &TestCase,
+    test_type: TestType,
+    replacements: &mut Vec<LuaReplacement>,
+) -> TestExecutionResult<Option<T>>
+where
+    T: serde::de::DeserializeOwned + serde::Serialize + CleanResponse,
+{
+    let source_path = prepare_test(test_case, test_type, replacements)?;
+    let run_result = run_test(test_case, &source_path);
+    read_results(test_case, run_result)
+}
+
+/// Reads the results of a finished test run, returning `None` if the server's
+/// response was empty. `run_result` is the outcome of running Neovim for the test
+#[allow(clippy::needless_pass_by_value)]
+fn read_results<T>(
+    test_case: &TestCase,
+    run_result: TestExecutionResult<()>,
+) -> TestExecutionResult<Option<T>>
+where
+    T: serde::de::DeserializeOwned + serde::Serialize + CleanResponse,
+{
+    // Stdout noise typically causes a timeout, so report it first if present
+    if test_case.detect_stdout_noise {
+        check_stdout_noise(test_case)?;
+    }
+    run_result?;
+    if test_case.assert_buffer_unchanged {
+        check_buffer_unchanged(test_case)?;
+    }
+    if !test_case.expected_server_requests.is_empty() {
+        check_server_requests(test_case)?;
+    }
+
+    let empty_result_path = test_case
+        .get_empty_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    let results_file_path = test_case
+        .get_results_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+
+    match (empty_result_path.exists(), results_file_path.exists()) {
+        // Invariant: `results.json` and `empty` should never both exist
+        (true, true) => unreachable!(),
+        (false, false) => Err(no_results_error(test_case)),
+        (true, false) => Ok(None),
+        (false, true) => {
+            let raw_results = read_raw_results(test_case, &results_file_path)?;
+            Ok(Some(parse_results(test_case, &raw_results)?))
+        }
+    }
+}
+
+/// Indicates if golden files should be (re)written rather than compared against,
+/// i.e. if `LSPRESSO_BLESS=1` is set
+fn bless_golden() -> bool {
+    std::env::var("LSPRESSO_BLESS").is_ok_and(|bless| bless.trim() == "1")
+}
+
+/// Compares the results of a finished test run against `test_case`'s golden file for
+/// `test_type`. If the golden file doesn't exist or [`bless_golden`] is set, the
+/// cleaned results are instead recorded to it. `run_result` is the outcome of running
+/// Neovim for the test
+///
+/// # Panics
+///
+/// Panics if `test_case.golden` isn't set
+fn evaluate_golden<T>(
+    test_case: &TestCase,
+    test_type: TestType,
+    run_result: TestExecutionResult<()>,
+    cmp: Option<&Comparator<T>>,
+) -> TestResult<(), T>
+where
+    T: Clone
+        + serde::de::DeserializeOwned
+        + serde::Serialize
+        + std::fmt::Debug
+        + CleanResponse
+        + ApproximateEq,
+{
+    let golden_path = test_case
+        .get_golden_file_path(test_type)
+        .expect("Test case has no golden directory");
+    let io_err =
+        |e: std::io::Error| TestExecutionError::IO(test_case.test_id.clone(), e.to_string());
+
+    if golden_path.exists() && !bless_golden() {
+        let raw_golden = fs::read_to_string(&golden_path).map_err(io_err)?;
+        // Golden files are recorded after cleaning, so they aren't cleaned again here
+        let golden: Option<T> = match raw_golden.trim() {
+            "null" => Ok(None),
+            raw => T::deserialize_response(raw).map(Some),
+        }
+        .map_err(|e| {
+            TestExecutionError::Serialization(
+                test_case.test_id.clone(),
+                format!(
+                    "Failed to deserialize golden file \"{}\": {e}",
+                    golden_path.display()
+                ),
+            )
+        })?;
+        let expected = golden.as_ref().map_or(Expected::Null, Expected::Value);
+        return evaluate_results(test_case, run_result, expected, cmp);
+    }
+
+    let results: Option<T> = read_results(test_case, run_result)?;
+    let serialized = serde_json::to_string_pretty(&results)
+        .map_err(|e| TestExecutionError::Serialization(test_case.test_id.clone(), e.to_string()))?;
+    if let Some(parent) = golden_path.parent() {
+        fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    fs::write(&golden_path, serialized + "\n").map_err(io_err)?;
+
+    Ok(())
+}
+
+/// Reads the results file at `path` as UTF-8, handling invalid UTF-8 according to
+/// `test_case.utf8_policy`
+fn read_raw_results(test_case: &TestCase, path: &Path) -> TestExecutionResult<String> {
+    let raw = fs::read(path)
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if test_case.utf8_policy == Utf8Policy::Lossy {
+        return Ok(String::from_utf8_lossy(&raw).into_owned());
+    }
+    String::from_utf8(raw).map_err(|e| {
+        TestExecutionError::Utf8(
+            test_case.test_id.clone(),
+            format!("Failed to read \"{}\" as UTF-8: {e}", path.display()),
+        )
+    })
+}
+
+/// Deserializes `raw_results` as `T`, and cleans and normalizes the resulting response
+fn parse_results<T>(test_case: &TestCase, raw_results: &str) -> TestExecutionResult<T>
+where
+    T: serde::de::DeserializeOwned + serde::Serialize + CleanResponse,
+{
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("parse_results", test_id = %test_case.test_id).entered();
+    let start = std::time::Instant::now();
+    let deserialized = if test_case.strip_invalid_fields {
+        from_str_stripping_invalid(raw_results)
+    } else {
+        T::deserialize_response(raw_results)
+    };
+    let raw_resp: T = deserialized.map_err(|e| {
+        TestExecutionError::Serialization(
+            test_case.test_id.clone(),
+            deserialization_error_message::<T>(raw_results, &e),
+        )
+    })?;
+    let cleaned = normalize_response(test_case, raw_resp.clean_response(test_case)?)?;
+    test_case
+        .record_phase("parse", start.elapsed())
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    Ok(cleaned)
+}
+
+/// Applies `test_case.normalize` (if set) to the JSON form of `response`
+fn normalize_response<T>(test_case: &TestCase, response: T) -> TestExecutionResult<T>
+where
+    T: serde::de::DeserializeOwned + serde::Serialize,
+{
+    let Some(normalize) = test_case.normalize else {
+        return Ok(response);
+    };
+    let to_error = |e: serde_json::Error| {
+        TestExecutionError::Serialization(
+            test_case.test_id.clone(),
+            format!("Failed to normalize response: {e}"),
+        )
+    };
+    let mut value = serde_json::to_value(response).map_err(to_error)?;
+    normalize(&mut value);
+    serde_json::from_value(value).map_err(to_error)
 }
 
 /// Invokes neovim to run the test with `test_case`'s associated `init.lua` file,
 /// opening `source_path`
 fn run_test(test_case: &TestCase, source_path: &Path) -> TestExecutionResult<()> {
-    let init_dot_lua_path = test_case
-        .get_init_lua_file_path()
-        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    run_test_with(test_case, source_path, || Ok(()))
+}
 
+/// Like [`run_test`], but calls `poll` repeatedly while neovim is running, for tests
+/// that hand information back to the client mid-session
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(test_id = %test_case.test_id, elapsed = tracing::field::Empty)
+    )
+)]
+fn run_test_with(
+    test_case: &TestCase,
+    source_path: &Path,
+    mut poll: impl FnMut() -> TestExecutionResult<()>,
+) -> TestExecutionResult<()> {
     // Restrict the number of tests invoking neovim at a given time to prevent timeout issues
-    let (lock, cvar) = &*get_runner_count();
+    let (lock, cvar, suite_start) = &*get_runner_count();
     let _guard = RunnerGuard::new(lock, cvar); // Ensures proper decrement on exit
+    check_suite_deadline(test_case, *suite_start, suite_deadline())?;
 
+    let timeout = run_timeout(test_case);
+    let mut command = nvim_command(test_case, source_path)?;
     let start = std::time::Instant::now();
-    let mut child = Command::new(&test_case.nvim_path)
-        .arg("-u")
-        .arg(init_dot_lua_path)
-        .arg("--noplugin")
-        .arg(source_path)
-        // NOTE: Running with `--headless` would be better, but this causes *all* tests
-        // to fail on GH's runners, likely due to the lack of appearance of a tty.
-        // .arg("--headless")
-        .arg("-n") // disable swap files
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| TestExecutionError::Neovim(test_case.test_id.clone(), e.to_string()))?;
-
-    // In theory, the timeout set in `init.lua` should be sufficient to prevent
-    // the neovim process from hanging. However, if `init.lua` is malformed (an
-    // error for this library), then the timer will never start. Add the same
-    // timeout (with an arbitrary cushion) here as a fallback
-    let timeout_cushion = std::time::Duration::from_millis(500);
-    while start.elapsed() < test_case.timeout + timeout_cushion {
-        match child.try_wait() {
-            Ok(Some(_)) => {
-                if test_case.did_exceed_timeout() {
-                    Err(TestExecutionError::TimeoutExceeded(TimeoutError {
-                        test_id: test_case.test_id.clone(),
-                        timeout: test_case.timeout,
-                    }))?;
-                }
-                return Ok(());
+    let mut child = ChildGuard::new(
+        command
+            .spawn()
+            .map_err(|e| TestExecutionError::Neovim(test_case.test_id.clone(), e.to_string()))?,
+        test_case.on_drop_kill,
+        nvim_leads_group(test_case),
+    );
+    #[cfg(feature = "tracing")]
+    tracing::debug!(elapsed = ?start.elapsed(), "spawned neovim");
+    let record_phase = |phase: &str| {
+        test_case
+            .record_phase(phase, start.elapsed())
+            .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))
+    };
+    record_phase("spawn")?;
+
+    let fallback = fallback_timeout(test_case, timeout);
+    while start.elapsed() < fallback {
+        match child.has_exited() {
+            Ok(true) => {
+                #[cfg(feature = "tracing")]
+                tracing::Span::current().record("elapsed", tracing::field::debug(start.elapsed()));
+                record_phase("run")?;
+                return check_exited_run(test_case, timeout);
             }
-            Ok(None) => {} // still running
+            Ok(false) => poll()?, // still running
             Err(e) => Err(TestExecutionError::Neovim(
                 test_case.test_id.clone(),
                 e.to_string(),
@@ -245,6 +980,104 @@ fn run_test(test_case: &TestCase, source_path: &Path) -> TestExecutionResult<()>
         }
     }
 
+    timed_out_run(test_case, timeout)
+}
+
+/// Returns the vimscript that moves `runtime` to the front of `runtimepath` and
+/// `packpath`, leaving the rest of their entries in place
+fn prepend_runtime_cmd(runtime: &Path) -> String {
+    // Escape commas for the option's list syntax, then single quotes for vimscript's
+    // literal string syntax
+    let runtime_str = runtime
+        .to_string_lossy()
+        .replace(',', "\\,")
+        .replace('\'', "''");
+    format!(
+        "let &runtimepath = '{runtime_str},' . &runtimepath | let &packpath = '{runtime_str},' . &packpath"
+    )
+}
+
+/// Builds the command used to invoke neovim for `test_case`, opening `source_path`
+fn nvim_command(test_case: &TestCase, source_path: &Path) -> TestExecutionResult<Command> {
+    let init_dot_lua_path = test_case
+        .get_init_lua_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+
+    let mut command = Command::new(&test_case.nvim_path);
+    // Neovim leads its own process group, which the server joins, so that both can be
+    // killed together (see `TestCase::on_drop_kill`). A background group can't read
+    // from the terminal, so neovim is given no stdin.
+    #[cfg(unix)]
+    if nvim_leads_group(test_case) {
+        std::os::unix::process::CommandExt::process_group(&mut command, 0);
+        command.stdin(Stdio::null());
+    }
+    command.arg("-u").arg(init_dot_lua_path).arg("--noplugin");
+    if let Some(runtime) = &test_case.nvim_runtime {
+        command
+            .env("VIMRUNTIME", runtime)
+            .arg("--cmd")
+            .arg(prepend_runtime_cmd(runtime));
+    }
+    command
+        .arg(source_path)
+        // NOTE: Running with `--headless` would be better, but this causes *all* tests
+        // to fail on GH's runners, likely due to the lack of appearance of a tty.
+        // .arg("--headless")
+        .arg("-n"); // disable swap files
+    if test_case.inherit_stdio {
+        command.stdout(Stdio::inherit()).stderr(Stdio::inherit());
+    } else {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    }
+
+    Ok(command)
+}
+
+/// In theory, the timeout set in `init.lua` should be sufficient to prevent
+/// the neovim process from hanging. However, if `init.lua` is malformed (an
+/// error for this library), then the timer will never start. Returns `timeout`
+/// (with an arbitrary cushion) to be used as a fallback, extended by any start
+/// delay the timer is paused for
+fn fallback_timeout(test_case: &TestCase, timeout: Duration) -> Duration {
+    timeout
+        .saturating_add(test_case.start_type.start_delay())
+        .saturating_add(scale_duration(Duration::from_millis(500), timeout_scale()))
+}
+
+/// Checks the outcome of a neovim process that exited on its own
+fn check_exited_run(test_case: &TestCase, timeout: Duration) -> TestExecutionResult<()> {
+    if test_case.did_exceed_timeout() {
+        Err(timeout_error(test_case, timeout)?)?;
+    }
+    Ok(())
+}
+
+/// Builds the error for a test that exceeded `timeout`, including any `$/progress`
+/// tokens observed while waiting on a [`ServerStartType::Progress`] token. If the
+/// server never attached, [`TestExecutionError::ServerNeverAttached`] is returned instead
+fn timeout_error(
+    test_case: &TestCase,
+    timeout: Duration,
+) -> TestExecutionResult<TestExecutionError> {
+    // The most common cause of a timeout, and one that deserves a clearer explanation
+    if !test_case.did_server_attach() {
+        return Ok(TestExecutionError::ServerNeverAttached(
+            test_case.test_id.clone(),
+        ));
+    }
+    let observed_progress_tokens = test_case
+        .read_observed_progress_tokens()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    Ok(TestExecutionError::TimeoutExceeded(TimeoutError {
+        test_id: test_case.test_id.clone(),
+        timeout,
+        observed_progress_tokens,
+    }))
+}
+
+/// Builds the error for a neovim process that failed to exit before the fallback timeout
+fn timed_out_run(test_case: &TestCase, timeout: Duration) -> TestExecutionResult<()> {
     // A test can also timeout due to neovim encountering an error (i.e. a malformed
     // `init.lua` file). If we have an error recorded, it's better to report that
     // than the timeout
@@ -257,10 +1090,92 @@ fn run_test(test_case: &TestCase, source_path: &Path) -> TestExecutionResult<()>
         Err(TestExecutionError::Neovim(test_case.test_id.clone(), error))?;
     }
 
-    Err(TestExecutionError::TimeoutExceeded(TimeoutError {
-        test_id: test_case.test_id.clone(),
-        timeout: test_case.timeout,
-    }))?
+    Err(timeout_error(test_case, timeout)?)?
+}
+
+/// Returns [`TestExecutionError::ProtocolNoise`] if the client recorded any invalid
+/// messages from the server
+fn check_stdout_noise(test_case: &TestCase) -> TestExecutionResult<()> {
+    let noise_path = test_case
+        .get_noise_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if noise_path.exists() {
+        let noise = fs::read_to_string(&noise_path)
+            .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+        Err(TestExecutionError::ProtocolNoise(
+            test_case.test_id.clone(),
+            noise,
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// Returns [`TestExecutionError::BufferChanged`] if the client recorded changes to
+/// the source file's buffer during the tested request
+fn check_buffer_unchanged(test_case: &TestCase) -> TestExecutionResult<()> {
+    #[derive(serde::Deserialize)]
+    struct BufferChanges {
+        before: String,
+        after: String,
+    }
+
+    let changes_path = test_case
+        .get_buffer_changes_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if changes_path.exists() {
+        let raw_changes = fs::read_to_string(&changes_path)
+            .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+        let BufferChanges { before, after } = serde_json::from_str(&raw_changes).map_err(|e| {
+            TestExecutionError::Serialization(test_case.test_id.clone(), e.to_string())
+        })?;
+        Err(TestExecutionError::BufferChanged {
+            test_id: test_case.test_id.clone(),
+            before,
+            after,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Returns [`TestExecutionError::UnexpectedServerRequests`] if the number of
+/// server-initiated requests recorded by the client for any counted method doesn't
+/// satisfy its expected [`compare::Cardinality`]
+fn check_server_requests(test_case: &TestCase) -> TestExecutionResult<()> {
+    let requests_path = test_case
+        .get_server_requests_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    let raw_requests = match fs::read_to_string(&requests_path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => Err(TestExecutionError::IO(
+            test_case.test_id.clone(),
+            e.to_string(),
+        ))?,
+    };
+
+    for (method, expected) in &test_case.expected_server_requests {
+        let actual = raw_requests.lines().filter(|line| line == method).count();
+        if !expected.check(actual) {
+            Err(TestExecutionError::UnexpectedServerRequests {
+                test_id: test_case.test_id.clone(),
+                method: method.clone(),
+                expected: *expected,
+                actual,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders `commands` as the body of a Lua list, for the `COMMANDS` replacement
+fn commands_str(commands: Option<&Vec<String>>) -> String {
+    commands.map_or_else(String::new, |cmds| {
+        cmds.iter()
+            .fold(String::new(), |accum, cmd| accum + &format!("\"{cmd}\",\n"))
+    })
 }
 
 fn benchmark<T>(
@@ -268,32 +1183,98 @@ fn benchmark<T>(
     config: BenchmarkConfig,
     action: impl Fn() -> TestResult<(), T>,
 ) -> Result<Vec<Duration>, BenchmarkError> {
-    let handle_result = |res: TestResult<(), T>, fail_fast: bool| -> Result<(), BenchmarkError> {
-        match (fail_fast, res) {
-            (true, Err(TestError::ResponseMismatch(_)) | Ok(())) | (false, _) => Ok(()),
-            (true, Err(TestError::TestSetup(setup))) => Err(BenchmarkError::TestSetup(setup)),
-            (true, Err(TestError::TestExecution(execution))) => {
-                Err(BenchmarkError::TestExecution(execution))
-            }
-        }
-    };
-    match config.end_condition {
+    run_benchmark_iterations(config.end_condition, || {
+        handle_benchmark_result(action(), config.fail_fast)
+    })?;
+    test_case.get_benchmark_results()
+}
+
+/// Benchmarks `action` across `positions`, cycling through them in order on each
+/// iteration. Measurements are attributed to the position whose iteration recorded them.
+fn benchmark_positions<T>(
+    test_case: &TestCase,
+    config: BenchmarkConfig,
+    positions: &[Position],
+    action: impl Fn(Position) -> TestResult<(), T>,
+) -> Result<PositionBenchmarkStats, BenchmarkError> {
+    if positions.is_empty() {
+        return Err(BenchmarkError::NoResults);
+    }
+    // Results from previous benchmarks of this test case may still be present
+    let recorded = || test_case.get_benchmark_results().unwrap_or_default();
+    let mut n_recorded = recorded().len();
+    let mut measurements = vec![Vec::new(); positions.len()];
+    let mut cycle = positions.iter().enumerate().cycle();
+    run_benchmark_iterations(config.end_condition, || {
+        // Invariant: `positions` is non-empty, so `cycle` never ends
+        let (idx, pos) = cycle.next().unwrap();
+        handle_benchmark_result(action(*pos), config.fail_fast)?;
+        let results = recorded();
+        measurements[idx].extend_from_slice(results.get(n_recorded..).unwrap_or_default());
+        n_recorded = results.len();
+        Ok(())
+    })?;
+
+    let all: Vec<Duration> = measurements.iter().flatten().copied().collect();
+    let aggregate = BenchmarkStats::from_durations(&all).ok_or(BenchmarkError::NoResults)?;
+    Ok(PositionBenchmarkStats {
+        positions: positions
+            .iter()
+            .zip(&measurements)
+            .map(|(pos, durations)| (*pos, BenchmarkStats::from_durations(durations)))
+            .collect(),
+        aggregate,
+    })
+}
+
+/// Invokes `iteration` until `end_condition` is met
+fn run_benchmark_iterations(
+    end_condition: EndCondition,
+    mut iteration: impl FnMut() -> Result<(), BenchmarkError>,
+) -> Result<(), BenchmarkError> {
+    match end_condition {
         EndCondition::Time(duration) => {
             let start = std::time::Instant::now();
             while start.elapsed() < duration {
-                handle_result(action(), config.fail_fast)?;
+                iteration()?;
             }
         }
         EndCondition::Count(iterations) => {
             for _ in 0..iterations {
-                handle_result(action(), config.fail_fast)?;
+                iteration()?;
             }
         }
     }
-    test_case.get_benchmark_results()
+    Ok(())
+}
+
+/// Determines whether a single benchmark iteration's result should end the benchmark
+fn handle_benchmark_result<T>(
+    res: TestResult<(), T>,
+    fail_fast: bool,
+) -> Result<(), BenchmarkError> {
+    match (fail_fast, res) {
+        // The remaining iterations would fail the same way, regardless of `fail_fast`
+        (
+            _,
+            Err(TestError::TestExecution(e @ TestExecutionError::SuiteDeadlineExceeded { .. })),
+        ) => Err(BenchmarkError::TestExecution(e)),
+        (true, Err(TestError::ResponseMismatch(_) | TestError::JsonMismatch(_)) | Ok(()))
+        | (false, _) => Ok(()),
+        (true, Err(TestError::TestSetup(setup))) => Err(BenchmarkError::TestSetup(setup)),
+        (true, Err(TestError::TestExecution(execution))) => {
+            Err(BenchmarkError::TestExecution(execution))
+        }
+        // Report the first failed run's error
+        (true, Err(TestError::NvimVersions(versions))) => versions
+            .results
+            .into_iter()
+            .find_map(|(_, result)| result.err())
+            .map_or(Ok(()), |e| handle_benchmark_result::<T>(Err(e), fail_fast)),
+    }
 }
 
-pub type CodeActionComparator = fn(&CodeActionResponse, &CodeActionResponse, &TestCase) -> bool;
+pub type CodeActionComparator = Comparator<CodeActionResponse>;
 
 /// Tests the server's response to a [`textDocument/codeAction`] request
 ///
@@ -312,12 +1293,12 @@ pub type CodeActionComparator = fn(&CodeActionResponse, &CodeActionResponse, &Te
 /// Panics if JSON serialization of `context` fails
 ///
 /// [`textDocument/codeAction`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_codeAction
-pub fn test_code_action(
+pub fn test_code_action<'a>(
     test_case: &TestCase,
     range: Range,
     context: &CodeActionContext,
     cmp: Option<CodeActionComparator>,
-    expected: Option<&CodeActionResponse>,
+    expected: impl Into<Expected<'a, CodeActionResponse>>,
 ) -> TestResult<(), CodeActionResponse> {
     let context_json =
         serde_json::to_string_pretty(context).expect("JSON serialization of `context` failed");
@@ -332,7 +1313,7 @@ pub fn test_code_action(
                 json: context_json,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -363,7 +1344,7 @@ pub fn benchmark_code_action(
     })
 }
 
-pub type CodeActionResolveComparator = fn(&CodeAction, &CodeAction, &TestCase) -> bool;
+pub type CodeActionResolveComparator = Comparator<CodeAction>;
 
 /// Tests the server's response to a [`codeAction/resolve`] request
 ///
@@ -407,7 +1388,7 @@ pub fn test_code_action_resolve(
             ],
             json: code_action_json,
         }],
-        Some(expected),
+        Expected::Value(expected),
         cmp,
     )
 }
@@ -436,7 +1417,7 @@ pub fn benchmark_code_action_resolve(
     })
 }
 
-pub type CodeLensComparator = fn(&Vec<CodeLens>, &Vec<CodeLens>, &TestCase) -> bool;
+pub type CodeLensComparator = Comparator<Vec<CodeLens>>;
 
 /// Tests the server's response to a [`textDocument/codeLens`] request
 ///
@@ -452,31 +1433,31 @@ pub type CodeLensComparator = fn(&Vec<CodeLens>, &Vec<CodeLens>, &TestCase) -> b
 /// or some other failure occurs
 ///
 /// [`textDocument/codeLens`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_codeLens
-pub fn test_code_lens(
+pub fn test_code_lens<'a>(
     test_case: &TestCase,
     commands: Option<&Vec<String>>,
     cmp: Option<CodeLensComparator>,
-    expected: Option<&Vec<CodeLens>>,
+    expected: impl Into<Expected<'a, Vec<CodeLens>>>,
 ) -> TestResult<(), Vec<CodeLens>> {
-    let command_str = commands.map_or_else(String::new, |cmds| {
-        cmds.iter()
-            .fold(String::new(), |accum, cmd| accum + &format!("\"{cmd}\",\n"))
-    });
     collect_results(
         test_case,
         TestType::CodeLens,
-        &mut vec![
-            LuaReplacement::ParamTextDocument,
-            LuaReplacement::Other {
-                from: "COMMANDS",
-                to: command_str,
-            },
-        ],
-        expected,
+        &mut code_lens_replacements(commands),
+        expected.into(),
         cmp,
     )
 }
 
+fn code_lens_replacements(commands: Option<&Vec<String>>) -> Vec<LuaReplacement> {
+    vec![
+        LuaReplacement::ParamTextDocument,
+        LuaReplacement::Other {
+            from: "COMMANDS",
+            to: commands_str(commands),
+        },
+    ]
+}
+
 /// Benchmarks the server's response time to a [`textDocument/codeLens`] request
 ///
 /// - `commands`: A list of LSP command names the client should advertise support for in its
@@ -498,7 +1479,7 @@ pub fn benchmark_code_lens(
     })
 }
 
-pub type CodeLensResolveComparator = fn(&CodeLens, &CodeLens, &TestCase) -> bool;
+pub type CodeLensResolveComparator = Comparator<CodeLens>;
 
 /// Tests the server's response to a [`codeLens/resolve`] request
 ///
@@ -520,38 +1501,41 @@ pub type CodeLensResolveComparator = fn(&CodeLens, &CodeLens, &TestCase) -> bool
 ///
 /// [`codeLens/resolve`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeLens_resolve
 #[allow(clippy::result_large_err)]
-pub fn test_code_lens_resolve(
+pub fn test_code_lens_resolve<'a>(
     test_case: &TestCase,
     commands: Option<&Vec<String>>,
     code_lens: &CodeLens,
     cmp: Option<CodeLensResolveComparator>,
-    expected: Option<&CodeLens>,
+    expected: impl Into<Expected<'a, CodeLens>>,
 ) -> TestResult<(), CodeLens> {
-    let command_str = commands.map_or_else(String::new, |cmds| {
-        cmds.iter()
-            .fold(String::new(), |accum, cmd| accum + &format!("\"{cmd}\",\n"))
-    });
-    let code_lens_json =
-        serde_json::to_string_pretty(code_lens).expect("JSON serialization of `code_lens` failed");
     collect_results(
         test_case,
         TestType::CodeLensResolve,
-        &mut vec![
-            LuaReplacement::ParamDestructure {
-                name: "code_lens",
-                fields: vec!["range", "data", "command"],
-                json: code_lens_json,
-            },
-            LuaReplacement::Other {
-                from: "COMMANDS",
-                to: command_str,
-            },
-        ],
-        expected,
+        &mut code_lens_resolve_replacements(commands, code_lens),
+        expected.into(),
         cmp,
     )
 }
 
+fn code_lens_resolve_replacements(
+    commands: Option<&Vec<String>>,
+    code_lens: &CodeLens,
+) -> Vec<LuaReplacement> {
+    let code_lens_json =
+        serde_json::to_string_pretty(code_lens).expect("JSON serialization of `code_lens` failed");
+    vec![
+        LuaReplacement::ParamDestructure {
+            name: "code_lens",
+            fields: vec!["range", "data", "command"],
+            json: code_lens_json,
+        },
+        LuaReplacement::Other {
+            from: "COMMANDS",
+            to: commands_str(commands),
+        },
+    ]
+}
+
 /// Benchmarks the server's response time to a [`codeLens/resolve`] request
 ///
 /// - `commands` is a list of LSP command names the client should advertise support for in its
@@ -579,8 +1563,162 @@ pub fn benchmark_code_lens_resolve(
     })
 }
 
-pub type ColorPresentationComparator =
-    fn(&Vec<ColorPresentation>, &Vec<ColorPresentation>, &TestCase) -> bool;
+pub type CodeLensThenExecuteComparator = Comparator<CodeLensExecution>;
+
+/// Tests a [`textDocument/codeLens`] request followed by a [`workspace/executeCommand`]
+/// request for one of the returned lenses' commands, mirroring a user clicking a lens
+/// (i.e. "Run test") in their editor.
+///
+/// The first code lens for which `pick` returns true is selected. If it doesn't have a
+/// command, it's resolved via a [`codeLens/resolve`] request first. The lens's command and
+/// arguments are then executed. Each step is carried out in the same session, so any state
+/// the server keeps between them is preserved.
+///
+/// The outcome compared to `expected` consists of the server's response to the
+/// `workspace/executeCommand` request, the edit of each [`workspace/applyEdit`] request the
+/// server sent while executing the command, and the target file's contents after those
+/// edits were applied.
+///
+/// - `commands`: A list of LSP command names the client should advertise support for in its
+///   capabilities (e.g. "rust-analyzer.runSingle"). This enables command-based `CodeLens`
+///   responses from the server, such as "Run" or "Debug" actions.
+/// - `pick`: Selects the code lens whose command should be executed
+/// - `cmp`: An optional custom comparator function that can be used to determine equality
+///   between the expected and actual outcomes of executing the command.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, no code lens is selected by `pick`,
+/// the selected lens has no command, the expected results don't match, or some other
+/// failure occurs
+///
+/// [`textDocument/codeLens`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_codeLens
+/// [`codeLens/resolve`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeLens_resolve
+/// [`workspace/executeCommand`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_executeCommand
+/// [`workspace/applyEdit`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_applyEdit
+#[allow(clippy::needless_pass_by_value)]
+pub fn test_code_lens_then_execute<'a>(
+    test_case: &TestCase,
+    commands: Option<&Vec<String>>,
+    pick: impl Fn(&CodeLens) -> bool,
+    cmp: Option<CodeLensThenExecuteComparator>,
+    expected: impl Into<Expected<'a, CodeLensExecution>>,
+) -> TestResult<(), CodeLensExecution> {
+    reject_golden(test_case, "test_code_lens_then_execute")?;
+    let expected = expected.into();
+    if test_case.nvim_paths.is_empty() {
+        return code_lens_then_execute_once(test_case, commands, &pick, expected, cmp.as_ref());
+    }
+
+    let results = nvim_version_cases(test_case)
+        .into_iter()
+        .map(|version_case| {
+            let result =
+                code_lens_then_execute_once(&version_case, commands, &pick, expected, cmp.as_ref());
+            (version_case.nvim_path, result)
+        })
+        .collect();
+    nvim_versions_result(test_case, results)
+}
+
+/// Performs a single run of [`test_code_lens_then_execute`] with `test_case.nvim_path`
+fn code_lens_then_execute_once(
+    test_case: &TestCase,
+    commands: Option<&Vec<String>>,
+    pick: &impl Fn(&CodeLens) -> bool,
+    expected: Expected<'_, CodeLensExecution>,
+    cmp: Option<&CodeLensThenExecuteComparator>,
+) -> TestResult<(), CodeLensExecution> {
+    let source_path = prepare_test(
+        test_case,
+        TestType::CodeLensThenExecute,
+        &mut code_lens_replacements(commands),
+    )?;
+    // The client waits on the lens to execute, which is picked as soon as the server's
+    // lenses have been recorded
+    let mut picked = None;
+    let run_result = run_test_with(test_case, &source_path, || {
+        if picked.is_some() {
+            return Ok(());
+        }
+        if let Some(lenses) = read_code_lenses(test_case)? {
+            let lens = lenses.into_iter().find(|lens| pick(lens));
+            write_picked_code_lens(test_case, lens.as_ref())?;
+            picked = Some(lens);
+        }
+        Ok(())
+    });
+    match picked {
+        Some(None) => {
+            Err(TestExecutionError::NoMatchingCodeLens(
+                test_case.test_id.clone(),
+            ))?;
+        }
+        Some(Some(CodeLens { command: None, .. })) => {
+            let resolved_path = test_case
+                .get_resolved_code_lens_file_path()
+                .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+            if resolved_path.exists() {
+                let resolved: CodeLens =
+                    parse_results(test_case, &read_raw_results(test_case, &resolved_path)?)?;
+                if resolved.command.is_none() {
+                    Err(TestExecutionError::MissingCodeLensCommand(
+                        test_case.test_id.clone(),
+                    ))?;
+                }
+            }
+        }
+        Some(Some(_)) | None => {}
+    }
+
+    evaluate_results(test_case, run_result, expected, cmp)
+}
+
+/// Reads the code lenses recorded by the client during [`test_code_lens_then_execute`].
+/// Returns `None` if the client hasn't recorded them yet.
+fn read_code_lenses(test_case: &TestCase) -> TestExecutionResult<Option<Vec<CodeLens>>> {
+    let lenses_path = test_case
+        .get_code_lenses_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if !lenses_path.exists() {
+        return Ok(None);
+    }
+    // The lenses are sent back to the server as is, so they aren't cleaned
+    let raw_lenses = read_raw_results(test_case, &lenses_path)?;
+    let lenses: Option<Vec<CodeLens>> = serde_json::from_str(&raw_lenses).map_err(|e| {
+        TestExecutionError::Serialization(
+            test_case.test_id.clone(),
+            deserialization_error_message::<Vec<CodeLens>>(&raw_lenses, &e),
+        )
+    })?;
+    Ok(Some(lenses.unwrap_or_default()))
+}
+
+/// Writes out the code lens for the client to execute during
+/// [`test_code_lens_then_execute`], or `null` if none was picked
+///
+/// # Panics
+///
+/// Panics if JSON serialization of the picked code lens fails
+fn write_picked_code_lens(
+    test_case: &TestCase,
+    picked: Option<&CodeLens>,
+) -> TestExecutionResult<()> {
+    let io_error =
+        |e: std::io::Error| TestExecutionError::IO(test_case.test_id.clone(), e.to_string());
+    // Written via a temporary file, so the client never reads a partial write
+    let picked_path = test_case
+        .get_picked_code_lens_file_path()
+        .map_err(io_error)?;
+    let tmp_path = picked_path.with_extension("json.tmp");
+    let picked_json =
+        serde_json::to_string(&picked).expect("JSON serialization of the picked code lens failed");
+    fs::write(&tmp_path, picked_json).map_err(io_error)?;
+    fs::rename(&tmp_path, &picked_path).map_err(io_error)?;
+    Ok(())
+}
+
+pub type ColorPresentationComparator = Comparator<Vec<ColorPresentation>>;
 
 /// Tests the server's response to a [`textDocument/colorPresentation`] request
 ///
@@ -619,7 +1757,7 @@ pub fn test_color_presentation(
             },
             LuaReplacement::ParamRange(range),
         ],
-        Some(expected),
+        Expected::Value(expected),
         cmp,
     )
 }
@@ -649,12 +1787,15 @@ pub fn benchmark_color_presentation(
     })
 }
 
-pub type CompletionComparator = fn(&CompletionResponse, &CompletionResponse, &TestCase) -> bool;
+pub type CompletionComparator = Comparator<CompletionResponse>;
 
 /// Tests the server's response to a [`textDocument/completion`] request
 ///
 /// - `cursor_pos`: The position of the cursor when the request is issued. Passed
-///   to the client via the request's [`CompletionParams`]
+///   to the client via the request's [`CompletionParams`]. See [`TestCase::at_marker`]
+///   for placing the cursor relative to some text in the source file.
+/// - `context`: Passed to the client via the request's [`CompletionParams`]. If `None`,
+///   the request is sent without a context.
 /// - `cmp`: An optional custom comparator function that can be used to determine equality
 ///   between the expected and actual results.
 ///
@@ -663,49 +1804,77 @@ pub type CompletionComparator = fn(&CompletionResponse, &CompletionResponse, &Te
 /// Returns [`TestError`] if the test case is invalid, the expected results don't match,
 /// or some other failure occurs
 ///
+/// # Panics
+///
+/// Panics if JSON serialization of `context` fails
+///
 /// [`textDocument/completion`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_completion
-pub fn test_completion(
+pub fn test_completion<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
+    context: Option<&CompletionContext>,
     cmp: Option<CompletionComparator>,
-    expected: Option<&CompletionResponse>,
+    expected: impl Into<Expected<'a, CompletionResponse>>,
 ) -> TestResult<(), CompletionResponse> {
     collect_results(
         test_case,
         TestType::Completion,
-        &mut vec![
-            LuaReplacement::ParamTextDocument,
-            LuaReplacement::ParamPosition {
-                pos: cursor_pos,
-                name: None,
-            },
-        ],
-        expected,
+        &mut completion_replacements(cursor_pos, context),
+        expected.into(),
         cmp,
     )
 }
 
+fn completion_replacements(
+    cursor_pos: Position,
+    context: Option<&CompletionContext>,
+) -> Vec<LuaReplacement> {
+    let mut replacements = vec![
+        LuaReplacement::ParamTextDocument,
+        LuaReplacement::ParamPosition {
+            pos: cursor_pos,
+            name: None,
+        },
+    ];
+    if let Some(context) = context {
+        let context_json =
+            serde_json::to_string_pretty(context).expect("JSON serialization of `context` failed");
+        replacements.push(LuaReplacement::ParamDirect {
+            name: "context",
+            json: context_json,
+        });
+    }
+    replacements
+}
+
 /// Benchmarks the server's response time to a [`textDocument/completion`] request
 ///
 /// - `cursor_pos`: The position of the cursor when the request is issued. Passed
 ///   to the client via the request's [`CompletionParams`]
+/// - `context`: Passed to the client via the request's [`CompletionParams`]. If `None`,
+///   the request is sent without a context.
 ///
 /// # Errors
 ///
 /// Returns [`BenchmarkError`] if the test case is invalid or if benchmarking fails
 ///
+/// # Panics
+///
+/// Panics if JSON serialization of `context` fails
+///
 /// [`textDocument/completion`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_completion
 pub fn benchmark_completion(
     test_case: &TestCase,
     config: BenchmarkConfig,
     cursor_pos: Position,
+    context: Option<&CompletionContext>,
 ) -> Result<Vec<Duration>, BenchmarkError> {
     benchmark(test_case, config, || {
-        test_completion(test_case, cursor_pos, None, None)
+        test_completion(test_case, cursor_pos, context, None, None)
     })
 }
 
-pub type CompletionResolveComparator = fn(&CompletionItem, &CompletionItem, &TestCase) -> bool;
+pub type CompletionResolveComparator = Comparator<CompletionItem>;
 
 /// Tests the server's response to a [`completionItem/resolve`] request
 ///
@@ -724,11 +1893,11 @@ pub type CompletionResolveComparator = fn(&CompletionItem, &CompletionItem, &Tes
 ///
 /// [`completionItem/resolve`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#completionItem_resolve
 #[allow(clippy::result_large_err)]
-pub fn test_completion_resolve(
+pub fn test_completion_resolve<'a>(
     test_case: &TestCase,
     completion_item: &CompletionItem,
     cmp: Option<CompletionResolveComparator>,
-    expected: Option<&CompletionItem>,
+    expected: impl Into<Expected<'a, CompletionItem>>,
 ) -> TestResult<(), CompletionItem> {
     let completion_item_json = serde_json::to_string_pretty(completion_item)
         .expect("JSON serialization of `completion_item` failed");
@@ -760,7 +1929,7 @@ pub fn test_completion_resolve(
             ],
             json: completion_item_json,
         }],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -788,8 +1957,7 @@ pub fn benchmark_completion_resolve(
     })
 }
 
-pub type DeclarationComparator =
-    fn(&GotoDeclarationResponse, &GotoDeclarationResponse, &TestCase) -> bool;
+pub type DeclarationComparator = Comparator<GotoDeclarationResponse>;
 
 /// Tests the server's response to a [`textDocument/declaration`] request
 ///
@@ -813,11 +1981,11 @@ pub type DeclarationComparator =
 ///
 /// [`textDocument/declaration`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_declaration
 #[allow(clippy::result_large_err)]
-pub fn test_declaration(
+pub fn test_declaration<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<DeclarationComparator>,
-    expected: Option<&GotoDeclarationResponse>,
+    expected: impl Into<Expected<'a, GotoDeclarationResponse>>,
 ) -> TestResult<(), GotoDeclarationResponse> {
     collect_results(
         test_case,
@@ -829,7 +1997,7 @@ pub fn test_declaration(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -854,8 +2022,7 @@ pub fn benchmark_declaration(
     })
 }
 
-pub type DefinitionComparator =
-    fn(&GotoDefinitionResponse, &GotoDefinitionResponse, &TestCase) -> bool;
+pub type DefinitionComparator = Comparator<GotoDefinitionResponse>;
 
 /// Tests the server's response to a [`textDocument/definition`] request
 ///
@@ -878,27 +2045,31 @@ pub type DefinitionComparator =
 ///
 /// [`textDocument/definition`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_definition
 #[allow(clippy::result_large_err)]
-pub fn test_definition(
+pub fn test_definition<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<DefinitionComparator>,
-    expected: Option<&GotoDefinitionResponse>,
+    expected: impl Into<Expected<'a, GotoDefinitionResponse>>,
 ) -> TestResult<(), GotoDefinitionResponse> {
     collect_results(
         test_case,
         TestType::Definition,
-        &mut vec![
-            LuaReplacement::ParamTextDocument,
-            LuaReplacement::ParamPosition {
-                pos: cursor_pos,
-                name: None,
-            },
-        ],
-        expected,
+        &mut definition_replacements(cursor_pos),
+        expected.into(),
         cmp,
     )
 }
 
+fn definition_replacements(cursor_pos: Position) -> Vec<LuaReplacement> {
+    vec![
+        LuaReplacement::ParamTextDocument,
+        LuaReplacement::ParamPosition {
+            pos: cursor_pos,
+            name: None,
+        },
+    ]
+}
+
 /// Benchmarks the server's response time to a [`textDocument/definition`] request
 ///
 /// - `cursor_pos`: The position of the cursor when the request is issued. Passed
@@ -919,8 +2090,7 @@ pub fn benchmark_definition(
     })
 }
 
-pub type DiagnosticComparator =
-    fn(&DocumentDiagnosticReport, &DocumentDiagnosticReport, &TestCase) -> bool;
+pub type DiagnosticComparator = Comparator<DocumentDiagnosticReport>;
 
 /// Tests the server's response to a [`textDocument/diagnostic`] request
 ///
@@ -973,7 +2143,7 @@ pub fn test_diagnostic(
                 json: previous_result_id_json,
             },
         ],
-        Some(expected),
+        Expected::Value(expected),
         cmp,
     )
 }
@@ -999,18 +2169,90 @@ pub fn benchmark_diagnostic(
     previous_result_id: Option<&str>,
 ) -> Result<Vec<Duration>, BenchmarkError> {
     benchmark(test_case, config, || {
-        test_diagnostic(
-            test_case,
-            identifier,
-            previous_result_id,
-            None,
-            &DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport::default()),
-        )
+        test_diagnostic(
+            test_case,
+            identifier,
+            previous_result_id,
+            None,
+            &DocumentDiagnosticReport::Full(RelatedFullDocumentDiagnosticReport::default()),
+        )
+    })
+}
+
+/// Tests the server's support for unchanged [`textDocument/diagnostic`] reports. A full
+/// diagnostic request is issued first to obtain a `result_id`, then the request is
+/// repeated with that `previous_result_id`. The test passes if the server replies
+/// with an `Unchanged` report whose `result_id` is `expected_unchanged_result_id`.
+///
+/// - `expected_unchanged_result_id`: The `result_id` the server is expected to return
+///   in its `Unchanged` report
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the expected results don't match,
+/// or some other failure occurs
+///
+/// [`textDocument/diagnostic`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_diagnostic
+#[allow(clippy::result_large_err)]
+pub fn test_diagnostic_unchanged(
+    test_case: &TestCase,
+    expected_unchanged_result_id: &str,
+) -> TestResult<(), DocumentDiagnosticReport> {
+    let expected = DocumentDiagnosticReport::Unchanged(RelatedUnchangedDocumentDiagnosticReport {
+        related_documents: None,
+        unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport {
+            result_id: expected_unchanged_result_id.to_string(),
+        },
+    });
+    // Only the report's kind and `result_id` are part of the contract
+    let cmp = DiagnosticComparator::new(|expected, actual, _| match (expected, actual) {
+        (
+            DocumentDiagnosticReport::Unchanged(expected),
+            DocumentDiagnosticReport::Unchanged(actual),
+        ) => {
+            expected.unchanged_document_diagnostic_report.result_id
+                == actual.unchanged_document_diagnostic_report.result_id
+        }
+        _ => false,
+    });
+    collect_results(
+        test_case,
+        TestType::DiagnosticUnchanged,
+        &mut vec![
+            LuaReplacement::ParamTextDocument,
+            LuaReplacement::Other {
+                from: "REUSE_RESULT_ID",
+                to: "true".to_string(),
+            },
+        ],
+        Expected::Value(&expected),
+        Some(cmp),
+    )
+}
+
+/// Benchmarks the server's response time to a [`textDocument/diagnostic`] request
+/// issued with the `previous_result_id` of a prior full report. The reported
+/// time only covers the request issued with `previous_result_id`.
+///
+/// - `expected_unchanged_result_id`: The `result_id` the server is expected to return
+///   in its `Unchanged` report
+///
+/// # Errors
+///
+/// Returns [`BenchmarkError`] if the test case is invalid or if benchmarking fails
+///
+/// [`textDocument/diagnostic`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_diagnostic
+pub fn benchmark_diagnostic_unchanged(
+    test_case: &TestCase,
+    config: BenchmarkConfig,
+    expected_unchanged_result_id: &str,
+) -> Result<Vec<Duration>, BenchmarkError> {
+    benchmark(test_case, config, || {
+        test_diagnostic_unchanged(test_case, expected_unchanged_result_id)
     })
 }
 
-pub type DocumentColorComparator =
-    fn(&Vec<ColorInformation>, &Vec<ColorInformation>, &TestCase) -> bool;
+pub type DocumentColorComparator = Comparator<Vec<ColorInformation>>;
 
 /// Tests the server's response to a [`textDocument/documentColor`] request
 ///
@@ -1032,7 +2274,7 @@ pub fn test_document_color(
         test_case,
         TestType::DocumentColor,
         &mut vec![LuaReplacement::ParamTextDocument],
-        Some(expected),
+        Expected::Value(expected),
         cmp,
     )
 }
@@ -1053,8 +2295,7 @@ pub fn benchmark_document_color(
     })
 }
 
-pub type DocumentHighlightComparator =
-    fn(&Vec<DocumentHighlight>, &Vec<DocumentHighlight>, &TestCase) -> bool;
+pub type DocumentHighlightComparator = Comparator<Vec<DocumentHighlight>>;
 
 /// Tests the server's response to a [`textDocument/documentHighlight`] request
 ///
@@ -1069,11 +2310,11 @@ pub type DocumentHighlightComparator =
 /// or some other failure occurs
 ///
 /// [`textDocument/documentHighlight`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentHighlight
-pub fn test_document_highlight(
+pub fn test_document_highlight<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<DocumentHighlightComparator>,
-    expected: Option<&Vec<DocumentHighlight>>,
+    expected: impl Into<Expected<'a, Vec<DocumentHighlight>>>,
 ) -> TestResult<(), Vec<DocumentHighlight>> {
     collect_results(
         test_case,
@@ -1085,7 +2326,7 @@ pub fn test_document_highlight(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1110,7 +2351,7 @@ pub fn benchmark_document_highlight(
     })
 }
 
-pub type DocumentLinkComparator = fn(&Vec<DocumentLink>, &Vec<DocumentLink>, &TestCase) -> bool;
+pub type DocumentLinkComparator = Comparator<Vec<DocumentLink>>;
 
 /// Tests the server's response to a [`textDocument/documentLink`] request
 ///
@@ -1123,16 +2364,16 @@ pub type DocumentLinkComparator = fn(&Vec<DocumentLink>, &Vec<DocumentLink>, &Te
 /// or some other failure occurs
 ///
 /// [`textDocument/documentLink`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentLink
-pub fn test_document_link(
+pub fn test_document_link<'a>(
     test_case: &TestCase,
     cmp: Option<DocumentLinkComparator>,
-    expected: Option<&Vec<DocumentLink>>,
+    expected: impl Into<Expected<'a, Vec<DocumentLink>>>,
 ) -> TestResult<(), Vec<DocumentLink>> {
     collect_results(
         test_case,
         TestType::DocumentLink,
         &mut vec![LuaReplacement::ParamTextDocument],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1153,7 +2394,7 @@ pub fn benchmark_document_link(
     })
 }
 
-pub type DocumentLinkResolveComparator = fn(&DocumentLink, &DocumentLink, &TestCase) -> bool;
+pub type DocumentLinkResolveComparator = Comparator<DocumentLink>;
 
 /// Tests the server's response to a [`documentLink/resolve`] request
 ///
@@ -1172,11 +2413,11 @@ pub type DocumentLinkResolveComparator = fn(&DocumentLink, &DocumentLink, &TestC
 ///
 /// [`documentLink/resolve`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#documentLink_resolve
 #[allow(clippy::result_large_err)]
-pub fn test_document_link_resolve(
+pub fn test_document_link_resolve<'a>(
     test_case: &TestCase,
     params: &DocumentLink,
     cmp: Option<DocumentLinkResolveComparator>,
-    expected: Option<&DocumentLink>,
+    expected: impl Into<Expected<'a, DocumentLink>>,
 ) -> TestResult<(), DocumentLink> {
     let document_link_json =
         serde_json::to_string_pretty(params).expect("JSON serialization of `params` failed");
@@ -1188,7 +2429,7 @@ pub fn test_document_link_resolve(
             fields: vec!["range", "target", "tooltip", "data"],
             json: document_link_json,
         }],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1217,8 +2458,7 @@ pub fn benchmark_document_link_resolve(
     })
 }
 
-pub type DocumentSymbolComparator =
-    fn(&DocumentSymbolResponse, &DocumentSymbolResponse, &TestCase) -> bool;
+pub type DocumentSymbolComparator = Comparator<DocumentSymbolResponse>;
 
 /// Tests the server's response to a [`textDocument/documentSymbol`] request
 ///
@@ -1239,16 +2479,16 @@ pub type DocumentSymbolComparator =
 /// or some other failure occurs
 ///
 /// [`textDocument/documentSymbol`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_documentSymbol
-pub fn test_document_symbol(
+pub fn test_document_symbol<'a>(
     test_case: &TestCase,
     cmp: Option<DocumentSymbolComparator>,
-    expected: Option<&DocumentSymbolResponse>,
+    expected: impl Into<Expected<'a, DocumentSymbolResponse>>,
 ) -> TestResult<(), DocumentSymbolResponse> {
     collect_results(
         test_case,
         TestType::DocumentSymbol,
         &mut vec![LuaReplacement::ParamTextDocument],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1270,12 +2510,13 @@ pub fn benchmark_document_symbol(
     })
 }
 
-pub type FoldingRangeComparator = fn(&Vec<FoldingRange>, &Vec<FoldingRange>, &TestCase) -> bool;
+pub type FoldingRangeComparator = Comparator<Vec<FoldingRange>>;
 
 /// Tests the server's response to a [`textDocument/foldingRange`] request
 ///
 /// - `cmp`: An optional custom comparator function that can be used to determine equality
-///   between the expected and actual results.
+///   between the expected and actual results. [`compare::folding_covers`] is
+///   recommended for most tests, as it ignores character offsets and kinds.
 ///
 /// # Errors
 ///
@@ -1283,16 +2524,16 @@ pub type FoldingRangeComparator = fn(&Vec<FoldingRange>, &Vec<FoldingRange>, &Te
 /// or some other failure occurs
 ///
 /// [`textDocument/foldingRange`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_foldingRange
-pub fn test_folding_range(
+pub fn test_folding_range<'a>(
     test_case: &TestCase,
     cmp: Option<FoldingRangeComparator>,
-    expected: Option<&Vec<FoldingRange>>,
+    expected: impl Into<Expected<'a, Vec<FoldingRange>>>,
 ) -> TestResult<(), Vec<FoldingRange>> {
     collect_results(
         test_case,
         TestType::FoldingRange,
         &mut vec![LuaReplacement::ParamTextDocument],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1314,18 +2555,10 @@ pub fn benchmark_folding_range(
 }
 
 fn default_format_opts() -> FormattingOptions {
-    FormattingOptions {
-        tab_size: 4,
-        insert_spaces: true,
-        properties: HashMap::new(),
-        trim_trailing_whitespace: Some(true),
-        insert_final_newline: Some(true),
-        trim_final_newlines: Some(true),
-    }
+    FormattingOptionsBuilder::default().build()
 }
 
-pub type FormattingComparator =
-    fn(&StateOrResponse<Vec<TextEdit>>, &StateOrResponse<Vec<TextEdit>>, &TestCase) -> bool;
+pub type FormattingComparator = Comparator<StateOrResponse<Vec<TextEdit>>>;
 
 /// Tests the server's response to a [`textDocument/formatting`] request.
 ///
@@ -1382,6 +2615,16 @@ pub fn test_formatting(
             cmp,
             state.to_string(),
         )),
+        Some(StateOrResponse::Both { edits, state }) => test_formatting_both(
+            test_case,
+            TestType::Formatting,
+            options_json,
+            cmp,
+            EditsAndState {
+                edits: edits.clone(),
+                state: state.clone(),
+            },
+        ),
         None => to_parent_err_type(test_formatting_resp(
             test_case,
             TestType::Formatting,
@@ -1427,6 +2670,20 @@ pub fn benchmark_formatting(
     })
 }
 
+/// Compares formatting results with `cmp`, or for exact equality if it isn't provided
+fn compare_formatting(
+    cmp: Option<&FormattingComparator>,
+    expected: &StateOrResponse<Vec<TextEdit>>,
+    actual: &StateOrResponse<Vec<TextEdit>>,
+    test_case: &TestCase,
+) -> Result<(), Option<String>> {
+    match cmp {
+        Some(cmp) => cmp.compare(expected, actual, test_case),
+        None if expected == actual => Ok(()),
+        None => Err(None),
+    }
+}
+
 /// Performs the test for [`test_formatting`] when the expected result is `Some(Vec<TextEdit>)` or
 /// `None`.
 fn test_formatting_resp(
@@ -1436,15 +2693,13 @@ fn test_formatting_resp(
     cmp: Option<FormattingComparator>,
     expected: Option<&Vec<TextEdit>>,
 ) -> TestResult<(), Vec<TextEdit>> {
-    let outer_cmp =
-        |expected: &Vec<TextEdit>, actual: &Vec<TextEdit>, test_case: &TestCase| -> bool {
+    let outer_cmp = Comparator::wrap(
+        move |expected: &Vec<TextEdit>, actual: &Vec<TextEdit>, test_case: &TestCase| {
             let result_expected = StateOrResponse::Response(expected.clone());
             let result_actual = StateOrResponse::Response(actual.clone());
-            cmp.as_ref().map_or_else(
-                || result_expected == result_actual,
-                |cmp_fn| cmp_fn(&result_expected, &result_actual, test_case),
-            )
-        };
+            compare_formatting(cmp.as_ref(), &result_expected, &result_actual, test_case)
+        },
+    );
     collect_results(
         test_case,
         test_type,
@@ -1453,17 +2708,87 @@ fn test_formatting_resp(
                 from: "INVOKE_ACTION",
                 to: "false".to_string(),
             },
+            LuaReplacement::Other {
+                from: "RECORD_STATE",
+                to: "false".to_string(),
+            },
             LuaReplacement::ParamTextDocument,
             LuaReplacement::ParamDirect {
                 name: "options",
                 json: options_json,
             },
         ],
-        expected,
-        Some(&outer_cmp),
+        expected.into(),
+        Some(outer_cmp),
     )
 }
 
+/// Performs the test for [`test_formatting`] when the expected result is both
+/// `Vec<TextEdit>` and `String`.
+#[allow(clippy::needless_pass_by_value)]
+fn test_formatting_both(
+    test_case: &TestCase,
+    test_type: TestType,
+    options_json: String,
+    cmp: Option<FormattingComparator>,
+    expected: EditsAndState,
+) -> TestResult<(), StateOrResponse<Vec<TextEdit>>> {
+    reject_golden(test_case, "test_formatting")?;
+    let outer_cmp = Comparator::wrap(
+        move |expected: &EditsAndState, actual: &EditsAndState, test_case: &TestCase| {
+            let result_expected = StateOrResponse::from(expected.clone());
+            let result_actual = StateOrResponse::from(actual.clone());
+            compare_formatting(cmp.as_ref(), &result_expected, &result_actual, test_case)
+        },
+    );
+    let result = collect_results(
+        test_case,
+        test_type,
+        &mut vec![
+            LuaReplacement::Other {
+                from: "INVOKE_ACTION",
+                to: "false".to_string(),
+            },
+            LuaReplacement::Other {
+                from: "RECORD_STATE",
+                to: "true".to_string(),
+            },
+            LuaReplacement::ParamTextDocument,
+            LuaReplacement::ParamDirect {
+                name: "options",
+                json: options_json,
+            },
+        ],
+        Expected::Value(&expected),
+        Some(outer_cmp),
+    );
+    // `EditsAndState` is an implementation detail, map it to the public type
+    result.map_err(edits_and_state_error)
+}
+
+/// Maps an error involving [`EditsAndState`] to the public [`StateOrResponse`] type
+fn edits_and_state_error(
+    error: TestError<EditsAndState>,
+) -> TestError<StateOrResponse<Vec<TextEdit>>> {
+    match error {
+        TestError::ResponseMismatch(ResponseMismatchError {
+            test_id,
+            expected,
+            actual,
+            reason,
+        }) => TestError::ResponseMismatch(ResponseMismatchError {
+            test_id,
+            expected: expected.map(StateOrResponse::from),
+            actual: actual.map(StateOrResponse::from),
+            reason,
+        }),
+        TestError::TestExecution(e) => TestError::TestExecution(e),
+        TestError::TestSetup(e) => TestError::TestSetup(e),
+        TestError::NvimVersions(e) => TestError::NvimVersions(e.map(edits_and_state_error)),
+        TestError::JsonMismatch(e) => TestError::JsonMismatch(e),
+    }
+}
+
 /// Performs the test for [`test_formatting`] when the expected result is `String`.
 #[allow(clippy::needless_pass_by_value)]
 fn test_formatting_state(
@@ -1473,14 +2798,14 @@ fn test_formatting_state(
     cmp: Option<FormattingComparator>,
     expected: String,
 ) -> TestResult<(), String> {
-    let outer_cmp = |expected: &String, actual: &String, test_case: &TestCase| -> bool {
-        let result_expected = StateOrResponse::State(expected.to_string());
-        let result_actual = StateOrResponse::State(actual.to_string());
-        cmp.as_ref().map_or_else(
-            || result_expected == result_actual,
-            |cmp_fn| cmp_fn(&result_expected, &result_actual, test_case),
-        )
-    };
+    reject_golden(test_case, "test_formatting")?;
+    let outer_cmp = Comparator::wrap(
+        move |expected: &String, actual: &String, test_case: &TestCase| {
+            let result_expected = StateOrResponse::State(expected.to_string());
+            let result_actual = StateOrResponse::State(actual.to_string());
+            compare_formatting(cmp.as_ref(), &result_expected, &result_actual, test_case)
+        },
+    );
     collect_results(
         test_case,
         test_type,
@@ -1489,6 +2814,10 @@ fn test_formatting_state(
                 from: "INVOKE_ACTION",
                 to: "true".to_string(),
             },
+            LuaReplacement::Other {
+                from: "RECORD_STATE",
+                to: "false".to_string(),
+            },
             LuaReplacement::Other {
                 from: "INVOKE_FN",
                 to: "vim.lsp.buf.format".to_string(),
@@ -1502,12 +2831,12 @@ fn test_formatting_state(
                 json: "false".to_string(),
             },
         ],
-        Some(&expected),
-        Some(&outer_cmp),
+        Expected::Value(&expected),
+        Some(outer_cmp),
     )
 }
 
-pub type HoverComparator = fn(&Hover, &Hover, &TestCase) -> bool;
+pub type HoverComparator = Comparator<Hover>;
 
 /// Tests the server's response to a [`textDocument/hover`] request
 ///
@@ -1523,27 +2852,31 @@ pub type HoverComparator = fn(&Hover, &Hover, &TestCase) -> bool;
 ///
 /// [`textDocument/hover`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_hover
 #[allow(clippy::result_large_err)]
-pub fn test_hover(
+pub fn test_hover<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<HoverComparator>,
-    expected: Option<&Hover>,
+    expected: impl Into<Expected<'a, Hover>>,
 ) -> TestResult<(), Hover> {
     collect_results(
         test_case,
         TestType::Hover,
-        &mut vec![
-            LuaReplacement::ParamTextDocument,
-            LuaReplacement::ParamPosition {
-                pos: cursor_pos,
-                name: None,
-            },
-        ],
-        expected,
+        &mut hover_replacements(cursor_pos),
+        expected.into(),
         cmp,
     )
 }
 
+fn hover_replacements(cursor_pos: Position) -> Vec<LuaReplacement> {
+    vec![
+        LuaReplacement::ParamTextDocument,
+        LuaReplacement::ParamPosition {
+            pos: cursor_pos,
+            name: None,
+        },
+    ]
+}
+
 /// Benchmarks the server's response time to a [`textDocument/hover`] request
 ///
 /// - `cursor_pos`: The position of the cursor when the request is issued. Passed
@@ -1564,8 +2897,31 @@ pub fn benchmark_hover(
     })
 }
 
-pub type ImplementationComparator =
-    fn(&GotoImplementationResponse, &GotoImplementationResponse, &TestCase) -> bool;
+/// Benchmarks the server's response time to [`textDocument/hover`] requests issued at
+/// each of `positions`. Iterations cycle through `positions` in order, so each position
+/// receives roughly the same number of requests. This reveals position-dependent costs
+/// that benchmarking a single position would hide.
+///
+/// - `positions`: The positions of the cursor when the requests are issued. Passed
+///   to the client via the request's [`HoverParams`]
+///
+/// # Errors
+///
+/// Returns [`BenchmarkError`] if the test case is invalid, if `positions` is empty,
+/// or if benchmarking fails
+///
+/// [`textDocument/hover`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_hover
+pub fn benchmark_hover_positions(
+    test_case: &TestCase,
+    config: BenchmarkConfig,
+    positions: &[Position],
+) -> Result<PositionBenchmarkStats, BenchmarkError> {
+    benchmark_positions(test_case, config, positions, |cursor_pos| {
+        test_hover(test_case, cursor_pos, None, None)
+    })
+}
+
+pub type ImplementationComparator = Comparator<GotoImplementationResponse>;
 
 /// Tests the server's response to a [`textDocument/implementation`] request
 ///
@@ -1589,11 +2945,11 @@ pub type ImplementationComparator =
 ///
 /// [`textDocument/implementation`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_implementation
 #[allow(clippy::result_large_err)]
-pub fn test_implementation(
+pub fn test_implementation<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<ImplementationComparator>,
-    expected: Option<&GotoImplementationResponse>,
+    expected: impl Into<Expected<'a, GotoImplementationResponse>>,
 ) -> TestResult<(), GotoImplementationResponse> {
     collect_results(
         test_case,
@@ -1605,7 +2961,7 @@ pub fn test_implementation(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1631,8 +2987,7 @@ pub fn benchmark_implementation(
     })
 }
 
-pub type IncomingCallsComparator =
-    fn(&Vec<CallHierarchyIncomingCall>, &Vec<CallHierarchyIncomingCall>, &TestCase) -> bool;
+pub type IncomingCallsComparator = Comparator<Vec<CallHierarchyIncomingCall>>;
 
 /// Tests the server's response to a [`callHierarchy/incomingCalls`] request
 ///
@@ -1650,11 +3005,11 @@ pub type IncomingCallsComparator =
 /// Panics if JSON serialization of `call_item` fails
 ///
 /// [`callHierarchy/incomingCalls`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#callHierarchy_incomingCalls
-pub fn test_incoming_calls(
+pub fn test_incoming_calls<'a>(
     test_case: &TestCase,
     call_item: &CallHierarchyItem,
     cmp: Option<IncomingCallsComparator>,
-    expected: Option<&Vec<CallHierarchyIncomingCall>>,
+    expected: impl Into<Expected<'a, Vec<CallHierarchyIncomingCall>>>,
 ) -> TestResult<(), Vec<CallHierarchyIncomingCall>> {
     let call_item_json =
         serde_json::to_string_pretty(call_item).expect("JSON serialization of `call_item` failed");
@@ -1665,7 +3020,7 @@ pub fn test_incoming_calls(
             name: "item",
             json: call_item_json,
         }],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1693,7 +3048,7 @@ pub fn benchmark_incoming_calls(
     })
 }
 
-pub type InlayHintComparator = fn(&Vec<InlayHint>, &Vec<InlayHint>, &TestCase) -> bool;
+pub type InlayHintComparator = Comparator<Vec<InlayHint>>;
 
 /// Tests the server's response to a [`textDocument/inlayHint`] request
 ///
@@ -1707,11 +3062,11 @@ pub type InlayHintComparator = fn(&Vec<InlayHint>, &Vec<InlayHint>, &TestCase) -
 /// or some other failure occurs
 ///
 /// [`textDocument/inlayHint`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_inlayHint
-pub fn test_inlay_hint(
+pub fn test_inlay_hint<'a>(
     test_case: &TestCase,
     range: Range,
     cmp: Option<InlayHintComparator>,
-    expected: Option<&Vec<InlayHint>>,
+    expected: impl Into<Expected<'a, Vec<InlayHint>>>,
 ) -> TestResult<(), Vec<InlayHint>> {
     collect_results(
         test_case,
@@ -1720,7 +3075,7 @@ pub fn test_inlay_hint(
             LuaReplacement::ParamTextDocument,
             LuaReplacement::ParamRange(range),
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1744,8 +3099,38 @@ pub fn benchmark_inlay_hint(
     })
 }
 
-pub type LinkedEditingRangeComparator =
-    fn(&LinkedEditingRanges, &LinkedEditingRanges, &TestCase) -> bool;
+/// Tests the server's response to a [`textDocument/inlayHint`] request spanning the
+/// lines visible in Neovim's window, rather than a fixed range. The visible lines
+/// depend on the test case's [`viewport`](TestCase::viewport), which makes this useful
+/// for testing servers that optimize their responses for the visible range.
+///
+/// - `cmp`: An optional custom comparator function that can be used to determine equality
+///   between the expected and actual results.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the expected results don't match,
+/// or some other failure occurs
+///
+/// [`textDocument/inlayHint`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_inlayHint
+pub fn test_inlay_hint_visible<'a>(
+    test_case: &TestCase,
+    cmp: Option<InlayHintComparator>,
+    expected: impl Into<Expected<'a, Vec<InlayHint>>>,
+) -> TestResult<(), Vec<InlayHint>> {
+    collect_results(
+        test_case,
+        TestType::InlayHint,
+        &mut vec![
+            LuaReplacement::ParamTextDocument,
+            LuaReplacement::ParamVisibleRange,
+        ],
+        expected.into(),
+        cmp,
+    )
+}
+
+pub type LinkedEditingRangeComparator = Comparator<LinkedEditingRanges>;
 
 /// Tests the server's response to a [`textDocument/linkedEditingRange`] request
 ///
@@ -1764,11 +3149,11 @@ pub type LinkedEditingRangeComparator =
 /// Panics if JSON serialization of `call_item` fails
 ///
 /// [`textDocument/moniker`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_linkedEditingRange
-pub fn test_linked_editing_range(
+pub fn test_linked_editing_range<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<LinkedEditingRangeComparator>,
-    expected: Option<&LinkedEditingRanges>,
+    expected: impl Into<Expected<'a, LinkedEditingRanges>>,
 ) -> TestResult<(), LinkedEditingRanges> {
     collect_results(
         test_case,
@@ -1780,7 +3165,7 @@ pub fn test_linked_editing_range(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1809,7 +3194,7 @@ pub fn benchmark_linked_editing_range(
     })
 }
 
-pub type MonikerComparator = fn(&Vec<Moniker>, &Vec<Moniker>, &TestCase) -> bool;
+pub type MonikerComparator = Comparator<Vec<Moniker>>;
 
 /// Tests the server's response to a [`textDocument/moniker`] request
 ///
@@ -1828,11 +3213,11 @@ pub type MonikerComparator = fn(&Vec<Moniker>, &Vec<Moniker>, &TestCase) -> bool
 /// Panics if JSON serialization of `call_item` fails
 ///
 /// [`textDocument/moniker`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_moniker
-pub fn test_moniker(
+pub fn test_moniker<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<MonikerComparator>,
-    expected: Option<&Vec<Moniker>>,
+    expected: impl Into<Expected<'a, Vec<Moniker>>>,
 ) -> TestResult<(), Vec<Moniker>> {
     collect_results(
         test_case,
@@ -1844,7 +3229,7 @@ pub fn test_moniker(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1873,7 +3258,7 @@ pub fn benchmark_moniker(
     })
 }
 
-pub type OnTypeFormattingComparator = fn(&Vec<TextEdit>, &Vec<TextEdit>, &TestCase) -> bool;
+pub type OnTypeFormattingComparator = Comparator<Vec<TextEdit>>;
 
 /// Tests the server's response to a [`textDocument/onTypeFormatting`] request
 ///
@@ -1906,13 +3291,13 @@ pub type OnTypeFormattingComparator = fn(&Vec<TextEdit>, &Vec<TextEdit>, &TestCa
 /// Panics if JSON serialization of `character` or `options` fails
 ///
 /// [`callHierarchy/outgoingCalls`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_onTypeFormatting
-pub fn test_on_type_formatting(
+pub fn test_on_type_formatting<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     character: &str,
     options: Option<&FormattingOptions>,
     cmp: Option<OnTypeFormattingComparator>,
-    expected: Option<&Vec<TextEdit>>,
+    expected: impl Into<Expected<'a, Vec<TextEdit>>>,
 ) -> TestResult<(), Vec<TextEdit>> {
     let character_json =
         serde_json::to_string_pretty(character).expect("JSON serialization of `character` failed");
@@ -1940,7 +3325,7 @@ pub fn test_on_type_formatting(
                 json: options_json,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -1985,8 +3370,7 @@ pub fn benchmark_on_type_formatting(
     })
 }
 
-pub type OutgoingCallsComparator =
-    fn(&Vec<CallHierarchyOutgoingCall>, &Vec<CallHierarchyOutgoingCall>, &TestCase) -> bool;
+pub type OutgoingCallsComparator = Comparator<Vec<CallHierarchyOutgoingCall>>;
 
 /// Tests the server's response to a [`callHierarchy/outgoingCalls`] request
 ///
@@ -2004,11 +3388,11 @@ pub type OutgoingCallsComparator =
 /// Panics if JSON serialization of `call_item` fails
 ///
 /// [`callHierarchy/outgoingCalls`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#callHierarchy_outgoingCalls
-pub fn test_outgoing_calls(
+pub fn test_outgoing_calls<'a>(
     test_case: &TestCase,
     call_item: &CallHierarchyItem,
     cmp: Option<OutgoingCallsComparator>,
-    expected: Option<&Vec<CallHierarchyOutgoingCall>>,
+    expected: impl Into<Expected<'a, Vec<CallHierarchyOutgoingCall>>>,
 ) -> TestResult<(), Vec<CallHierarchyOutgoingCall>> {
     let call_item_json =
         serde_json::to_string_pretty(call_item).expect("JSON serialization of `call_item` failed");
@@ -2019,7 +3403,7 @@ pub fn test_outgoing_calls(
             name: "item",
             json: call_item_json,
         }],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2047,8 +3431,7 @@ pub fn benchmark_outgoing_calls(
     })
 }
 
-pub type PrepareCallHierarchyComparator =
-    fn(&Vec<CallHierarchyItem>, &Vec<CallHierarchyItem>, &TestCase) -> bool;
+pub type PrepareCallHierarchyComparator = Comparator<Vec<CallHierarchyItem>>;
 
 /// Tests the server's response to a [`textDocument/prepareCallHierarchy`] request
 ///
@@ -2063,11 +3446,11 @@ pub type PrepareCallHierarchyComparator =
 /// or some other failure occurs
 ///
 /// [`textDocument/prepareCallHierarchy`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_prepareCallHierarchy
-pub fn test_prepare_call_hierarchy(
+pub fn test_prepare_call_hierarchy<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<PrepareCallHierarchyComparator>,
-    expected: Option<&Vec<CallHierarchyItem>>,
+    expected: impl Into<Expected<'a, Vec<CallHierarchyItem>>>,
 ) -> TestResult<(), Vec<CallHierarchyItem>> {
     collect_results(
         test_case,
@@ -2079,7 +3462,7 @@ pub fn test_prepare_call_hierarchy(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2104,8 +3487,7 @@ pub fn benchmark_prepare_call_hierarchy(
     })
 }
 
-pub type PrepareRenameComparator =
-    fn(&PrepareRenameResponse, &PrepareRenameResponse, &TestCase) -> bool;
+pub type PrepareRenameComparator = Comparator<PrepareRenameResponse>;
 
 /// Tests the server's response to a [`textDocument/prepareRename`] request
 ///
@@ -2120,11 +3502,11 @@ pub type PrepareRenameComparator =
 /// or some other failure occurs
 ///
 /// [`textDocument/prepareCallHierarchy`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_prepareRename
-pub fn test_prepare_rename(
+pub fn test_prepare_rename<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<PrepareRenameComparator>,
-    expected: Option<&PrepareRenameResponse>,
+    expected: impl Into<Expected<'a, PrepareRenameResponse>>,
 ) -> TestResult<(), PrepareRenameResponse> {
     collect_results(
         test_case,
@@ -2136,7 +3518,7 @@ pub fn test_prepare_rename(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2161,8 +3543,7 @@ pub fn benchmark_prepare_rename(
     })
 }
 
-pub type PrepareTypeHierarchyComparator =
-    fn(&Vec<TypeHierarchyItem>, &Vec<TypeHierarchyItem>, &TestCase) -> bool;
+pub type PrepareTypeHierarchyComparator = Comparator<Vec<TypeHierarchyItem>>;
 
 /// Tests the server's response to a [`textDocument/prepareTypeHierarchy`] request
 ///
@@ -2184,12 +3565,12 @@ pub type PrepareTypeHierarchyComparator =
 /// Panics if JSON serialization of `items` fails
 ///
 /// [`textDocument/prepareTypeHierarchy`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_prepareTypeHierarchy
-pub fn test_prepare_type_hierarchy(
+pub fn test_prepare_type_hierarchy<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     items: Option<&Vec<TypeHierarchyItem>>,
     cmp: Option<PrepareTypeHierarchyComparator>,
-    expected: Option<&Vec<TypeHierarchyItem>>,
+    expected: impl Into<Expected<'a, Vec<TypeHierarchyItem>>>,
 ) -> TestResult<(), Vec<TypeHierarchyItem>> {
     // TODO: We may need to prepend the relative paths in `items` with the test case root
     let items_json = items.map_or_else(
@@ -2210,7 +3591,7 @@ pub fn test_prepare_type_hierarchy(
                 to: items_json,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2243,7 +3624,7 @@ pub fn benchmark_prepare_type_hierarchy(
     })
 }
 
-pub type PublishDiagnosticsComparator = fn(&Vec<Diagnostic>, &Vec<Diagnostic>, &TestCase) -> bool;
+pub type PublishDiagnosticsComparator = Comparator<Vec<Diagnostic>>;
 
 // NOTE: As far as I can tell, we can't directly accept a `PublishDiagnosticsParams` object,
 // since diagnostics are requested via a `textDocument/publishDiagnostics` notification instead
@@ -2255,7 +3636,9 @@ pub type PublishDiagnosticsComparator = fn(&Vec<Diagnostic>, &Vec<Diagnostic>, &
 /// Specifying a [`ServerStartType::Progress`] for a diagnostics test is overloaded to
 /// determine which [`DiagnosticChanged`] autocmd to use. This can be useful if your
 /// server sends multiple [`textDocument/publishDiagnostics`] notifications before
-/// fully analyzing a source file.
+/// fully analyzing a source file. Set [`TestCase::settle`] to also collect all diagnostics
+/// published within a fixed duration of that notification (or of the first one, without
+/// a [`ServerStartType::Progress`]).
 ///
 /// An `Option` is not used for `expected` because the LSP spec does not allow for
 /// nil parameters in the [`textDocument/publishDiagnostics`] notification
@@ -2279,12 +3662,192 @@ pub fn test_publish_diagnostics(
         test_case,
         TestType::PublishDiagnostics,
         &mut Vec::new(),
-        Some(expected),
+        Expected::Value(expected),
+        cmp,
+    )
+}
+
+/// Tests that the server clears its diagnostics once the source file is fixed. The
+/// diagnostics initially published for the source file are compared to
+/// `initial_expected`, after which `fix_edits` are applied to the buffer (notifying the
+/// server via `textDocument/didChange`). The diagnostics collected `settle` later must
+/// be empty.
+///
+/// The test case's timeout must allow for `settle` in addition to the initial
+/// diagnostics. Note that Neovim debounces `textDocument/didChange` notifications by
+/// 150ms, so `settle` should be longer than that.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the initial diagnostics don't
+/// match, the diagnostics aren't cleared, or some other failure occurs
+///
+/// [`textDocument/publishDiagnostics`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_publishDiagnostics
+pub fn test_diagnostics_cleared(
+    test_case: &TestCase,
+    initial_expected: &Vec<Diagnostic>,
+    fix_edits: &[TextEdit],
+    settle: Duration,
+) -> TestResult<(), Vec<Diagnostic>> {
+    reject_golden(test_case, "test_diagnostics_cleared")?;
+    let edits_json =
+        serde_json::to_string_pretty(fix_edits).expect("JSON serialization of `fix_edits` failed");
+    let fixed = fetch_results::<Vec<Diagnostic>>(
+        test_case,
+        TestType::PublishDiagnostics,
+        &mut vec![
+            LuaReplacement::Other {
+                from: "FIX_EDITS",
+                to: format!("vim.json.decode([[\n{edits_json}\n]])"),
+            },
+            LuaReplacement::Other {
+                from: "FIX_SETTLE_MS",
+                to: settle.as_millis().to_string(),
+            },
+        ],
+    )?
+    .unwrap_or_default();
+
+    let initial_path = test_case
+        .get_initial_diagnostics_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if !initial_path.exists() {
+        Err(no_results_error(test_case))?;
+    }
+    let initial: Vec<Diagnostic> =
+        parse_results(test_case, &read_raw_results(test_case, &initial_path)?)?;
+    let initial_expected = normalize_response(test_case, initial_expected.clone())?;
+    if !Vec::<Diagnostic>::approx_eq(&initial_expected, &initial) {
+        return Err(TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id.clone(),
+            expected: Some(initial_expected),
+            actual: Some(initial),
+            reason: Some("Diagnostics published before the fix was applied don't match".into()),
+        }));
+    }
+
+    if fixed.is_empty() {
+        return Ok(());
+    }
+    Err(TestError::ResponseMismatch(ResponseMismatchError {
+        test_id: test_case.test_id.clone(),
+        expected: Some(Vec::new()),
+        actual: Some(fixed),
+        reason: Some(format!(
+            "Diagnostics weren't cleared within {}ms of applying the fix",
+            settle.as_millis()
+        )),
+    }))
+}
+
+/// Tests the server's [`textDocument/publishDiagnostics`] notification for one of the
+/// test case's other files, rather than its source file. `for_file` is opened alongside
+/// the source file, and only the diagnostics published for it are collected. This
+/// allows testing diagnostics that propagate across files, i.e. an error in `for_file`
+/// caused by the contents of the source file.
+///
+/// - `for_file`: The path of the file to collect diagnostics for, relative to the
+///   test case's root directory. Must be one of the test case's files.
+/// - `cmp`: An optional custom comparator function that can be used to determine equality
+///   between the expected and actual results.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, `for_file` isn't one of the test
+/// case's files, the expected results don't match, or some other failure occurs
+///
+/// [`textDocument/publishDiagnostics`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_publishDiagnostics
+pub fn test_publish_diagnostics_for_file(
+    test_case: &TestCase,
+    for_file: &Path,
+    cmp: Option<PublishDiagnosticsComparator>,
+    expected: &Vec<Diagnostic>,
+) -> TestResult<(), Vec<Diagnostic>> {
+    let is_known_file = if test_case.reuse_dir.is_some() {
+        test_case
+            .get_source_file_path(for_file)
+            .is_ok_and(|path| path.is_file())
+    } else {
+        test_case.source_file.path == for_file
+            || test_case
+                .other_files
+                .iter()
+                .any(|file| file.path == for_file)
+    };
+    if !is_known_file {
+        Err(TestSetupError::UnknownFile(for_file.to_path_buf()))?;
+    }
+    let for_file_path = test_case
+        .get_source_file_path(for_file)
+        .map_err(|e| TestSetupError::IO(e.to_string()))?;
+    collect_results(
+        test_case,
+        TestType::PublishDiagnostics,
+        &mut vec![LuaReplacement::Other {
+            from: "DIAGNOSTICS_FILE",
+            to: format!("'{}'", for_file_path.display()),
+        }],
+        Expected::Value(expected),
         cmp,
     )
 }
 
-pub type RangeFormattingComparator = fn(&Vec<TextEdit>, &Vec<TextEdit>, &TestCase) -> bool;
+/// Tests the server's [`textDocument/publishDiagnostics`] notification, as with
+/// [`test_publish_diagnostics`], and additionally checks that the diagnostics were
+/// published no later than `within` after the server attached to the source file.
+///
+/// - `within`: The maximum acceptable latency between the server attaching to the
+///   source file and the diagnostics being published
+/// - `cmp`: An optional custom comparator function that can be used to determine equality
+///   between the expected and actual results.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the expected results don't match,
+/// the diagnostics' latency exceeds `within`, or some other failure occurs
+///
+/// [`textDocument/publishDiagnostics`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_publishDiagnostics
+pub fn test_diagnostics_latency(
+    test_case: &TestCase,
+    within: Duration,
+    cmp: Option<PublishDiagnosticsComparator>,
+    expected: &Vec<Diagnostic>,
+) -> TestResult<(), Vec<Diagnostic>> {
+    let io_error =
+        |e: std::io::Error| TestExecutionError::IO(test_case.test_id.clone(), e.to_string());
+    let latency_path = test_case.get_latency_file_path().map_err(io_error)?;
+    collect_results(
+        test_case,
+        TestType::PublishDiagnostics,
+        &mut vec![LuaReplacement::Other {
+            from: "LATENCY_PATH",
+            to: format!("'{}'", latency_path.display()),
+        }],
+        Expected::Value(expected),
+        cmp,
+    )?;
+    if !latency_path.exists() {
+        return Err(no_results_error(test_case).into());
+    }
+    let raw_latency = fs::read_to_string(&latency_path).map_err(io_error)?;
+    // `vim.uv.hrtime()` measures in ns
+    let latency = raw_latency
+        .trim()
+        .parse::<u64>()
+        .map(Duration::from_nanos)
+        .map_err(|e| TestExecutionError::Serialization(test_case.test_id.clone(), e.to_string()))?;
+    if latency > within {
+        Err(TestExecutionError::LatencyExceeded {
+            test_id: test_case.test_id.clone(),
+            latency,
+            within,
+        })?;
+    }
+
+    Ok(())
+}
+
+pub type RangeFormattingComparator = Comparator<Vec<TextEdit>>;
 
 /// Tests the server's response to a [`textDocument/rangeFormatting`] request
 ///
@@ -2315,12 +3878,12 @@ pub type RangeFormattingComparator = fn(&Vec<TextEdit>, &Vec<TextEdit>, &TestCas
 /// Panics if JSON serialization of `options` fails
 ///
 /// [`textDocument/rangeFormatting`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_rangeFormatting
-pub fn test_range_formatting(
+pub fn test_range_formatting<'a>(
     test_case: &TestCase,
     range: Range,
     options: Option<&FormattingOptions>,
     cmp: Option<RangeFormattingComparator>,
-    expected: Option<&Vec<TextEdit>>,
+    expected: impl Into<Expected<'a, Vec<TextEdit>>>,
 ) -> TestResult<(), Vec<TextEdit>> {
     let options_json = options
         .map_or_else(
@@ -2339,7 +3902,7 @@ pub fn test_range_formatting(
                 json: options_json,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2381,7 +3944,7 @@ pub fn benchmark_range_formatting(
     })
 }
 
-pub type ReferencesComparator = fn(&Vec<Location>, &Vec<Location>, &TestCase) -> bool;
+pub type ReferencesComparator = Comparator<Vec<Location>>;
 
 /// Tests the server's response to a [`textDocument/references`] request
 ///
@@ -2401,37 +3964,41 @@ pub type ReferencesComparator = fn(&Vec<Location>, &Vec<Location>, &TestCase) ->
 /// Panics if JSON serialization of `include_declaration` fails
 ///
 /// [`textDocument/references`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_references
-pub fn test_references(
+pub fn test_references<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     include_declaration: bool,
     cmp: Option<ReferencesComparator>,
-    expected: Option<&Vec<Location>>,
+    expected: impl Into<Expected<'a, Vec<Location>>>,
 ) -> TestResult<(), Vec<Location>> {
-    let include_decl_json = serde_json::to_string_pretty(&include_declaration)
-        .expect("JSON serialization of `include_declaration` failed");
     collect_results(
         test_case,
         TestType::References,
-        &mut vec![
-            LuaReplacement::ParamTextDocument,
-            LuaReplacement::ParamPosition {
-                pos: cursor_pos,
-                name: None,
-            },
-            LuaReplacement::ParamNested {
-                name: "context",
-                fields: vec![LuaReplacement::ParamDirect {
-                    name: "includeDeclaration",
-                    json: include_decl_json,
-                }],
-            },
-        ],
-        expected,
+        &mut references_replacements(cursor_pos, include_declaration),
+        expected.into(),
         cmp,
     )
 }
 
+fn references_replacements(cursor_pos: Position, include_declaration: bool) -> Vec<LuaReplacement> {
+    let include_decl_json = serde_json::to_string_pretty(&include_declaration)
+        .expect("JSON serialization of `include_declaration` failed");
+    vec![
+        LuaReplacement::ParamTextDocument,
+        LuaReplacement::ParamPosition {
+            pos: cursor_pos,
+            name: None,
+        },
+        LuaReplacement::ParamNested {
+            name: "context",
+            fields: vec![LuaReplacement::ParamDirect {
+                name: "includeDeclaration",
+                json: include_decl_json,
+            }],
+        },
+    ]
+}
+
 /// Benchmarks the server's response time to a [`textDocument/references`] request
 ///
 /// - `cursor_pos`: The position of the cursor when the request is issued. Passed
@@ -2458,7 +4025,7 @@ pub fn benchmark_references(
     })
 }
 
-pub type RenameComparator = fn(&WorkspaceEdit, &WorkspaceEdit, &TestCase) -> bool;
+pub type RenameComparator = Comparator<WorkspaceEdit>;
 
 /// Tests the server's response to a [`textDocument/rename`] request
 ///
@@ -2479,12 +4046,12 @@ pub type RenameComparator = fn(&WorkspaceEdit, &WorkspaceEdit, &TestCase) -> boo
 ///
 /// [`textDocument/rename`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_rename
 #[allow(clippy::result_large_err)]
-pub fn test_rename(
+pub fn test_rename<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     new_name: &str,
     cmp: Option<RenameComparator>,
-    expected: Option<&WorkspaceEdit>,
+    expected: impl Into<Expected<'a, WorkspaceEdit>>,
 ) -> TestResult<(), WorkspaceEdit> {
     let new_name_json =
         serde_json::to_string_pretty(new_name).expect("JSON serialization of `new_name` failed");
@@ -2502,7 +4069,7 @@ pub fn test_rename(
                 json: new_name_json,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2533,8 +4100,7 @@ pub fn benchmark_rename(
     })
 }
 
-pub type SelectionRangeComparator =
-    fn(&Vec<SelectionRange>, &Vec<SelectionRange>, &TestCase) -> bool;
+pub type SelectionRangeComparator = Comparator<Vec<SelectionRange>>;
 
 /// Tests the server's response to a [`textDocument/selectionRange`] request
 ///
@@ -2552,11 +4118,11 @@ pub type SelectionRangeComparator =
 /// Panics if JSON serialization of `positions` fails
 ///
 /// [`textDocument/typeDefinition`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_selectionRange
-pub fn test_selection_range(
+pub fn test_selection_range<'a>(
     test_case: &TestCase,
     positions: &Vec<Position>,
     cmp: Option<SelectionRangeComparator>,
-    expected: Option<&Vec<SelectionRange>>,
+    expected: impl Into<Expected<'a, Vec<SelectionRange>>>,
 ) -> TestResult<(), Vec<SelectionRange>> {
     let positions_json =
         serde_json::to_string_pretty(positions).expect("JSON serialization of `positions` failed");
@@ -2570,7 +4136,7 @@ pub fn test_selection_range(
                 json: positions_json,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2598,8 +4164,7 @@ pub fn benchmark_selection_range(
     })
 }
 
-pub type SemanticTokensFullComparator =
-    fn(&SemanticTokensResult, &SemanticTokensResult, &TestCase) -> bool;
+pub type SemanticTokensFullComparator = Comparator<SemanticTokensResult>;
 
 /// Tests the server's response to a [`textDocument/semanticTokens/full`] request
 ///
@@ -2620,16 +4185,16 @@ pub type SemanticTokensFullComparator =
 /// or some other failure occurs
 ///
 /// [`textDocument/semanticTokens/full`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens_fullRequest
-pub fn test_semantic_tokens_full(
+pub fn test_semantic_tokens_full<'a>(
     test_case: &TestCase,
     cmp: Option<SemanticTokensFullComparator>,
-    expected: Option<&SemanticTokensResult>,
+    expected: impl Into<Expected<'a, SemanticTokensResult>>,
 ) -> TestResult<(), SemanticTokensResult> {
     collect_results(
         test_case,
         TestType::SemanticTokensFull,
         &mut vec![LuaReplacement::ParamTextDocument],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2650,8 +4215,7 @@ pub fn benchmark_semantic_tokens_full(
     })
 }
 
-pub type SemanticTokensFullDeltaComparator =
-    fn(&SemanticTokensFullDeltaResult, &SemanticTokensFullDeltaResult, &TestCase) -> bool;
+pub type SemanticTokensFullDeltaComparator = Comparator<SemanticTokensFullDeltaResult>;
 
 /// Tests the server's response to a [`textDocument/semanticTokens/full/delta`] request
 ///
@@ -2678,16 +4242,16 @@ pub type SemanticTokensFullDeltaComparator =
 /// [`textDocument/semanticTokens/full`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens_fullRequest
 /// [`textDocument/semanticTokens/full/delta`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens_deltaRequest
 #[allow(clippy::result_large_err)]
-pub fn test_semantic_tokens_full_delta(
+pub fn test_semantic_tokens_full_delta<'a>(
     test_case: &TestCase,
     cmp: Option<SemanticTokensFullDeltaComparator>,
-    expected: Option<&SemanticTokensFullDeltaResult>,
+    expected: impl Into<Expected<'a, SemanticTokensFullDeltaResult>>,
 ) -> TestResult<(), SemanticTokensFullDeltaResult> {
     collect_results(
         test_case,
         TestType::SemanticTokensFullDelta,
         &mut vec![LuaReplacement::ParamTextDocument],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2713,8 +4277,7 @@ pub fn benchmark_semantic_tokens_full_delta(
     })
 }
 
-pub type SemanticTokensRangeComparator =
-    fn(&SemanticTokensRangeResult, &SemanticTokensRangeResult, &TestCase) -> bool;
+pub type SemanticTokensRangeComparator = Comparator<SemanticTokensRangeResult>;
 
 /// Tests the server's response to a [`textDocument/semanticTokens/range`] request
 ///
@@ -2736,11 +4299,11 @@ pub type SemanticTokensRangeComparator =
 /// or some other failure occurs
 ///
 /// [`textDocument/semanticTokens/range`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#semanticTokens_rangeRequest
-pub fn test_semantic_tokens_range(
+pub fn test_semantic_tokens_range<'a>(
     test_case: &TestCase,
     range: Range,
     cmp: Option<SemanticTokensRangeComparator>,
-    expected: Option<&SemanticTokensRangeResult>,
+    expected: impl Into<Expected<'a, SemanticTokensRangeResult>>,
 ) -> TestResult<(), SemanticTokensRangeResult> {
     collect_results(
         test_case,
@@ -2749,7 +4312,7 @@ pub fn test_semantic_tokens_range(
             LuaReplacement::ParamTextDocument,
             LuaReplacement::ParamRange(range),
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2773,7 +4336,7 @@ pub fn benchmark_semantic_tokens_range(
     })
 }
 
-pub type SignatureHelpComparator = fn(&SignatureHelp, &SignatureHelp, &TestCase) -> bool;
+pub type SignatureHelpComparator = Comparator<SignatureHelp>;
 
 /// Tests the server's response to a [`textDocument/signatureHelp`] request
 ///
@@ -2793,12 +4356,12 @@ pub type SignatureHelpComparator = fn(&SignatureHelp, &SignatureHelp, &TestCase)
 /// Panics if JSON serialization of `context` fails
 ///
 /// [`textDocument/signatureHelp`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_signatureHelp
-pub fn test_signature_help(
+pub fn test_signature_help<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     context: Option<&SignatureHelpContext>,
     cmp: Option<SignatureHelpComparator>,
-    expected: Option<&SignatureHelp>,
+    expected: impl Into<Expected<'a, SignatureHelp>>,
 ) -> TestResult<(), SignatureHelp> {
     let context_json = context.map_or_else(
         || "null".to_string(),
@@ -2806,21 +4369,117 @@ pub fn test_signature_help(
     );
     collect_results(
         test_case,
-        TestType::SignatureHelp,
+        TestType::SignatureHelp,
+        &mut vec![
+            LuaReplacement::ParamTextDocument,
+            LuaReplacement::ParamPosition {
+                pos: cursor_pos,
+                name: None,
+            },
+            LuaReplacement::ParamDirect {
+                name: "context",
+                json: context_json,
+            },
+        ],
+        expected.into(),
+        cmp,
+    )
+}
+
+/// Tests the server's response to a [`textDocument/signatureHelp`] request re-triggered
+/// while a prior signature help is active, i.e. after moving on to the next argument
+/// of a call. An initial request is issued at `first_pos`, then a second request is
+/// issued at `second_pos`, carrying the first response as the context's
+/// `active_signature_help`. The second response is compared against
+/// `expected_after_retrigger`.
+///
+/// If the server responds to the retrigger, the response's active parameter must also
+/// come after the first response's (see
+/// [`active_indices`](types::signature_help::active_indices)), whatever the form of
+/// `expected_after_retrigger`.
+///
+/// - `first_pos`: The position of the cursor when the initial request is issued
+/// - `second_pos`: The position of the cursor when the retrigger request is issued
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the expected results don't match,
+/// the active parameter didn't advance, or some other failure occurs
+///
+/// # Panics
+///
+/// Panics if JSON serialization of the retrigger context fails
+///
+/// [`textDocument/signatureHelp`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_signatureHelp
+pub fn test_signature_help_retrigger<'a>(
+    test_case: &TestCase,
+    first_pos: Position,
+    second_pos: Position,
+    expected_after_retrigger: impl Into<Expected<'a, SignatureHelp>>,
+) -> TestResult<(), SignatureHelp> {
+    let expected = expected_after_retrigger.into();
+    // The client fills in `active_signature_help` with the first response
+    let retrigger_context = SignatureHelpContextBuilder::new()
+        .trigger_kind(SignatureHelpTriggerKind::CONTENT_CHANGE)
+        .is_retrigger(true)
+        .build();
+    let context_json = serde_json::to_string_pretty(&retrigger_context)
+        .expect("JSON serialization of the retrigger context failed");
+    collect_results(
+        test_case,
+        TestType::SignatureHelpRetrigger,
         &mut vec![
             LuaReplacement::ParamTextDocument,
             LuaReplacement::ParamPosition {
-                pos: cursor_pos,
+                pos: first_pos,
                 name: None,
             },
-            LuaReplacement::ParamDirect {
-                name: "context",
-                json: context_json,
+            LuaReplacement::Other {
+                from: "RETRIGGER_POSITION",
+                to: format!(
+                    "{{ line = {}, character = {} }}",
+                    second_pos.line, second_pos.character
+                ),
+            },
+            LuaReplacement::Other {
+                from: "RETRIGGER_CONTEXT",
+                to: format!("vim.json.decode([[\n{context_json}\n]])"),
             },
         ],
         expected,
-        cmp,
-    )
+        None::<SignatureHelpComparator>,
+    )?;
+
+    // Check the actual retrigger response, regardless of the form of `expected`
+    let results_path = test_case
+        .get_results_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if !results_path.exists() {
+        return Ok(());
+    }
+    let after: SignatureHelp =
+        parse_results(test_case, &read_raw_results(test_case, &results_path)?)?;
+    let initial_path = test_case
+        .get_initial_signature_help_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if !initial_path.exists() {
+        Err(no_results_error(test_case))?;
+    }
+    let initial: SignatureHelp =
+        parse_results(test_case, &read_raw_results(test_case, &initial_path)?)?;
+    let (_, initial_param) = types::signature_help::active_indices(&initial);
+    let (_, retrigger_param) = types::signature_help::active_indices(&after);
+    if retrigger_param > initial_param {
+        return Ok(());
+    }
+    Err(TestError::ResponseMismatch(ResponseMismatchError {
+        test_id: test_case.test_id.clone(),
+        expected: None,
+        actual: Some(after),
+        reason: Some(format!(
+            "The active parameter didn't advance across the retrigger ({initial_param:?} -> {retrigger_param:?})"
+        )),
+    }))
 }
 
 /// Benchmarks the server's response time to a [`textDocument/signatureHelp`] request
@@ -2849,8 +4508,7 @@ pub fn benchmark_signature_help(
     })
 }
 
-pub type TypeDefinitionComparator =
-    fn(&GotoTypeDefinitionResponse, &GotoTypeDefinitionResponse, &TestCase) -> bool;
+pub type TypeDefinitionComparator = Comparator<GotoTypeDefinitionResponse>;
 
 /// Tests the server's response to a [`textDocument/typeDefinition`] request
 ///
@@ -2874,11 +4532,11 @@ pub type TypeDefinitionComparator =
 ///
 /// [`textDocument/typeDefinition`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocument_typeDefinition
 #[allow(clippy::result_large_err)]
-pub fn test_type_definition(
+pub fn test_type_definition<'a>(
     test_case: &TestCase,
     cursor_pos: Position,
     cmp: Option<TypeDefinitionComparator>,
-    expected: Option<&GotoTypeDefinitionResponse>,
+    expected: impl Into<Expected<'a, GotoTypeDefinitionResponse>>,
 ) -> TestResult<(), GotoTypeDefinitionResponse> {
     collect_results(
         test_case,
@@ -2890,7 +4548,7 @@ pub fn test_type_definition(
                 name: None,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -2915,8 +4573,7 @@ pub fn benchmark_type_definition(
     })
 }
 
-pub type WorkspaceDiagnosticComparator =
-    fn(&WorkspaceDiagnosticReport, &WorkspaceDiagnosticReport, &TestCase) -> bool;
+pub type WorkspaceDiagnosticComparator = Comparator<WorkspaceDiagnosticReport>;
 
 /// Tests the server's response to a [`workspace/diagnostic`] request
 ///
@@ -2963,7 +4620,7 @@ pub fn test_workspace_diagnostic(
                 json: previous_result_ids_json,
             },
         ],
-        Some(expected),
+        Expected::Value(expected),
         cmp,
     )
 }
@@ -2999,7 +4656,7 @@ pub fn benchmark_workspace_diagnostic(
     })
 }
 
-pub type WorkspaceExecuteCommandComparator = fn(&Value, &Value, &TestCase) -> bool;
+pub type WorkspaceExecuteCommandComparator = Comparator<Value>;
 
 /// Tests the server's response to a [`workspace/executeCommand`] request
 ///
@@ -3027,18 +4684,28 @@ pub type WorkspaceExecuteCommandComparator = fn(&Value, &Value, &TestCase) -> bo
 /// Panics if JSON serialization of `command` or `arguments` fails
 ///
 /// [`workspace/executeCommand`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_executeCommand
-pub fn test_workspace_execute_command(
+pub fn test_workspace_execute_command<'a>(
     test_case: &TestCase,
     commands: Option<&Vec<String>>,
     command: &str,
     arguments: Option<&Vec<Value>>,
     cmp: Option<WorkspaceExecuteCommandComparator>,
-    expected: Option<&Value>,
+    expected: impl Into<Expected<'a, Value>>,
 ) -> TestResult<(), Value> {
-    let command_str = commands.map_or_else(String::new, |cmds| {
-        cmds.iter()
-            .fold(String::new(), |accum, cmd| accum + &format!("\"{cmd}\",\n"))
-    });
+    collect_results(
+        test_case,
+        TestType::WorkspaceExecuteCommand,
+        &mut execute_command_replacements(commands, command, arguments),
+        expected.into(),
+        cmp,
+    )
+}
+
+fn execute_command_replacements(
+    commands: Option<&Vec<String>>,
+    command: &str,
+    arguments: Option<&Vec<Value>>,
+) -> Vec<LuaReplacement> {
     let command_json =
         serde_json::to_string_pretty(command).expect("JSON serialization of `command` failed");
     let arguments_json = arguments.map_or_else(
@@ -3047,30 +4714,28 @@ pub fn test_workspace_execute_command(
             serde_json::to_string_pretty(args).expect("JSON serialization of `arguments` failed")
         },
     );
-    collect_results(
-        test_case,
-        TestType::WorkspaceExecuteCommand,
-        &mut vec![
-            LuaReplacement::Other {
-                from: "COMMANDS",
-                to: command_str,
-            },
-            LuaReplacement::ParamDirect {
-                name: "command",
-                json: command_json,
-            },
-            LuaReplacement::ParamDirect {
-                name: "arguments",
-                json: arguments_json,
-            },
-            LuaReplacement::Other {
-                from: "INVOKE_ACTION",
-                to: false.to_string(),
-            },
-        ],
-        expected,
-        cmp,
-    )
+    vec![
+        LuaReplacement::Other {
+            from: "COMMANDS",
+            to: commands_str(commands),
+        },
+        LuaReplacement::ParamDirect {
+            name: "command",
+            json: command_json,
+        },
+        LuaReplacement::ParamDirect {
+            name: "arguments",
+            json: arguments_json,
+        },
+        LuaReplacement::Other {
+            from: "INVOKE_ACTION",
+            to: false.to_string(),
+        },
+        LuaReplacement::Other {
+            from: "RECORD_STATE",
+            to: false.to_string(),
+        },
+    ]
 }
 
 /// Benchmarks the server's response time to a [`workspace/executeCommand`] request
@@ -3103,8 +4768,437 @@ pub fn benchmark_workspace_execute_command(
     })
 }
 
-pub type WorkspaceSymbolComparator =
-    fn(&WorkspaceSymbolResponse, &WorkspaceSymbolResponse, &TestCase) -> bool;
+pub type CustomRequestComparator = Comparator<Value>;
+
+/// Tests the server's response to a request whose method isn't modeled by `lsp_types`,
+/// such as a server-specific extension. Any `file://` URIs within the response are
+/// cleaned in the same manner as other test types, so that paths within the test
+/// directory can be compared against relative paths.
+///
+/// - `method`: The request's method, i.e. `rust-analyzer/expandMacro`
+/// - `params`: A JSON object containing the request's parameters. Each of its fields
+///   is passed to the server as is
+/// - `cmp`: An optional custom comparator function that can be used to determine equality
+///   between the expected and actual results.
+///
+/// # Warnings
+///
+/// Responses of `serde_json::Value::Null` are equivalent to a lack of response. If you expect
+/// to receive a `null` response, you should use `None` as the expected value.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the expected results don't match,
+/// or some other failure occurs
+///
+/// # Panics
+///
+/// Panics if JSON serialization of `params` fails
+pub fn test_custom_request<'a>(
+    test_case: &TestCase,
+    method: &str,
+    params: &Value,
+    cmp: Option<CustomRequestComparator>,
+    expected: impl Into<Expected<'a, Value>>,
+) -> TestResult<(), Value> {
+    let params_json =
+        serde_json::to_string_pretty(params).expect("JSON serialization of `params` failed");
+    collect_results(
+        test_case,
+        TestType::Custom,
+        &mut vec![
+            LuaReplacement::Other {
+                from: "REQUEST_METHOD",
+                to: method.to_string(),
+            },
+            LuaReplacement::ParamDestructureAll { json: params_json },
+        ],
+        expected.into(),
+        cmp,
+    )
+}
+
+/// Tests that the server responds to a `test_type` request without error. Any
+/// non-error response, including `null`, is accepted. This provides coverage for
+/// methods without a meaningful return value, where the only thing to check is
+/// that the server handled the request.
+///
+/// - `test_type`: The request to issue. Test types whose responses are gathered by
+///   other means ([`TestType::PublishDiagnostics`], [`TestType::DiagnosticUnchanged`],
+///   and [`TestType::SemanticTokensFullDelta`]) aren't supported. For methods not
+///   modeled by `lsp_types`, use [`test_custom_request`] instead
+/// - `params`: A JSON object containing the request's parameters. Each of its fields
+///   is passed to the server as is
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the server responds with a
+/// `ResponseError`, or some other failure occurs (i.e. the timeout is exceeded)
+///
+/// # Panics
+///
+/// Panics if JSON serialization of `params` fails
+pub fn test_no_error(
+    test_case: &TestCase,
+    test_type: TestType,
+    params: &Value,
+) -> TestResult<(), Value> {
+    reject_golden(test_case, "test_no_error")?;
+    if !is_plain_request(test_type) {
+        Err(TestSetupError::UnsupportedTestType(test_type))?;
+    }
+    let params_json =
+        serde_json::to_string_pretty(params).expect("JSON serialization of `params` failed");
+    fetch_results::<Value>(
+        test_case,
+        test_type,
+        &mut vec![
+            LuaReplacement::ParamDestructureAll { json: params_json },
+            LuaReplacement::Other {
+                from: "INVOKE_ACTION",
+                to: false.to_string(),
+            },
+            LuaReplacement::Other {
+                from: "RECORD_STATE",
+                to: false.to_string(),
+            },
+        ],
+    )?;
+
+    let response_error_path = test_case
+        .get_response_error_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if response_error_path.exists() {
+        let response_error = fs::read_to_string(&response_error_path)
+            .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+        Err(TestExecutionError::ResponseError(
+            test_case.test_id.clone(),
+            response_error,
+        ))?;
+    }
+
+    Ok(())
+}
+
+/// The JSON-RPC error code for `MethodNotFound`
+const METHOD_NOT_FOUND_CODE: i64 = -32601;
+
+/// Tests that the server responds to a request with an unknown or unsupported method
+/// with a `MethodNotFound` (`-32601`) error, rather than crashing, hanging, or sending
+/// a result. Unlike [`test_custom_request`], no particular result is expected.
+///
+/// - `method`: The request's method. The request is sent with the source file's
+///   `textDocument` and a `position` at the start of the file as parameters
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the server responds with anything
+/// other than a `MethodNotFound` error, or some other failure occurs (i.e. the timeout
+/// is exceeded)
+pub fn test_method_not_found(test_case: &TestCase, method: &str) -> TestResult<(), Value> {
+    reject_golden(test_case, "test_method_not_found")?;
+    let results = fetch_results::<Value>(
+        test_case,
+        TestType::Custom,
+        &mut vec![
+            LuaReplacement::Other {
+                from: "REQUEST_METHOD",
+                to: method.to_string(),
+            },
+            LuaReplacement::ParamTextDocument,
+            LuaReplacement::ParamPosition {
+                pos: Position::default(),
+                name: None,
+            },
+        ],
+    )?;
+
+    let response_error_path = test_case
+        .get_response_error_file_path()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    let actual = if response_error_path.exists() {
+        let response_error = fs::read_to_string(&response_error_path)
+            .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+        let code = serde_json::from_str::<Value>(&response_error)
+            .ok()
+            .and_then(|err| err.get("code").and_then(Value::as_i64));
+        if code == Some(METHOD_NOT_FOUND_CODE) {
+            return Ok(());
+        }
+        response_error
+    } else {
+        results.map_or_else(
+            || "null".to_string(),
+            |result| serde_json::to_string_pretty(&result).unwrap_or_else(|_| result.to_string()),
+        )
+    };
+
+    Err(TestExecutionError::MethodFound {
+        test_id: test_case.test_id.clone(),
+        method: method.to_string(),
+        actual,
+    }
+    .into())
+}
+
+/// Indicates if `test_type` can be issued as a single request built from arbitrary
+/// JSON parameters, rather than requiring its own action
+const fn is_plain_request(test_type: TestType) -> bool {
+    !matches!(
+        test_type,
+        TestType::PublishDiagnostics
+            | TestType::Custom
+            | TestType::DiagnosticUnchanged
+            | TestType::LogMessage
+            | TestType::Progress
+            | TestType::SemanticTokensFullDelta
+    )
+}
+
+/// Tests that the server sends a [`window/logMessage`] or [`window/showMessage`]
+/// notification satisfying `matcher` in response to `trigger`. Messages received at
+/// any point before the test case's [`settle`](TestCase::settle) window elapses are
+/// considered, where the window begins once the trigger completes. If no settle
+/// duration is set, only messages received by the time the trigger completes are
+/// considered.
+///
+/// - `trigger`: The action expected to cause the server to send the message. When
+///   a request is issued, its response is ignored
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, no received message satisfies
+/// `matcher`, or some other failure occurs (i.e. the timeout is exceeded)
+///
+/// # Panics
+///
+/// Panics if JSON serialization of the trigger's `params` fails
+///
+/// [`window/logMessage`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#window_logMessage
+/// [`window/showMessage`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#window_showMessage
+pub fn test_expect_log_message(
+    test_case: &TestCase,
+    trigger: LogTrigger,
+    matcher: LogMatcher,
+) -> TestResult<(), Value> {
+    reject_golden(test_case, "test_expect_log_message")?;
+    let mut replacements = match trigger {
+        // Leaving the method empty skips issuing a request
+        LogTrigger::Open => vec![LuaReplacement::Other {
+            from: "REQUEST_METHOD",
+            to: String::new(),
+        }],
+        LogTrigger::Request(test_type, params) => {
+            if !is_plain_request(test_type) {
+                Err(TestSetupError::UnsupportedTestType(test_type))?;
+            }
+            let params_json = serde_json::to_string_pretty(params)
+                .expect("JSON serialization of `params` failed");
+            // Overrides the standard replacement, so the trigger's method is also the
+            // one polled for with `ServerStartType::AfterDidOpen`
+            vec![
+                LuaReplacement::Other {
+                    from: "REQUEST_METHOD",
+                    to: test_type.to_string(),
+                },
+                LuaReplacement::ParamDestructureAll { json: params_json },
+            ]
+        }
+    };
+    fetch_results::<Value>(test_case, TestType::LogMessage, &mut replacements)?;
+
+    let received = test_case
+        .read_messages()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if !received.iter().any(|message| matcher.matches(message)) {
+        Err(TestExecutionError::MissingLogMessage {
+            test_id: test_case.test_id.clone(),
+            matcher,
+            received,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Tests the trigger characters the server advertises for `which` in its response to
+/// `initialize`. The characters are compared as a set, so their order (and any
+/// duplicates) are ignored.
+///
+/// The test ends once the server is ready to service requests, without issuing one.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the advertised trigger characters
+/// don't match `expected`, or some other failure occurs (i.e. the timeout is exceeded)
+pub fn test_trigger_characters(
+    test_case: &TestCase,
+    which: TriggerKind,
+    expected: impl IntoIterator<Item = char>,
+) -> TestResult<(), Vec<String>> {
+    reject_golden(test_case, "test_trigger_characters")?;
+    let capabilities = fetch_server_capabilities(test_case)?;
+    let actual: Option<BTreeSet<String>> = capabilities.get(which.provider()).map(|provider| {
+        provider
+            .get("triggerCharacters")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect()
+    });
+    let expected: BTreeSet<String> = expected.into_iter().map(String::from).collect();
+    if actual.as_ref() == Some(&expected) {
+        return Ok(());
+    }
+
+    let reason = actual.as_ref().map_or_else(
+        || format!("The server doesn't advertise `{}`", which.provider()),
+        |actual| {
+            format!(
+                "Missing: {:?}, unexpected: {:?}",
+                expected.difference(actual).collect::<Vec<_>>(),
+                actual.difference(&expected).collect::<Vec<_>>()
+            )
+        },
+    );
+    Err(TestError::ResponseMismatch(ResponseMismatchError {
+        test_id: test_case.test_id.clone(),
+        expected: Some(expected.into_iter().collect()),
+        actual: actual.map(|actual| actual.into_iter().collect()),
+        reason: Some(reason),
+    }))
+}
+
+/// Starts the server without issuing a request, and returns the capabilities it
+/// advertised in its response to `initialize`
+pub(crate) fn fetch_server_capabilities(test_case: &TestCase) -> TestExecutionResult<Value> {
+    // Leaving the method empty skips issuing a request
+    fetch_results::<Value>(
+        test_case,
+        TestType::LogMessage,
+        &mut vec![LuaReplacement::Other {
+            from: "REQUEST_METHOD",
+            to: String::new(),
+        }],
+    )?;
+
+    test_case
+        .read_server_capabilities()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?
+        .ok_or_else(|| no_results_error(test_case))
+}
+
+/// Tests the `serverInfo` the server reports in its response to `initialize`. Only the
+/// provided fields are compared (`None` skips a field), so i.e. a server's version can
+/// be pinned to catch accidental downgrades without also pinning its name.
+///
+/// The test ends once the server is ready to service requests, without issuing one.
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the reported `serverInfo` doesn't
+/// match the provided fields, or some other failure occurs (i.e. the timeout is exceeded)
+pub fn test_server_info(
+    test_case: &TestCase,
+    expected_name: Option<&str>,
+    expected_version: Option<&str>,
+) -> TestResult<(), ServerInfo> {
+    reject_golden(test_case, "test_server_info")?;
+    // Leaving the method empty skips issuing a request
+    fetch_results::<Value>(
+        test_case,
+        TestType::LogMessage,
+        &mut vec![LuaReplacement::Other {
+            from: "REQUEST_METHOD",
+            to: String::new(),
+        }],
+    )?;
+
+    let actual = test_case
+        .read_server_info()
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?
+        .ok_or_else(|| no_results_error(test_case))?;
+    let is_match = actual.as_ref().map_or_else(
+        || expected_name.is_none() && expected_version.is_none(),
+        |info| {
+            expected_name.is_none_or(|name| info.name == name)
+                && expected_version.is_none_or(|version| info.version.as_deref() == Some(version))
+        },
+    );
+    if is_match {
+        return Ok(());
+    }
+
+    // Fields that weren't provided are taken from the actual response, so that only
+    // the compared fields show up in the diff
+    let expected = ServerInfo {
+        name: expected_name.map_or_else(
+            || {
+                actual
+                    .as_ref()
+                    .map(|info| info.name.clone())
+                    .unwrap_or_default()
+            },
+            str::to_string,
+        ),
+        version: expected_version
+            .map(str::to_string)
+            .or_else(|| actual.as_ref().and_then(|info| info.version.clone())),
+    };
+    Err(TestError::ResponseMismatch(ResponseMismatchError {
+        test_id: test_case.test_id.clone(),
+        expected: Some(expected),
+        reason: actual
+            .is_none()
+            .then(|| "The server didn't report a `serverInfo`".to_string()),
+        actual,
+    }))
+}
+
+/// Tests the `$/progress` notifications the server sends for `token`, i.e. while
+/// indexing a project. The test completes once the `end` message for `token` is
+/// received.
+///
+/// - `expected_monotonic`: If set, the percentages reported for `token` must never
+///   decrease, and the last one reported before the `end` message must be 100.
+///   Otherwise, only the completion of the progress is checked
+///
+/// # Errors
+///
+/// Returns [`TestError`] if the test case is invalid, the reported percentages are
+/// invalid, or some other failure occurs (i.e. the timeout is exceeded before `token`
+/// ends)
+pub fn test_indexing_progress(
+    test_case: &TestCase,
+    token: &str,
+    expected_monotonic: bool,
+) -> TestResult<(), Vec<WorkDoneProgress>> {
+    reject_golden(test_case, "test_indexing_progress")?;
+    let progress = fetch_results::<Vec<WorkDoneProgress>>(
+        test_case,
+        TestType::Progress,
+        &mut vec![LuaReplacement::Other {
+            from: "PROGRESS_TOKEN",
+            to: token.to_string(),
+        }],
+    )?
+    .unwrap_or_default();
+    if expected_monotonic {
+        check_progress_percentages(&progress).map_err(|reason| {
+            TestExecutionError::InvalidProgress {
+                test_id: test_case.test_id.clone(),
+                token: token.to_string(),
+                reason,
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+pub type WorkspaceSymbolComparator = Comparator<WorkspaceSymbolResponse>;
 
 /// Tests the server's response to a [`workspace/symbol`] request
 ///
@@ -3130,11 +5224,11 @@ pub type WorkspaceSymbolComparator =
 /// Panics if JSON serialization of `query` fails
 ///
 /// [`workspace/symbol`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_symbol
-pub fn test_workspace_symbol(
+pub fn test_workspace_symbol<'a>(
     test_case: &TestCase,
     query: &str,
     cmp: Option<WorkspaceSymbolComparator>,
-    expected: Option<&WorkspaceSymbolResponse>,
+    expected: impl Into<Expected<'a, WorkspaceSymbolResponse>>,
 ) -> TestResult<(), WorkspaceSymbolResponse> {
     let query_json =
         serde_json::to_string_pretty(query).expect("JSON serialization of `query` failed");
@@ -3148,7 +5242,7 @@ pub fn test_workspace_symbol(
                 json: query_json,
             },
         ],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -3176,8 +5270,7 @@ pub fn benchmark_workspace_symbol(
     })
 }
 
-pub type WorkspaceSymbolResolveComparator =
-    fn(&WorkspaceSymbol, &WorkspaceSymbol, &TestCase) -> bool;
+pub type WorkspaceSymbolResolveComparator = Comparator<WorkspaceSymbol>;
 
 /// Tests the server's response to a [`workspaceSymbol/resolve`] request
 ///
@@ -3212,7 +5305,7 @@ pub fn test_workspace_symbol_resolve(
             fields: vec!["name", "kind", "tags", "containerName", "location", "data"],
             json: params_json,
         }],
-        Some(expected),
+        Expected::Value(expected),
         cmp,
     )
 }
@@ -3255,7 +5348,7 @@ pub fn benchmark_workspace_symbol_resolve(
     })
 }
 
-pub type WorkspaceWillCreateFilesComparator = fn(&WorkspaceEdit, &WorkspaceEdit, &TestCase) -> bool;
+pub type WorkspaceWillCreateFilesComparator = Comparator<WorkspaceEdit>;
 
 /// Tests the server's response to a [`workspace/willCreateFiles`] request
 ///
@@ -3274,11 +5367,11 @@ pub type WorkspaceWillCreateFilesComparator = fn(&WorkspaceEdit, &WorkspaceEdit,
 ///
 /// [`workspace/willCreateFiles`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_willCreateFiles
 #[allow(clippy::result_large_err)]
-pub fn test_workspace_will_create_files(
+pub fn test_workspace_will_create_files<'a>(
     test_case: &TestCase,
     params: &CreateFilesParams,
     cmp: Option<WorkspaceWillCreateFilesComparator>,
-    expected: Option<&WorkspaceEdit>,
+    expected: impl Into<Expected<'a, WorkspaceEdit>>,
 ) -> TestResult<(), WorkspaceEdit> {
     let params_json =
         serde_json::to_string_pretty(params).expect("JSON serialization of `params` failed");
@@ -3290,7 +5383,7 @@ pub fn test_workspace_will_create_files(
             fields: vec!["files"],
             json: params_json,
         }],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -3318,7 +5411,7 @@ pub fn benchmark_workspace_will_create_files(
     })
 }
 
-pub type WorkspaceWillDeleteFilesComparator = fn(&WorkspaceEdit, &WorkspaceEdit, &TestCase) -> bool;
+pub type WorkspaceWillDeleteFilesComparator = Comparator<WorkspaceEdit>;
 
 /// Tests the server's response to a [`workspace/willDeleteFiles`] request
 ///
@@ -3337,11 +5430,11 @@ pub type WorkspaceWillDeleteFilesComparator = fn(&WorkspaceEdit, &WorkspaceEdit,
 ///
 /// [`workspace/willDeleteFiles`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_willDeleteFiles
 #[allow(clippy::result_large_err)]
-pub fn test_workspace_will_delete_files(
+pub fn test_workspace_will_delete_files<'a>(
     test_case: &TestCase,
     params: &DeleteFilesParams,
     cmp: Option<WorkspaceWillDeleteFilesComparator>,
-    expected: Option<&WorkspaceEdit>,
+    expected: impl Into<Expected<'a, WorkspaceEdit>>,
 ) -> TestResult<(), WorkspaceEdit> {
     let params_json =
         serde_json::to_string_pretty(params).expect("JSON serialization of `params` failed");
@@ -3353,7 +5446,7 @@ pub fn test_workspace_will_delete_files(
             fields: vec!["files"],
             json: params_json,
         }],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -3381,7 +5474,7 @@ pub fn benchmark_workspace_will_delete_files(
     })
 }
 
-pub type WorkspaceWillRenameFilesComparator = fn(&WorkspaceEdit, &WorkspaceEdit, &TestCase) -> bool;
+pub type WorkspaceWillRenameFilesComparator = Comparator<WorkspaceEdit>;
 
 /// Tests the server's response to a [`workspace/willRenameFiles`] request
 ///
@@ -3400,11 +5493,11 @@ pub type WorkspaceWillRenameFilesComparator = fn(&WorkspaceEdit, &WorkspaceEdit,
 ///
 /// [`workspace/willRenameFiles`]: https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#workspace_willRenameFiles
 #[allow(clippy::result_large_err)]
-pub fn test_workspace_will_rename_files(
+pub fn test_workspace_will_rename_files<'a>(
     test_case: &TestCase,
     params: &RenameFilesParams,
     cmp: Option<WorkspaceWillRenameFilesComparator>,
-    expected: Option<&WorkspaceEdit>,
+    expected: impl Into<Expected<'a, WorkspaceEdit>>,
 ) -> TestResult<(), WorkspaceEdit> {
     let params_json =
         serde_json::to_string_pretty(params).expect("JSON serialization of `params` failed");
@@ -3416,7 +5509,7 @@ pub fn test_workspace_will_rename_files(
             fields: vec!["files"],
             json: params_json,
         }],
-        expected,
+        expected.into(),
         cmp,
     )
 }
@@ -3443,3 +5536,176 @@ pub fn benchmark_workspace_will_rename_files(
         test_workspace_will_rename_files(test_case, params, None, None)
     })
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{
+        cap_timeout, check_suite_deadline, parse_suite_deadline, prepend_runtime_cmd,
+        read_raw_results,
+    };
+    use crate::types::{TestCase, TestExecutionError, TestFile, Utf8Policy};
+
+    #[test]
+    fn suite_deadline_parsing() {
+        assert_eq!(Some(Duration::from_secs(30)), parse_suite_deadline("30"));
+        assert_eq!(
+            Some(Duration::from_millis(1500)),
+            parse_suite_deadline(" 1.5\n")
+        );
+        for invalid in ["", "0", "-1", "inf", "NaN", "30s"] {
+            assert_eq!(None, parse_suite_deadline(invalid), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn raw_results_utf8_policy() {
+        let test_case = TestCase::new("server", TestFile::new("main.rs", ""));
+        let path = std::env::temp_dir().join(format!("lspresso-utf8-{}.json", test_case.test_id));
+        std::fs::write(&path, b"\"a\xffb\"").unwrap();
+
+        let err = read_raw_results(&test_case, &path).unwrap_err();
+        assert!(
+            matches!(&err, TestExecutionError::Utf8(_, msg) if msg.contains(&*path.to_string_lossy())),
+            "{err:?}"
+        );
+        let test_case = test_case.utf8_policy(Utf8Policy::Lossy);
+        assert_eq!(
+            "\"a\u{FFFD}b\"",
+            read_raw_results(&test_case, &path).unwrap()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn runtime_prepended() {
+        assert_eq!(
+            "let &runtimepath = '/opt/nvim/runtime,' . &runtimepath | let &packpath = '/opt/nvim/runtime,' . &packpath",
+            prepend_runtime_cmd(std::path::Path::new("/opt/nvim/runtime"))
+        );
+        assert_eq!(
+            "let &runtimepath = '/it''s\\,here,' . &runtimepath | let &packpath = '/it''s\\,here,' . &packpath",
+            prepend_runtime_cmd(std::path::Path::new("/it's,here"))
+        );
+    }
+
+    #[test]
+    fn suite_deadline_check() {
+        let test_case = TestCase::new("server", TestFile::new("main.rs", ""));
+        let suite_start = Instant::now();
+        assert!(check_suite_deadline(&test_case, suite_start, None).is_ok());
+        assert!(
+            check_suite_deadline(&test_case, suite_start, Some(Duration::from_secs(3600))).is_ok()
+        );
+
+        let deadline = Duration::from_millis(1);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(
+            Err(TestExecutionError::SuiteDeadlineExceeded {
+                test_id: test_case.test_id.clone(),
+                deadline,
+            }),
+            check_suite_deadline(&test_case, suite_start, Some(deadline))
+        );
+    }
+
+    #[test]
+    fn timeout_capped_at_deadline() {
+        let timeout = Duration::from_secs(5);
+        let suite_start = Instant::now();
+        assert_eq!(timeout, cap_timeout(timeout, suite_start, None));
+        assert_eq!(
+            timeout,
+            cap_timeout(timeout, suite_start, Some(Duration::from_secs(3600)))
+        );
+
+        let capped = cap_timeout(timeout, suite_start, Some(Duration::from_secs(2)));
+        assert!(capped <= Duration::from_secs(2) && capped > Duration::from_secs(1));
+
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(
+            Duration::ZERO,
+            cap_timeout(timeout, suite_start, Some(Duration::from_millis(1)))
+        );
+    }
+
+    // These tests rely on `/proc` to check whether processes are running
+    #[cfg(target_os = "linux")]
+    mod process_group {
+        use std::{
+            fs,
+            process::Command,
+            time::{Duration, Instant},
+        };
+
+        use crate::ChildGuard;
+
+        /// Spawns a shell leading its own process group, which starts a long-running
+        /// process and exits, leaving it orphaned as a server outliving neovim would be.
+        /// Returns the guard for the shell along with the orphan's pid.
+        fn spawn_orphan(kill_on_drop: bool) -> (ChildGuard, u32) {
+            let pid_path = std::env::temp_dir().join(format!(
+                "lspresso-orphan-{}-{kill_on_drop}",
+                std::process::id()
+            ));
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(format!("sleep 30 & echo $! > {}", pid_path.display()));
+            std::os::unix::process::CommandExt::process_group(&mut command, 0);
+            let mut guard = ChildGuard::new(command.spawn().unwrap(), kill_on_drop, true);
+            while !guard.has_exited().unwrap() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            let pid = fs::read_to_string(&pid_path)
+                .unwrap()
+                .trim()
+                .parse()
+                .unwrap();
+            fs::remove_file(&pid_path).unwrap();
+            (guard, pid)
+        }
+
+        /// Indicates if `pid` is running, waiting briefly for it to exit
+        fn is_running(pid: u32) -> bool {
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_secs(1) {
+                // Zombies are waiting to be reaped by their new parent, they've exited
+                match fs::read_to_string(format!("/proc/{pid}/stat")) {
+                    Ok(stat) if !stat.contains(") Z ") => {}
+                    _ => return false,
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            true
+        }
+
+        #[test]
+        fn on_drop_kill_orphans() {
+            let (guard, orphan) = spawn_orphan(true);
+            assert!(is_running(orphan));
+            drop(guard);
+            assert!(!is_running(orphan));
+        }
+
+        #[test]
+        fn has_exited_leaves_child_unreaped() {
+            let (guard, orphan) = spawn_orphan(true);
+            // The shell's pid stays reserved until the guard is dropped
+            assert_eq!(Some(true), crate::exited_unreaped(guard.child.id()));
+            assert!(!guard.reaped);
+            drop(guard);
+            assert!(!is_running(orphan));
+        }
+
+        #[test]
+        fn on_drop_kill_disabled() {
+            let (guard, orphan) = spawn_orphan(false);
+            let pgid = guard.child.id();
+            drop(guard);
+            assert!(is_running(orphan));
+            crate::kill_process_group(pgid);
+        }
+    }
+}
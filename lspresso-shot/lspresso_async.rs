@@ -0,0 +1,298 @@
+//! Async counterparts of the `test_*` functions, for use with [tokio]-based test runners.
+//!
+//! These share the request parameters, result parsing, and comparison logic of their
+//! blocking counterparts, but wait on Neovim via [`tokio::process`] rather than blocking
+//! the calling thread. The Neovim process is killed if the returned future is dropped
+//! before completion.
+//!
+//! ```ignore
+//! #[tokio::test]
+//! async fn hover() {
+//!     let test_case = TestCase::new("Path to server", TestFile::new("main.rs", "..."));
+//!     lspresso_shot!(test_hover_async(&test_case, Position::new(1, 2), None, &expected).await);
+//! }
+//! ```
+//!
+//! [tokio]: https://docs.rs/tokio/latest/tokio/
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    CompletionComparator, DefinitionComparator, DocumentSymbolComparator, HoverComparator,
+    LuaReplacement, ReferencesComparator, RunnerGuard, check_exited_run, check_suite_deadline,
+    completion_replacements, definition_replacements, evaluate_golden, evaluate_results,
+    exited_unreaped, fallback_timeout, get_runner_count, hover_replacements, kill_process_group,
+    nvim_command, nvim_leads_group, nvim_version_cases, nvim_versions_result, prepare_test,
+    references_replacements, run_timeout, suite_deadline, timed_out_run,
+    types::{
+        ApproximateEq, CleanResponse, Expected, TestCase, TestExecutionError, TestExecutionResult,
+        TestResult, TestType, compare::Comparator,
+    },
+};
+use lsp_types::{
+    CompletionContext, CompletionResponse, DocumentSymbolResponse, GotoDefinitionResponse, Hover,
+    Location, Position,
+};
+
+/// The async equivalent of `collect_results`
+async fn collect_results_async<T>(
+    test_case: &TestCase,
+    test_type: TestType,
+    replacements: &mut Vec<LuaReplacement>,
+    expected: Expected<'_, T>,
+    cmp: Option<Comparator<T>>,
+) -> TestResult<(), T>
+where
+    T: Clone
+        + serde::de::DeserializeOwned
+        + serde::Serialize
+        + std::fmt::Debug
+        + CleanResponse
+        + ApproximateEq,
+{
+    if test_case.nvim_paths.is_empty() {
+        return collect_results_once_async(
+            test_case,
+            test_type,
+            replacements,
+            expected,
+            cmp.as_ref(),
+        )
+        .await;
+    }
+
+    let mut results = Vec::with_capacity(test_case.nvim_paths.len());
+    for version_case in nvim_version_cases(test_case) {
+        let result = collect_results_once_async(
+            &version_case,
+            test_type,
+            &mut replacements.clone(),
+            expected,
+            cmp.as_ref(),
+        )
+        .await;
+        results.push((version_case.nvim_path, result));
+    }
+    nvim_versions_result(test_case, results)
+}
+
+/// The async equivalent of `collect_results_once`
+async fn collect_results_once_async<T>(
+    test_case: &TestCase,
+    test_type: TestType,
+    replacements: &mut Vec<LuaReplacement>,
+    expected: Expected<'_, T>,
+    cmp: Option<&Comparator<T>>,
+) -> TestResult<(), T>
+where
+    T: Clone
+        + serde::de::DeserializeOwned
+        + serde::Serialize
+        + std::fmt::Debug
+        + CleanResponse
+        + ApproximateEq,
+{
+    let source_path = prepare_test(test_case, test_type, replacements)?;
+    let run_result = run_test_async(test_case, &source_path).await;
+    if test_case.golden.is_some() {
+        return evaluate_golden(test_case, test_type, run_result, cmp);
+    }
+    evaluate_results(test_case, run_result, expected, cmp)
+}
+
+/// Kills the process group led by the inner pgid when dropped, see
+/// [`kill_process_group`]. Disarmed (set to `None`) once the leader has been reaped,
+/// as its pid may then be reused.
+struct ProcessGroupGuard(Option<u32>);
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.0 {
+            kill_process_group(pgid);
+        }
+    }
+}
+
+/// The async equivalent of `run_test`
+async fn run_test_async(test_case: &TestCase, source_path: &Path) -> TestExecutionResult<()> {
+    // Restrict the number of tests invoking neovim at a given time to prevent timeout issues.
+    // The limit is shared with the blocking functions, so we poll for a free slot rather
+    // than blocking the executor on the condition variable
+    let runner_count = get_runner_count();
+    let (lock, cvar, suite_start) = &*runner_count;
+    let _guard = loop {
+        if let Some(guard) = RunnerGuard::try_new(lock, cvar) {
+            break guard;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    };
+    check_suite_deadline(test_case, *suite_start, suite_deadline())?;
+
+    let timeout = run_timeout(test_case);
+    let mut command = tokio::process::Command::from(nvim_command(test_case, source_path)?);
+    command.kill_on_drop(test_case.on_drop_kill);
+    let mut child = command
+        .spawn()
+        .map_err(|e| TestExecutionError::Neovim(test_case.test_id.clone(), e.to_string()))?;
+    // `kill_on_drop` only kills neovim itself, so the server is killed via the group.
+    // Declared after `child`, so the group is killed before `child` is dropped (and reaped)
+    let mut group_guard = ProcessGroupGuard(
+        child
+            .id()
+            .filter(|_| test_case.on_drop_kill && nvim_leads_group(test_case)),
+    );
+
+    let start = Instant::now();
+    let fallback = fallback_timeout(test_case, timeout);
+    while start.elapsed() < fallback {
+        // As with `ChildGuard::has_exited`, leave an exited child unreaped where possible
+        let exited = match child.id().and_then(exited_unreaped) {
+            Some(exited) => exited,
+            None => {
+                let exited = child
+                    .try_wait()
+                    .map_err(|e| {
+                        TestExecutionError::Neovim(test_case.test_id.clone(), e.to_string())
+                    })?
+                    .is_some();
+                if exited {
+                    group_guard.0 = None;
+                }
+                exited
+            }
+        };
+        if exited {
+            return check_exited_run(test_case, timeout);
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    // `child` is killed when dropped, unless `on_drop_kill` is disabled
+    timed_out_run(test_case, timeout)
+}
+
+/// The async equivalent of [`crate::test_completion`]
+///
+/// # Errors
+///
+/// Returns [`crate::types::TestError`] if the test case is invalid, the expected results
+/// don't match, or some other failure occurs
+///
+/// # Panics
+///
+/// Panics if JSON serialization of `context` fails
+#[allow(clippy::result_large_err)]
+pub async fn test_completion_async<'a>(
+    test_case: &TestCase,
+    cursor_pos: Position,
+    context: Option<&CompletionContext>,
+    cmp: Option<CompletionComparator>,
+    expected: impl Into<Expected<'a, CompletionResponse>>,
+) -> TestResult<(), CompletionResponse> {
+    collect_results_async(
+        test_case,
+        TestType::Completion,
+        &mut completion_replacements(cursor_pos, context),
+        expected.into(),
+        cmp,
+    )
+    .await
+}
+
+/// The async equivalent of [`crate::test_definition`]
+///
+/// # Errors
+///
+/// Returns [`crate::types::TestError`] if the test case is invalid, the expected results
+/// don't match, or some other failure occurs
+#[allow(clippy::result_large_err)]
+pub async fn test_definition_async<'a>(
+    test_case: &TestCase,
+    cursor_pos: Position,
+    cmp: Option<DefinitionComparator>,
+    expected: impl Into<Expected<'a, GotoDefinitionResponse>>,
+) -> TestResult<(), GotoDefinitionResponse> {
+    collect_results_async(
+        test_case,
+        TestType::Definition,
+        &mut definition_replacements(cursor_pos),
+        expected.into(),
+        cmp,
+    )
+    .await
+}
+
+/// The async equivalent of [`crate::test_document_symbol`]
+///
+/// # Errors
+///
+/// Returns [`crate::types::TestError`] if the test case is invalid, the expected results
+/// don't match, or some other failure occurs
+#[allow(clippy::result_large_err)]
+pub async fn test_document_symbol_async<'a>(
+    test_case: &TestCase,
+    cmp: Option<DocumentSymbolComparator>,
+    expected: impl Into<Expected<'a, DocumentSymbolResponse>>,
+) -> TestResult<(), DocumentSymbolResponse> {
+    collect_results_async(
+        test_case,
+        TestType::DocumentSymbol,
+        &mut vec![LuaReplacement::ParamTextDocument],
+        expected.into(),
+        cmp,
+    )
+    .await
+}
+
+/// The async equivalent of [`crate::test_hover`]
+///
+/// # Errors
+///
+/// Returns [`crate::types::TestError`] if the test case is invalid, the expected results
+/// don't match, or some other failure occurs
+#[allow(clippy::result_large_err)]
+pub async fn test_hover_async<'a>(
+    test_case: &TestCase,
+    cursor_pos: Position,
+    cmp: Option<HoverComparator>,
+    expected: impl Into<Expected<'a, Hover>>,
+) -> TestResult<(), Hover> {
+    collect_results_async(
+        test_case,
+        TestType::Hover,
+        &mut hover_replacements(cursor_pos),
+        expected.into(),
+        cmp,
+    )
+    .await
+}
+
+/// The async equivalent of [`crate::test_references`]
+///
+/// # Errors
+///
+/// Returns [`crate::types::TestError`] if the test case is invalid, the expected results
+/// don't match, or some other failure occurs
+///
+/// # Panics
+///
+/// Panics if JSON serialization of `include_declaration` fails
+#[allow(clippy::result_large_err)]
+pub async fn test_references_async<'a>(
+    test_case: &TestCase,
+    cursor_pos: Position,
+    include_declaration: bool,
+    cmp: Option<ReferencesComparator>,
+    expected: impl Into<Expected<'a, Vec<Location>>>,
+) -> TestResult<(), Vec<Location>> {
+    collect_results_async(
+        test_case,
+        TestType::References,
+        &mut references_replacements(cursor_pos, include_declaration),
+        expected.into(),
+        cmp,
+    )
+    .await
+}
@@ -0,0 +1,83 @@
+//! Adapter for driving lspresso-shot benchmarks via [criterion].
+//!
+//! Each iteration of a lspresso-shot benchmark spawns a new Neovim instance and
+//! server, so timing the entire iteration would mostly measure startup costs. Instead,
+//! [`bench_request`] uses criterion's [`Bencher::iter_custom`] with the harness's own
+//! measurements, which only cover the time between issuing the request and receiving
+//! the response (the same values returned by the `benchmark_*` functions).
+//!
+//! ```ignore
+//! fn bench_hover(c: &mut Criterion) {
+//!     let test_case = TestCase::new("Path to server", TestFile::new("main.rs", "..."));
+//!     bench_request(c, &test_case, TestType::Hover, || {
+//!         test_hover(&test_case, Position::new(1, 2), None, None)
+//!     });
+//! }
+//! ```
+//!
+//! [criterion]: https://docs.rs/criterion/latest/criterion/
+
+use std::time::Duration;
+
+use criterion::{Bencher, Criterion};
+
+use crate::types::{TestCase, TestError, TestResult, TestType};
+
+/// Registers a criterion benchmark named after `test_type`, where each iteration
+/// runs `action` (typically a call to the corresponding `test_*` function with no
+/// expected result) and reports the measured request time to criterion.
+///
+/// Response mismatches are ignored, as with the `benchmark_*` functions.
+///
+/// # Panics
+///
+/// Panics if `action` fails to set up or execute the test case, or if no measurement
+/// is recorded for an iteration.
+pub fn bench_request<T>(
+    c: &mut Criterion,
+    test_case: &TestCase,
+    test_type: TestType,
+    action: impl Fn() -> TestResult<(), T>,
+) {
+    c.bench_function(&test_type.to_string(), |b: &mut Bencher| {
+        b.iter_custom(|iters| {
+            let mut total = Duration::ZERO;
+            for _ in 0..iters {
+                total += measure_once(test_case, &action);
+            }
+            total
+        });
+    });
+}
+
+/// Runs `action` once, returning the request time recorded by the harness
+fn measure_once<T>(test_case: &TestCase, action: &impl Fn() -> TestResult<(), T>) -> Duration {
+    let n_measurements = test_case
+        .get_benchmark_results()
+        .map_or(0, |results| results.len());
+    match action() {
+        Ok(()) | Err(TestError::ResponseMismatch(_) | TestError::JsonMismatch(_)) => {}
+        Err(TestError::TestSetup(e)) => panic!("{e}"),
+        Err(TestError::TestExecution(e)) => panic!("{e}"),
+        Err(TestError::NvimVersions(e)) => {
+            if e.results.iter().any(|(_, result)| {
+                matches!(
+                    result,
+                    Err(TestError::TestSetup(_) | TestError::TestExecution(_))
+                )
+            }) {
+                panic!("Test {}: a run failed to execute", e.test_id);
+            }
+        }
+    }
+    let results = test_case
+        .get_benchmark_results()
+        .unwrap_or_else(|e| panic!("Test {}: {e}", test_case.test_id));
+    assert!(
+        results.len() > n_measurements,
+        "Test {}: No measurement was recorded",
+        test_case.test_id
+    );
+    // Invariant: `results` has at least one element, checked above
+    *results.last().unwrap()
+}
@@ -1,11 +1,16 @@
-vim.filetype.add({
-    extension = {
-        FILE_EXTENSION = 'lspresso_shot',
-    },
-})
+local attach_filetypes = ATTACH_FILETYPES ---@diagnostic disable-line: undefined-global
+-- By default, only attach to buffers sharing the source file's extension
+if #attach_filetypes == 0 then
+    vim.filetype.add({
+        extension = {
+            FILE_EXTENSION = 'lspresso_shot',
+        },
+    })
+    attach_filetypes = { 'lspresso_shot' }
+end
 
 vim.api.nvim_create_autocmd('FileType', {
-    pattern = 'lspresso_shot',
+    pattern = attach_filetypes,
     callback = function(ev)
         if vim.bo[ev.buf].buftype == 'nofile' then
             report_error('Invalid buffer type opened') ---@diagnostic disable-line: undefined-global
@@ -13,11 +18,45 @@ vim.api.nvim_create_autocmd('FileType', {
         end
         vim.lsp.start {
             name = 'lspresso_shot',
-            cmd = { 'EXECUTABLE_PATH' },
-            root_dir = 'ROOT_PATH/src',
+            cmd = SERVER_CMD, ---@diagnostic disable-line: undefined-global
+            root_dir = ROOT_DIR, ---@diagnostic disable-line: undefined-global
+            -- Keep the server in Neovim's process group, so it's killed along with Neovim
+            detached = false,
             settings = {},
             capabilities = capabilities, ---@diagnostic disable-line: undefined-global
-            on_attach = function(client, _) ---@diagnostic disable-line: unused-local
+            trace = 'TRACE_LEVEL',
+            on_init = function(client, _)
+                -- Recorded first, so the server's capabilities are captured as sent
+                record_capabilities(client) ---@diagnostic disable-line: undefined-global
+                record_server_info(client) ---@diagnostic disable-line: undefined-global
+                capture_outgoing(client) ---@diagnostic disable-line: undefined-global
+                filter_watched_files(client) ---@diagnostic disable-line: undefined-global
+                override_sync_kind(client) ---@diagnostic disable-line: undefined-global
+            end,
+            on_error = function(code, err)
+                record_noise(code, err) ---@diagnostic disable-line: undefined-global
+            end,
+            handlers = count_server_requests(add_server_request_handlers({ ---@diagnostic disable-line: undefined-global
+                ['$/logTrace'] = function(_, result, _)
+                    record_trace(result) ---@diagnostic disable-line: undefined-global
+                end,
+                ['window/logMessage'] = function(err, result, ctx)
+                    record_message(result) ---@diagnostic disable-line: undefined-global
+                    return vim.lsp.handlers['window/logMessage'](err, result, ctx)
+                end,
+                ['window/showMessage'] = function(err, result, ctx)
+                    record_message(result) ---@diagnostic disable-line: undefined-global
+                    return vim.lsp.handlers['window/showMessage'](err, result, ctx)
+                end,
+            })),
+            on_attach = function(client, bufnr) ---@diagnostic disable-line: unused-local
+                record_attached() ---@diagnostic disable-line: undefined-global
+                -- Preopened files are only attached for their `textDocument/didOpen`
+                if is_preopened(bufnr) then ---@diagnostic disable-line: undefined-global
+                    return
+                end
+                preopen_files(client) ---@diagnostic disable-line: undefined-global
+                set_initial_cursor(client, bufnr) ---@diagnostic disable-line: undefined-global
                 ---@diagnostic disable-next-line: undefined-global, exp-in-action
                 LSP_ACTION
             end,
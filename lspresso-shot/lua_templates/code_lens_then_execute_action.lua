@@ -0,0 +1,111 @@
+local progress_count = 0 -- track how many times we've tried for the logs
+
+--- Writes `value` to `path` as JSON via a temporary file, so that the test harness
+--- polling for `path` never observes a partial write
+---@param path string
+---@param value any
+---@return boolean
+local function write_json_atomic(path, value)
+    local tmp_path = path .. '.tmp'
+    local file = io.open(tmp_path, 'w')
+    if not file then
+        report_error('Could not open ' .. tmp_path) ---@diagnostic disable-line: undefined-global
+        return false
+    end
+    file:write(vim.json.encode(value, { escape_slash = true }))
+    file:close()
+    return os.rename(tmp_path, path) ~= nil
+end
+
+---@diagnostic disable-next-line: unused-function, unused-local
+local function check_progress_result()
+    progress_count = progress_count + 1
+    if progress_count ~= PROGRESS_THRESHOLD then ---@diagnostic disable-line: undefined-global
+        report_log(tostring(progress_count) .. ' < ' .. tostring(PROGRESS_THRESHOLD) .. '\n') ---@diagnostic disable-line: undefined-global
+        return
+    end
+    local params = {}
+    ---@diagnostic disable-next-line: undefined-global, exp-in-action
+    PARAM_ASSIGN
+
+    report_log('Issuing code lens request (Attempt ' .. tostring(progress_count) .. ')\n') ---@diagnostic disable-line: undefined-global
+    local start = vim.uv.hrtime()
+    local lens_result = vim.lsp.buf_request_sync(0, 'textDocument/codeLens', params)
+    -- Time spent waiting on the test harness to pick a lens isn't counted
+    local lens_elapsed_ns = vim.uv.hrtime() - start
+    local lenses = {}
+    if lens_result and #lens_result >= 1 and lens_result[1].result then
+        lenses = lens_result[1].result
+    elseif lens_result and #lens_result >= 1 and lens_result[1].err then
+        record_response_error(lens_result[1].err) ---@diagnostic disable-line: undefined-global
+        mark_empty_file() ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+    -- The test harness picks a lens from these, and writes it back for us to execute
+    if not write_json_atomic('CODE_LENSES_PATH', #lenses > 0 and lenses or vim.NIL) then
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+    report_log('Waiting for a code lens to be picked\n') ---@diagnostic disable-line: undefined-global
+    vim.wait(TIMEOUT_MS, function() ---@diagnostic disable-line: undefined-global
+        return vim.uv.fs_stat('PICKED_CODE_LENS_PATH') ~= nil
+    end, 5)
+    local picked_file = io.open('PICKED_CODE_LENS_PATH', 'r')
+    if not picked_file then
+        report_error('Could not open picked code lens file') ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+    ---@diagnostic disable-next-line: need-check-nil
+    local lens = vim.json.decode(picked_file:read('*a'), { luanil = { object = true } })
+    picked_file:close() ---@diagnostic disable-line: need-check-nil
+    if lens == vim.NIL then
+        report_log('No code lens was picked\n') ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+
+    if not lens.command then
+        report_log('Resolving the picked code lens\n') ---@diagnostic disable-line: undefined-global
+        local resolve_result = vim.lsp.buf_request_sync(0, 'codeLens/resolve', lens)
+        if resolve_result and #resolve_result >= 1 and resolve_result[1].result then
+            lens = resolve_result[1].result
+        end
+        write_json_atomic('RESOLVED_CODE_LENS_PATH', lens)
+        if not lens.command then
+            exit() ---@diagnostic disable-line: undefined-global
+        end
+    end
+
+    -- Record the edits the server asks us to apply while executing the command, and
+    -- apply them as usual
+    local applied_edits = {}
+    local client = vim.lsp.get_clients({ bufnr = 0 })[1]
+    local apply_edit = client.handlers['workspace/applyEdit'] or vim.lsp.handlers['workspace/applyEdit']
+    client.handlers['workspace/applyEdit'] = function(err, result, ctx, config)
+        table.insert(applied_edits, result.edit)
+        return apply_edit(err, result, ctx, config)
+    end
+
+    report_log('Executing ' .. lens.command.command .. '\n') ---@diagnostic disable-line: undefined-global
+    start = vim.uv.hrtime()
+    local exec_result = vim.lsp.buf_request_sync(0, 'workspace/executeCommand', {
+        command = lens.command.command,
+        arguments = lens.command.arguments,
+    })
+    local elapsed_ns = lens_elapsed_ns + vim.uv.hrtime() - start
+    record_benchmark_result(elapsed_ns) ---@diagnostic disable-line: undefined-global
+    if not exec_result then
+        ---@diagnostic disable-next-line: undefined-global
+        report_log('No valid workspace/executeCommand result returned: ' .. vim.inspect(exec_result) .. '\n')
+    elseif #exec_result >= 1 and exec_result[1].err then
+        record_response_error(exec_result[1].err) ---@diagnostic disable-line: undefined-global
+        mark_empty_file() ---@diagnostic disable-line: undefined-global
+    else
+        local response = #exec_result >= 1 and exec_result[1].result or nil
+        local lines = vim.api.nvim_buf_get_lines(0, 0, -1, true)
+        write_json_atomic('RESULTS_FILE', {
+            response = response,
+            appliedEdits = #applied_edits > 0 and applied_edits or nil,
+            state = table.concat(lines, '\n'),
+        })
+    end
+    exit() ---@diagnostic disable-line: undefined-global
+end
@@ -1,34 +1,139 @@
 local progress_count = 0 -- track how many times we've tried for the logs
+local settle_ms = SETTLE_MS ---@diagnostic disable-line: undefined-global
+-- Where to record the latency of the first diagnostics, if measured
+local latency_path = LATENCY_PATH ---@diagnostic disable-line: undefined-global
+local attach_time = nil
+-- The file to collect diagnostics for, if not the source file
+local diagnostics_file = DIAGNOSTICS_FILE ---@diagnostic disable-line: undefined-global
+-- Edits applied once the initial diagnostics are collected, after which the
+-- diagnostics are collected again `fix_settle_ms` later
+local fix_edits = FIX_EDITS ---@diagnostic disable-line: undefined-global
+local fix_settle_ms = FIX_SETTLE_MS ---@diagnostic disable-line: undefined-global
+local fixed = false
+
+--- Returns the buffer diagnostics are collected from
+---@return integer
+local function diagnostics_buf()
+    if diagnostics_file then
+        return vim.fn.bufnr(diagnostics_file)
+    end
+    return 0
+end
+
+--- Marks the point the server attached to the buffer, from which the latency
+--- of the first diagnostics is measured
+---@diagnostic disable-next-line: unused-local, unused-function
+local function mark_attached(client)
+    if latency_path then
+        attach_time = vim.uv.hrtime()
+    end
+    -- Open the targeted file as well, so the server publishes diagnostics for it
+    if diagnostics_file and vim.fn.bufnr(diagnostics_file) == -1 then
+        local bufnr = vim.fn.bufadd(diagnostics_file)
+        vim.fn.bufload(bufnr)
+        vim.lsp.buf_attach_client(bufnr, client.id)
+    end
+end
+
+--- Records the time elapsed between the server attaching and the diagnostics
+--- being published
+local function record_latency()
+    if not attach_time then
+        return
+    end
+    local latency_file = io.open(latency_path, 'w')
+    if not latency_file then
+        report_error('Could not open latency file') ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+    ---@diagnostic disable: need-check-nil
+    latency_file:write(tostring(vim.uv.hrtime() - attach_time))
+    latency_file:close()
+    ---@diagnostic enable: need-check-nil
+    attach_time = nil
+end
+
+--- Records the diagnostics collected before `fix_edits` are applied, then applies
+--- them to the buffer, which notifies the server via `textDocument/didChange`
+---@param diagnostics table
+local function apply_fix(diagnostics)
+    fixed = true
+    local initial_file = io.open('INITIAL_DIAGNOSTICS_PATH', 'w')
+    if not initial_file then
+        report_error('Could not open initial diagnostics file') ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+    ---@diagnostic disable: need-check-nil
+    initial_file:write(vim.json.encode(diagnostics, { escape_slash = true }))
+    initial_file:close()
+    ---@diagnostic enable: need-check-nil
+
+    local bufnr = diagnostics_buf()
+    if bufnr == 0 then
+        bufnr = vim.api.nvim_get_current_buf()
+    end
+    local client = vim.lsp.get_clients({ bufnr = bufnr })[1]
+    vim.lsp.util.apply_text_edits(fix_edits, bufnr, client and client.offset_encoding or 'utf-16')
+    report_log('Applied fix, settling diagnostics for ' .. tostring(fix_settle_ms) .. 'ms\n') ---@diagnostic disable-line: undefined-global
+end
+
+local function write_diagnostics()
+    local diagnostics_result = vim.diagnostic.get(diagnostics_buf(), {})
+    if fix_edits and not fixed then
+        local diagnostics = {}
+        for _, diagnostic in pairs(diagnostics_result or {}) do
+            table.insert(diagnostics, diagnostic.user_data.lsp)
+        end
+        apply_fix(diagnostics)
+        vim.defer_fn(write_diagnostics, fix_settle_ms)
+        return
+    end
+    if diagnostics_result then
+        local results_file = io.open('RESULTS_FILE', 'w')
+        if not results_file then
+            report_error('Could not open results file') ---@diagnostic disable-line: undefined-global
+            exit() ---@diagnostic disable-line: undefined-global
+        end
+
+        local diagnostics = {}
+        for _, diagnostic in pairs(diagnostics_result) do
+            table.insert(diagnostics, diagnostic.user_data.lsp)
+        end
+
+        ---@diagnostic disable: need-check-nil
+        results_file:write(vim.json.encode(diagnostics, { escape_slash = true }))
+        results_file:close()
+        ---@diagnostic enable: need-check-nil
+    else
+        ---@diagnostic disable-next-line: undefined-global
+        mark_empty_file() ---@diagnostic disable-line: undefined-global
+    end
+    exit() ---@diagnostic disable-line: undefined-global
+end
 
 vim.api.nvim_create_autocmd('DiagnosticChanged', {
-    callback = function(_)
+    callback = function(ev)
+        if diagnostics_file and ev.buf ~= diagnostics_buf() then
+            return
+        end
         progress_count = progress_count + 1
-        if progress_count ~= PROGRESS_THRESHOLD then ---@diagnostic disable-line: undefined-global
+        if progress_count < PROGRESS_THRESHOLD then ---@diagnostic disable-line: undefined-global
             report_log(tostring(progress_count) .. ' < ' .. tostring(PROGRESS_THRESHOLD) .. '\n') ---@diagnostic disable-line: undefined-global
             return
         end
-        report_log('Issuing diagnostic request (Attempt ' .. tostring(progress_count) .. ')\n') ---@diagnostic disable-line: undefined-global
-        local diagnostics_result = vim.diagnostic.get(0, {})
-        if diagnostics_result then
-            local results_file = io.open('RESULTS_FILE', 'w')
-            if not results_file then
-                report_error('Could not open results file') ---@diagnostic disable-line: undefined-global
-                exit() ---@diagnostic disable-line: undefined-global
-            end
-
-            local diagnostics = {}
-            for _, diagnostic in pairs(diagnostics_result) do
-                table.insert(diagnostics, diagnostic.user_data.lsp)
-            end
-
-            ---@diagnostic disable: need-check-nil
-            results_file:write(vim.json.encode(diagnostics, { escape_slash = true }))
-            results_file:close()
-            ---@diagnostic enable: need-check-nil
-        else
-            ---@diagnostic disable-next-line: undefined-global
-            mark_empty_file() ---@diagnostic disable-line: undefined-global
+        -- Already settling, or the results were already recorded
+        if progress_count > PROGRESS_THRESHOLD then ---@diagnostic disable-line: undefined-global
+            return
         end
-        exit() ---@diagnostic disable-line: undefined-global
+        -- Collect all diagnostics published within `settle_ms` of the threshold being reached
+        if settle_ms then
+            record_latency()
+            report_log('Settling diagnostics for ' .. tostring(settle_ms) .. 'ms\n') ---@diagnostic disable-line: undefined-global
+            vim.defer_fn(write_diagnostics, settle_ms)
+            return
+        end
+        report_log('Issuing diagnostic request (Attempt ' .. tostring(progress_count) .. ')\n') ---@diagnostic disable-line: undefined-global
+        record_latency()
+        write_diagnostics()
     end,
 })
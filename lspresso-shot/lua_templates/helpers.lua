@@ -1,3 +1,7 @@
+-- When the `init.lua` was loaded, used to measure how long the server takes to be ready
+---@diagnostic disable-next-line: unused-local
+local init_time = vim.uv.hrtime()
+
 ---@param msg string
 ---@diagnostic disable-next-line: unused-local, unused-function
 local function report_error(msg)
@@ -44,6 +48,324 @@ local function record_benchmark_result(time_ns)
     end
 end
 
+---@param phase string
+---@param time_ns number
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_timing(phase, time_ns)
+    if not RECORD_TIMING then ---@diagnostic disable-line: undefined-global
+        return
+    end
+    local timing_file, err = io.open('TIMING_PATH', 'a')
+    if not timing_file then
+        report_error('Could not open timing file: ' .. err)
+    else
+        timing_file:write(phase .. ' ' .. tostring(time_ns) .. '\n')
+        timing_file:close()
+    end
+end
+
+---@param trace table
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_trace(trace)
+    local trace_file, err = io.open('TRACE_PATH', 'a')
+    if not trace_file then
+        report_error('Could not open trace file: ' .. err)
+    else
+        trace_file:write(vim.json.encode(trace, { escape_slash = true }) .. '\n')
+        trace_file:close()
+    end
+end
+
+---@param err table
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_response_error(err)
+    local response_error_file, open_err = io.open('RESPONSE_ERROR_FILE', 'w')
+    if not response_error_file then
+        report_error('Could not open response error file: ' .. open_err)
+    else
+        response_error_file:write(vim.json.encode(err, { escape_slash = true }))
+        response_error_file:close()
+    end
+end
+
+---@param message table
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_message(message)
+    local messages_file, err = io.open('MESSAGES_PATH', 'a')
+    if not messages_file then
+        report_error('Could not open messages file: ' .. err)
+    else
+        messages_file:write(vim.json.encode(message, { escape_slash = true }) .. '\n')
+        messages_file:close()
+    end
+end
+
+---@param client vim.lsp.Client
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_capabilities(client)
+    local capabilities_file, err = io.open('CAPABILITIES_PATH', 'w')
+    if not capabilities_file then
+        report_error('Could not open capabilities file: ' .. err)
+    else
+        capabilities_file:write(vim.json.encode(client.server_capabilities, { escape_slash = true }))
+        capabilities_file:close()
+    end
+end
+
+--- Records the `serverInfo` the server reported in its response to `initialize`
+---@param client vim.lsp.Client
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_server_info(client)
+    local server_info_file, err = io.open('SERVER_INFO_PATH', 'w')
+    if not server_info_file then
+        report_error('Could not open server info file: ' .. err)
+    else
+        server_info_file:write(vim.json.encode(client.server_info or vim.NIL, { escape_slash = true }))
+        server_info_file:close()
+    end
+end
+
+--- Marks that the server attached to a buffer, so that a server that never attached
+--- can be told apart from one that never responded
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_attached()
+    local attached_file, err = io.open('ATTACHED_PATH', 'w')
+    if not attached_file then
+        report_error('Could not open attached file: ' .. err)
+    else
+        attached_file:close()
+    end
+end
+
+local outgoing_requests = {}
+
+--- Wraps `client.request` to record the method and params of each request sent to
+--- the server, if the test case enables it
+---@param client vim.lsp.Client
+---@diagnostic disable-next-line: unused-local, unused-function
+local function capture_outgoing(client)
+    if not CAPTURE_OUTGOING then ---@diagnostic disable-line: undefined-global
+        return
+    end
+    local request = client.request
+    client.request = function(...)
+        local args = { ... }
+        -- Handles both `client:request(method, ...)` and `client.request(method, ...)`
+        local offset = args[1] == client and 1 or 0
+        table.insert(outgoing_requests, { method = args[offset + 1], params = args[offset + 2] or vim.NIL })
+        local outgoing_file, err = io.open('OUTGOING_PATH', 'w')
+        if not outgoing_file then
+            report_error('Could not open outgoing requests file: ' .. err)
+        else
+            outgoing_file:write(vim.json.encode(outgoing_requests, { escape_slash = true }))
+            outgoing_file:close()
+        end
+        return request(...)
+    end
+end
+
+local preopen_paths = PREOPEN_FILES ---@diagnostic disable-line: undefined-global
+
+--- Indicates whether `bufnr` was opened via `preopen_files`
+---@param bufnr integer
+---@return boolean
+---@diagnostic disable-next-line: unused-local, unused-function
+local function is_preopened(bufnr)
+    return vim.tbl_contains(preopen_paths, vim.api.nvim_buf_get_name(bufnr))
+end
+
+--- Loads each of the test case's preopened files into a buffer attached to `client`,
+--- so that the server receives a `textDocument/didOpen` for each of them before the
+--- tested request is issued
+---@param client vim.lsp.Client
+---@diagnostic disable-next-line: unused-local, unused-function
+local function preopen_files(client)
+    for _, path in ipairs(preopen_paths) do
+        local bufnr = vim.fn.bufadd(path)
+        vim.fn.bufload(bufnr)
+        vim.lsp.buf_attach_client(bufnr, client.id)
+    end
+end
+
+local observed_progress_tokens = nil
+
+--- Starts recording each distinct `$/progress` token sent by the server, so that a
+--- test waiting on a mistyped token can report the tokens it did observe
+---@diagnostic disable-next-line: unused-local, unused-function
+local function track_progress_tokens()
+    observed_progress_tokens = {}
+    local tokens_file, err = io.open('OBSERVED_TOKENS_PATH', 'w')
+    if not tokens_file then
+        report_error('Could not open progress tokens file: ' .. err)
+    else
+        tokens_file:close()
+    end
+end
+
+---@param token integer|string
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_progress_token(token)
+    local token_str = tostring(token)
+    if not observed_progress_tokens or observed_progress_tokens[token_str] then
+        return
+    end
+    observed_progress_tokens[token_str] = true
+    local tokens_file, err = io.open('OBSERVED_TOKENS_PATH', 'a')
+    if not tokens_file then
+        report_error('Could not open progress tokens file: ' .. err)
+    else
+        tokens_file:write(token_str .. '\n')
+        tokens_file:close()
+    end
+end
+
+--- Resolves the URI of a file operation to a path. Relative URIs are resolved against
+--- the project directory
+---@param uri string
+---@return string
+local function file_operation_path(uri)
+    if uri:match('^%a[%w+.-]*:') then
+        return vim.uri_to_fname(uri)
+    elseif uri:sub(1, 1) == '/' then
+        return uri
+    end
+    return 'PROJECT_PATH/' .. uri
+end
+
+--- Mirrors the file operations of a `workspace/will*Files` request on disk, if the test
+--- case enables it. Files are created before the request is sent (`stage == 'before'`),
+--- and deleted or renamed once it's been answered (`stage == 'after'`)
+---@param method string
+---@param params table
+---@param stage 'before'|'after'
+---@diagnostic disable-next-line: unused-local, unused-function
+local function perform_file_operations(method, params, stage)
+    for _, file in ipairs(params.files or {}) do
+        if stage == 'before' and method == 'workspace/willCreateFiles' and CREATE_ON_DISK then ---@diagnostic disable-line: undefined-global
+            local path = file_operation_path(file.uri)
+            vim.fn.mkdir(vim.fs.dirname(path), 'p')
+            -- Opened for appending, so an existing file isn't truncated
+            local created, err = io.open(path, 'a')
+            if not created then
+                report_error('Could not create ' .. path .. ': ' .. err)
+            else
+                created:close()
+            end
+        elseif stage == 'after' and method == 'workspace/willDeleteFiles' and DELETE_ON_DISK then ---@diagnostic disable-line: undefined-global
+            vim.fn.delete(file_operation_path(file.uri), 'rf')
+        elseif stage == 'after' and method == 'workspace/willRenameFiles' and RENAME_ON_DISK then ---@diagnostic disable-line: undefined-global
+            local new_path = file_operation_path(file.newUri)
+            vim.fn.mkdir(vim.fs.dirname(new_path), 'p')
+            local ok, err = os.rename(file_operation_path(file.oldUri), new_path)
+            if not ok then
+                report_error('Could not rename to ' .. new_path .. ': ' .. err)
+            end
+        end
+    end
+end
+
+---@param before string[]
+---@param after string[]
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_buffer_changes(before, after)
+    local changes_file, err = io.open('BUFFER_CHANGES_PATH', 'w')
+    if not changes_file then
+        report_error('Could not open buffer changes file: ' .. err)
+    else
+        local changes = { before = table.concat(before, '\n'), after = table.concat(after, '\n') }
+        changes_file:write(vim.json.encode(changes, { escape_slash = true }))
+        changes_file:close()
+    end
+end
+
+---@param method string
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_server_request(method)
+    local requests_file, err = io.open('COUNTED_REQUESTS_PATH', 'a')
+    if not requests_file then
+        report_error('Could not open server requests file: ' .. err)
+    else
+        requests_file:write(method .. '\n')
+        requests_file:close()
+    end
+end
+
+--- Responds to the server-initiated requests a bare client may leave unanswered,
+--- which would otherwise stall the server until the test times out. Any responses
+--- provided via `TestCase::server_request_handlers` take precedence.
+---@param handlers table<string, function>
+---@return table<string, function>
+---@diagnostic disable-next-line: unused-local, unused-function
+local function add_server_request_handlers(handlers)
+    handlers['workspace/configuration'] = function(_, result, _)
+        local config = {}
+        for i = 1, #((result or {}).items or {}) do
+            config[i] = vim.NIL
+        end
+        return config
+    end
+    handlers['workspace/workspaceFolders'] = function(_, _, ctx)
+        local client = vim.lsp.get_client_by_id(ctx.client_id)
+        return client and client.workspace_folders or vim.NIL
+    end
+    local create_progress = vim.lsp.handlers['window/workDoneProgress/create']
+    handlers['window/workDoneProgress/create'] = function(err, result, ctx, config)
+        -- Defer to the stock handler where available so the token is still tracked
+        if create_progress then
+            create_progress(err, result, ctx, config)
+        end
+        return vim.NIL
+    end
+    local overrides = vim.json.decode([[
+SERVER_REQUEST_HANDLERS
+]])
+    for method, response in pairs(overrides) do
+        handlers[method] = function(_, _, _)
+            return response
+        end
+    end
+    return handlers
+end
+
+--- Wraps the handlers of the server-initiated requests being counted, so that each
+--- request is recorded before being responded to as usual
+---@param handlers table<string, function>
+---@return table<string, function>
+---@diagnostic disable-next-line: unused-local, unused-function
+local function count_server_requests(handlers)
+    for _, method in ipairs(COUNTED_METHODS) do ---@diagnostic disable-line: undefined-global
+        local handler = handlers[method] or vim.lsp.handlers[method]
+        handlers[method] = function(err, result, ctx, config)
+            record_server_request(method)
+            if handler then
+                return handler(err, result, ctx, config)
+            end
+            return nil, vim.lsp.rpc_response_error(vim.lsp.protocol.ErrorCodes.MethodNotFound, method)
+        end
+    end
+    return handlers
+end
+
+---@param code integer
+---@param err any
+---@diagnostic disable-next-line: unused-local, unused-function
+local function record_noise(code, err)
+    if not DETECT_NOISE then ---@diagnostic disable-line: undefined-global
+        return
+    end
+    local errors = vim.lsp.rpc.client_errors
+    if code ~= errors.INVALID_SERVER_MESSAGE and code ~= errors.INVALID_SERVER_JSON and code ~= errors.READ_ERROR then
+        return
+    end
+    local noise_file, open_err = io.open('NOISE_PATH', 'a')
+    if not noise_file then
+        report_error('Could not open noise file: ' .. open_err)
+    else
+        noise_file:write(tostring(errors[code]) .. ': ' .. vim.inspect(err) .. '\n')
+        noise_file:close()
+    end
+end
+
 local messages = {}
 
 local original_notify = vim.notify
@@ -61,8 +383,22 @@ local function exit()
     vim.cmd('qa!')
 end
 
+--- Polls for the tested method's support when the server starts after `textDocument/didOpen`
+---@type uv.uv_timer_t?
+local poll_timer = nil
+
+--- Stops and closes `poll_timer`, if it's running
+local function close_poll_timer()
+    if poll_timer and not poll_timer:is_closing() then
+        poll_timer:stop()
+        poll_timer:close()
+    end
+    poll_timer = nil
+end
+
 ---@diagnostic disable-next-line: unused-local, unused-function
 local function timeout_exit()
+    close_poll_timer()
     report_error('Timeout of `TIMEOUT_MS`ms exceeded')
     local timeout_file, err = io.open('TIMEOUT_PATH', 'w')
     if not timeout_file then
@@ -76,6 +412,10 @@ local function timeout_exit()
 end
 
 
+-- Use a fixed viewport so that servers optimizing for the visible range behave deterministically
+vim.o.lines = VIEWPORT_ROWS ---@diagnostic disable-line: undefined-global
+vim.o.columns = VIEWPORT_COLS ---@diagnostic disable-line: undefined-global
+
 local capabilities = vim.lsp.protocol.make_client_capabilities()
 capabilities.experimental = {
     commands = {
@@ -86,6 +426,112 @@ capabilities.experimental = {
     },
 }
 
+-- When set, only changes to files matching one of these globs (relative to the
+-- project root) are delivered to the server via `workspace/didChangeWatchedFiles`
+local watch_patterns = {}
+for _, glob in ipairs(WATCH_GLOBS) do ---@diagnostic disable-line: undefined-global
+    table.insert(watch_patterns, vim.glob.to_lpeg(glob))
+end
+if #watch_patterns > 0 then
+    capabilities.workspace.didChangeWatchedFiles = {
+        dynamicRegistration = true,
+        relativePatternSupport = true,
+    }
+end
+
+---@param uri string
+---@return boolean
+local function is_watched(uri)
+    local path = vim.uri_to_fname(uri)
+    local relative = path:sub(#'PROJECT_PATH' + 2)
+    for _, pattern in ipairs(watch_patterns) do
+        if pattern:match(relative) then
+            return true
+        end
+    end
+    return false
+end
+
+--- Drops any `workspace/didChangeWatchedFiles` events for files not matching
+--- the test case's watch globs
+---@param client vim.lsp.Client
+---@diagnostic disable-next-line: unused-local, unused-function
+local function filter_watched_files(client)
+    if #watch_patterns == 0 then
+        return
+    end
+    local notify = client.notify
+    ---@diagnostic disable-next-line: duplicate-set-field
+    client.notify = function(self, method, params)
+        if method == 'workspace/didChangeWatchedFiles' and params and params.changes then
+            local changes = vim.tbl_filter(function(change)
+                return is_watched(change.uri)
+            end, params.changes)
+            if #changes == 0 then
+                report_log('Dropped unwatched file events: ' .. vim.inspect(params.changes) .. '\n')
+                return true
+            end
+            params = vim.tbl_extend('force', params, { changes = changes })
+        end
+        return notify(self, method, params)
+    end
+end
+
+--- Overrides the `textDocument/didChange` sync kind advertised by the server, if
+--- the test case sets one
+---@param client vim.lsp.Client
+---@diagnostic disable-next-line: unused-local, unused-function
+local function override_sync_kind(client)
+    local sync_kind = SYNC_KIND ---@diagnostic disable-line: undefined-global
+    if not sync_kind then
+        return
+    end
+    local sync = client.server_capabilities.textDocumentSync
+    if type(sync) == 'table' then
+        sync.change = sync_kind
+    else
+        client.server_capabilities.textDocumentSync = { openClose = true, change = sync_kind }
+    end
+end
+
+--- Moves the cursor to the test case's initial cursor position in `bufnr`, if one is
+--- set, and records where it ended up. The position's character offset is in the
+--- client's position encoding, and is converted to the byte offset Neovim expects
+---@param client vim.lsp.Client
+---@param bufnr integer
+---@diagnostic disable-next-line: unused-local, unused-function
+local function set_initial_cursor(client, bufnr)
+    local initial_cursor = INITIAL_CURSOR ---@diagnostic disable-line: undefined-global
+    if not initial_cursor then
+        return
+    end
+    local encoding = client.offset_encoding or 'utf-16'
+    local row = initial_cursor[1]
+    local line = vim.api.nvim_buf_get_lines(bufnr, row - 1, row, false)[1] or ''
+    local col = vim.str_byteindex(line, encoding, initial_cursor[2], false)
+    local winid = vim.fn.bufwinid(bufnr)
+    winid = winid == -1 and 0 or winid
+    local ok, set_err = pcall(vim.api.nvim_win_set_cursor, winid, { row, col })
+    if not ok then
+        report_error('Failed to set initial cursor: ' .. tostring(set_err)) ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+        return
+    end
+
+    local cursor = vim.api.nvim_win_get_cursor(winid)
+    local cursor_line = vim.api.nvim_buf_get_lines(bufnr, cursor[1] - 1, cursor[1], false)[1] or ''
+    local cursor_file, err = io.open('CURSOR_POSITION_PATH', 'w')
+    if not cursor_file then
+        report_error('Could not open initial cursor file: ' .. err) ---@diagnostic disable-line: undefined-global
+    else
+        cursor_file:write(vim.json.encode({
+            line = cursor[1] - 1,
+            character = vim.str_utfindex(cursor_line, encoding, cursor[2], false),
+        }))
+        cursor_file:close()
+    end
+end
+
 vim.lsp.log.set_format_func(function(msg)
     report_log('LSP LOG: ' .. msg)
     return nil
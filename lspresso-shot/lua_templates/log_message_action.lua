@@ -0,0 +1,29 @@
+local progress_count = 0 -- track how many times we've tried for the logs
+local settle_ms = SETTLE_MS ---@diagnostic disable-line: undefined-global
+
+---@diagnostic disable-next-line: unused-function, unused-local
+local function check_progress_result()
+    progress_count = progress_count + 1
+    if progress_count ~= PROGRESS_THRESHOLD then ---@diagnostic disable-line: undefined-global
+        report_log(tostring(progress_count) .. ' < ' .. tostring(PROGRESS_THRESHOLD) .. '\n') ---@diagnostic disable-line: undefined-global
+        return
+    end
+    -- An empty method indicates that opening the source file is the trigger
+    if 'REQUEST_METHOD' ~= '' then
+        local params = {}
+        ---@diagnostic disable-next-line: undefined-global, exp-in-action
+PARAM_ASSIGN
+
+        report_log('Params: ' .. tostring(vim.inspect(params)) .. '\n') ---@diagnostic disable-line: undefined-global
+        report_log('Issuing REQUEST_METHOD request (Attempt ' .. tostring(progress_count) .. ')\n') ---@diagnostic disable-line: undefined-global
+        vim.lsp.buf_request_sync(0, 'REQUEST_METHOD', params)
+    end
+    -- Messages are recorded as they arrive, we only need to keep the event loop
+    -- running for the settle window
+    if settle_ms then
+        report_log('Settling messages for ' .. tostring(settle_ms) .. 'ms\n') ---@diagnostic disable-line: undefined-global
+        vim.wait(settle_ms)
+    end
+    mark_empty_file() ---@diagnostic disable-line: undefined-global
+    exit() ---@diagnostic disable-line: undefined-global
+end
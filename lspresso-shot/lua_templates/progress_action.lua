@@ -0,0 +1,24 @@
+local progress_values = {}
+
+-- Record every `$/progress` notification sent for the tested token, writing them
+-- out once the token's progress ends
+vim.lsp.handlers['$/progress'] = function(_, result, _)
+    if tostring(result.token) ~= 'PROGRESS_TOKEN' then
+        return
+    end
+    report_log('Progress: ' .. vim.inspect(result.value) .. '\n') ---@diagnostic disable-line: undefined-global
+    table.insert(progress_values, result.value)
+    if result.value.kind ~= 'end' then
+        return
+    end
+    local results_file = io.open('RESULTS_FILE', 'w')
+    if not results_file then
+        report_error('Could not open results file') ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+    ---@diagnostic disable: need-check-nil
+    results_file:write(vim.json.encode(progress_values, { escape_slash = true }))
+    results_file:close()
+    ---@diagnostic enable: need-check-nil
+    exit() ---@diagnostic disable-line: undefined-global
+end
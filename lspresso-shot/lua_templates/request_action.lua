@@ -11,12 +11,38 @@ local function check_progress_result()
     ---@diagnostic disable-next-line: undefined-global, exp-in-action
 PARAM_ASSIGN
 
+    if REUSE_RESULT_ID then ---@diagnostic disable-line: undefined-global
+        -- Obtain a result id from an initial request, and pass it back to the server
+        report_log('Issuing REQUEST_METHOD request for a result id\n') ---@diagnostic disable-line: undefined-global
+        local initial_result = vim.lsp.buf_request_sync(0, 'REQUEST_METHOD', params)
+        local result_id = initial_result and #initial_result >= 1 and initial_result[1].result
+            and initial_result[1].result.resultId
+        if not result_id then
+            ---@diagnostic disable-next-line: undefined-global
+            report_error('No resultId returned: ' .. vim.inspect(initial_result))
+            exit() ---@diagnostic disable-line: undefined-global
+        end
+        params.previousResultId = result_id
+    end
+
     report_log('Params: ' .. tostring(vim.inspect(params)) .. '\n') ---@diagnostic disable-line: undefined-global
     report_log('Issuing REQUEST_METHOD request (Attempt ' .. tostring(progress_count) .. ')\n') ---@diagnostic disable-line: undefined-global
+    local lines_before = vim.api.nvim_buf_get_lines(0, 0, -1, false)
+    perform_file_operations('REQUEST_METHOD', params, 'before') ---@diagnostic disable-line: undefined-global
     local start = vim.uv.hrtime()
+    record_timing('ready', start - init_time) ---@diagnostic disable-line: undefined-global
     local req_result = vim.lsp.buf_request_sync(0, 'REQUEST_METHOD', params)
     local elapsed_ns = vim.uv.hrtime() - start
     record_benchmark_result(elapsed_ns) ---@diagnostic disable-line: undefined-global
+    record_timing('request', elapsed_ns) ---@diagnostic disable-line: undefined-global
+    perform_file_operations('REQUEST_METHOD', params, 'after') ---@diagnostic disable-line: undefined-global
+
+    if ASSERT_BUFFER_UNCHANGED then ---@diagnostic disable-line: undefined-global
+        local lines_after = vim.api.nvim_buf_get_lines(0, 0, -1, false)
+        if not vim.deep_equal(lines_before, lines_after) then
+            record_buffer_changes(lines_before, lines_after) ---@diagnostic disable-line: undefined-global
+        end
+    end
 
     if not req_result then
         ---@diagnostic disable-next-line: undefined-global
@@ -31,6 +57,9 @@ PARAM_ASSIGN
         results_file:write(vim.json.encode(req_result[1].result, { escape_slash = true }))
         results_file:close()
         ---@diagnostic enable: need-check-nil
+    elseif req_result and #req_result >= 1 and req_result[1].err then
+        record_response_error(req_result[1].err) ---@diagnostic disable-line: undefined-global
+        mark_empty_file() ---@diagnostic disable-line: undefined-global
     else
         ---@diagnostic disable-next-line: undefined-global
         mark_empty_file() ---@diagnostic disable-line: undefined-global
@@ -0,0 +1,71 @@
+local progress_count = 0 -- track how many times we've tried for the logs
+
+---@diagnostic disable-next-line: unused-function, unused-local
+local function check_progress_result()
+    progress_count = progress_count + 1
+    if progress_count ~= PROGRESS_THRESHOLD then ---@diagnostic disable-line: undefined-global
+        report_log(tostring(progress_count) .. ' < ' .. tostring(PROGRESS_THRESHOLD) .. '\n') ---@diagnostic disable-line: undefined-global
+        return
+    end
+    local params = {}
+    ---@diagnostic disable-next-line: undefined-global, exp-in-action
+    PARAM_ASSIGN
+
+    report_log('Issuing initial signature help request (Attempt ' .. tostring(progress_count) .. ')\n') ---@diagnostic disable-line: undefined-global
+    local start = vim.uv.hrtime()
+    local initial_result = vim.lsp.buf_request_sync(0, 'textDocument/signatureHelp', params)
+
+    local active_signature_help = nil
+    if not initial_result then
+        ---@diagnostic disable-next-line: undefined-global
+        report_log('No valid initial signature help result returned: ' .. vim.inspect(initial_result) .. '\n') ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    elseif initial_result and #initial_result >= 1 and initial_result[1].result then
+        active_signature_help = initial_result[1].result
+    else
+        ---@diagnostic disable-next-line: undefined-global
+        report_log('Empty initial signature help result returned: ' .. vim.inspect(initial_result) .. '\n')
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+
+    local initial_file = io.open('INITIAL_SIGNATURE_HELP_PATH', "w")
+    if not initial_file then
+        report_error('Could not open initial signature help file') ---@diagnostic disable-line: undefined-global
+        exit() ---@diagnostic disable-line: undefined-global
+    end
+    ---@diagnostic disable: need-check-nil
+    initial_file:write(vim.json.encode(active_signature_help, { escape_slash = true }))
+    initial_file:close()
+    ---@diagnostic enable: need-check-nil
+
+    report_log('Issuing signature help retrigger request\n') ---@diagnostic disable-line: undefined-global
+    local context = RETRIGGER_CONTEXT ---@diagnostic disable-line: undefined-global
+    context.activeSignatureHelp = active_signature_help
+    local retrigger_result = vim.lsp.buf_request_sync(0, 'textDocument/signatureHelp', {
+        textDocument = params.textDocument,
+        position = RETRIGGER_POSITION, ---@diagnostic disable-line: undefined-global
+        context = context,
+    })
+    local elapsed_ns = vim.uv.hrtime() - start
+    record_benchmark_result(elapsed_ns) ---@diagnostic disable-line: undefined-global
+    if not retrigger_result then
+        ---@diagnostic disable-next-line: undefined-global
+        report_log('No valid signature help retrigger result returned: ' .. vim.inspect(retrigger_result) .. '\n') ---@diagnostic disable-line: undefined-global
+    elseif retrigger_result and #retrigger_result >= 1 and retrigger_result[1].result then
+        local results_file = io.open('RESULTS_FILE', "w")
+        if not results_file then
+            report_error('Could not open results file') ---@diagnostic disable-line: undefined-global
+            exit() ---@diagnostic disable-line: undefined-global
+        end
+
+        ---@diagnostic disable: need-check-nil
+        results_file:write(vim.json.encode(retrigger_result[1].result, { escape_slash = true }))
+        results_file:close()
+        ---@diagnostic enable: need-check-nil
+    else
+        ---@diagnostic disable-next-line: undefined-global
+        report_log('Empty signature help retrigger result returned: ' .. vim.inspect(retrigger_result) .. '\n')
+        mark_empty_file() ---@diagnostic disable-line: undefined-global
+    end
+    exit() ---@diagnostic disable-line: undefined-global
+end
@@ -48,10 +48,23 @@ local function check_progress_result()
                 report_error('Could not open results file') ---@diagnostic disable-line: undefined-global
                 exit() ---@diagnostic disable-line: undefined-global
             end
+            local result = resp[1].result
+            if RECORD_STATE then ---@diagnostic disable-line: undefined-global
+                -- Apply the returned edits ourselves so that the resulting buffer state
+                -- reflects exactly what the server responded with
+                local client = vim.lsp.get_clients({ bufnr = 0 })[1]
+                local offset_encoding = client and client.offset_encoding or 'utf-16'
+                vim.lsp.util.apply_text_edits(result, 0, offset_encoding)
+                local lines = vim.api.nvim_buf_get_lines(0, 0, -1, true)
+                result = { edits = result, state = table.concat(lines, '\n') }
+            end
             ---@diagnostic disable: need-check-nil
-            results_file:write(vim.json.encode(resp[1].result, { escape_slash = true }))
+            results_file:write(vim.json.encode(result, { escape_slash = true }))
             results_file:close()
             ---@diagnostic enable: need-check-nil
+        elseif resp and #resp >= 1 and resp[1].err then
+            record_response_error(resp[1].err) ---@diagnostic disable-line: undefined-global
+            mark_empty_file() ---@diagnostic disable-line: undefined-global
         else
             ---@diagnostic disable-next-line: undefined-global
             mark_empty_file() ---@diagnostic disable-line: undefined-global
@@ -0,0 +1,505 @@
+//! An experimental pool of prewarmed server instances, for suites where the server's
+//! startup dominates the time spent running tests.
+//!
+//! Each instance of a [`ServerPool`] keeps a single server process alive behind a relay,
+//! which Neovim connects to over a Unix socket in place of spawning the server itself.
+//! The server is initialized once, by the first Neovim instance to connect, after which
+//! the relay answers each subsequent client's `initialize` request with the server's
+//! original response. The `initialize` params of later clients never reach the server,
+//! so settings that only take effect on initialization (i.e. `root_uri`, `root_markers`,
+//! or the client's capabilities) are always those of the pool's template. Neovim is
+//! still spawned fresh for every test.
+//!
+//! # Isolation
+//!
+//! Between tests, an instance is reset as follows:
+//!
+//! - Every document the previous test opened is closed via `textDocument/didClose`, so
+//!   the server discards its buffer state for them.
+//! - The instance's project directory is restored to the pool's template. The template's
+//!   files are rewritten, and the previous test's source and other files that aren't
+//!   part of the template are removed. Files created by other means (i.e. by the server,
+//!   or via [`TestCase::create_on_disk`]) are left in place.
+//! - Responses to requests issued by the previous test, and any messages the server sends
+//!   while no test is running, are dropped rather than delivered to the next test. Requests
+//!   the server sends in the meantime are answered with `null`.
+//! - Each test runs in a new Neovim instance, so no buffers, diagnostics, or other client
+//!   state carry over.
+//!
+//! Server-internal state that isn't tied to open documents (i.e. caches or indexes) is
+//! shared between the tests run on an instance. Likewise, a server that publishes
+//! diagnostics for a document some time after it's closed may deliver them to the next
+//! test.
+//!
+//! ```ignore
+//! let template = TestCase::new("Path to server", TestFile::new("src/main.rs", ""))
+//!     .other_file(cargo_dot_toml());
+//! let pool = ServerPool::new(&template, NonZeroUsize::new(2).unwrap())?;
+//!
+//! let test_case = TestCase::new("Path to server", TestFile::new("src/main.rs", "..."));
+//! lspresso_shot!(pool.run(&test_case, |test_case| {
+//!     test_hover(test_case, Position::new(1, 2), None, Some(&expected))
+//! }));
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, BufRead, BufReader, Read as _, Write},
+    num::NonZeroUsize,
+    os::unix::net::{UnixListener, UnixStream},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::{
+        Arc, Condvar, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use serde_json::Value;
+
+use crate::{
+    LuaReplacement, fetch_results,
+    types::{TestCase, TestError, TestFile, TestResult, TestSetupResult, TestType},
+};
+
+/// A pool of prewarmed server instances that tests can be dispatched to. See the
+/// [module level documentation](self) for the isolation guarantees between tests.
+///
+/// **Experimental**: Only available on Unix, behind the `pool` feature.
+pub struct ServerPool {
+    template: TestCase,
+    instances: Vec<PoolInstance>,
+    idle: Mutex<Vec<usize>>,
+    available: Condvar,
+}
+
+/// A single server process, along with the relay Neovim connects to it through
+struct PoolInstance {
+    /// The test case the instance was prewarmed with, whose `reuse_dir` and
+    /// `pool_socket` are set to the instance's project directory and socket
+    test_case: TestCase,
+    server: Child,
+    relay: Arc<Relay>,
+}
+
+impl ServerPool {
+    /// Spawns `size` instances of `template`'s server, each with its own project
+    /// directory populated with `template`'s source and other files. Each instance is
+    /// prewarmed by opening `template`'s source file and waiting for the server to be
+    /// ready, as determined by `template.start_type`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TestError`](crate::types::TestError) if `template` is invalid, a server
+    /// can't be spawned, or prewarming an instance fails
+    pub fn new(template: &TestCase, size: NonZeroUsize) -> TestResult<Self, Value> {
+        template.validate()?;
+        let mut instances = Vec::with_capacity(size.get());
+        for _ in 0..size.get() {
+            instances.push(PoolInstance::spawn(template)?);
+        }
+        for instance in &instances {
+            // Leaving the method empty skips issuing a request
+            fetch_results::<Value>(
+                &instance.test_case,
+                TestType::LogMessage,
+                &mut vec![LuaReplacement::Other {
+                    from: "REQUEST_METHOD",
+                    to: String::new(),
+                }],
+            )?;
+        }
+
+        Ok(Self {
+            template: template.clone(),
+            idle: Mutex::new((0..instances.len()).collect()),
+            instances,
+            available: Condvar::new(),
+        })
+    }
+
+    /// Runs `test` against an idle instance of the pool, blocking until one is
+    /// available. `test` is passed a copy of `test_case` that runs in the instance's
+    /// project directory, where `test_case`'s source and other files are written for
+    /// the duration of the test.
+    ///
+    /// Since the instance's server has already started, `test_case.start_type` should
+    /// generally be [`ServerStartType::Simple`](crate::types::ServerStartType::Simple).
+    /// Any `reuse_dir` set on `test_case` is ignored, as are settings only sent to the
+    /// server on initialization, see the [module level documentation](self).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TestError`](crate::types::TestError) if `test` fails, or the instance's
+    /// project directory can't be updated
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pool's lock is poisoned
+    pub fn run<T>(
+        &self,
+        test_case: &TestCase,
+        test: impl FnOnce(&TestCase) -> TestResult<(), T>,
+    ) -> TestResult<(), T> {
+        let index = {
+            let mut idle = self.idle.lock().unwrap();
+            loop {
+                if let Some(index) = idle.pop() {
+                    break index;
+                }
+                idle = self.available.wait(idle).unwrap();
+            }
+        };
+        let instance = &self.instances[index];
+        let mut pooled = test_case.clone();
+        pooled.reuse_dir.clone_from(&instance.test_case.reuse_dir);
+        pooled
+            .pool_socket
+            .clone_from(&instance.test_case.pool_socket);
+
+        let result = test_files(test_case)
+            .try_for_each(|file| write_file(&pooled, file))
+            .map_err(TestError::from)
+            .and_then(|()| test(&pooled));
+        let restored = self.restore_files(&pooled, test_case);
+        self.idle.lock().unwrap().push(index);
+        self.available.notify_one();
+
+        result?;
+        restored?;
+        Ok(())
+    }
+
+    /// Restores `pooled`'s project directory to the pool's template after running
+    /// `test_case`, removing any of `test_case`'s files that aren't part of it
+    fn restore_files(&self, pooled: &TestCase, test_case: &TestCase) -> TestSetupResult<()> {
+        for TestFile { path, .. } in test_files(test_case) {
+            if test_files(&self.template).any(|file| file.path == *path) {
+                continue;
+            }
+            match fs::remove_file(pooled.get_source_file_path(path)?) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e)?,
+                _ => {}
+            }
+        }
+        test_files(&self.template).try_for_each(|file| write_file(pooled, file))
+    }
+}
+
+impl Drop for ServerPool {
+    fn drop(&mut self) {
+        for instance in &mut self.instances {
+            instance.relay.shutdown.store(true, Ordering::SeqCst);
+            _ = instance.server.kill();
+            _ = instance.server.wait();
+            if let Some(socket) = &instance.test_case.pool_socket {
+                // Wake the relay's listener so it can observe the shutdown
+                _ = UnixStream::connect(socket);
+                _ = fs::remove_file(socket);
+            }
+        }
+    }
+}
+
+/// Returns `test_case`'s source file, followed by its other files
+fn test_files(test_case: &TestCase) -> impl Iterator<Item = &TestFile> {
+    std::iter::once(&test_case.source_file).chain(&test_case.other_files)
+}
+
+/// Writes `file` into `test_case`'s project directory
+fn write_file(test_case: &TestCase, file: &TestFile) -> TestSetupResult<()> {
+    let path = test_case.get_source_file_path(&file.path)?;
+    // Invariant: test file paths should always have a parent directory
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, file.contents.as_bytes())?;
+    Ok(())
+}
+
+impl PoolInstance {
+    /// Populates a new project directory from `template`, spawns the server, and
+    /// starts relaying messages between it and the socket Neovim connects to
+    fn spawn(template: &TestCase) -> TestSetupResult<Self> {
+        let mut test_case = template.clone();
+        test_case.test_id = TestCase::generate_test_id(template.content_hash());
+        test_case.reuse_dir = None;
+        let project_dir = test_case.get_source_file_path("")?;
+        test_files(template).try_for_each(|file| write_file(&test_case, file))?;
+        let socket = test_case.get_artifacts_dir()?.join("pool.sock");
+        if socket.exists() {
+            fs::remove_file(&socket)?;
+        }
+        let listener = UnixListener::bind(&socket)?;
+
+        let mut server = Command::new(&template.executable_path)
+            .current_dir(&project_dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let relay = Arc::new(Relay {
+            server_stdin: Mutex::new(server.stdin.take().expect("stdin is piped")),
+            state: Mutex::new(RelayState::default()),
+            shutdown: AtomicBool::new(false),
+        });
+        let server_stdout = server.stdout.take().expect("stdout is piped");
+        {
+            let relay = relay.clone();
+            std::thread::spawn(move || relay.relay_server(BufReader::new(server_stdout)));
+        }
+        {
+            let relay = relay.clone();
+            std::thread::spawn(move || relay.relay_clients(&listener));
+        }
+
+        test_case.reuse_dir = Some(project_dir);
+        test_case.pool_socket = Some(socket);
+        Ok(Self {
+            test_case,
+            server,
+            relay,
+        })
+    }
+}
+
+/// Relays messages between a server and the Neovim instance currently connected to it
+struct Relay {
+    server_stdin: Mutex<ChildStdin>,
+    state: Mutex<RelayState>,
+    shutdown: AtomicBool,
+}
+
+#[derive(Default)]
+struct RelayState {
+    /// The connection to the current client, if any
+    client: Option<UnixStream>,
+    /// Incremented for each client, to discard responses meant for previous ones
+    generation: u64,
+    /// The next id to assign to a request relayed to the server
+    next_id: i64,
+    /// The client's id, method, and generation of each request awaiting a response
+    /// from the server, keyed by the id it was relayed with
+    pending: HashMap<i64, (Value, String, u64)>,
+    /// The server's response to the first `initialize` request
+    initialize_result: Option<Value>,
+    initialized: bool,
+    /// The URIs of the documents opened by the current client
+    open_documents: HashSet<String>,
+}
+
+impl Relay {
+    /// Forwards messages from the server to the current client until the server exits
+    fn relay_server(&self, mut server_stdout: impl BufRead) {
+        while let Ok(Some(mut message)) = read_message(&mut server_stdout) {
+            let mut state = self.state.lock().unwrap();
+            if message.get("method").is_none() {
+                // A response, which is delivered only to the client that sent the request
+                let Some((id, method, generation)) = message
+                    .get("id")
+                    .and_then(Value::as_i64)
+                    .and_then(|id| state.pending.remove(&id))
+                else {
+                    continue;
+                };
+                if method == "initialize" && state.initialize_result.is_none() {
+                    state.initialize_result = message.get("result").cloned();
+                }
+                if generation != state.generation {
+                    continue;
+                }
+                message["id"] = id;
+            }
+            let delivered = state
+                .client
+                .as_mut()
+                .is_some_and(|client| write_message(client, &message).is_ok());
+            // Requests sent between tests are answered so they don't stall the server
+            if !delivered && message.get("method").is_some() {
+                if let Some(id) = message.get("id") {
+                    self.send_to_server(
+                        &serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": null }),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Accepts client connections one at a time, relaying each client's messages to the
+    /// server until it disconnects
+    fn relay_clients(&self, listener: &UnixListener) {
+        for stream in listener.incoming() {
+            if self.shutdown.load(Ordering::SeqCst) {
+                return;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let Ok(writer) = stream.try_clone() else {
+                continue;
+            };
+            {
+                let mut state = self.state.lock().unwrap();
+                state.client = Some(writer);
+                state.generation += 1;
+            }
+            let mut reader = BufReader::new(stream);
+            while let Ok(Some(message)) = read_message(&mut reader) {
+                self.relay_client_message(message);
+            }
+            self.disconnect_client();
+        }
+    }
+
+    /// Relays a single message from the client to the server, answering the parts of
+    /// the server's lifecycle that only happen once on the server's behalf
+    fn relay_client_message(&self, mut message: Value) {
+        let mut state = self.state.lock().unwrap();
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let Some(method) = method else {
+            // A response to a request from the server
+            drop(state);
+            self.send_to_server(&message);
+            return;
+        };
+        let Some(id) = message.get("id").cloned() else {
+            let uri = || {
+                message
+                    .pointer("/params/textDocument/uri")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            };
+            match method.as_str() {
+                "initialized" if state.initialized => return,
+                "initialized" => state.initialized = true,
+                // The server outlives the client
+                "exit" => return,
+                "textDocument/didOpen" => {
+                    state.open_documents.extend(uri());
+                }
+                "textDocument/didClose" => {
+                    if let Some(uri) = uri() {
+                        state.open_documents.remove(&uri);
+                    }
+                }
+                "$/cancelRequest" => {
+                    let generation = state.generation;
+                    let relayed_id = state.pending.iter().find_map(|(relayed_id, pending)| {
+                        (pending.0 == message["params"]["id"] && pending.2 == generation)
+                            .then_some(*relayed_id)
+                    });
+                    let Some(relayed_id) = relayed_id else {
+                        return;
+                    };
+                    message["params"]["id"] = Value::from(relayed_id);
+                }
+                _ => {}
+            }
+            drop(state);
+            self.send_to_server(&message);
+            return;
+        };
+
+        let result = match method.as_str() {
+            "initialize" => state.initialize_result.clone(),
+            "shutdown" => Some(Value::Null),
+            _ => None,
+        };
+        if let Some(result) = result {
+            if let Some(client) = state.client.as_mut() {
+                _ = write_message(
+                    client,
+                    &serde_json::json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                );
+            }
+            return;
+        }
+        let relayed_id = state.next_id;
+        state.next_id += 1;
+        let generation = state.generation;
+        state.pending.insert(relayed_id, (id, method, generation));
+        message["id"] = Value::from(relayed_id);
+        drop(state);
+        self.send_to_server(&message);
+    }
+
+    /// Closes any documents the disconnected client left open
+    fn disconnect_client(&self) {
+        let open_documents = {
+            let mut state = self.state.lock().unwrap();
+            state.client = None;
+            std::mem::take(&mut state.open_documents)
+        };
+        for uri in open_documents {
+            self.send_to_server(&serde_json::json!({
+                "jsonrpc": "2.0",
+                "method": "textDocument/didClose",
+                "params": { "textDocument": { "uri": uri } },
+            }));
+        }
+    }
+
+    fn send_to_server(&self, message: &Value) {
+        _ = write_message(&mut *self.server_stdin.lock().unwrap(), message);
+    }
+}
+
+/// Reads a single JSON-RPC message from `reader`, returning `None` once the stream ends
+fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = Some(value.trim().parse::<usize>().map_err(io::Error::other)?);
+            }
+        }
+    }
+    let content_length = content_length
+        .ok_or_else(|| io::Error::other("message is missing its `Content-Length` header"))?;
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(io::Error::other)
+}
+
+/// Writes `message` to `writer` as a single JSON-RPC message
+fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use super::{read_message, write_message};
+
+    #[test]
+    fn message_round_trip() {
+        let messages = [
+            serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            serde_json::json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+        ];
+        let mut bytes = Vec::new();
+        for message in &messages {
+            write_message(&mut bytes, message).unwrap();
+        }
+
+        let mut reader = BufReader::new(bytes.as_slice());
+        for message in messages {
+            assert_eq!(Some(message), read_message(&mut reader).unwrap());
+        }
+        assert_eq!(None, read_message(&mut reader).unwrap());
+    }
+}
@@ -8,9 +8,7 @@ impl CleanResponse for CodeActionResponse {
             match action {
                 CodeActionOrCommand::Command(_) => {}
                 CodeActionOrCommand::CodeAction(action) => {
-                    if let Some(ref mut edit) = action.edit {
-                        *edit = edit.clone().clean_response(test_case)?;
-                    }
+                    *action = action.clone().clean_response(test_case)?;
                 }
             }
         }
@@ -23,6 +21,9 @@ impl CleanResponse for CodeAction {
         if let Some(ref mut edit) = self.edit {
             *edit = edit.clone().clean_response(test_case)?;
         }
+        if let Some(ref mut diagnostics) = self.diagnostics {
+            *diagnostics = diagnostics.clone().clean_response(test_case)?;
+        }
         Ok(self)
     }
 }
@@ -1,9 +1,53 @@
-use lsp_types::CodeLens;
+use lsp_types::{CodeLens, LSPAny, WorkspaceEdit};
+use serde::{Deserialize, Serialize};
 
-use super::{ApproximateEq, CleanResponse};
+use super::{ApproximateEq, CleanResponse, TestCase, TestExecutionResult};
 
 impl CleanResponse for CodeLens {}
 impl CleanResponse for Vec<CodeLens> {}
 
 impl ApproximateEq for CodeLens {}
 impl ApproximateEq for Vec<CodeLens> {}
+
+/// The outcome of executing a code lens's command, see
+/// [`crate::test_code_lens_then_execute`]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLensExecution {
+    /// The server's response to the `workspace/executeCommand` request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<LSPAny>,
+    /// The edit of each `workspace/applyEdit` request the server sent while executing
+    /// the command, in the order they were received
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applied_edits: Vec<WorkspaceEdit>,
+    /// The contents of the target file after `applied_edits` were applied
+    pub state: String,
+}
+
+impl CleanResponse for CodeLensExecution {
+    fn clean_response(mut self, test_case: &TestCase) -> TestExecutionResult<Self> {
+        self.response = self
+            .response
+            .map(|response| response.clean_response(test_case))
+            .transpose()?;
+        self.applied_edits = self
+            .applied_edits
+            .into_iter()
+            .map(|edit| edit.clean_response(test_case))
+            .collect::<TestExecutionResult<_>>()?;
+        Ok(self)
+    }
+}
+
+impl ApproximateEq for CodeLensExecution {
+    fn approx_eq(a: &Self, b: &Self) -> bool {
+        a.response == b.response
+            && a.state == b.state
+            && a.applied_edits.len() == b.applied_edits.len()
+            && a.applied_edits
+                .iter()
+                .zip(&b.applied_edits)
+                .all(|(a, b)| WorkspaceEdit::approx_eq(a, b))
+    }
+}
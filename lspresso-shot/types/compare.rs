@@ -1,10 +1,1299 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, path::Path};
 
 use anstyle::{AnsiColor, Color, Style};
-use serde::Serialize;
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, CodeAction,
+    CodeActionResponse, CodeLens, ColorInformation, ColorPresentation, CompletionItem,
+    CompletionResponse, CompletionTextEdit, Diagnostic, DocumentDiagnosticReport,
+    DocumentHighlight, DocumentHighlightKind, DocumentLink, DocumentSymbol, DocumentSymbolResponse,
+    FoldingRange, GotoDefinitionResponse, Hover, InlayHint, LSPAny, LinkedEditingRanges, Location,
+    Moniker, Position, PrepareRenameResponse, Range, SelectionRange, SemanticTokensFullDeltaResult,
+    SemanticTokensRangeResult, SemanticTokensResult, SignatureHelp, TextEdit, TypeHierarchyItem,
+    Uri, WorkspaceDiagnosticReport, WorkspaceEdit, WorkspaceSymbol, WorkspaceSymbolResponse,
+};
+use serde::{Serialize, de::DeserializeOwned};
 
-pub const GREEN: Option<Color> = Some(anstyle::Color::Ansi(AnsiColor::Green));
-pub const RED: Option<Color> = Some(anstyle::Color::Ansi(AnsiColor::Red));
+use super::{
+    ApproximateEq, StateOrResponse, TestCase, TestType,
+    code_lens::CodeLensExecution,
+    hover::hover_text as flatten_hover,
+    signature_help::{active_indices, signatures_eq},
+};
+use crate::{
+    DocumentHighlightComparator, FoldingRangeComparator, FormattingComparator,
+    RangeFormattingComparator, SelectionRangeComparator,
+};
+
+/// Describes the expected number of items in a list-like response. Useful for
+/// smoke tests where the exact contents of a response are unstable, but the
+/// number of items isn't.
+///
+/// ```ignore
+/// test_references(
+///     &test_case,
+///     cursor_pos,
+///     true,
+///     Some(Comparator::new(|_, actual, _| Cardinality::AtLeast(2).matches(actual))),
+///     Some(&vec![]), // the expected value is ignored by the comparator
+/// )
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Cardinality {
+    /// Exactly `n` items
+    Exactly(usize),
+    /// `n` or more items
+    AtLeast(usize),
+    /// `n` or fewer items
+    AtMost(usize),
+    /// Between `lo` and `hi` items, inclusive
+    Between(usize, usize),
+}
+
+impl Cardinality {
+    /// Checks if `count` satisfies `self`
+    #[must_use]
+    pub const fn check(&self, count: usize) -> bool {
+        match *self {
+            Self::Exactly(n) => count == n,
+            Self::AtLeast(n) => count >= n,
+            Self::AtMost(n) => count <= n,
+            Self::Between(lo, hi) => lo <= count && count <= hi,
+        }
+    }
+
+    /// Checks if the number of items in `actual` satisfies `self`
+    #[must_use]
+    pub fn matches<T: ItemCount + ?Sized>(&self, actual: &T) -> bool {
+        self.check(actual.item_count())
+    }
+}
+
+/// Counts the number of items contained in a list-like response
+pub trait ItemCount {
+    fn item_count(&self) -> usize;
+}
+
+impl<T> ItemCount for Vec<T> {
+    fn item_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> ItemCount for [T] {
+    fn item_count(&self) -> usize {
+        self.len()
+    }
+}
+
+impl ItemCount for CompletionResponse {
+    fn item_count(&self) -> usize {
+        match self {
+            Self::Array(items) => items.len(),
+            Self::List(list) => list.items.len(),
+        }
+    }
+}
+
+/// Nested symbols are flattened, i.e. each child symbol counts as an item.
+impl ItemCount for DocumentSymbolResponse {
+    fn item_count(&self) -> usize {
+        fn count_nested(syms: &[DocumentSymbol]) -> usize {
+            syms.iter()
+                .map(|sym| 1 + sym.children.as_deref().map_or(0, count_nested))
+                .sum()
+        }
+        match self {
+            Self::Flat(syms) => syms.len(),
+            Self::Nested(syms) => count_nested(syms),
+        }
+    }
+}
+
+impl ItemCount for GotoDefinitionResponse {
+    fn item_count(&self) -> usize {
+        match self {
+            Self::Scalar(_) => 1,
+            Self::Array(locs) => locs.len(),
+            Self::Link(links) => links.len(),
+        }
+    }
+}
+
+impl ItemCount for WorkspaceSymbolResponse {
+    fn item_count(&self) -> usize {
+        match self {
+            Self::Flat(syms) => syms.len(),
+            Self::Nested(syms) => syms.len(),
+        }
+    }
+}
+
+/// Checks that `actual` resolves to the active signature `sig_index` and the active
+/// parameter `param_index`, ignoring all other fields (labels, documentation, etc.).
+/// Both places the active parameter can be specified are accounted for, as described
+/// in [`active_indices`].
+///
+/// ```ignore
+/// test_signature_help(
+///     &test_case,
+///     cursor_pos,
+///     None,
+///     Some(Comparator::new(|_, actual, _| compare::signature_active(actual, 0, 1))),
+///     Some(&SignatureHelp::default()), // the expected value is ignored by the comparator
+/// )
+/// ```
+#[must_use]
+pub fn signature_active(actual: &SignatureHelp, sig_index: u32, param_index: u32) -> bool {
+    active_indices(actual) == (Some(sig_index), Some(param_index))
+}
+
+/// Compares two `SignatureHelp`s like their [`ApproximateEq`](super::ApproximateEq)
+/// implementation, except that the active signature and parameter are compared after
+/// being resolved per the spec (see [`active_indices`]). Omitted or out of range
+/// indices therefore match their defaults.
+///
+/// ```ignore
+/// test_signature_help(
+///     &test_case,
+///     cursor_pos,
+///     None,
+///     Some(Comparator::new(|expected, actual, _| compare::signature_help_resolved(expected, actual))),
+///     Some(&expected),
+/// )
+/// ```
+#[must_use]
+pub fn signature_help_resolved(expected: &SignatureHelp, actual: &SignatureHelp) -> bool {
+    expected.signatures.len() == actual.signatures.len()
+        && expected
+            .signatures
+            .iter()
+            .zip(actual.signatures.iter())
+            .all(|(a, b)| signatures_eq(a, b))
+        && active_indices(expected) == active_indices(actual)
+}
+
+/// Returns a comparator for [`test_folding_range`](crate::test_folding_range) that
+/// ignores the expected value, and instead checks that the actual response is
+/// well-formed:
+///
+/// - Each range starts on or before the line it ends on
+/// - Ranges are sorted by their start line
+/// - Any two ranges are either disjoint or nested (no partial overlaps)
+///
+/// ```ignore
+/// test_folding_range(
+///     &test_case,
+///     Some(compare::folding_well_formed()),
+///     Some(&vec![]), // the expected value is ignored by the comparator
+/// )
+/// ```
+#[must_use]
+pub fn folding_well_formed() -> FoldingRangeComparator {
+    FoldingRangeComparator::explained(|_, actual, _| check_folding_well_formed(actual))
+}
+
+fn check_folding_well_formed(ranges: &[FoldingRange]) -> Result<(), String> {
+    for (i, range) in ranges.iter().enumerate() {
+        if range.start_line > range.end_line {
+            return Err(format!(
+                "folding range {i} starts after it ends (lines {}-{})",
+                range.start_line, range.end_line
+            ));
+        }
+    }
+    for (i, pair) in ranges.windows(2).enumerate() {
+        if pair[0].start_line > pair[1].start_line {
+            return Err(format!(
+                "folding ranges {i} and {} are out of order (start lines {} and {})",
+                i + 1,
+                pair[0].start_line,
+                pair[1].start_line
+            ));
+        }
+    }
+    for (i, outer) in ranges.iter().enumerate() {
+        for (j, inner) in ranges.iter().enumerate().skip(i + 1) {
+            // Sorted, so `inner` starts on or after `outer`
+            let disjoint = inner.start_line > outer.end_line;
+            let nested = inner.end_line <= outer.end_line;
+            if !disjoint && !nested {
+                return Err(format!(
+                    "folding ranges {i} (lines {}-{}) and {j} (lines {}-{}) partially overlap",
+                    outer.start_line, outer.end_line, inner.start_line, inner.end_line
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that for each expected `(start_line, end_line)` span, some folding range in
+/// `actual` spans exactly those lines, ignoring character offsets, kinds, and any
+/// additional ranges. This is the recommended matcher for most folding range tests,
+/// as the exact columns a server reports are rarely relevant.
+///
+/// ```ignore
+/// test_folding_range(
+///     &test_case,
+///     Some(FoldingRangeComparator::explained(|_, actual, _| {
+///         compare::folding_covers(&[(3, 10), (12, 15)], actual)
+///     })),
+///     Some(&vec![]), // the expected value is ignored by the comparator
+/// )
+/// ```
+///
+/// # Errors
+///
+/// Returns the first span no folding range covers
+pub fn folding_covers(spans: &[(u32, u32)], actual: &[FoldingRange]) -> Result<(), String> {
+    for (start_line, end_line) in spans {
+        if !actual
+            .iter()
+            .any(|range| range.start_line == *start_line && range.end_line == *end_line)
+        {
+            return Err(format!(
+                "no folding range covers lines {start_line}-{end_line}"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a comparator for [`test_selection_range`](crate::test_selection_range) that
+/// ignores the expected value, and instead checks that in each selection range of
+/// the actual response, every parent strictly contains its child.
+///
+/// ```ignore
+/// test_selection_range(
+///     &test_case,
+///     positions,
+///     Some(compare::selection_range_nested()),
+///     Some(&vec![]), // the expected value is ignored by the comparator
+/// )
+/// ```
+#[must_use]
+pub fn selection_range_nested() -> SelectionRangeComparator {
+    SelectionRangeComparator::explained(|_, actual, _| check_selection_range_nested(actual))
+}
+
+fn check_selection_range_nested(ranges: &[SelectionRange]) -> Result<(), String> {
+    for (i, selection) in ranges.iter().enumerate() {
+        let mut depth = 0;
+        let mut child = selection;
+        if child.range.start > child.range.end {
+            return Err(format!("selection range {i} starts after it ends"));
+        }
+        while let Some(parent) = child.parent.as_deref() {
+            depth += 1;
+            let contains =
+                parent.range.start <= child.range.start && child.range.end <= parent.range.end;
+            if !contains || parent.range == child.range {
+                return Err(format!(
+                    "selection range {i}: parent at depth {depth} ({}) doesn't strictly contain its child ({})",
+                    format_range(&parent.range),
+                    format_range(&child.range)
+                ));
+            }
+            child = parent;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns a comparator for [`test_range_formatting`](crate::test_range_formatting)
+/// that compares only the expected and actual edits intersecting `range`, ignoring
+/// any others. Servers may return edits slightly outside of the requested range
+/// (i.e. normalizing whitespace at its boundaries). Edits touching either end of
+/// `range` are considered to intersect it.
+///
+/// ```ignore
+/// test_range_formatting(
+///     &test_case,
+///     range,
+///     None,
+///     Some(compare::edits_within(range)),
+///     Some(&expected),
+/// )
+/// ```
+#[must_use]
+pub fn edits_within(range: Range) -> RangeFormattingComparator {
+    RangeFormattingComparator::explained(move |expected, actual, _| {
+        check_edits_within(range, expected, actual, false)
+    })
+}
+
+/// Like [`edits_within`], but additionally fails if any edit in the actual response
+/// isn't fully contained within `range`.
+#[must_use]
+pub fn edits_exactly_within(range: Range) -> RangeFormattingComparator {
+    RangeFormattingComparator::explained(move |expected, actual, _| {
+        check_edits_within(range, expected, actual, true)
+    })
+}
+
+fn check_edits_within(
+    range: Range,
+    expected: &[TextEdit],
+    actual: &[TextEdit],
+    strict: bool,
+) -> Result<(), String> {
+    if strict {
+        let contained =
+            |edit: &TextEdit| range.start <= edit.range.start && edit.range.end <= range.end;
+        if let Some((i, edit)) = actual.iter().enumerate().find(|(_, edit)| !contained(edit)) {
+            return Err(format!(
+                "actual edit {i} ({}) falls outside of the range {}",
+                format_range(&edit.range),
+                format_range(&range)
+            ));
+        }
+    }
+    let intersecting = |edits: &[TextEdit]| -> Vec<TextEdit> {
+        edits
+            .iter()
+            .filter(|edit| edit.range.start <= range.end && range.start <= edit.range.end)
+            .cloned()
+            .collect()
+    };
+    let expected = intersecting(expected);
+    let actual = intersecting(actual);
+    if expected != actual {
+        return Err(format!(
+            "edits intersecting the range {} differ (expected {} edit(s), got {})",
+            format_range(&range),
+            expected.len(),
+            actual.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns a comparator for [`test_formatting`](crate::test_formatting) that compares
+/// the formatted buffer text after normalizing line endings, trailing whitespace on
+/// each line, and the final newline. Any edits (for `StateOrResponse::Both`) are still
+/// compared exactly.
+///
+/// ```ignore
+/// test_formatting(
+///     &test_case,
+///     None,
+///     Some(compare::text_normalized()),
+///     &StateOrResponse::State("fn main() {}".to_string()), // trailing newline not required
+/// )
+/// ```
+#[must_use]
+pub fn text_normalized() -> FormattingComparator {
+    FormattingComparator::new(|expected, actual, _| states_match(expected, actual, false))
+}
+
+/// Like [`text_normalized`], but leading and trailing blank lines are ignored as well
+#[must_use]
+pub fn text_normalized_trim_blank_lines() -> FormattingComparator {
+    FormattingComparator::new(|expected, actual, _| states_match(expected, actual, true))
+}
+
+fn states_match(
+    expected: &StateOrResponse<Vec<TextEdit>>,
+    actual: &StateOrResponse<Vec<TextEdit>>,
+    trim_blank_lines: bool,
+) -> bool {
+    let texts_match = |expected: &str, actual: &str| {
+        normalize_text(expected, trim_blank_lines) == normalize_text(actual, trim_blank_lines)
+    };
+    match (expected, actual) {
+        (StateOrResponse::State(expected), StateOrResponse::State(actual)) => {
+            texts_match(expected, actual)
+        }
+        (
+            StateOrResponse::Both {
+                edits: expected_edits,
+                state: expected_state,
+            },
+            StateOrResponse::Both {
+                edits: actual_edits,
+                state: actual_state,
+            },
+        ) => expected_edits == actual_edits && texts_match(expected_state, actual_state),
+        _ => expected == actual,
+    }
+}
+
+/// Normalizes `text` for [`text_normalized`]. Line endings are converted to `\n` and
+/// trailing whitespace is stripped from each line, along with any trailing newlines.
+/// If `trim_blank_lines` is set, leading blank lines are removed as well.
+fn normalize_text(text: &str, trim_blank_lines: bool) -> String {
+    let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    let first_content = if trim_blank_lines {
+        lines
+            .iter()
+            .position(|line| !line.is_empty())
+            .unwrap_or(lines.len())
+    } else {
+        0
+    };
+    lines[first_content..].join("\n")
+}
+
+/// Returns a comparator for [`test_document_highlight`](crate::test_document_highlight)
+/// that compares the expected and actual highlights as a set of `(range, kind)` pairs,
+/// ignoring ordering. Useful when the point of a test is how each occurrence is
+/// classified (i.e. that an assignment is reported as [`DocumentHighlightKind::WRITE`]).
+///
+/// ```ignore
+/// test_document_highlight(
+///     &test_case,
+///     cursor_pos,
+///     Some(compare::highlights_by_kind()),
+///     Some(&vec![
+///         DocumentHighlight {
+///             range: Range::new(Position::new(1, 8), Position::new(1, 11)),
+///             kind: Some(DocumentHighlightKind::READ),
+///         },
+///         DocumentHighlight {
+///             range: Range::new(Position::new(0, 4), Position::new(0, 7)),
+///             kind: Some(DocumentHighlightKind::WRITE),
+///         },
+///     ]),
+/// )
+/// ```
+#[must_use]
+pub fn highlights_by_kind() -> DocumentHighlightComparator {
+    DocumentHighlightComparator::explained(|expected, actual, _| {
+        check_highlights_by_kind(expected, actual)
+    })
+}
+
+fn check_highlights_by_kind(
+    expected: &[DocumentHighlight],
+    actual: &[DocumentHighlight],
+) -> Result<(), String> {
+    let mut unmatched: Vec<(Range, Option<DocumentHighlightKind>)> = expected
+        .iter()
+        .map(|highlight| (highlight.range, highlight.kind))
+        .collect();
+    for (i, highlight) in actual.iter().enumerate() {
+        let Some(idx) = unmatched
+            .iter()
+            .position(|(range, kind)| *range == highlight.range && *kind == highlight.kind)
+        else {
+            return Err(format!(
+                "unexpected highlight {i} ({}, kind {:?})",
+                format_range(&highlight.range),
+                highlight.kind
+            ));
+        };
+        unmatched.swap_remove(idx);
+    }
+    if let Some((range, kind)) = unmatched.first() {
+        return Err(format!(
+            "missing highlight ({}, kind {kind:?}), {} expected highlight(s) unmatched",
+            format_range(range),
+            unmatched.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Call hierarchy responses whose items can be compared via [`call_items_by`]
+pub trait CallItems {
+    /// The response's items: each prepared item, the caller of each incoming call, or
+    /// the callee of each outgoing call
+    fn call_items(&self) -> Vec<&CallHierarchyItem>;
+}
+
+impl CallItems for Vec<CallHierarchyItem> {
+    fn call_items(&self) -> Vec<&CallHierarchyItem> {
+        self.iter().collect()
+    }
+}
+
+impl CallItems for Vec<CallHierarchyIncomingCall> {
+    fn call_items(&self) -> Vec<&CallHierarchyItem> {
+        self.iter().map(|call| &call.from).collect()
+    }
+}
+
+impl CallItems for Vec<CallHierarchyOutgoingCall> {
+    fn call_items(&self) -> Vec<&CallHierarchyItem> {
+        self.iter().map(|call| &call.to).collect()
+    }
+}
+
+/// Compares the items of call hierarchy responses (from
+/// [`test_prepare_call_hierarchy`](crate::test_prepare_call_hierarchy),
+/// [`test_incoming_calls`](crate::test_incoming_calls), or
+/// [`test_outgoing_calls`](crate::test_outgoing_calls)) by the key `key` extracts from
+/// each item, as a set. Any fields `key` doesn't extract (i.e. `data`, `detail`, or
+/// `range`), the ranges of each call, and the order of the items are ignored. The uris
+/// of both the expected and actual items are cleaned before `key` is applied, so they're
+/// relative to the test case's `src` directory.
+///
+/// ```ignore
+/// test_incoming_calls(
+///     &test_case,
+///     &item,
+///     Some(Comparator::explained(|expected, actual, test_case| {
+///         compare::call_items_by(expected, actual, test_case, |item| {
+///             (item.name.clone(), item.uri.clone(), item.selection_range)
+///         })
+///     })),
+///     Some(&vec![CallHierarchyIncomingCall {
+///         from: CallHierarchyItem {
+///             name: "caller".to_string(),
+///             uri: Uri::from_str("main.rs").unwrap(),
+///             selection_range: Range::new(Position::new(3, 3), Position::new(3, 9)),
+///             ..item.clone() // ignored
+///         },
+///         from_ranges: vec![], // ignored
+///     }]),
+/// )
+/// ```
+///
+/// # Errors
+///
+/// Returns a description of the first unexpected or missing item
+pub fn call_items_by<T, K>(
+    expected: &T,
+    actual: &T,
+    test_case: &TestCase,
+    key: impl Fn(&CallHierarchyItem) -> K,
+) -> Result<(), String>
+where
+    T: CallItems,
+    K: PartialEq + std::fmt::Debug,
+{
+    let cleaned = |items: Vec<&CallHierarchyItem>| {
+        items
+            .into_iter()
+            .map(|item| {
+                let uri = super::clean_uri(&item.uri, test_case).map_err(|e| e.to_string())?;
+                Ok(CallHierarchyItem {
+                    uri,
+                    ..item.clone()
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+    };
+    let expected = cleaned(expected.call_items())?;
+    let actual = cleaned(actual.call_items())?;
+    check_call_items(&expected, &actual, key)
+}
+
+fn check_call_items<K: PartialEq + std::fmt::Debug>(
+    expected: &[CallHierarchyItem],
+    actual: &[CallHierarchyItem],
+    key: impl Fn(&CallHierarchyItem) -> K,
+) -> Result<(), String> {
+    let mut unmatched: Vec<K> = expected.iter().map(&key).collect();
+    for (i, item) in actual.iter().enumerate() {
+        let item_key = key(item);
+        let Some(idx) = unmatched.iter().position(|k| *k == item_key) else {
+            return Err(format!("unexpected item {i} with key {item_key:?}"));
+        };
+        unmatched.swap_remove(idx);
+    }
+    if let Some(item_key) = unmatched.first() {
+        return Err(format!(
+            "missing item with key {item_key:?}, {} expected item(s) unmatched",
+            unmatched.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that the targets of a goto-family response (i.e. from `test_definition` or
+/// `test_implementation`) match `expected` as a set, ignoring everything else. Each
+/// target is a path and range: for `Link` responses, the target uri and
+/// `target_selection_range`, and for `Scalar` and `Array` responses, each location's
+/// uri and range. `origin_selection_range`, `target_range`, and the order of the
+/// targets are ignored. The uris of the actual targets are cleaned before they're
+/// compared, so `expected` paths are relative to the test case's `src` directory.
+///
+/// ```ignore
+/// test_definition(
+///     &test_case,
+///     cursor_pos,
+///     Some(Comparator::explained(|_, actual, test_case| {
+///         compare::goto_targets(
+///             &[("lib.rs", Range::new(Position::new(2, 7), Position::new(2, 10)))],
+///             actual,
+///             test_case,
+///         )
+///     })),
+///     Some(&GotoDefinitionResponse::Array(vec![])), // the expected value is ignored by the comparator
+/// )
+/// ```
+///
+/// # Errors
+///
+/// Returns a description of the first unexpected or missing target
+pub fn goto_targets<P: AsRef<Path>>(
+    expected: &[(P, Range)],
+    actual: &GotoDefinitionResponse,
+    test_case: &TestCase,
+) -> Result<(), String> {
+    let targets = goto_target_list(actual)
+        .into_iter()
+        .map(|(uri, range)| {
+            let uri = super::clean_uri(uri, test_case).map_err(|e| e.to_string())?;
+            Ok((uri.path().as_str().to_string(), range))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    check_goto_targets(expected, &targets)
+}
+
+/// Flattens the targets of `response` into uri and range pairs, see [`goto_targets`]
+fn goto_target_list(response: &GotoDefinitionResponse) -> Vec<(&Uri, Range)> {
+    match response {
+        GotoDefinitionResponse::Scalar(loc) => vec![(&loc.uri, loc.range)],
+        GotoDefinitionResponse::Array(locs) => {
+            locs.iter().map(|loc| (&loc.uri, loc.range)).collect()
+        }
+        GotoDefinitionResponse::Link(links) => links
+            .iter()
+            .map(|link| (&link.target_uri, link.target_selection_range))
+            .collect(),
+    }
+}
+
+fn check_goto_targets<P: AsRef<Path>>(
+    expected: &[(P, Range)],
+    actual: &[(String, Range)],
+) -> Result<(), String> {
+    let mut unmatched: Vec<(&Path, Range)> = expected
+        .iter()
+        .map(|(path, range)| (path.as_ref(), *range))
+        .collect();
+    for (i, (path, range)) in actual.iter().enumerate() {
+        let Some(idx) = unmatched
+            .iter()
+            .position(|(p, r)| *p == Path::new(path) && r == range)
+        else {
+            return Err(format!(
+                "unexpected target {i} ({path} at {})",
+                format_range(range)
+            ));
+        };
+        unmatched.swap_remove(idx);
+    }
+    if let Some((path, range)) = unmatched.first() {
+        return Err(format!(
+            "missing target {} at {}, {} expected target(s) unmatched",
+            path.display(),
+            format_range(range),
+            unmatched.len()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Semantic tokens responses whose `result_id` can be ignored via [`ignore_result_id`]
+pub trait ResultId {
+    /// Clears the response's `result_id`, if it has one
+    fn clear_result_id(&mut self);
+}
+
+impl ResultId for SemanticTokensResult {
+    fn clear_result_id(&mut self) {
+        match self {
+            Self::Tokens(tokens) => tokens.result_id = None,
+            Self::Partial(_) => {}
+        }
+    }
+}
+
+impl ResultId for SemanticTokensRangeResult {
+    fn clear_result_id(&mut self) {
+        match self {
+            Self::Tokens(tokens) => tokens.result_id = None,
+            Self::Partial(_) => {}
+        }
+    }
+}
+
+impl ResultId for SemanticTokensFullDeltaResult {
+    fn clear_result_id(&mut self) {
+        match self {
+            Self::Tokens(tokens) => tokens.result_id = None,
+            Self::TokensDelta(delta) => delta.result_id = None,
+            Self::PartialTokensDelta { .. } => {}
+        }
+    }
+}
+
+/// Returns a comparator for the semantic tokens tests that ignores the `result_id` of
+/// both responses entirely. By default, only the presence of a `result_id` is compared,
+/// so an expected `result_id` of `None` doesn't match a response with one.
+///
+/// ```ignore
+/// test_semantic_tokens_full(
+///     &test_case,
+///     Some(compare::ignore_result_id()),
+///     Some(&SemanticTokensResult::Tokens(SemanticTokens {
+///         result_id: None, // ignored
+///         data: vec![token],
+///     })),
+/// )
+/// ```
+#[must_use]
+pub fn ignore_result_id<T>() -> Comparator<T>
+where
+    T: ResultId + ApproximateEq + Clone + Serialize,
+{
+    Comparator::new(|expected: &T, actual: &T, test_case| {
+        let (mut expected, mut actual) = (expected.clone(), actual.clone());
+        expected.clear_result_id();
+        actual.clear_result_id();
+        T::approx_eq(&expected, &actual) || within_float_tolerance(test_case, &expected, &actual)
+    })
+}
+
+/// Checks that accepting the completion item labeled `label` in `actual`, with the
+/// cursor at `position`, turns the test case's target file into `expected_text`. The
+/// item's `text_edit` (or the list's default edit range) is applied along with any
+/// `additional_text_edits`. Items without an edit insert their `insert_text` (or
+/// label) at `position`. For `InsertReplaceEdit`s, the insert range is used. Snippet
+/// syntax isn't expanded, so the resulting text contains any placeholders verbatim.
+///
+/// This catches off-by-one edit ranges that comparing labels alone would miss.
+///
+/// ```ignore
+/// test_completion(
+///     &test_case,
+///     Position::new(1, 8),
+///     None,
+///     Some(Comparator::explained(|_, actual, test_case| {
+///         compare::completion_applies_as(actual, test_case, Position::new(1, 8), "println!", "fn main() {\n    println!\n}")
+///     })),
+///     Some(&CompletionResponse::Array(vec![])), // the expected value is ignored by the comparator
+/// )
+/// ```
+///
+/// # Errors
+///
+/// Returns a description of why the item doesn't apply as `expected_text`
+pub fn completion_applies_as(
+    actual: &CompletionResponse,
+    test_case: &TestCase,
+    position: Position,
+    label: &str,
+    expected_text: &str,
+) -> Result<(), String> {
+    let source = test_case
+        .source_text()
+        .map_err(|e| format!("failed to read the source file: {e}"))?;
+    check_completion_applies_as(&source, actual, position, label, expected_text)
+}
+
+fn check_completion_applies_as(
+    source: &str,
+    actual: &CompletionResponse,
+    position: Position,
+    label: &str,
+    expected_text: &str,
+) -> Result<(), String> {
+    let items = match actual {
+        CompletionResponse::Array(items) => items,
+        CompletionResponse::List(list) => &list.items,
+    };
+    let Some(item) = items.iter().find(|item| item.label == label) else {
+        return Err(format!("no completion item labeled \"{label}\""));
+    };
+    let main_edit = match &item.text_edit {
+        Some(CompletionTextEdit::Edit(edit)) => edit.clone(),
+        Some(CompletionTextEdit::InsertAndReplace(edit)) => TextEdit {
+            range: edit.insert,
+            new_text: edit.new_text.clone(),
+        },
+        None => TextEdit {
+            range: Range::new(position, position),
+            new_text: item
+                .insert_text
+                .clone()
+                .unwrap_or_else(|| item.label.clone()),
+        },
+    };
+    let mut edits = vec![main_edit];
+    edits.extend(item.additional_text_edits.iter().flatten().cloned());
+    let applied = apply_edits(source, &edits)?;
+    if applied != expected_text {
+        return Err(format!(
+            "applying \"{label}\" results in {applied:?}, expected {expected_text:?}"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks that the completion item labeled `before` precedes the one labeled `after`
+/// in `actual`, as a client would display them. Items are ordered by their `sort_text`
+/// (falling back to their label), with ties keeping the server's order, so `List` and
+/// `Array` responses are handled alike. This tests completion ranking without
+/// specifying the full list.
+///
+/// ```ignore
+/// test_completion(
+///     &test_case,
+///     Position::new(1, 8),
+///     None,
+///     Some(Comparator::explained(|_, actual, _| {
+///         compare::completion_order(actual, "println!", "print!")
+///     })),
+///     Some(&CompletionResponse::Array(vec![])), // the expected value is ignored by the comparator
+/// )
+/// ```
+///
+/// # Errors
+///
+/// Returns a description of why `before` doesn't precede `after`
+pub fn completion_order(
+    actual: &CompletionResponse,
+    before: &str,
+    after: &str,
+) -> Result<(), String> {
+    let items = match actual {
+        CompletionResponse::Array(items) => items,
+        CompletionResponse::List(list) => &list.items,
+    };
+    let mut ordered: Vec<&CompletionItem> = items.iter().collect();
+    // Stable, so items with equal sort texts keep the server's order
+    ordered.sort_by_cached_key(|item| item.sort_text.as_ref().unwrap_or(&item.label).clone());
+    let index_of = |label: &str| {
+        ordered
+            .iter()
+            .position(|item| item.label == label)
+            .ok_or_else(|| format!("no completion item labeled \"{label}\""))
+    };
+    let (before_index, after_index) = (index_of(before)?, index_of(after)?);
+    if before_index >= after_index {
+        return Err(format!(
+            "expected \"{before}\" (at index {before_index}) to precede \"{after}\" (at index {after_index})"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Applies `edits` to `text`. As with the rest of the library, positions are
+/// interpreted in terms of characters.
+fn apply_edits(text: &str, edits: &[TextEdit]) -> Result<String, String> {
+    let mut offsets = Vec::with_capacity(edits.len());
+    for edit in edits {
+        let start = position_offset(text, edit.range.start);
+        let end = position_offset(text, edit.range.end);
+        match (start, end) {
+            (Some(start), Some(end)) if start <= end => offsets.push((start, end, edit)),
+            _ => {
+                return Err(format!(
+                    "edit range {} is invalid or outside of the source file's contents",
+                    format_range(&edit.range)
+                ));
+            }
+        }
+    }
+    offsets.sort_by_key(|(start, end, _)| (*start, *end));
+    for pair in offsets.windows(2) {
+        if pair[0].1 > pair[1].0 {
+            return Err(format!(
+                "edit ranges {} and {} overlap",
+                format_range(&pair[0].2.range),
+                format_range(&pair[1].2.range)
+            ));
+        }
+    }
+    let mut applied = text.to_string();
+    // Apply from the end of the text, so earlier offsets remain valid
+    for (start, end, edit) in offsets.into_iter().rev() {
+        applied.replace_range(start..end, &edit.new_text);
+    }
+
+    Ok(applied)
+}
+
+/// Converts `position` to a byte offset into `text`, if it's within bounds
+fn position_offset(text: &str, position: Position) -> Option<usize> {
+    let mut line_start = 0;
+    for (i, line) in text.split_inclusive('\n').enumerate() {
+        if i == position.line as usize {
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            return content
+                .char_indices()
+                .map(|(idx, _)| idx)
+                .chain(std::iter::once(content.len()))
+                .nth(position.character as usize)
+                .map(|idx| line_start + idx);
+        }
+        line_start += line.len();
+    }
+    // The (empty) line following a trailing newline, or the start of an empty file
+    let line_count = text.split_inclusive('\n').count();
+    (position.line as usize == line_count
+        && position.character == 0
+        && (text.is_empty() || text.ends_with('\n')))
+    .then_some(text.len())
+}
+
+/// Compares only the text of `expected` and `actual`'s contents, ignoring their ranges
+/// and the form the contents are given in. Each item of a `HoverContents::Array` is
+/// placed on its own line, and the languages of `MarkedString`s and kinds of
+/// `MarkupContent` are discarded. Useful when a server's choice of hover form is an
+/// implementation detail.
+///
+/// ```ignore
+/// test_hover(
+///     &test_case,
+///     cursor_pos,
+///     Some(Comparator::explained(|expected, actual, _| compare::hover_text(expected, actual))),
+///     Some(&expected),
+/// )
+/// ```
+///
+/// # Errors
+///
+/// Returns a description of the differing text
+pub fn hover_text(expected: &Hover, actual: &Hover) -> Result<(), String> {
+    let expected = flatten_hover(&expected.contents);
+    let actual = flatten_hover(&actual.contents);
+    if expected != actual {
+        return Err(format!(
+            "hover text differs, expected {expected:?}, got {actual:?}"
+        ));
+    }
+    Ok(())
+}
+
+/// Returns a comparator that only enforces the fields set in the expected value. Any
+/// field the expected value leaves as `None` (or otherwise serializes as `null` or
+/// omits) matches whatever the actual response contains, as do any fields present
+/// only in the actual response. Arrays must have the same length, with each item
+/// compared in the same way. Numbers are compared within the test case's
+/// [`TestCase::float_tolerance`].
+///
+/// This is deliberately looser than the default equality, and is opt-in. It's useful
+/// for rich types like `CompletionItem` or `Diagnostic`, where spelling out every
+/// field would over-specify the test.
+///
+/// ```ignore
+/// test_completion(
+///     &test_case,
+///     cursor_pos,
+///     None,
+///     Some(compare::none_is_wildcard()),
+///     Some(&CompletionResponse::Array(vec![CompletionItem {
+///         label: "println!".to_string(),
+///         ..Default::default() // all other fields are ignored
+///     }])),
+/// )
+/// ```
+#[must_use]
+pub fn none_is_wildcard<T: Serialize>() -> Comparator<T> {
+    Comparator::explained(|expected: &T, actual: &T, test_case| {
+        let to_json = |value: &T| serde_json::to_value(value).map_err(|e| e.to_string());
+        check_set_fields(
+            &to_json(expected)?,
+            &to_json(actual)?,
+            "$",
+            test_case.float_tolerance,
+        )
+    })
+}
+
+/// Indicates if `expected` and `actual` are equal, treating two numbers as equal if
+/// they differ by at most `tolerance`. This is the check applied by
+/// [`TestCase::float_tolerance`], for use in custom comparators.
+///
+/// ```ignore
+/// test_document_color(
+///     &test_case,
+///     Some(Comparator::new(|expected, actual, _| {
+///         let to_json = |colors: &Vec<ColorInformation>| serde_json::to_value(colors).unwrap();
+///         compare::values_within_tolerance(&to_json(expected), &to_json(actual), 1e-3)
+///     })),
+///     &expected,
+/// )
+/// ```
+#[must_use]
+pub fn values_within_tolerance(
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    tolerance: f64,
+) -> bool {
+    use serde_json::Value;
+
+    match (expected, actual) {
+        (Value::Number(expected), Value::Number(actual)) => {
+            match (expected.as_f64(), actual.as_f64()) {
+                (Some(expected), Some(actual)) => (expected - actual).abs() <= tolerance,
+                _ => expected == actual,
+            }
+        }
+        (Value::Object(expected), Value::Object(actual)) => {
+            expected.len() == actual.len()
+                && expected.iter().all(|(key, expected)| {
+                    actual
+                        .get(key)
+                        .is_some_and(|actual| values_within_tolerance(expected, actual, tolerance))
+                })
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual)
+                    .all(|(expected, actual)| values_within_tolerance(expected, actual, tolerance))
+        }
+        (expected, actual) => expected == actual,
+    }
+}
+
+/// Indicates if `expected` and `actual` match once `test_case.float_tolerance` is
+/// accounted for. Always `false` if no tolerance is set, as the responses have
+/// already been compared exactly.
+pub(crate) fn within_float_tolerance<T: Serialize>(
+    test_case: &TestCase,
+    expected: &T,
+    actual: &T,
+) -> bool {
+    if test_case.float_tolerance <= 0.0 {
+        return false;
+    }
+    match (serde_json::to_value(expected), serde_json::to_value(actual)) {
+        (Ok(expected), Ok(actual)) => {
+            values_within_tolerance(&expected, &actual, test_case.float_tolerance)
+        }
+        _ => false,
+    }
+}
+
+/// Checks that every non-null value in `expected` is matched in `actual`, treating
+/// numbers within `tolerance` of each other as equal. `path` describes the location
+/// of `expected` within the overall value
+fn check_set_fields(
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    path: &str,
+    tolerance: f64,
+) -> Result<(), String> {
+    use serde_json::Value;
+
+    match (expected, actual) {
+        (Value::Null, _) => Ok(()),
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                if expected.is_null() {
+                    continue;
+                }
+                let path = format!("{path}.{key}");
+                let Some(actual) = actual.get(key) else {
+                    return Err(format!("{path} is missing, expected {expected}"));
+                };
+                check_set_fields(expected, actual, &path, tolerance)?;
+            }
+            Ok(())
+        }
+        (Value::Array(expected), Value::Array(actual)) => {
+            if expected.len() != actual.len() {
+                return Err(format!(
+                    "{path} has {} item(s), expected {}",
+                    actual.len(),
+                    expected.len()
+                ));
+            }
+            for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                check_set_fields(expected, actual, &format!("{path}[{i}]"), tolerance)?;
+            }
+            Ok(())
+        }
+        (expected, actual)
+            if expected == actual || values_within_tolerance(expected, actual, tolerance) =>
+        {
+            Ok(())
+        }
+        (expected, actual) => Err(format!("{path} is {actual}, expected {expected}")),
+    }
+}
+
+/// Compares an expected and actual response, both given as JSON. Returns an error if
+/// either can't be deserialized as the response type of the request being compared.
+pub type JsonComparator = fn(&serde_json::Value, &serde_json::Value) -> serde_json::Result<bool>;
+
+/// Deserializes `expected` and `actual` as `T`, then compares them with `T`'s
+/// [`ApproximateEq`] implementation
+fn json_approx_eq<T>(
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+) -> serde_json::Result<bool>
+where
+    T: DeserializeOwned + ApproximateEq,
+{
+    let expected = T::deserialize(expected)?;
+    let actual = T::deserialize(actual)?;
+    Ok(T::approx_eq(&expected, &actual))
+}
+
+/// Returns the comparator used by default for responses to `test_type`, for callers
+/// that only have the responses as JSON (i.e. a CLI). The responses are deserialized
+/// as the request's `lsp_types` response type, and compared with the same
+/// [`ApproximateEq`] implementation the corresponding `test_*` function uses, so the
+/// documented serialization ambiguities of each type are accounted for.
+///
+/// Returns `None` for test types whose results aren't compared for equality
+/// ([`TestType::LogMessage`] and [`TestType::Progress`]).
+#[must_use]
+pub fn default_comparator(test_type: TestType) -> Option<JsonComparator> {
+    Some(match test_type {
+        TestType::CodeAction => json_approx_eq::<CodeActionResponse>,
+        TestType::CodeActionResolve => json_approx_eq::<CodeAction>,
+        TestType::CodeLens => json_approx_eq::<Vec<CodeLens>>,
+        TestType::CodeLensResolve => json_approx_eq::<CodeLens>,
+        TestType::CodeLensThenExecute => json_approx_eq::<CodeLensExecution>,
+        TestType::ColorPresentation => json_approx_eq::<Vec<ColorPresentation>>,
+        TestType::Completion => json_approx_eq::<CompletionResponse>,
+        TestType::CompletionResolve => json_approx_eq::<CompletionItem>,
+        TestType::Custom | TestType::WorkspaceExecuteCommand => json_approx_eq::<LSPAny>,
+        TestType::Declaration
+        | TestType::Definition
+        | TestType::Implementation
+        | TestType::TypeDefinition => json_approx_eq::<GotoDefinitionResponse>,
+        TestType::Diagnostic | TestType::DiagnosticUnchanged => {
+            json_approx_eq::<DocumentDiagnosticReport>
+        }
+        TestType::DocumentColor => json_approx_eq::<Vec<ColorInformation>>,
+        TestType::DocumentHighlight => json_approx_eq::<Vec<DocumentHighlight>>,
+        TestType::DocumentLink => json_approx_eq::<Vec<DocumentLink>>,
+        TestType::DocumentLinkResolve => json_approx_eq::<DocumentLink>,
+        TestType::DocumentSymbol => json_approx_eq::<DocumentSymbolResponse>,
+        TestType::FoldingRange => json_approx_eq::<Vec<FoldingRange>>,
+        TestType::Formatting | TestType::OnTypeFormatting | TestType::RangeFormatting => {
+            json_approx_eq::<Vec<TextEdit>>
+        }
+        TestType::Hover => json_approx_eq::<Hover>,
+        TestType::IncomingCalls => json_approx_eq::<Vec<CallHierarchyIncomingCall>>,
+        TestType::InlayHint => json_approx_eq::<Vec<InlayHint>>,
+        TestType::LinkedEditingRange => json_approx_eq::<LinkedEditingRanges>,
+        TestType::LogMessage | TestType::Progress => return None,
+        TestType::Moniker => json_approx_eq::<Vec<Moniker>>,
+        TestType::OutgoingCalls => json_approx_eq::<Vec<CallHierarchyOutgoingCall>>,
+        TestType::PrepareCallHierarchy => json_approx_eq::<Vec<CallHierarchyItem>>,
+        TestType::PrepareRename => json_approx_eq::<PrepareRenameResponse>,
+        TestType::PrepareTypeHierarchy => json_approx_eq::<Vec<TypeHierarchyItem>>,
+        TestType::PublishDiagnostics => json_approx_eq::<Vec<Diagnostic>>,
+        TestType::References => json_approx_eq::<Vec<Location>>,
+        TestType::Rename
+        | TestType::WorkspaceWillCreateFiles
+        | TestType::WorkspaceWillDeleteFiles
+        | TestType::WorkspaceWillRenameFiles => json_approx_eq::<WorkspaceEdit>,
+        TestType::SelectionRange => json_approx_eq::<Vec<SelectionRange>>,
+        TestType::SemanticTokensFull => json_approx_eq::<SemanticTokensResult>,
+        TestType::SemanticTokensFullDelta => json_approx_eq::<SemanticTokensFullDeltaResult>,
+        TestType::SemanticTokensRange => json_approx_eq::<SemanticTokensRangeResult>,
+        TestType::SignatureHelp | TestType::SignatureHelpRetrigger => {
+            json_approx_eq::<SignatureHelp>
+        }
+        TestType::WorkspaceDiagnostic => json_approx_eq::<WorkspaceDiagnosticReport>,
+        TestType::WorkspaceSymbol => json_approx_eq::<WorkspaceSymbolResponse>,
+        TestType::WorkspaceSymbolResolve => json_approx_eq::<WorkspaceSymbol>,
+    })
+}
+
+fn format_range(range: &Range) -> String {
+    format!(
+        "{}:{}-{}:{}",
+        range.start.line, range.start.character, range.end.line, range.end.character
+    )
+}
+
+/// A custom comparison between the expected and actual responses of a test, used in
+/// place of the default equality check. Comparators either return a plain `bool` (see
+/// [`Comparator::new`]), or a `Result` whose error describes why the actual response
+/// was rejected (see [`Comparator::explained`]). The description is displayed in the
+/// resulting [`ResponseMismatchError`](super::ResponseMismatchError).
+///
+/// ```ignore
+/// test_hover(
+///     &test_case,
+///     cursor_pos,
+///     Some(Comparator::explained(|expected, actual, _| {
+///         if actual.range.is_none() {
+///             return Err("hover is missing a range".to_string());
+///         }
+///         compare::hover_text(expected, actual)
+///     })),
+///     Some(&expected),
+/// )
+/// ```
+#[allow(clippy::type_complexity)]
+pub struct Comparator<T>(
+    Box<dyn Fn(&T, &T, &TestCase) -> Result<(), Option<String>> + Send + Sync>,
+);
+
+impl<T> Comparator<T> {
+    /// Creates a comparator from a `bool`-returning comparison, i.e. a closure or
+    /// function written against earlier versions of the library. A rejection has no
+    /// description.
+    pub fn new<F>(cmp: F) -> Self
+    where
+        F: Fn(&T, &T, &TestCase) -> bool + Send + Sync + 'static,
+    {
+        Self(Box::new(move |expected, actual, test_case| {
+            if cmp(expected, actual, test_case) {
+                Ok(())
+            } else {
+                Err(None)
+            }
+        }))
+    }
+
+    /// Creates a comparator from a comparison that describes why it rejected the
+    /// actual response
+    pub fn explained<F>(cmp: F) -> Self
+    where
+        F: Fn(&T, &T, &TestCase) -> Result<(), String> + Send + Sync + 'static,
+    {
+        Self(Box::new(move |expected, actual, test_case| {
+            cmp(expected, actual, test_case).map_err(Some)
+        }))
+    }
+
+    /// Creates a comparator from a comparison whose rejection may or may not have a
+    /// description, such as one wrapping another comparator
+    pub(crate) fn wrap<F>(cmp: F) -> Self
+    where
+        F: Fn(&T, &T, &TestCase) -> Result<(), Option<String>> + Send + Sync + 'static,
+    {
+        Self(Box::new(cmp))
+    }
+
+    /// Compares `expected` and `actual`, returning the rejection's description (if
+    /// any) on a mismatch
+    pub(crate) fn compare(
+        &self,
+        expected: &T,
+        actual: &T,
+        test_case: &TestCase,
+    ) -> Result<(), Option<String>> {
+        (self.0)(expected, actual, test_case)
+    }
+}
+
+impl<T> std::fmt::Debug for Comparator<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Comparator")
+    }
+}
+
+pub(crate) const GREEN: Option<Color> = Some(anstyle::Color::Ansi(AnsiColor::Green));
+pub(crate) const RED: Option<Color> = Some(anstyle::Color::Ansi(AnsiColor::Red));
 
 fn compare_fields(
     f: &mut std::fmt::Formatter<'_>,
@@ -50,12 +1339,13 @@ fn compare_fields(
     std::fmt::Result::Ok(())
 }
 
-pub fn write_fields_comparison<T: Serialize>(
+pub(crate) fn write_fields_comparison<T: Serialize>(
     f: &mut std::fmt::Formatter<'_>,
     name: &str,
     expected: &T,
     actual: &T,
     indent: usize,
+    full_diff: bool,
 ) -> std::fmt::Result {
     let mut expected_value = serde_json::to_value(expected).unwrap();
     let mut actual_value = serde_json::to_value(actual).unwrap();
@@ -65,6 +1355,19 @@ pub fn write_fields_comparison<T: Serialize>(
     } else {
         format!("{name}: ")
     };
+    // Collapse identical subtrees so the differing fields aren't buried
+    if !full_diff
+        && indent > 0
+        && (expected_value.is_object() || expected_value.is_array())
+        && expected_value == actual_value
+    {
+        writeln!(
+            f,
+            "{}",
+            paint(GREEN, &format!("{padding}{key_render}… (unchanged)"))
+        )?;
+        return Ok(());
+    }
 
     match expected_value {
         serde_json::Value::Object(ref mut map) => {
@@ -84,6 +1387,7 @@ pub fn write_fields_comparison<T: Serialize>(
                             expected_val,
                             &actual_val,
                             indent + 1,
+                            full_diff,
                         )?;
                     }
                     _ => {
@@ -116,7 +1420,7 @@ pub fn write_fields_comparison<T: Serialize>(
                     .get(i)
                     .unwrap_or(&serde_json::Value::Null)
                     .to_owned();
-                write_fields_comparison(f, name, expected_val, &actual_val, indent + 1)?;
+                write_fields_comparison(f, name, expected_val, &actual_val, indent + 1, full_diff)?;
             }
             // Display entries present in the `actual` array but not in the `expected` array
             for i in array.len()..actual_value.as_array().map_or(0, |a| a.len()) {
@@ -130,6 +1434,7 @@ pub fn write_fields_comparison<T: Serialize>(
                     &serde_json::Value::Null,
                     &actual_val,
                     indent + 1,
+                    full_diff,
                 )?;
             }
             writeln!(f, "{padding}],")?;
@@ -140,7 +1445,632 @@ pub fn write_fields_comparison<T: Serialize>(
     Ok(())
 }
 
-pub fn paint(color: Option<impl Into<Color>>, text: &str) -> String {
+pub(crate) fn paint(color: Option<impl Into<Color>>, text: &str) -> String {
     let style = Style::new().fg_color(color.map(Into::into));
     format!("{style}{text}{style:#}")
 }
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr as _;
+
+    use lsp_types::{
+        CallHierarchyItem, CompletionItem, CompletionList, CompletionResponse, CompletionTextEdit,
+        DocumentHighlight, DocumentHighlightKind, DocumentSymbol, DocumentSymbolResponse,
+        FoldingRange, GotoDefinitionResponse, Hover, HoverContents, LanguageString, Location,
+        LocationLink, MarkedString, MarkupContent, MarkupKind, ParameterInformation,
+        ParameterLabel, Position, Range, SelectionRange, SemanticToken, SemanticTokens,
+        SemanticTokensResult, SignatureHelp, SignatureInformation, SymbolKind, TextEdit, Uri,
+    };
+
+    use super::{
+        ApproximateEq as _, Cardinality, Comparator, ResultId as _, StateOrResponse,
+        check_call_items, check_completion_applies_as, check_edits_within,
+        check_folding_well_formed, check_goto_targets, check_highlights_by_kind,
+        check_selection_range_nested, check_set_fields, completion_order, default_comparator,
+        folding_covers, goto_target_list, hover_text, signature_active, signature_help_resolved,
+        states_match,
+    };
+    use crate::types::{TestCase, TestFile, TestType};
+
+    #[allow(deprecated)]
+    fn doc_sym(children: Option<Vec<DocumentSymbol>>) -> DocumentSymbol {
+        DocumentSymbol {
+            name: "sym".to_string(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            range: Range::default(),
+            selection_range: Range::default(),
+            children,
+        }
+    }
+
+    #[test]
+    fn cardinality_check() {
+        assert!(Cardinality::Exactly(2).check(2));
+        assert!(!Cardinality::Exactly(2).check(3));
+        assert!(Cardinality::AtLeast(2).check(5));
+        assert!(!Cardinality::AtLeast(2).check(1));
+        assert!(Cardinality::AtMost(2).check(0));
+        assert!(!Cardinality::AtMost(2).check(3));
+        assert!(Cardinality::Between(1, 3).check(1));
+        assert!(Cardinality::Between(1, 3).check(3));
+        assert!(!Cardinality::Between(1, 3).check(4));
+    }
+
+    #[test]
+    fn cardinality_nested_document_symbols() {
+        let resp = DocumentSymbolResponse::Nested(vec![
+            doc_sym(Some(vec![
+                doc_sym(None),
+                doc_sym(Some(vec![doc_sym(None)])),
+            ])),
+            doc_sym(None),
+        ]);
+        assert!(Cardinality::Exactly(5).matches(&resp));
+    }
+
+    fn sig_info(n_params: usize, active_parameter: Option<u32>) -> SignatureInformation {
+        SignatureInformation {
+            label: "foo".to_string(),
+            documentation: None,
+            parameters: Some(
+                (0..n_params)
+                    .map(|i| ParameterInformation {
+                        label: ParameterLabel::Simple(format!("param{i}")),
+                        documentation: None,
+                    })
+                    .collect(),
+            ),
+            active_parameter,
+        }
+    }
+
+    #[test]
+    fn signature_active_resolution() {
+        // Top-level active parameter
+        let help = SignatureHelp {
+            signatures: vec![sig_info(3, None)],
+            active_signature: Some(0),
+            active_parameter: Some(2),
+        };
+        assert!(signature_active(&help, 0, 2));
+
+        // Per-signature active parameter takes precedence
+        let help = SignatureHelp {
+            signatures: vec![sig_info(1, None), sig_info(3, Some(1))],
+            active_signature: Some(1),
+            active_parameter: Some(2),
+        };
+        assert!(signature_active(&help, 1, 1));
+
+        // Out of range values default to 0
+        let help = SignatureHelp {
+            signatures: vec![sig_info(2, None)],
+            active_signature: Some(5),
+            active_parameter: Some(5),
+        };
+        assert!(signature_active(&help, 0, 0));
+    }
+
+    #[test]
+    fn signature_help_raw_vs_resolved() {
+        let expected = SignatureHelp {
+            signatures: vec![sig_info(2, None)],
+            active_signature: Some(0),
+            active_parameter: Some(0),
+        };
+        let out_of_range = SignatureHelp {
+            signatures: vec![sig_info(2, None)],
+            active_signature: Some(5),
+            active_parameter: Some(5),
+        };
+        assert!(!SignatureHelp::approx_eq(&expected, &out_of_range));
+        assert!(signature_help_resolved(&expected, &out_of_range));
+
+        // Where the active parameter is specified doesn't matter for either
+        let per_signature = SignatureHelp {
+            signatures: vec![sig_info(2, Some(0))],
+            active_signature: Some(0),
+            active_parameter: None,
+        };
+        assert!(SignatureHelp::approx_eq(&expected, &per_signature));
+        assert!(signature_help_resolved(&expected, &per_signature));
+
+        let mut other_label = per_signature;
+        other_label.signatures[0].label = "bar".to_string();
+        assert!(!SignatureHelp::approx_eq(&expected, &other_label));
+        assert!(!signature_help_resolved(&expected, &other_label));
+    }
+
+    #[test]
+    fn comparator_reasons() {
+        let test_case = TestCase::new("server", TestFile::new("main.rs", ""));
+        let cmp = Comparator::new(|expected: &u32, actual: &u32, _| expected == actual);
+        assert_eq!(Ok(()), cmp.compare(&1, &1, &test_case));
+        assert_eq!(Err(None), cmp.compare(&1, &2, &test_case));
+        let cmp = Comparator::explained(|expected: &u32, actual: &u32, _| {
+            if expected == actual {
+                Ok(())
+            } else {
+                Err(format!("{actual} isn't {expected}"))
+            }
+        });
+        assert_eq!(Ok(()), cmp.compare(&1, &1, &test_case));
+        assert_eq!(
+            Err(Some("2 isn't 1".to_string())),
+            cmp.compare(&1, &2, &test_case)
+        );
+    }
+
+    fn fold(start_line: u32, end_line: u32) -> FoldingRange {
+        FoldingRange {
+            start_line,
+            end_line,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn folding_well_formed() {
+        assert!(check_folding_well_formed(&[]).is_ok());
+        assert!(
+            check_folding_well_formed(&[fold(0, 10), fold(1, 3), fold(4, 10), fold(11, 12)])
+                .is_ok()
+        );
+        // Inverted
+        assert!(check_folding_well_formed(&[fold(3, 1)]).is_err());
+        // Unsorted
+        assert!(check_folding_well_formed(&[fold(4, 5), fold(0, 1)]).is_err());
+        // Partial overlap
+        assert!(check_folding_well_formed(&[fold(0, 5), fold(3, 8)]).is_err());
+    }
+
+    fn selection(start: u32, end: u32, parent: Option<SelectionRange>) -> SelectionRange {
+        SelectionRange {
+            range: Range::new(Position::new(start, 0), Position::new(end, 0)),
+            parent: parent.map(Box::new),
+        }
+    }
+
+    #[test]
+    fn selection_range_nested() {
+        let nested = selection(2, 3, Some(selection(1, 4, Some(selection(0, 5, None)))));
+        assert!(check_selection_range_nested(&[nested]).is_ok());
+        // Parent doesn't contain the child
+        let disjoint = selection(5, 6, Some(selection(1, 4, None)));
+        assert!(check_selection_range_nested(&[disjoint]).is_err());
+        // Parent equal to the child
+        let equal = selection(1, 4, Some(selection(1, 4, None)));
+        assert!(check_selection_range_nested(&[equal]).is_err());
+    }
+
+    fn edit(start: u32, end: u32, new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(start, 0), Position::new(end, 0)),
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    fn edits_within() {
+        let range = Range::new(Position::new(2, 0), Position::new(4, 0));
+        let expected = vec![edit(2, 3, "foo")];
+        // Edits outside of the range are ignored
+        let actual = vec![edit(0, 1, " "), edit(2, 3, "foo"), edit(5, 6, " ")];
+        assert!(check_edits_within(range, &expected, &actual, false).is_ok());
+        assert!(check_edits_within(range, &expected, &actual, true).is_err());
+        // Edits touching the range's boundary intersect it
+        let actual = vec![edit(2, 3, "foo"), edit(4, 5, " ")];
+        assert!(check_edits_within(range, &expected, &actual, false).is_err());
+        // Differing edits within the range
+        let actual = vec![edit(2, 3, "bar")];
+        assert!(check_edits_within(range, &expected, &actual, false).is_err());
+        assert!(check_edits_within(range, &expected, &expected, true).is_ok());
+    }
+
+    fn highlight(line: u32, kind: Option<DocumentHighlightKind>) -> DocumentHighlight {
+        DocumentHighlight {
+            range: Range::new(Position::new(line, 0), Position::new(line, 3)),
+            kind,
+        }
+    }
+
+    #[test]
+    fn highlights_by_kind() {
+        let write = Some(DocumentHighlightKind::WRITE);
+        let read = Some(DocumentHighlightKind::READ);
+        let expected = vec![highlight(0, write), highlight(1, read)];
+        // Ordering is ignored
+        let actual = vec![highlight(1, read), highlight(0, write)];
+        assert!(check_highlights_by_kind(&expected, &actual).is_ok());
+        // Misclassified occurrence
+        let actual = vec![highlight(0, read), highlight(1, read)];
+        assert!(check_highlights_by_kind(&expected, &actual).is_err());
+        // Missing occurrence
+        let actual = vec![highlight(0, write)];
+        assert!(check_highlights_by_kind(&expected, &actual).is_err());
+        // Duplicate occurrences must be expected as such
+        let actual = vec![highlight(0, write), highlight(0, write), highlight(1, read)];
+        assert!(check_highlights_by_kind(&expected, &actual).is_err());
+        // An unspecified kind only matches an unspecified kind
+        let expected = vec![highlight(0, None)];
+        assert!(check_highlights_by_kind(&expected, &[highlight(0, None)]).is_ok());
+        assert!(check_highlights_by_kind(&expected, &[highlight(0, write)]).is_err());
+    }
+
+    #[test]
+    fn default_comparators() {
+        for test_type in TestType::all() {
+            let has_comparator = default_comparator(*test_type).is_some();
+            assert_eq!(
+                has_comparator,
+                !matches!(test_type, TestType::LogMessage | TestType::Progress),
+                "{test_type}"
+            );
+        }
+
+        let cmp = default_comparator(TestType::Definition).unwrap();
+        let location = serde_json::json!({
+            "uri": "file:///main.rs",
+            "range": {
+                "start": { "line": 1, "character": 2 },
+                "end": { "line": 3, "character": 4 },
+            },
+        });
+        assert!(cmp(&location, &location).unwrap());
+        assert!(!cmp(&location, &serde_json::json!([location])).unwrap());
+        // An empty list of locations is equivalent to an empty list of links
+        assert!(cmp(&serde_json::json!([]), &serde_json::json!([])).unwrap());
+        // Not a valid `GotoDefinitionResponse`
+        assert!(cmp(&location, &serde_json::json!(42)).is_err());
+    }
+
+    #[test]
+    fn completion_applies_as() {
+        let source = "fn main() {\n    pri\n}\n";
+        let position = Position::new(1, 7);
+        let item = |text_edit: Option<TextEdit>| CompletionItem {
+            label: "println!".to_string(),
+            text_edit: text_edit.map(CompletionTextEdit::Edit),
+            ..Default::default()
+        };
+        let replace = |start: u32| TextEdit {
+            range: Range::new(Position::new(1, start), position),
+            new_text: "println!".to_string(),
+        };
+        let expected = "fn main() {\n    println!\n}\n";
+
+        let response = CompletionResponse::Array(vec![item(Some(replace(4)))]);
+        assert!(
+            check_completion_applies_as(source, &response, position, "println!", expected).is_ok()
+        );
+        // Off by one, leaving a stray character behind
+        let response = CompletionResponse::Array(vec![item(Some(replace(5)))]);
+        assert!(
+            check_completion_applies_as(source, &response, position, "println!", expected).is_err()
+        );
+        // Without an edit, the label is inserted at the cursor
+        let response = CompletionResponse::Array(vec![item(None)]);
+        assert!(
+            check_completion_applies_as(
+                source,
+                &response,
+                position,
+                "println!",
+                "fn main() {\n    priprintln!\n}\n"
+            )
+            .is_ok()
+        );
+        // No item with the label
+        assert!(
+            check_completion_applies_as(source, &response, position, "print!", expected).is_err()
+        );
+        // Edit range out of bounds
+        let response = CompletionResponse::Array(vec![item(Some(TextEdit {
+            range: Range::new(Position::new(5, 0), Position::new(5, 1)),
+            new_text: String::new(),
+        }))]);
+        assert!(
+            check_completion_applies_as(source, &response, position, "println!", expected).is_err()
+        );
+    }
+
+    #[test]
+    fn completion_orders() {
+        let item = |label: &str, sort_text: Option<&str>| CompletionItem {
+            label: label.to_string(),
+            sort_text: sort_text.map(ToString::to_string),
+            ..Default::default()
+        };
+        let items = vec![
+            item("b", None),
+            item("a", None),
+            item("z", Some("0")),
+            item("y", Some("b")),
+        ];
+        let array = CompletionResponse::Array(items.clone());
+        let list = CompletionResponse::List(CompletionList {
+            is_incomplete: false,
+            items,
+            ..Default::default()
+        });
+
+        for response in [&array, &list] {
+            // Sorted by `sort_text`, then label
+            assert!(completion_order(response, "z", "a").is_ok());
+            assert!(completion_order(response, "a", "b").is_ok());
+            assert!(completion_order(response, "a", "z").is_err());
+            // Ties keep the server's order
+            assert!(completion_order(response, "b", "y").is_ok());
+            assert!(completion_order(response, "y", "b").is_err());
+            // An item doesn't precede itself
+            assert!(completion_order(response, "a", "a").is_err());
+            // No item with the label
+            assert!(completion_order(response, "a", "c").is_err());
+        }
+    }
+
+    #[test]
+    fn values_within_tolerance() {
+        let color = |red: f64| serde_json::json!({ "red": red, "green": 0.5, "blue": 1 });
+
+        assert!(super::values_within_tolerance(
+            &color(0.1),
+            &color(0.1),
+            0.0
+        ));
+        assert!(!super::values_within_tolerance(
+            &color(0.1),
+            &color(0.1001),
+            0.0
+        ));
+        assert!(super::values_within_tolerance(
+            &color(0.1),
+            &color(0.1001),
+            1e-3
+        ));
+        assert!(!super::values_within_tolerance(
+            &color(0.1),
+            &color(0.2),
+            1e-3
+        ));
+        // Nested within arrays, and compared structurally otherwise
+        assert!(super::values_within_tolerance(
+            &serde_json::json!([color(0.1)]),
+            &serde_json::json!([color(0.1001)]),
+            1e-3
+        ));
+        assert!(!super::values_within_tolerance(
+            &serde_json::json!([color(0.1)]),
+            &serde_json::json!([color(0.1), color(0.1)]),
+            1e-3
+        ));
+        assert!(!super::values_within_tolerance(
+            &serde_json::json!({ "red": 0.1 }),
+            &serde_json::json!({ "red": 0.1, "alpha": 1 }),
+            1e-3
+        ));
+        assert!(!super::values_within_tolerance(
+            &serde_json::json!("0.1"),
+            &serde_json::json!(0.1),
+            1e-3
+        ));
+    }
+
+    #[test]
+    fn hover_text_forms() {
+        let hover = |contents: HoverContents| Hover {
+            contents,
+            range: None,
+        };
+        let array = hover(HoverContents::Array(vec![
+            MarkedString::String("fn foo()".to_string()),
+            MarkedString::LanguageString(LanguageString {
+                language: "rust".to_string(),
+                value: "Docs".to_string(),
+            }),
+        ]));
+        let markup = hover(HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: "fn foo()\nDocs".to_string(),
+        }));
+        let scalar = hover(HoverContents::Scalar(MarkedString::String(
+            "fn foo()".to_string(),
+        )));
+        assert!(hover_text(&array, &markup).is_ok());
+        assert_eq!(
+            Err("hover text differs, expected \"fn foo()\\nDocs\", got \"fn foo()\"".to_string()),
+            hover_text(&array, &scalar)
+        );
+    }
+
+    #[test]
+    fn folding_covers_spans() {
+        let range = |start_line, start_character, end_line| FoldingRange {
+            start_line,
+            start_character,
+            end_line,
+            ..Default::default()
+        };
+        let actual = [
+            range(0, None, 1),
+            range(3, Some(4), 10),
+            range(5, Some(0), 7),
+        ];
+        assert!(folding_covers(&[(3, 10), (0, 1)], &actual).is_ok());
+        assert!(folding_covers(&[], &actual).is_ok());
+        assert!(folding_covers(&[(3, 9)], &actual).is_err());
+        assert!(folding_covers(&[(0, 1)], &[]).is_err());
+    }
+
+    #[test]
+    fn set_fields() {
+        let actual = serde_json::json!({
+            "label": "println!",
+            "kind": 3,
+            "detail": "macro",
+            "tags": [1],
+        });
+        let matches = |expected| check_set_fields(&expected, &actual, "$", 0.0);
+        assert!(matches(serde_json::json!({ "label": "println!" })).is_ok());
+        assert!(matches(serde_json::json!({ "label": "println!", "detail": null })).is_ok());
+        assert!(matches(serde_json::json!({ "tags": [1] })).is_ok());
+        assert!(matches(serde_json::json!(null)).is_ok());
+        assert_eq!(
+            Err("$.kind is 3, expected 2".to_string()),
+            matches(serde_json::json!({ "kind": 2 }))
+        );
+        assert_eq!(
+            Err("$.documentation is missing, expected \"docs\"".to_string()),
+            matches(serde_json::json!({ "documentation": "docs" }))
+        );
+        assert_eq!(
+            Err("$.tags has 1 item(s), expected 2".to_string()),
+            matches(serde_json::json!({ "tags": [1, 2] }))
+        );
+
+        // Numbers are compared within the tolerance
+        let actual = serde_json::json!({ "red": 0.1001, "label": "red" });
+        let expected = serde_json::json!({ "red": 0.1 });
+        assert!(check_set_fields(&expected, &actual, "$", 0.0).is_err());
+        assert!(check_set_fields(&expected, &actual, "$", 1e-3).is_ok());
+        assert!(check_set_fields(&serde_json::json!({ "red": 0.2 }), &actual, "$", 1e-3).is_err());
+    }
+
+    #[test]
+    fn text_normalized() {
+        let state = |text: &str| StateOrResponse::State(text.to_string());
+        let matches =
+            |expected: &str, actual: &str| states_match(&state(expected), &state(actual), false);
+        assert!(matches("fn main() {}", "fn main() {}\n"));
+        assert!(matches("a\nb\n", "a  \r\nb\t\r\n\n"));
+        assert!(!matches("a\nb", "a\n\nb"));
+        assert!(!matches("a", "\na"));
+        assert!(states_match(&state("a\n"), &state("\n\na\n\n"), true));
+        // Edits are still compared exactly
+        let both = |new_text: &str| StateOrResponse::Both {
+            edits: vec![edit(0, 0, new_text)],
+            state: "a".to_string(),
+        };
+        assert!(!states_match(&both("a"), &both("a\n"), false));
+    }
+
+    #[test]
+    fn semantic_tokens_result_id() {
+        let tokens = |result_id: Option<&str>| {
+            SemanticTokensResult::Tokens(SemanticTokens {
+                result_id: result_id.map(str::to_string),
+                data: vec![SemanticToken {
+                    delta_line: 1,
+                    delta_start: 2,
+                    length: 3,
+                    token_type: 4,
+                    token_modifiers_bitset: 5,
+                }],
+            })
+        };
+        // Only the presence of a `result_id` is compared by default
+        assert!(SemanticTokensResult::approx_eq(
+            &tokens(Some("1")),
+            &tokens(Some("2"))
+        ));
+        assert!(!SemanticTokensResult::approx_eq(
+            &tokens(None),
+            &tokens(Some("2"))
+        ));
+
+        let mut expected = tokens(None);
+        let mut actual = tokens(Some("2"));
+        expected.clear_result_id();
+        actual.clear_result_id();
+        assert!(SemanticTokensResult::approx_eq(&expected, &actual));
+    }
+
+    #[test]
+    fn call_items() {
+        let item = |name: &str, line: u32| CallHierarchyItem {
+            name: name.to_string(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: Uri::from_str("main.rs").unwrap(),
+            range: Range::default(),
+            selection_range: Range::new(Position::new(line, 3), Position::new(line, 6)),
+            data: None,
+        };
+        let key = |item: &CallHierarchyItem| (item.name.clone(), item.selection_range.start.line);
+        let (foo, bar) = (item("foo", 0), item("bar", 2));
+        // Fields outside of the key are ignored
+        let mut foo_with_data = foo.clone();
+        foo_with_data.data = Some(serde_json::json!({ "id": 7 }));
+        foo_with_data.detail = Some("fn foo()".to_string());
+
+        assert!(
+            check_call_items(
+                &[foo.clone(), bar.clone()],
+                &[bar.clone(), foo_with_data],
+                key
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            Err("unexpected item 0 with key (\"bar\", 2)".to_string()),
+            check_call_items(&[foo.clone()], &[bar.clone()], key)
+        );
+        assert_eq!(
+            Err("missing item with key (\"bar\", 2), 1 expected item(s) unmatched".to_string()),
+            check_call_items(&[foo.clone(), bar], &[foo], key)
+        );
+        // Only the fields in the key are compared
+        let name_only = |item: &CallHierarchyItem| item.name.clone();
+        assert!(check_call_items(&[item("foo", 0)], &[item("foo", 5)], name_only).is_ok());
+    }
+
+    #[test]
+    fn goto_targets() {
+        let range = |line: u32| Range::new(Position::new(line, 3), Position::new(line, 6));
+        let link = GotoDefinitionResponse::Link(vec![LocationLink {
+            origin_selection_range: Some(range(9)),
+            target_uri: Uri::from_str("src/lib.rs").unwrap(),
+            target_range: Range::new(Position::new(0, 0), Position::new(4, 1)),
+            target_selection_range: range(0),
+        }]);
+        let array = GotoDefinitionResponse::Array(vec![
+            Location::new(Uri::from_str("src/main.rs").unwrap(), range(2)),
+            Location::new(Uri::from_str("src/lib.rs").unwrap(), range(0)),
+        ]);
+        let targets = |response: &GotoDefinitionResponse| {
+            goto_target_list(response)
+                .into_iter()
+                .map(|(uri, range)| (uri.path().as_str().to_string(), range))
+                .collect::<Vec<_>>()
+        };
+
+        // Only the target selection range of a link is compared
+        assert!(check_goto_targets(&[("src/lib.rs", range(0))], &targets(&link)).is_ok());
+        // Targets are compared as a set
+        assert!(
+            check_goto_targets(
+                &[("src/lib.rs", range(0)), ("src/main.rs", range(2))],
+                &targets(&array)
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            Err("unexpected target 0 (src/main.rs at 2:3-2:6)".to_string()),
+            check_goto_targets(&[("src/lib.rs", range(0))], &targets(&array))
+        );
+        assert_eq!(
+            Err(
+                "missing target src/main.rs at 2:3-2:6, 1 expected target(s) unmatched".to_string()
+            ),
+            check_goto_targets(
+                &[("src/lib.rs", range(0)), ("src/main.rs", range(2))],
+                &targets(&link)
+            )
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::{path::PathBuf, sync::OnceLock, time::Duration};
+
+use super::TestCase;
+
+static DEFAULTS: OnceLock<TestCaseDefaults> = OnceLock::new();
+
+/// Suite-wide configuration merged into every [`TestCase::new`], so that policy shared
+/// by all of a suite's test cases (i.e. the Neovim executable, or a generous timeout
+/// for slow CI machines) is set in one place. Defaults are installed once per process,
+/// before any test cases are created:
+///
+/// ```ignore
+/// TestCaseDefaults::default()
+///     .nvim_path("/opt/nvim/bin/nvim")
+///     .timeout(Duration::from_secs(5))
+///     .cleanup(true)
+///     .install()
+///     .expect("Defaults were already installed");
+/// ```
+///
+/// Precedence, from highest to lowest:
+///
+/// 1. Explicit `TestCase` builder calls, which always win since they're applied after
+///    `TestCase::new`
+/// 2. Environment variables, i.e. `LSPRESSO_NVIM` for `nvim_path`
+/// 3. The installed `TestCaseDefaults`
+/// 4. The built-in defaults of `TestCase::new`
+///
+/// `LSPRESSO_TIMEOUT_SCALE` is applied to whichever timeout results, see
+/// [`TestCase::effective_timeout`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestCaseDefaults {
+    pub nvim_path: Option<PathBuf>,
+    pub nvim_runtime: Option<PathBuf>,
+    pub timeout: Option<Duration>,
+    pub cleanup: Option<bool>,
+    pub root_uri: Option<PathBuf>,
+    pub full_diff: Option<bool>,
+}
+
+impl TestCaseDefaults {
+    /// Set the default Neovim executable, see [`TestCase::nvim_path`]
+    #[must_use]
+    pub fn nvim_path<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.nvim_path = Some(path.into());
+        self
+    }
+
+    /// Set the default Neovim runtime, see [`TestCase::nvim_runtime`]
+    #[must_use]
+    pub fn nvim_runtime<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.nvim_runtime = Some(path.into());
+        self
+    }
+
+    /// Set the default timeout, see [`TestCase::timeout`]
+    #[must_use]
+    pub fn timeout<T: Into<Duration>>(mut self, timeout: T) -> Self {
+        self.timeout = Some(timeout.into());
+        self
+    }
+
+    /// Set whether test directories are cleaned up by default, see [`TestCase::cleanup`]
+    #[must_use]
+    pub const fn cleanup(mut self, cleanup: bool) -> Self {
+        self.cleanup = Some(cleanup);
+        self
+    }
+
+    /// Set the default root directory advertised to the server, see
+    /// [`TestCase::root_uri`]
+    #[must_use]
+    pub fn root_uri(mut self, root: PathBuf) -> Self {
+        self.root_uri = Some(root);
+        self
+    }
+
+    /// Set whether mismatches display the full comparison by default, see
+    /// [`TestCase::full_diff`]
+    #[must_use]
+    pub const fn full_diff(mut self, full_diff: bool) -> Self {
+        self.full_diff = Some(full_diff);
+        self
+    }
+
+    /// Installs `self` as the process-wide defaults, applied to every `TestCase`
+    /// created afterwards. Test cases created beforehand are unaffected.
+    ///
+    /// # Errors
+    ///
+    /// Returns `self` if defaults were already installed, as they can only be set once
+    pub fn install(self) -> Result<(), Self> {
+        DEFAULTS.set(self)
+    }
+
+    /// Returns the process-wide defaults, if they've been installed
+    #[must_use]
+    pub fn installed() -> Option<&'static Self> {
+        DEFAULTS.get()
+    }
+
+    /// Merges `self` into `test_case`, leaving fields without a default untouched.
+    /// `LSPRESSO_NVIM` takes precedence over `self.nvim_path`.
+    pub(crate) fn apply(&self, test_case: &mut TestCase) {
+        if let Some(path) = &self.nvim_path {
+            if std::env::var_os("LSPRESSO_NVIM").is_none() {
+                test_case.nvim_path.clone_from(path);
+            }
+        }
+        if let Some(path) = &self.nvim_runtime {
+            test_case.nvim_runtime = Some(path.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            test_case.timeout = timeout;
+        }
+        if let Some(cleanup) = self.cleanup {
+            test_case.cleanup = cleanup;
+        }
+        if let Some(root) = &self.root_uri {
+            test_case.root_uri = Some(root.clone());
+        }
+        if let Some(full_diff) = self.full_diff {
+            test_case.full_diff = full_diff;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::TestCaseDefaults;
+    use crate::types::{TestCase, TestFile};
+
+    #[test]
+    fn defaults_precedence() {
+        let defaults = TestCaseDefaults::default()
+            .timeout(Duration::from_secs(5))
+            .cleanup(true)
+            .root_uri(PathBuf::from("nested"));
+        let mut test_case = TestCase::new("server", TestFile::new("main.rs", ""));
+        let full_diff = test_case.full_diff;
+        defaults.apply(&mut test_case);
+        assert_eq!(Duration::from_secs(5), test_case.timeout);
+        assert!(test_case.cleanup);
+        assert_eq!(Some(PathBuf::from("nested")), test_case.root_uri);
+        // Fields without a default are untouched
+        assert_eq!(full_diff, test_case.full_diff);
+
+        // Explicit builder calls win
+        let test_case = test_case.timeout(Duration::from_secs(1)).cleanup(false);
+        assert_eq!(Duration::from_secs(1), test_case.timeout);
+        assert!(!test_case.cleanup);
+    }
+}
@@ -1,11 +1,31 @@
-use lsp_types::DocumentLink;
+use std::path::Path;
 
-use super::{ApproximateEq, CleanResponse, TestCase, TestExecutionResult, clean_uri};
+use lsp_types::{DocumentLink, Uri};
+
+use super::{
+    ApproximateEq, CleanResponse, TestCase, TestExecutionError, TestExecutionResult, clean_uri,
+};
+
+/// Cleans `target` if it's a `file://` uri within the test case's source directory.
+/// Links to external targets (i.e. `https://` urls, or files outside the test
+/// directory such as system headers) are left untouched.
+fn clean_target(target: &mut Uri, test_case: &TestCase) -> TestExecutionResult<()> {
+    if !target.as_str().starts_with("file://") {
+        return Ok(());
+    }
+    let root = test_case
+        .get_source_file_path("")
+        .map_err(|e| TestExecutionError::IO(test_case.test_id.clone(), e.to_string()))?;
+    if Path::new(target.path().as_str()).starts_with(&root) {
+        *target = clean_uri(target, test_case)?;
+    }
+    Ok(())
+}
 
 impl CleanResponse for DocumentLink {
     fn clean_response(mut self, test_case: &TestCase) -> TestExecutionResult<Self> {
         if let Some(ref mut uri) = self.target {
-            *uri = clean_uri(uri, test_case)?;
+            clean_target(uri, test_case)?;
         }
         Ok(self)
     }
@@ -15,7 +35,7 @@ impl CleanResponse for Vec<DocumentLink> {
     fn clean_response(mut self, test_case: &TestCase) -> TestExecutionResult<Self> {
         for link in &mut self {
             if let Some(ref mut uri) = link.target {
-                *uri = clean_uri(uri, test_case)?;
+                clean_target(uri, test_case)?;
             }
         }
         Ok(self)
@@ -24,3 +44,44 @@ impl CleanResponse for Vec<DocumentLink> {
 
 impl ApproximateEq for DocumentLink {}
 impl ApproximateEq for Vec<DocumentLink> {}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr as _;
+
+    use lsp_types::{DocumentLink, Range, Uri};
+
+    use crate::types::{CleanResponse as _, TestCase, TestFile};
+
+    #[test]
+    fn clean_targets_within_root() {
+        let test_case = TestCase::new("server", TestFile::new("main.rs", ""));
+        let root = test_case.get_source_file_path("").unwrap();
+        let link = |target: &str| DocumentLink {
+            range: Range::default(),
+            target: Some(Uri::from_str(target).unwrap()),
+            tooltip: None,
+            data: None,
+        };
+        let links = vec![
+            link(&format!("file://{}", root.join("lib.rs").display())),
+            link("file:///usr/include/stdio.h"),
+            link("https://example.com/docs"),
+        ];
+
+        let targets: Vec<_> = links
+            .clean_response(&test_case)
+            .unwrap()
+            .into_iter()
+            .map(|link| link.target.unwrap().as_str().to_string())
+            .collect();
+        assert_eq!(
+            vec![
+                "lib.rs",
+                "file:///usr/include/stdio.h",
+                "https://example.com/docs"
+            ],
+            targets
+        );
+    }
+}
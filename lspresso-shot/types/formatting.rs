@@ -1,7 +1,126 @@
-use lsp_types::TextEdit;
+use std::collections::HashMap;
+
+use lsp_types::{FormattingOptions, FormattingProperty, TextEdit};
+use serde::{Deserialize, Serialize};
 
 use super::{ApproximateEq, CleanResponse, ResponseMismatchError, StateOrResponse, TestError};
 
+/// Builds a [`FormattingOptions`] with typed setters for server-specific `properties`,
+/// for use with `test_formatting`, `test_range_formatting`, and `test_on_type_formatting`.
+///
+/// The builder starts from the same options used when `None` is passed to these
+/// functions:
+///
+/// ```rust
+/// lsp_types::FormattingOptions {
+///     tab_size: 4,
+///     insert_spaces: true,
+///     properties: std::collections::HashMap::new(),
+///     trim_trailing_whitespace: Some(true),
+///     insert_final_newline: Some(true),
+///     trim_final_newlines: Some(true),
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct FormattingOptionsBuilder {
+    options: FormattingOptions,
+}
+
+impl Default for FormattingOptionsBuilder {
+    fn default() -> Self {
+        Self {
+            options: FormattingOptions {
+                tab_size: 4,
+                insert_spaces: true,
+                properties: HashMap::new(),
+                trim_trailing_whitespace: Some(true),
+                insert_final_newline: Some(true),
+                trim_final_newlines: Some(true),
+            },
+        }
+    }
+}
+
+impl FormattingOptionsBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the size of a tab in spaces
+    #[must_use]
+    pub const fn tab_size(mut self, tab_size: u32) -> Self {
+        self.options.tab_size = tab_size;
+        self
+    }
+
+    /// Set whether spaces are preferred over tabs
+    #[must_use]
+    pub const fn insert_spaces(mut self, insert_spaces: bool) -> Self {
+        self.options.insert_spaces = insert_spaces;
+        self
+    }
+
+    /// Set whether trailing whitespace is trimmed on a line
+    #[must_use]
+    pub const fn trim_trailing_whitespace(mut self, trim: Option<bool>) -> Self {
+        self.options.trim_trailing_whitespace = trim;
+        self
+    }
+
+    /// Set whether a newline is inserted at the end of the file if one doesn't exist
+    #[must_use]
+    pub const fn insert_final_newline(mut self, insert: Option<bool>) -> Self {
+        self.options.insert_final_newline = insert;
+        self
+    }
+
+    /// Set whether all newlines after the final newline at the end of the file are trimmed
+    #[must_use]
+    pub const fn trim_final_newlines(mut self, trim: Option<bool>) -> Self {
+        self.options.trim_final_newlines = trim;
+        self
+    }
+
+    /// Set the boolean property `name`
+    #[must_use]
+    pub fn property_bool<S: Into<String>>(mut self, name: S, value: bool) -> Self {
+        self.options
+            .properties
+            .insert(name.into(), FormattingProperty::Bool(value));
+        self
+    }
+
+    /// Set the numeric property `name`
+    #[must_use]
+    pub fn property_number<S: Into<String>>(mut self, name: S, value: i32) -> Self {
+        self.options
+            .properties
+            .insert(name.into(), FormattingProperty::Number(value));
+        self
+    }
+
+    /// Set the string property `name`
+    #[must_use]
+    pub fn property_string<S: Into<String>, V: Into<String>>(mut self, name: S, value: V) -> Self {
+        self.options
+            .properties
+            .insert(name.into(), FormattingProperty::String(value.into()));
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> FormattingOptions {
+        self.options
+    }
+}
+
+impl From<FormattingOptionsBuilder> for FormattingOptions {
+    fn from(value: FormattingOptionsBuilder) -> Self {
+        value.build()
+    }
+}
+
 impl CleanResponse for StateOrResponse<Vec<TextEdit>> {}
 impl CleanResponse for Vec<TextEdit> {}
 
@@ -12,6 +131,7 @@ impl From<TestError<String>> for TestError<StateOrResponse<Vec<TextEdit>>> {
                 test_id,
                 expected,
                 actual,
+                reason,
             }) => {
                 let expected = expected.map(StateOrResponse::State);
                 let actual = actual.map(StateOrResponse::State);
@@ -19,13 +139,35 @@ impl From<TestError<String>> for TestError<StateOrResponse<Vec<TextEdit>>> {
                     test_id,
                     expected,
                     actual,
+                    reason,
                 })
             }
             TestError::TestExecution(e) => Self::TestExecution(e),
             TestError::TestSetup(e) => Self::TestSetup(e),
+            TestError::NvimVersions(e) => Self::NvimVersions(e.map(Into::into)),
+            TestError::JsonMismatch(e) => Self::JsonMismatch(e),
         }
     }
 }
 
 impl ApproximateEq for Vec<TextEdit> {}
 impl ApproximateEq for StateOrResponse<Vec<TextEdit>> {}
+
+/// The results recorded when testing against [`StateOrResponse::Both`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct EditsAndState {
+    pub edits: Vec<TextEdit>,
+    pub state: String,
+}
+
+impl From<EditsAndState> for StateOrResponse<Vec<TextEdit>> {
+    fn from(value: EditsAndState) -> Self {
+        Self::Both {
+            edits: value.edits,
+            state: value.state,
+        }
+    }
+}
+
+impl CleanResponse for EditsAndState {}
+impl ApproximateEq for EditsAndState {}
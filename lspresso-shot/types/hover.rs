@@ -1,7 +1,69 @@
-use lsp_types::Hover;
+use lsp_types::{Hover, HoverContents, MarkedString, MarkupContent};
+use serde_json::Value;
 
 use super::{ApproximateEq, CleanResponse};
 
-impl CleanResponse for Hover {}
+impl CleanResponse for Hover {
+    /// `HoverContents` is untagged, so an array of two plain strings deserializes as a
+    /// `LanguageString` rather than a `HoverContents::Array`, see
+    /// https://github.com/serde-rs/json/issues/1244. Array contents are deserialized
+    /// item by item instead.
+    fn deserialize_response(raw: &str) -> serde_json::Result<Self> {
+        let mut value: Value = serde_json::from_str(raw)?;
+        let Some(Value::Array(items)) = value.get_mut("contents").map(Value::take) else {
+            return serde_json::from_str(raw);
+        };
+        let items = items
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<serde_json::Result<Vec<MarkedString>>>()?;
+        // Deserialize the remaining fields with placeholder contents
+        value["contents"] = Value::String(String::new());
+        let mut hover: Self = serde_json::from_value(value)?;
+        hover.contents = HoverContents::Array(items);
+        Ok(hover)
+    }
+}
 
-impl ApproximateEq for Hover {}
+/// A `HoverContents::Scalar` is equivalent to a single item `HoverContents::Array`,
+/// so both forms are normalized to a list of marked strings before comparing
+impl ApproximateEq for Hover {
+    fn approx_eq(a: &Self, b: &Self) -> bool {
+        a.range == b.range
+            && NormalizedContents::new(&a.contents) == NormalizedContents::new(&b.contents)
+    }
+}
+
+#[derive(PartialEq)]
+enum NormalizedContents<'a> {
+    Marked(Vec<&'a MarkedString>),
+    Markup(&'a MarkupContent),
+}
+
+impl<'a> NormalizedContents<'a> {
+    fn new(contents: &'a HoverContents) -> Self {
+        match contents {
+            HoverContents::Scalar(marked) => Self::Marked(vec![marked]),
+            HoverContents::Array(marked) => Self::Marked(marked.iter().collect()),
+            HoverContents::Markup(markup) => Self::Markup(markup),
+        }
+    }
+}
+
+/// Flattens `contents` into its text, with each item on its own line. Languages and
+/// markup kinds are discarded.
+pub(crate) fn hover_text(contents: &HoverContents) -> String {
+    let marked_text = |marked: &MarkedString| match marked {
+        MarkedString::String(value) => value.clone(),
+        MarkedString::LanguageString(lang) => lang.value.clone(),
+    };
+    match contents {
+        HoverContents::Scalar(marked) => marked_text(marked),
+        HoverContents::Array(marked) => marked
+            .iter()
+            .map(marked_text)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        HoverContents::Markup(markup) => markup.value.clone(),
+    }
+}
@@ -1,7 +1,23 @@
-use lsp_types::InlayHint;
+use lsp_types::{InlayHint, InlayHintLabel};
 
-use super::{ApproximateEq, CleanResponse};
+use super::{ApproximateEq, CleanResponse, TestCase, TestExecutionResult, clean_uri, clean_value};
 
-impl CleanResponse for Vec<InlayHint> {}
+impl CleanResponse for Vec<InlayHint> {
+    fn clean_response(mut self, test_case: &TestCase) -> TestExecutionResult<Self> {
+        for hint in &mut self {
+            if let InlayHintLabel::LabelParts(ref mut parts) = hint.label {
+                for part in parts {
+                    if let Some(ref mut location) = part.location {
+                        location.uri = clean_uri(&location.uri, test_case)?;
+                    }
+                }
+            }
+            if let Some(ref mut data) = hint.data {
+                clean_value(data, test_case)?;
+            }
+        }
+        Ok(self)
+    }
+}
 
 impl ApproximateEq for Vec<InlayHint> {}
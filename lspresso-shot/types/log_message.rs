@@ -0,0 +1,41 @@
+use lsp_types::{LogMessageParams, MessageType};
+use serde_json::Value;
+
+use super::TestType;
+
+/// Describes a `window/logMessage` or `window/showMessage` notification the server
+/// is expected to send, for use with [`test_expect_log_message`](crate::test_expect_log_message)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogMatcher {
+    /// The type of the message. If `None`, messages of any type are matched
+    pub typ: Option<MessageType>,
+    /// A substring of the message's text
+    pub contains: String,
+}
+
+impl LogMatcher {
+    #[must_use]
+    pub fn new<S: Into<String>>(typ: Option<MessageType>, contains: S) -> Self {
+        Self {
+            typ,
+            contains: contains.into(),
+        }
+    }
+
+    /// Indicates if `message` satisfies `self`
+    #[must_use]
+    pub fn matches(&self, message: &LogMessageParams) -> bool {
+        self.typ.is_none_or(|typ| typ == message.typ) && message.message.contains(&self.contains)
+    }
+}
+
+/// The action expected to cause the server to send a message
+#[derive(Debug, Clone, Copy)]
+pub enum LogTrigger<'a> {
+    /// Opening the test case's source file. This covers any messages sent while
+    /// the server starts up and handles the initial `textDocument/didOpen`
+    Open,
+    /// Issuing a `test_type` request with `params`, in the same manner as
+    /// [`test_no_error`](crate::test_no_error)
+    Request(TestType, &'a Value),
+}
@@ -0,0 +1,171 @@
+use std::{
+    io::{BufRead, BufReader, Write as _},
+    path::Path,
+    process::{Command, Stdio},
+    sync::mpsc,
+    time::Duration,
+};
+
+use serde_json::Value;
+
+/// The longest the probe waits for a response to `initialize`, before scaling by
+/// `LSPRESSO_TIMEOUT_SCALE`. A language server answers `initialize` well before it's
+/// ready to service requests, so this is much shorter than a typical test timeout.
+pub(crate) const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Spawns the server at `executable_path` and sends it a minimal `initialize` request
+/// rooted at `root`, waiting up to `timeout` for a well-formed response. Returns a
+/// description of what went wrong if the server doesn't respond as a language server
+/// would.
+pub(crate) fn probe_server(
+    executable_path: &Path,
+    root: &Path,
+    timeout: Duration,
+) -> Result<(), String> {
+    let mut child = Command::new(executable_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("failed to spawn the server: {e}"))?;
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let stdout = child.stdout.take().expect("stdout is piped");
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        _ = tx.send(read_initialize_response(BufReader::new(stdout)));
+    });
+    let request = serde_json::json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "initialize",
+        "params": {
+            "processId": std::process::id(),
+            "rootUri": format!("file://{}", root.display()),
+            "capabilities": {},
+        },
+    })
+    .to_string();
+    // If the server exits without reading the request, that's reported by the reader
+    _ = write!(stdin, "Content-Length: {}\r\n\r\n{request}", request.len())
+        .and_then(|()| stdin.flush());
+
+    let result = rx.recv_timeout(timeout).unwrap_or_else(|_| {
+        Err(format!(
+            "no response to `initialize` within {}ms",
+            timeout.as_millis()
+        ))
+    });
+    drop(stdin);
+    _ = child.kill();
+    _ = child.wait();
+
+    result
+}
+
+/// Reads JSON-RPC messages from `reader` until the response to the probe's `initialize`
+/// request is found. Any requests or notifications sent by the server beforehand (i.e.
+/// `window/logMessage`) are skipped.
+fn read_initialize_response<R: BufRead>(mut reader: R) -> Result<(), String> {
+    loop {
+        let mut content_length = None;
+        loop {
+            let mut line = String::new();
+            if reader
+                .read_line(&mut line)
+                .map_err(|e| format!("failed to read from the server: {e}"))?
+                == 0
+            {
+                return Err("the server closed stdout without responding to `initialize`".into());
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            match line.split_once(':') {
+                Some((name, value)) if name.eq_ignore_ascii_case("Content-Length") => {
+                    content_length = Some(
+                        value
+                            .trim()
+                            .parse::<usize>()
+                            .map_err(|_| format!("invalid header {line:?}"))?,
+                    );
+                }
+                Some(_) => {}
+                None => return Err(format!("expected a JSON-RPC header, got {line:?}")),
+            }
+        }
+        let Some(content_length) = content_length else {
+            return Err("message is missing its `Content-Length` header".into());
+        };
+        let mut body = vec![0; content_length];
+        reader
+            .read_exact(&mut body)
+            .map_err(|e| format!("failed to read from the server: {e}"))?;
+        let message: Value =
+            serde_json::from_slice(&body).map_err(|e| format!("message isn't valid JSON: {e}"))?;
+
+        if message.get("method").is_some() || message.get("id") != Some(&Value::from(0)) {
+            continue;
+        }
+        // An error response still indicates that the server speaks the protocol
+        return match (message.get("result"), message.get("error")) {
+            (Some(result), None) if result.get("capabilities").is_some_and(Value::is_object) => {
+                Ok(())
+            }
+            (None, Some(error)) if error.is_object() => Ok(()),
+            _ => Err(format!("malformed response to `initialize`: {message}")),
+        };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::read_initialize_response;
+
+    fn frame(messages: &[&str]) -> Vec<u8> {
+        messages
+            .iter()
+            .flat_map(|message| {
+                format!("Content-Length: {}\r\n\r\n{message}", message.len()).into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn initialize_response() {
+        let read = |bytes: Vec<u8>| read_initialize_response(bytes.as_slice());
+
+        assert!(
+            read(frame(&[
+                r#"{"jsonrpc":"2.0","id":0,"result":{"capabilities":{}}}"#
+            ]))
+            .is_ok()
+        );
+        // Notifications sent before the response are skipped
+        assert!(
+            read(frame(&[
+                r#"{"jsonrpc":"2.0","method":"window/logMessage","params":{"type":3,"message":"hi"}}"#,
+                r#"{"jsonrpc":"2.0","id":0,"result":{"capabilities":{}}}"#,
+            ]))
+            .is_ok()
+        );
+        assert!(
+            read(frame(&[
+                r#"{"jsonrpc":"2.0","id":0,"error":{"code":-32603,"message":"oops"}}"#
+            ]))
+            .is_ok()
+        );
+        // Missing capabilities
+        assert!(read(frame(&[r#"{"jsonrpc":"2.0","id":0,"result":{}}"#])).is_err());
+        // Not JSON-RPC
+        assert!(read(b"Usage: server [OPTIONS]\n".to_vec()).is_err());
+        // Echoed request, followed by EOF
+        assert!(
+            read(frame(&[
+                r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{}}"#
+            ]))
+            .is_err()
+        );
+    }
+}
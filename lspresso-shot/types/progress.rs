@@ -0,0 +1,104 @@
+use lsp_types::WorkDoneProgress;
+
+use super::CleanResponse;
+
+impl CleanResponse for Vec<WorkDoneProgress> {}
+
+/// Checks that the percentages reported over the course of `progress` never decrease,
+/// and that the last one reported before the progress ends is 100
+pub(crate) fn check_progress_percentages(progress: &[WorkDoneProgress]) -> Result<(), String> {
+    let mut last: Option<u32> = None;
+    for (i, value) in progress.iter().enumerate() {
+        let percentage = match value {
+            WorkDoneProgress::Begin(begin) => begin.percentage,
+            WorkDoneProgress::Report(report) => report.percentage,
+            WorkDoneProgress::End(_) => {
+                return match last {
+                    Some(100) => Ok(()),
+                    Some(last) => Err(format!("progress ended at {last}%, without reaching 100%")),
+                    None => Err("progress ended without reporting a percentage".to_string()),
+                };
+            }
+        };
+        let Some(percentage) = percentage else {
+            continue;
+        };
+        if percentage > 100 {
+            return Err(format!(
+                "message {i} reported an invalid percentage of {percentage}%"
+            ));
+        }
+        if let Some(last) = last.filter(|last| percentage < *last) {
+            return Err(format!(
+                "message {i} reported {percentage}%, decreasing from {last}%"
+            ));
+        }
+        last = Some(percentage);
+    }
+
+    Err("progress never ended".to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use lsp_types::{
+        WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd, WorkDoneProgressReport,
+    };
+
+    use super::check_progress_percentages;
+
+    fn begin(percentage: Option<u32>) -> WorkDoneProgress {
+        WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: "Indexing".to_string(),
+            percentage,
+            ..Default::default()
+        })
+    }
+
+    fn report(percentage: Option<u32>) -> WorkDoneProgress {
+        WorkDoneProgress::Report(WorkDoneProgressReport {
+            percentage,
+            ..Default::default()
+        })
+    }
+
+    fn end() -> WorkDoneProgress {
+        WorkDoneProgress::End(WorkDoneProgressEnd::default())
+    }
+
+    #[test]
+    fn progress_percentages() {
+        let progress = [
+            begin(Some(0)),
+            report(Some(50)),
+            report(None),
+            report(Some(100)),
+            end(),
+        ];
+        assert!(check_progress_percentages(&progress).is_ok());
+        // Repeated percentages are allowed
+        let progress = [
+            begin(None),
+            report(Some(50)),
+            report(Some(50)),
+            report(Some(100)),
+            end(),
+        ];
+        assert!(check_progress_percentages(&progress).is_ok());
+        // Decreasing percentage
+        let progress = [begin(Some(50)), report(Some(25)), report(Some(100)), end()];
+        assert!(check_progress_percentages(&progress).is_err());
+        // Never reaches 100
+        let progress = [begin(Some(0)), report(Some(50)), end()];
+        assert!(check_progress_percentages(&progress).is_err());
+        // No percentages reported
+        let progress = [begin(None), report(None), end()];
+        assert!(check_progress_percentages(&progress).is_err());
+        // Out of range percentage
+        let progress = [begin(Some(0)), report(Some(101)), end()];
+        assert!(check_progress_percentages(&progress).is_err());
+        // Never ends
+        let progress = [begin(Some(0)), report(Some(100))];
+        assert!(check_progress_percentages(&progress).is_err());
+    }
+}
@@ -1,7 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use lsp_types::{
-    DocumentChangeOperation, DocumentChanges, PrepareRenameResponse, ResourceOp, WorkspaceEdit,
+    ChangeAnnotation, DocumentChangeOperation, DocumentChanges, OneOf, PrepareRenameResponse,
+    Range, ResourceOp, TextDocumentEdit, TextEdit, WorkspaceEdit,
 };
 
 use super::{ApproximateEq, CleanResponse, TestCase, TestExecutionResult, clean_uri};
@@ -52,4 +53,282 @@ impl CleanResponse for WorkspaceEdit {
 impl CleanResponse for PrepareRenameResponse {}
 
 impl ApproximateEq for PrepareRenameResponse {}
-impl ApproximateEq for WorkspaceEdit {}
+/// Servers may express the same edit via either `changes` or `document_changes`,
+/// depending on the client's capabilities, so both are normalized into a list of
+/// edits per uri before comparing. Resource operations are compared separately, in
+/// order, and annotation identifiers are resolved against `change_annotations`, so
+/// only the annotations themselves need to match.
+impl ApproximateEq for WorkspaceEdit {
+    fn approx_eq(a: &Self, b: &Self) -> bool {
+        NormalizedWorkspaceEdit::new(a) == NormalizedWorkspaceEdit::new(b)
+    }
+}
+
+/// The annotation attached to an edit or resource operation
+#[derive(Debug, PartialEq)]
+enum Annotation<'a> {
+    None,
+    Resolved(&'a ChangeAnnotation),
+    /// The identifier isn't present in the edit's `change_annotations`
+    Unresolved(&'a str),
+}
+
+impl<'a> Annotation<'a> {
+    fn resolve(edit: &'a WorkspaceEdit, id: Option<&'a String>) -> Self {
+        let Some(id) = id else {
+            return Self::None;
+        };
+        edit.change_annotations
+            .as_ref()
+            .and_then(|annotations| annotations.get(id))
+            .map_or(Self::Unresolved(id), Self::Resolved)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct NormalizedTextEdit<'a> {
+    range: Range,
+    new_text: &'a str,
+    annotation: Annotation<'a>,
+}
+
+/// A `WorkspaceEdit` with the form chosen by the server (`changes` vs. the variants
+/// of `document_changes`) erased. Document versions are ignored, as `changes` can't
+/// express them.
+#[derive(Debug, PartialEq)]
+struct NormalizedWorkspaceEdit<'a> {
+    edits: BTreeMap<&'a str, Vec<NormalizedTextEdit<'a>>>,
+    resource_ops: Vec<(ResourceOp, Annotation<'a>)>,
+}
+
+impl<'a> NormalizedWorkspaceEdit<'a> {
+    fn new(edit: &'a WorkspaceEdit) -> Self {
+        let mut normalized = Self {
+            edits: BTreeMap::new(),
+            resource_ops: Vec::new(),
+        };
+        if let Some(ref changes) = edit.changes {
+            for (uri, edits) in changes {
+                normalized.push_edits(uri.as_str(), edits.iter().map(plain_edit));
+            }
+        }
+        match edit.document_changes {
+            Some(DocumentChanges::Edits(ref doc_edits)) => {
+                for doc_edit in doc_edits {
+                    normalized.push_document_edit(edit, doc_edit);
+                }
+            }
+            Some(DocumentChanges::Operations(ref ops)) => {
+                for op in ops {
+                    match op {
+                        DocumentChangeOperation::Op(op) => {
+                            let (op, id) = split_annotation(op);
+                            normalized
+                                .resource_ops
+                                .push((op, Annotation::resolve(edit, id)));
+                        }
+                        DocumentChangeOperation::Edit(doc_edit) => {
+                            normalized.push_document_edit(edit, doc_edit);
+                        }
+                    }
+                }
+            }
+            None => {}
+        }
+        normalized
+    }
+
+    fn push_document_edit(&mut self, edit: &'a WorkspaceEdit, doc_edit: &'a TextDocumentEdit) {
+        self.push_edits(
+            doc_edit.text_document.uri.as_str(),
+            doc_edit.edits.iter().map(|text_edit| match text_edit {
+                OneOf::Left(text_edit) => plain_edit(text_edit),
+                OneOf::Right(annotated) => NormalizedTextEdit {
+                    range: annotated.text_edit.range,
+                    new_text: &annotated.text_edit.new_text,
+                    annotation: Annotation::resolve(edit, Some(&annotated.annotation_id)),
+                },
+            }),
+        );
+    }
+
+    fn push_edits(
+        &mut self,
+        uri: &'a str,
+        edits: impl ExactSizeIterator<Item = NormalizedTextEdit<'a>>,
+    ) {
+        // A uri without any edits is equivalent to its absence
+        if edits.len() != 0 {
+            self.edits.entry(uri).or_default().extend(edits);
+        }
+    }
+}
+
+const fn plain_edit(edit: &TextEdit) -> NormalizedTextEdit<'_> {
+    NormalizedTextEdit {
+        range: edit.range,
+        new_text: edit.new_text.as_str(),
+        annotation: Annotation::None,
+    }
+}
+
+/// Returns a copy of `op` with its annotation identifier removed, alongside the
+/// identifier itself
+fn split_annotation(op: &ResourceOp) -> (ResourceOp, Option<&String>) {
+    let mut stripped = op.clone();
+    match stripped {
+        ResourceOp::Create(ref mut create) => create.annotation_id = None,
+        ResourceOp::Rename(ref mut rename) => rename.annotation_id = None,
+        ResourceOp::Delete(ref mut delete) => {
+            if let Some(options) = delete.options.as_mut() {
+                options.annotation_id = None;
+            }
+        }
+    }
+    let id = match op {
+        ResourceOp::Create(create) => create.annotation_id.as_ref(),
+        ResourceOp::Rename(rename) => rename.annotation_id.as_ref(),
+        ResourceOp::Delete(delete) => delete
+            .options
+            .as_ref()
+            .and_then(|options| options.annotation_id.as_ref()),
+    };
+    (stripped, id)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, str::FromStr as _};
+
+    use lsp_types::{
+        AnnotatedTextEdit, ChangeAnnotation, CreateFile, DocumentChangeOperation, DocumentChanges,
+        OneOf, OptionalVersionedTextDocumentIdentifier, Position, Range, ResourceOp,
+        TextDocumentEdit, TextEdit, Uri, WorkspaceEdit,
+    };
+
+    use crate::types::ApproximateEq;
+
+    fn uri(path: &str) -> Uri {
+        Uri::from_str(&format!("file://{path}")).unwrap()
+    }
+
+    fn text_edit(new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range::new(Position::new(0, 0), Position::new(0, 3)),
+            new_text: new_text.to_string(),
+        }
+    }
+
+    fn doc_edit(path: &str, edits: Vec<OneOf<TextEdit, AnnotatedTextEdit>>) -> TextDocumentEdit {
+        TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                uri: uri(path),
+                version: Some(1),
+            },
+            edits,
+        }
+    }
+
+    fn annotation(label: &str) -> ChangeAnnotation {
+        ChangeAnnotation {
+            label: label.to_string(),
+            needs_confirmation: None,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn workspace_edit_changes_vs_document_changes() {
+        let changes = WorkspaceEdit {
+            changes: Some(HashMap::from([
+                (uri("/a.rs"), vec![text_edit("foo")]),
+                (uri("/b.rs"), vec![text_edit("bar")]),
+            ])),
+            ..Default::default()
+        };
+        let document_changes = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Edits(vec![
+                doc_edit("/b.rs", vec![OneOf::Left(text_edit("bar"))]),
+                doc_edit("/a.rs", vec![OneOf::Left(text_edit("foo"))]),
+            ])),
+            ..Default::default()
+        };
+        assert!(WorkspaceEdit::approx_eq(&changes, &document_changes));
+
+        let operations = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                DocumentChangeOperation::Edit(doc_edit(
+                    "/a.rs",
+                    vec![OneOf::Left(text_edit("foo"))],
+                )),
+                DocumentChangeOperation::Edit(doc_edit(
+                    "/b.rs",
+                    vec![OneOf::Left(text_edit("bar"))],
+                )),
+            ])),
+            ..Default::default()
+        };
+        assert!(WorkspaceEdit::approx_eq(&changes, &operations));
+
+        let different = WorkspaceEdit {
+            changes: Some(HashMap::from([(uri("/a.rs"), vec![text_edit("foo")])])),
+            ..Default::default()
+        };
+        assert!(!WorkspaceEdit::approx_eq(&changes, &different));
+    }
+
+    #[test]
+    fn workspace_edit_annotations_and_resource_ops() {
+        let annotated = |id: &str| {
+            OneOf::Right(AnnotatedTextEdit {
+                text_edit: text_edit("foo"),
+                annotation_id: id.to_string(),
+            })
+        };
+        let create = |id: &str| {
+            DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                uri: uri("/new.rs"),
+                options: None,
+                annotation_id: Some(id.to_string()),
+            }))
+        };
+        let edit_with_id = |id: &str, label: &str| WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                create(id),
+                DocumentChangeOperation::Edit(doc_edit("/a.rs", vec![annotated(id)])),
+            ])),
+            change_annotations: Some(HashMap::from([(id.to_string(), annotation(label))])),
+            ..Default::default()
+        };
+        // Only the resolved annotations need to match, not their identifiers
+        assert!(WorkspaceEdit::approx_eq(
+            &edit_with_id("1", "Rename"),
+            &edit_with_id("rename-id", "Rename")
+        ));
+        assert!(!WorkspaceEdit::approx_eq(
+            &edit_with_id("1", "Rename"),
+            &edit_with_id("1", "Move")
+        ));
+
+        // Resource operations aren't expressible via `changes`
+        let changes = WorkspaceEdit {
+            changes: Some(HashMap::from([(uri("/a.rs"), vec![text_edit("foo")])])),
+            ..Default::default()
+        };
+        let with_op = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                    uri: uri("/new.rs"),
+                    options: None,
+                    annotation_id: None,
+                })),
+                DocumentChangeOperation::Edit(doc_edit(
+                    "/a.rs",
+                    vec![OneOf::Left(text_edit("foo"))],
+                )),
+            ])),
+            ..Default::default()
+        };
+        assert!(!WorkspaceEdit::approx_eq(&changes, &with_op));
+    }
+}
@@ -0,0 +1,795 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{TestCase, TestError, TestResult, TestType};
+
+/// The outcome of a single test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStatus {
+    /// The server's response matched the expected value
+    Passed,
+    /// The server's response didn't match the expected value
+    Failed,
+    /// The test couldn't be set up or run to completion (i.e. the timeout was exceeded)
+    Errored,
+}
+
+/// A record of a single test's outcome, for use with [`TestBatch`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestReport {
+    pub test_id: String,
+    pub test_type: TestType,
+    pub status: TestStatus,
+    /// The wall-clock time taken by the test, including setup
+    pub duration: Duration,
+    /// The error message of a failed or errored test, with any styling removed
+    pub message: Option<String>,
+}
+
+impl TestReport {
+    /// Builds a report for the test of `test_type` run on `test_case`, which produced
+    /// `result` after `duration`
+    #[must_use]
+    pub fn new<R: Serialize>(
+        test_case: &TestCase,
+        test_type: TestType,
+        duration: Duration,
+        result: &TestResult<(), R>,
+    ) -> Self {
+        let (status, message) = match result {
+            Ok(()) => (TestStatus::Passed, None),
+            Err(err @ (TestError::ResponseMismatch(_) | TestError::JsonMismatch(_))) => {
+                (TestStatus::Failed, Some(strip_ansi(&err.to_string())))
+            }
+            Err(err) => (TestStatus::Errored, Some(strip_ansi(&err.to_string()))),
+        };
+        Self {
+            test_id: test_case.test_id.clone(),
+            test_type,
+            status,
+            duration,
+            message,
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "test_id": self.test_id,
+            "test_type": self.test_type.to_string(),
+            "status": self.status,
+            "duration_secs": self.duration.as_secs_f64(),
+            "message": self.message,
+        })
+    }
+}
+
+/// Aggregate counts over the reports in a [`TestBatch`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub errored: usize,
+    /// The sum of each test's duration
+    pub total_time: Duration,
+}
+
+impl BatchSummary {
+    /// The total number of tests
+    #[must_use]
+    pub const fn total(&self) -> usize {
+        self.passed + self.failed + self.errored
+    }
+}
+
+impl std::fmt::Display for BatchSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} tests: {} passed, {} failed, {} errored ({:.2}s)",
+            self.total(),
+            self.passed,
+            self.failed,
+            self.errored,
+            self.total_time.as_secs_f64()
+        )
+    }
+}
+
+/// Collects the outcomes of multiple tests, as a non-panicking alternative to
+/// [`lspresso_shot!`](crate::lspresso_shot). Rather than stopping at the first failure,
+/// every test is run and recorded, and a summary of the whole batch can be produced
+/// afterwards.
+///
+/// ```ignore
+/// let mut batch = TestBatch::new();
+/// batch.run(&test_case, TestType::Hover, || {
+///     test_hover(&test_case, Position::new(1, 2), None, Some(&expected))
+/// });
+/// println!("{}", batch.summary());
+/// assert!(batch.all_passed(), "{batch}");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestBatch {
+    reports: Vec<TestReport>,
+}
+
+impl TestBatch {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            reports: Vec::new(),
+        }
+    }
+
+    /// Runs `test`, a `test_*` call on `test_case`, and records its outcome. Returns
+    /// whether the test passed.
+    pub fn run<R: Serialize>(
+        &mut self,
+        test_case: &TestCase,
+        test_type: TestType,
+        test: impl FnOnce() -> TestResult<(), R>,
+    ) -> bool {
+        let start = Instant::now();
+        let result = test();
+        let report = TestReport::new(test_case, test_type, start.elapsed(), &result);
+        self.push(report);
+        result.is_ok()
+    }
+
+    /// Records an already built report
+    pub fn push(&mut self, report: TestReport) {
+        self.reports.push(report);
+    }
+
+    /// Returns the recorded reports, in the order they were recorded
+    #[must_use]
+    pub fn reports(&self) -> &[TestReport] {
+        &self.reports
+    }
+
+    /// Returns the reports of tests that didn't pass
+    pub fn failures(&self) -> impl Iterator<Item = &TestReport> {
+        self.reports
+            .iter()
+            .filter(|report| report.status != TestStatus::Passed)
+    }
+
+    /// Indicates if every recorded test passed
+    #[must_use]
+    pub fn all_passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    #[must_use]
+    pub fn summary(&self) -> BatchSummary {
+        let mut summary = BatchSummary::default();
+        for report in &self.reports {
+            match report.status {
+                TestStatus::Passed => summary.passed += 1,
+                TestStatus::Failed => summary.failed += 1,
+                TestStatus::Errored => summary.errored += 1,
+            }
+            summary.total_time += report.duration;
+        }
+        summary
+    }
+
+    /// Writes the batch's summary and reports to `writer` as JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if writing to `writer` fails
+    pub fn to_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        let summary = self.summary();
+        let batch = serde_json::json!({
+            "summary": {
+                "total": summary.total(),
+                "passed": summary.passed,
+                "failed": summary.failed,
+                "errored": summary.errored,
+                "total_time_secs": summary.total_time.as_secs_f64(),
+            },
+            "reports": self.reports.iter().map(TestReport::to_json).collect::<Vec<_>>(),
+        });
+        serde_json::to_writer_pretty(writer, &batch)
+    }
+
+    /// Writes the batch to `writer` as JUnit XML, for display in CI dashboards. Each
+    /// report becomes a `testcase` element named after its test type and test ID.
+    /// Failed tests carry a `failure` element and errored tests an `error` element,
+    /// each containing the test's error message.
+    ///
+    /// # Errors
+    ///
+    /// Returns `std::io::Error` if writing to `writer` fails
+    pub fn to_junit<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let summary = self.summary();
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<testsuites tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+            summary.total(),
+            summary.failed,
+            summary.errored,
+            summary.total_time.as_secs_f64()
+        )?;
+        writeln!(
+            writer,
+            r#"  <testsuite name="lspresso-shot" tests="{}" failures="{}" errors="{}" time="{:.3}">"#,
+            summary.total(),
+            summary.failed,
+            summary.errored,
+            summary.total_time.as_secs_f64()
+        )?;
+        for report in &self.reports {
+            let test_type = escape_xml(&report.test_type.to_string());
+            write!(
+                writer,
+                r#"    <testcase name="{test_type} ({})" classname="{test_type}" time="{:.3}""#,
+                escape_xml(&report.test_id),
+                report.duration.as_secs_f64()
+            )?;
+            let element = match report.status {
+                TestStatus::Passed => {
+                    writeln!(writer, "/>")?;
+                    continue;
+                }
+                TestStatus::Failed => "failure",
+                TestStatus::Errored => "error",
+            };
+            writeln!(writer, ">")?;
+            let message = report.message.as_deref().unwrap_or_default();
+            writeln!(
+                writer,
+                r#"      <{element} message="{}">{}</{element}>"#,
+                escape_xml(message.lines().next().unwrap_or_default()),
+                escape_xml(message)
+            )?;
+            writeln!(writer, "    </testcase>")?;
+        }
+        writeln!(writer, "  </testsuite>")?;
+        writeln!(writer, "</testsuites>")
+    }
+}
+
+impl std::fmt::Display for TestBatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for report in self.failures() {
+            writeln!(
+                f,
+                "{} {} ({:?}):",
+                report.test_type, report.test_id, report.status
+            )?;
+            if let Some(message) = &report.message {
+                writeln!(f, "{message}")?;
+            }
+        }
+        write!(f, "{}", self.summary())
+    }
+}
+
+/// Why a [`ConformanceRunner`] skipped a test type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// No expected value was provided for the test type
+    NoExpectation,
+    /// The server doesn't advertise the capability for the test type's request
+    NotAdvertised,
+}
+
+/// The outcome of a single test type in a [`ConformanceReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConformanceStatus {
+    /// The test type was run, with the given outcome
+    Ran(TestStatus),
+    /// The test type wasn't run
+    Skipped(SkipReason),
+}
+
+impl std::fmt::Display for ConformanceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ran(TestStatus::Passed) => write!(f, "passed"),
+            Self::Ran(TestStatus::Failed) => write!(f, "failed"),
+            Self::Ran(TestStatus::Errored) => write!(f, "errored"),
+            Self::Skipped(SkipReason::NoExpectation) => write!(f, "skipped (no expectation)"),
+            Self::Skipped(SkipReason::NotAdvertised) => write!(f, "skipped (not advertised)"),
+        }
+    }
+}
+
+/// A single row of a [`ConformanceReport`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceEntry {
+    pub test_type: TestType,
+    pub status: ConformanceStatus,
+    /// The report of the test type's check, if it was run
+    pub report: Option<TestReport>,
+}
+
+type ConformanceCheck<'a> = Box<dyn FnOnce(&TestCase) -> TestReport + 'a>;
+
+/// Runs a set of checks against a single test case to produce a [`ConformanceReport`],
+/// a matrix of which request types a server supports. Each check is a `test_*` call
+/// with the expected value for its test type:
+///
+/// ```ignore
+/// let report = ConformanceRunner::new(&test_case)
+///     .expect(TestType::Hover, |test_case| {
+///         test_hover(test_case, Position::new(1, 2), None, Some(&expected_hover))
+///     })
+///     .expect(TestType::Definition, |test_case| {
+///         test_definition(test_case, Position::new(3, 4), None, Some(&expected_definition))
+///     })
+///     .run();
+/// println!("{report}");
+/// ```
+///
+/// Every test type in [`TestType::all`] appears in the report. Test types without a
+/// check are skipped with [`SkipReason::NoExpectation`], and checks for test types whose
+/// capability (see [`TestType::capability`]) the server doesn't advertise in its
+/// response to `initialize` are skipped with [`SkipReason::NotAdvertised`] rather than
+/// reported as failures. Capabilities registered dynamically via
+/// `client/registerCapability` aren't considered.
+pub struct ConformanceRunner<'a> {
+    test_case: &'a TestCase,
+    checks: Vec<(TestType, ConformanceCheck<'a>)>,
+}
+
+impl<'a> ConformanceRunner<'a> {
+    #[must_use]
+    pub const fn new(test_case: &'a TestCase) -> Self {
+        Self {
+            test_case,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Registers `test`, a `test_*` call for `test_type`, to be run on the runner's test
+    /// case. Registering a second check for the same test type replaces the first.
+    #[must_use]
+    pub fn expect<R: Serialize + 'a>(
+        mut self,
+        test_type: TestType,
+        test: impl FnOnce(&TestCase) -> TestResult<(), R> + 'a,
+    ) -> Self {
+        self.checks
+            .retain(|(registered, _)| *registered != test_type);
+        self.checks.push((
+            test_type,
+            Box::new(move |test_case| {
+                let start = Instant::now();
+                let result = test(test_case);
+                TestReport::new(test_case, test_type, start.elapsed(), &result)
+            }),
+        ));
+        self
+    }
+
+    /// Runs every registered check whose capability the server advertises. If the
+    /// server's capabilities can't be retrieved, every registered check is run.
+    #[must_use]
+    pub fn run(self) -> ConformanceReport {
+        let capabilities = crate::fetch_server_capabilities(self.test_case).ok();
+        self.run_with_capabilities(capabilities.as_ref())
+    }
+
+    fn run_with_capabilities(mut self, capabilities: Option<&Value>) -> ConformanceReport {
+        let mut entries = Vec::with_capacity(TestType::all().len());
+        for &test_type in TestType::all() {
+            let Some(idx) = self
+                .checks
+                .iter()
+                .position(|(registered, _)| *registered == test_type)
+            else {
+                entries.push(ConformanceEntry {
+                    test_type,
+                    status: ConformanceStatus::Skipped(SkipReason::NoExpectation),
+                    report: None,
+                });
+                continue;
+            };
+            let (_, check) = self.checks.swap_remove(idx);
+            if !is_advertised(capabilities, test_type) {
+                entries.push(ConformanceEntry {
+                    test_type,
+                    status: ConformanceStatus::Skipped(SkipReason::NotAdvertised),
+                    report: None,
+                });
+                continue;
+            }
+            let report = check(self.test_case);
+            entries.push(ConformanceEntry {
+                test_type,
+                status: ConformanceStatus::Ran(report.status),
+                report: Some(report),
+            });
+        }
+        ConformanceReport {
+            test_id: self.test_case.test_id.clone(),
+            entries,
+        }
+    }
+}
+
+/// Indicates if `capabilities` advertise support for `test_type`'s request. Test types
+/// without a capability, or unknown capabilities, are treated as advertised.
+fn is_advertised(capabilities: Option<&Value>, test_type: TestType) -> bool {
+    let (Some(capabilities), Some(pointer)) = (capabilities, test_type.capability()) else {
+        return true;
+    };
+    capabilities
+        .pointer(pointer)
+        .is_some_and(|provider| !matches!(provider, Value::Null | Value::Bool(false)))
+}
+
+/// A matrix of which request types a server passed, produced by a
+/// [`ConformanceRunner`]. Displays as a table with one row per test type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConformanceReport {
+    pub test_id: String,
+    entries: Vec<ConformanceEntry>,
+}
+
+impl ConformanceReport {
+    /// Returns an entry for every test type, in the order of [`TestType::all`]
+    #[must_use]
+    pub fn entries(&self) -> &[ConformanceEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry for `test_type`
+    #[must_use]
+    pub fn get(&self, test_type: TestType) -> Option<&ConformanceEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.test_type == test_type)
+    }
+
+    /// Aggregate counts over the test types that were run
+    #[must_use]
+    pub fn summary(&self) -> BatchSummary {
+        let mut summary = BatchSummary::default();
+        for report in self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.report.as_ref())
+        {
+            match report.status {
+                TestStatus::Passed => summary.passed += 1,
+                TestStatus::Failed => summary.failed += 1,
+                TestStatus::Errored => summary.errored += 1,
+            }
+            summary.total_time += report.duration;
+        }
+        summary
+    }
+
+    /// The number of test types that were skipped
+    #[must_use]
+    pub fn skipped(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.status, ConformanceStatus::Skipped(_)))
+            .count()
+    }
+
+    /// Indicates if every test type that was run passed
+    #[must_use]
+    pub fn all_passed(&self) -> bool {
+        self.entries.iter().all(|entry| {
+            matches!(
+                entry.status,
+                ConformanceStatus::Ran(TestStatus::Passed) | ConformanceStatus::Skipped(_)
+            )
+        })
+    }
+
+    /// Writes the report's summary and entries to `writer` as JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns `serde_json::Error` if writing to `writer` fails
+    pub fn to_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+        let summary = self.summary();
+        let entries: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let (status, skip_reason) = match entry.status {
+                    ConformanceStatus::Ran(status) => (serde_json::to_value(status)?, None),
+                    ConformanceStatus::Skipped(reason) => {
+                        (Value::from("skipped"), Some(serde_json::to_value(reason)?))
+                    }
+                };
+                Ok(serde_json::json!({
+                    "test_type": entry.test_type.to_string(),
+                    "status": status,
+                    "skip_reason": skip_reason,
+                    "report": entry.report.as_ref().map(TestReport::to_json),
+                }))
+            })
+            .collect::<serde_json::Result<_>>()?;
+        let report = serde_json::json!({
+            "test_id": self.test_id,
+            "summary": {
+                "passed": summary.passed,
+                "failed": summary.failed,
+                "errored": summary.errored,
+                "skipped": self.skipped(),
+                "total_time_secs": summary.total_time.as_secs_f64(),
+            },
+            "entries": entries,
+        });
+        serde_json::to_writer_pretty(writer, &report)
+    }
+}
+
+impl std::fmt::Display for ConformanceReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let methods: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| entry.test_type.to_string())
+            .collect();
+        let method_width = methods.iter().map(String::len).max().unwrap_or_default();
+        writeln!(f, "{:<method_width$}  status", "method")?;
+        for (entry, method) in self.entries.iter().zip(&methods) {
+            writeln!(f, "{method:<method_width$}  {}", entry.status)?;
+        }
+        write!(f, "{}, {} skipped", self.summary(), self.skipped())
+    }
+}
+
+/// Escapes the characters in `text` that are reserved in XML attributes and text
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Removes ANSI escape sequences (i.e. the colors used in mismatch diffs) from `text`
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip the escape sequence through its final byte
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            stripped.push(c);
+        }
+    }
+    stripped
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use crate::types::{
+        ResponseMismatchError, TestCase, TestError, TestExecutionError, TestFile, TestResult,
+        TestType,
+    };
+
+    use super::{
+        ConformanceRunner, ConformanceStatus, SkipReason, TestBatch, TestReport, TestStatus,
+        escape_xml, strip_ansi,
+    };
+
+    fn report(status: TestStatus, millis: u64) -> TestReport {
+        let test_case = TestCase::new("server", TestFile::new("main.rs", ""));
+        let result: TestResult<(), u32> = match status {
+            TestStatus::Passed => Ok(()),
+            TestStatus::Failed => Err(TestError::ResponseMismatch(ResponseMismatchError {
+                test_id: test_case.test_id.clone(),
+                expected: Some(1),
+                actual: Some(2),
+                reason: None,
+            })),
+            TestStatus::Errored => Err(TestError::TestExecution(TestExecutionError::NoResults(
+                test_case.test_id.clone(),
+            ))),
+        };
+        TestReport::new(
+            &test_case,
+            TestType::Hover,
+            Duration::from_millis(millis),
+            &result,
+        )
+    }
+
+    #[test]
+    fn batch_summary() {
+        let mut batch = TestBatch::new();
+        assert!(batch.all_passed());
+        batch.push(report(TestStatus::Passed, 100));
+        batch.push(report(TestStatus::Failed, 200));
+        batch.push(report(TestStatus::Errored, 300));
+        batch.push(report(TestStatus::Passed, 400));
+
+        let summary = batch.summary();
+        assert_eq!(summary.passed, 2);
+        assert_eq!(summary.failed, 1);
+        assert_eq!(summary.errored, 1);
+        assert_eq!(summary.total(), 4);
+        assert_eq!(summary.total_time, Duration::from_secs(1));
+        assert!(!batch.all_passed());
+        let failures: Vec<_> = batch.failures().map(|report| report.status).collect();
+        assert_eq!(failures, [TestStatus::Failed, TestStatus::Errored]);
+
+        let mut json = Vec::new();
+        batch.to_json(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["summary"]["failed"], 1);
+        assert_eq!(json["reports"][1]["status"], "failed");
+        assert_eq!(json["reports"][1]["test_type"], "textDocument/hover");
+        assert!(json["reports"][0]["message"].is_null());
+    }
+
+    #[test]
+    fn report_messages_are_unstyled() {
+        let failed = report(TestStatus::Failed, 0);
+        assert!(!failed.message.unwrap().contains('\x1b'));
+        assert_eq!(strip_ansi("\x1b[31mred\x1b[0m text"), "red text");
+    }
+
+    #[test]
+    fn batch_to_junit() {
+        let mut batch = TestBatch::new();
+        batch.push(report(TestStatus::Passed, 500));
+        batch.push(report(TestStatus::Failed, 250));
+        batch.push(report(TestStatus::Errored, 250));
+
+        let mut xml = Vec::new();
+        batch.to_junit(&mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains(
+            r#"<testsuite name="lspresso-shot" tests="3" failures="1" errors="1" time="1.000">"#
+        ));
+        let passed = &batch.reports()[0];
+        assert!(xml.contains(&format!(
+            r#"<testcase name="textDocument/hover ({})" classname="textDocument/hover" time="0.500"/>"#,
+            passed.test_id
+        )));
+        assert_eq!(xml.matches("<failure message=").count(), 1);
+        assert_eq!(xml.matches("<error message=").count(), 1);
+        assert_eq!(xml.matches("</testcase>").count(), 2);
+    }
+
+    #[test]
+    fn xml_escaping() {
+        assert_eq!(
+            escape_xml(r#"<a href="x">'&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&apos;&amp;&apos;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn conformance_matrix() {
+        let test_case = TestCase::new("server", TestFile::new("main.rs", ""));
+        let capabilities = serde_json::json!({
+            "hoverProvider": true,
+            "definitionProvider": false,
+            "referencesProvider": {},
+        });
+        let report = ConformanceRunner::new(&test_case)
+            .expect(TestType::Hover, |_| TestResult::<(), u32>::Ok(()))
+            .expect(TestType::Definition, |_| TestResult::<(), u32>::Ok(()))
+            .expect(TestType::Completion, |_| TestResult::<(), u32>::Ok(()))
+            .expect(TestType::References, |test_case| {
+                TestResult::<(), u32>::Err(TestError::TestExecution(TestExecutionError::NoResults(
+                    test_case.test_id.clone(),
+                )))
+            })
+            .run_with_capabilities(Some(&capabilities));
+
+        assert_eq!(report.entries().len(), TestType::all().len());
+        let status = |test_type| report.get(test_type).unwrap().status;
+        assert_eq!(
+            status(TestType::Hover),
+            ConformanceStatus::Ran(TestStatus::Passed)
+        );
+        assert_eq!(
+            status(TestType::References),
+            ConformanceStatus::Ran(TestStatus::Errored)
+        );
+        assert_eq!(
+            status(TestType::Definition),
+            ConformanceStatus::Skipped(SkipReason::NotAdvertised)
+        );
+        assert_eq!(
+            status(TestType::Completion),
+            ConformanceStatus::Skipped(SkipReason::NotAdvertised)
+        );
+        assert_eq!(
+            status(TestType::Rename),
+            ConformanceStatus::Skipped(SkipReason::NoExpectation)
+        );
+        assert_eq!(report.summary().total(), 2);
+        assert_eq!(report.skipped(), TestType::all().len() - 2);
+        assert!(!report.all_passed());
+
+        let table = report.to_string();
+        assert!(table.contains("textDocument/definition"));
+        assert!(table.contains("skipped (not advertised)"));
+
+        let mut json = Vec::new();
+        report.to_json(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["summary"]["errored"], 1);
+        let hover = &json["entries"][TestType::all()
+            .iter()
+            .position(|&test_type| test_type == TestType::Hover)
+            .unwrap()];
+        assert_eq!(hover["status"], "passed");
+        assert!(hover["skip_reason"].is_null());
+
+        // Without the server's capabilities, every check is run
+        let report = ConformanceRunner::new(&test_case)
+            .expect(TestType::Definition, |_| TestResult::<(), u32>::Ok(()))
+            .run_with_capabilities(None);
+        assert!(report.all_passed());
+        assert_eq!(report.summary().passed, 1);
+
+        // Resolve and sub-requests are gated on their sub-capabilities
+        let capabilities = serde_json::json!({
+            "completionProvider": { "resolveProvider": false },
+            "renameProvider": { "prepareProvider": true },
+            "semanticTokensProvider": { "full": true, "range": true },
+        });
+        let report = ConformanceRunner::new(&test_case)
+            .expect(TestType::Completion, |_| TestResult::<(), u32>::Ok(()))
+            .expect(TestType::CompletionResolve, |_| {
+                TestResult::<(), u32>::Ok(())
+            })
+            .expect(TestType::PrepareRename, |_| TestResult::<(), u32>::Ok(()))
+            .expect(TestType::SemanticTokensRange, |_| {
+                TestResult::<(), u32>::Ok(())
+            })
+            .expect(TestType::SemanticTokensFullDelta, |_| {
+                TestResult::<(), u32>::Ok(())
+            })
+            .run_with_capabilities(Some(&capabilities));
+        let status = |test_type| report.get(test_type).unwrap().status;
+        for test_type in [
+            TestType::Completion,
+            TestType::PrepareRename,
+            TestType::SemanticTokensRange,
+        ] {
+            assert_eq!(
+                status(test_type),
+                ConformanceStatus::Ran(TestStatus::Passed)
+            );
+        }
+        for test_type in [
+            TestType::CompletionResolve,
+            TestType::SemanticTokensFullDelta,
+        ] {
+            assert_eq!(
+                status(test_type),
+                ConformanceStatus::Skipped(SkipReason::NotAdvertised)
+            );
+        }
+    }
+}
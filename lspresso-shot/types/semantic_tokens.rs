@@ -6,14 +6,26 @@ impl CleanResponse for SemanticTokensResult {}
 impl CleanResponse for SemanticTokensFullDeltaResult {}
 impl CleanResponse for SemanticTokensRangeResult {}
 
+// NOTE: Servers assign opaque `result_id`s that typically differ between runs (or even
+// between requests within a run), so only the presence of a `result_id` is compared,
+// never its value. To ignore `result_id`s entirely, use `compare::ignore_result_id`.
+
+/// Indicates if `a` and `b` are either both present or both absent
+const fn result_ids_match(a: Option<&String>, b: Option<&String>) -> bool {
+    a.is_some() == b.is_some()
+}
+
 impl ApproximateEq for SemanticTokensResult {
     fn approx_eq(a: &Self, b: &Self) -> bool {
         match (a, b) {
+            (Self::Tokens(a), Self::Tokens(b)) => {
+                result_ids_match(a.result_id.as_ref(), b.result_id.as_ref()) && a.data == b.data
+            }
+            (Self::Partial(a), Self::Partial(b)) => a.data == b.data,
             (Self::Tokens(tokens), Self::Partial(partial))
             | (Self::Partial(partial), Self::Tokens(tokens)) => {
                 tokens.result_id.is_none() && tokens.data.eq(&partial.data)
             }
-            _ => a == b,
         }
     }
 }
@@ -21,6 +33,15 @@ impl ApproximateEq for SemanticTokensResult {
 impl ApproximateEq for SemanticTokensFullDeltaResult {
     fn approx_eq(a: &Self, b: &Self) -> bool {
         match (a, b) {
+            (Self::Tokens(a), Self::Tokens(b)) => {
+                result_ids_match(a.result_id.as_ref(), b.result_id.as_ref()) && a.data == b.data
+            }
+            (Self::TokensDelta(a), Self::TokensDelta(b)) => {
+                result_ids_match(a.result_id.as_ref(), b.result_id.as_ref()) && a.edits == b.edits
+            }
+            (Self::PartialTokensDelta { edits: a }, Self::PartialTokensDelta { edits: b }) => {
+                a == b
+            }
             (Self::Tokens(tokens), Self::TokensDelta(delta))
             | (Self::TokensDelta(delta), Self::Tokens(tokens)) => {
                 tokens.result_id.is_none() && tokens.data.is_empty() && delta.edits.is_empty()
@@ -33,7 +54,6 @@ impl ApproximateEq for SemanticTokensFullDeltaResult {
             | (Self::PartialTokensDelta { edits }, Self::TokensDelta(delta)) => {
                 delta.result_id.is_none() && delta.edits.is_empty() && edits.is_empty()
             }
-            _ => a == b,
         }
     }
 }
@@ -41,11 +61,14 @@ impl ApproximateEq for SemanticTokensFullDeltaResult {
 impl ApproximateEq for SemanticTokensRangeResult {
     fn approx_eq(a: &Self, b: &Self) -> bool {
         match (a, b) {
+            (Self::Tokens(a), Self::Tokens(b)) => {
+                result_ids_match(a.result_id.as_ref(), b.result_id.as_ref()) && a.data == b.data
+            }
+            (Self::Partial(a), Self::Partial(b)) => a.data == b.data,
             (Self::Tokens(tokens), Self::Partial(partial))
             | (Self::Partial(partial), Self::Tokens(tokens)) => {
                 tokens.result_id.is_none() && tokens.data.eq(&partial.data)
             }
-            _ => a == b,
         }
     }
 }
@@ -1,7 +1,161 @@
-use lsp_types::SignatureHelp;
+use lsp_types::{
+    SignatureHelp, SignatureHelpContext, SignatureHelpTriggerKind, SignatureInformation,
+};
 
 use super::{ApproximateEq, CleanResponse};
 
+/// Builds a [`SignatureHelpContext`] for use with `test_signature_help`.
+///
+/// The builder starts from a context describing a manual invocation, with no trigger
+/// character and no active signature help:
+///
+/// ```ignore
+/// // Typing another comma in an argument list, while `previous` is being shown
+/// let context = SignatureHelpContextBuilder::new()
+///     .trigger_character(",")
+///     .retrigger_from(&previous)
+///     .build();
+/// ```
+#[derive(Debug, Clone)]
+pub struct SignatureHelpContextBuilder {
+    context: SignatureHelpContext,
+}
+
+impl Default for SignatureHelpContextBuilder {
+    fn default() -> Self {
+        Self {
+            context: SignatureHelpContext {
+                trigger_kind: SignatureHelpTriggerKind::INVOKED,
+                trigger_character: None,
+                is_retrigger: false,
+                active_signature_help: None,
+            },
+        }
+    }
+}
+
+impl SignatureHelpContextBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the action that caused signature help to be triggered
+    #[must_use]
+    pub const fn trigger_kind(mut self, trigger_kind: SignatureHelpTriggerKind) -> Self {
+        self.context.trigger_kind = trigger_kind;
+        self
+    }
+
+    /// Set the character that caused signature help to be triggered. This also sets
+    /// the trigger kind to [`SignatureHelpTriggerKind::TRIGGER_CHARACTER`]
+    #[must_use]
+    pub fn trigger_character<S: Into<String>>(mut self, trigger_character: S) -> Self {
+        self.context.trigger_kind = SignatureHelpTriggerKind::TRIGGER_CHARACTER;
+        self.context.trigger_character = Some(trigger_character.into());
+        self
+    }
+
+    /// Set whether signature help was already showing when it was triggered
+    #[must_use]
+    pub const fn is_retrigger(mut self, is_retrigger: bool) -> Self {
+        self.context.is_retrigger = is_retrigger;
+        self
+    }
+
+    /// Set the signature help currently being shown
+    #[must_use]
+    pub fn active_signature_help(mut self, active_signature_help: Option<SignatureHelp>) -> Self {
+        self.context.active_signature_help = active_signature_help;
+        self
+    }
+
+    /// Marks the request as a retrigger while `previous` is being shown, i.e. after
+    /// typing another comma in an argument list. Sets `is_retrigger` and
+    /// `active_signature_help`
+    #[must_use]
+    pub fn retrigger_from(self, previous: &SignatureHelp) -> Self {
+        self.is_retrigger(true)
+            .active_signature_help(Some(previous.clone()))
+    }
+
+    #[must_use]
+    pub fn build(self) -> SignatureHelpContext {
+        self.context
+    }
+}
+
+impl From<SignatureHelpContextBuilder> for SignatureHelpContext {
+    fn from(value: SignatureHelpContextBuilder) -> Self {
+        value.build()
+    }
+}
+
 impl CleanResponse for SignatureHelp {}
 
-impl ApproximateEq for SignatureHelp {}
+/// The active parameter can be specified in two places: `SignatureHelp::active_parameter`,
+/// and `SignatureInformation::active_parameter`. Per the spec, the latter takes precedence
+/// for its signature. Two `SignatureHelp`s are considered equal if their signatures
+/// are equal (ignoring where the active parameter is specified), they specify the same
+/// `active_signature`, and each signature's active parameter is the same once that
+/// precedence is applied.
+///
+/// Indices are compared as given: an out of range or omitted index is *not* resolved
+/// to its spec-mandated default, so a server sending `active_signature: 5` for a single
+/// signature doesn't match an expected `active_signature: 0`. To compare the resolved
+/// indices instead, use [`compare::signature_help_resolved`](crate::types::compare::signature_help_resolved).
+impl ApproximateEq for SignatureHelp {
+    fn approx_eq(a: &Self, b: &Self) -> bool {
+        if a.signatures.len() != b.signatures.len() || a.active_signature != b.active_signature {
+            return false;
+        }
+        if a.signatures.is_empty() {
+            return a.active_parameter == b.active_parameter;
+        }
+        a.signatures
+            .iter()
+            .zip(b.signatures.iter())
+            .all(|(sig_a, sig_b)| {
+                signatures_eq(sig_a, sig_b)
+                    && sig_a.active_parameter.or(a.active_parameter)
+                        == sig_b.active_parameter.or(b.active_parameter)
+            })
+    }
+}
+
+/// Compares the label, documentation, and parameters of two signatures
+pub(crate) fn signatures_eq(a: &SignatureInformation, b: &SignatureInformation) -> bool {
+    a.label == b.label && a.documentation == b.documentation && a.parameters == b.parameters
+}
+
+/// Resolves the active signature and parameter indices of `help`, as described in
+/// the spec:
+///
+/// - If `active_signature` is omitted or out of range, it defaults to 0.
+/// - The active signature's `active_parameter` takes precedence over `help.active_parameter`.
+/// - If the resolved parameter is omitted or out of range, it defaults to 0 if the
+///   active signature has parameters.
+///
+/// Returns `None` for the signature index if there are no signatures, and `None`
+/// for the parameter index if the active signature has no parameters.
+#[must_use]
+pub fn active_indices(help: &SignatureHelp) -> (Option<u32>, Option<u32>) {
+    if help.signatures.is_empty() {
+        return (None, None);
+    }
+    let sig_idx = help
+        .active_signature
+        .filter(|&idx| (idx as usize) < help.signatures.len())
+        .unwrap_or(0);
+    let sig = &help.signatures[sig_idx as usize];
+    let n_params = sig.parameters.as_ref().map_or(0, Vec::len);
+    if n_params == 0 {
+        return (Some(sig_idx), None);
+    }
+    let param_idx = sig
+        .active_parameter
+        .or(help.active_parameter)
+        .filter(|&idx| (idx as usize) < n_params)
+        .unwrap_or(0);
+    (Some(sig_idx), Some(param_idx))
+}
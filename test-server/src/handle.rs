@@ -1,60 +1,76 @@
-use std::str::FromStr;
+use std::{fs, io::Write as _, path::Path, str::FromStr, time::Duration};
 
 use anyhow::Result;
 use log::{error, info};
-use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_server::{
+    Connection, ErrorCode, Message, Notification, Request, RequestId, Response, ResponseError,
+};
 use lsp_types::{
-    CallHierarchyIncomingCallsParams, CallHierarchyOutgoingCallsParams, CallHierarchyPrepareParams,
-    CodeAction, CodeActionParams, CodeLens, CodeLensParams, ColorPresentationParams,
-    CompletionItem, CompletionParams, CreateFilesParams, DeleteFilesParams, DocumentColorParams,
-    DocumentDiagnosticParams, DocumentFormattingParams, DocumentHighlightParams, DocumentLink,
-    DocumentLinkParams, DocumentOnTypeFormattingParams, DocumentRangeFormattingParams,
-    DocumentSymbolParams, ExecuteCommandParams, FoldingRangeParams, GotoDefinitionParams,
-    HoverParams, InlayHintParams, LinkedEditingRangeParams, MonikerParams, OneOf, ReferenceParams,
-    RenameFilesParams, RenameParams, SelectionRangeParams, SemanticTokensDeltaParams,
-    SemanticTokensParams, SemanticTokensRangeParams, ServerCapabilities, SignatureHelpParams,
-    TextDocumentPositionParams, TypeHierarchyPrepareParams, Uri, WorkspaceDiagnosticParams,
-    WorkspaceSymbol, WorkspaceSymbolParams,
-    notification::{DidOpenTextDocument, Notification as _, PublishDiagnostics},
+    ApplyWorkspaceEditParams, CallHierarchyIncomingCallsParams, CallHierarchyOutgoingCallsParams,
+    CallHierarchyPrepareParams, CodeAction, CodeActionParams, CodeLens, CodeLensParams,
+    ColorPresentationParams, CompletionItem, CompletionParams, CompletionTriggerKind,
+    CreateFilesParams, DeleteFilesParams, DocumentColorParams, DocumentDiagnosticParams,
+    DocumentFormattingParams, DocumentHighlightParams, DocumentLink, DocumentLinkParams,
+    DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, DocumentSymbolParams,
+    ExecuteCommandParams, FoldingRangeParams, GotoDefinitionParams, HoverParams, InlayHintParams,
+    LinkedEditingRangeParams, LogMessageParams, MonikerParams, NumberOrString, OneOf,
+    ProgressParams, ProgressParamsValue, PublishDiagnosticsParams, ReferenceParams,
+    RegistrationParams, RenameFilesParams, RenameParams, SelectionRangeParams,
+    SemanticTokensDeltaParams, SemanticTokensParams, SemanticTokensRangeParams, ServerCapabilities,
+    SignatureHelp, SignatureHelpParams, TextDocumentPositionParams, TypeHierarchyPrepareParams,
+    Uri, WorkspaceDiagnosticParams, WorkspaceSymbol, WorkspaceSymbolParams,
+    notification::{
+        DidChangeTextDocument, DidChangeWatchedFiles, DidOpenTextDocument, LogMessage,
+        Notification as _, Progress, PublishDiagnostics, ShowMessage,
+    },
     request::{
-        CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls, CallHierarchyPrepare,
-        CodeActionRequest, CodeActionResolveRequest, CodeLensRequest, CodeLensResolve,
-        ColorPresentationRequest, Completion, DocumentColor, DocumentDiagnosticRequest,
-        DocumentHighlightRequest, DocumentLinkRequest, DocumentLinkResolve, DocumentSymbolRequest,
-        ExecuteCommand, FoldingRangeRequest, Formatting, GotoDeclaration, GotoDeclarationParams,
-        GotoDefinition, GotoImplementation, GotoImplementationParams, GotoTypeDefinition,
-        GotoTypeDefinitionParams, HoverRequest, InlayHintRequest, LinkedEditingRange,
-        MonikerRequest, OnTypeFormatting, PrepareRenameRequest, RangeFormatting, References,
-        Rename, Request as _, ResolveCompletionItem, SelectionRangeRequest,
-        SemanticTokensFullDeltaRequest, SemanticTokensFullRequest, SemanticTokensRangeRequest,
-        SignatureHelpRequest, TypeHierarchyPrepare, WillCreateFiles, WillDeleteFiles,
-        WillRenameFiles, WorkspaceDiagnosticRequest, WorkspaceSymbolRequest,
-        WorkspaceSymbolResolve,
+        ApplyWorkspaceEdit, CallHierarchyIncomingCalls, CallHierarchyOutgoingCalls,
+        CallHierarchyPrepare, CodeActionRequest, CodeActionResolveRequest, CodeLensRequest,
+        CodeLensResolve, ColorPresentationRequest, Completion, DocumentColor,
+        DocumentDiagnosticRequest, DocumentHighlightRequest, DocumentLinkRequest,
+        DocumentLinkResolve, DocumentSymbolRequest, ExecuteCommand, FoldingRangeRequest,
+        Formatting, GotoDeclaration, GotoDeclarationParams, GotoDefinition, GotoImplementation,
+        GotoImplementationParams, GotoTypeDefinition, GotoTypeDefinitionParams, HoverRequest,
+        InlayHintRequest, LinkedEditingRange, MonikerRequest, OnTypeFormatting,
+        PrepareRenameRequest, RangeFormatting, References, RegisterCapability, Rename,
+        Request as _, ResolveCompletionItem, SelectionRangeRequest, SemanticTokensFullDeltaRequest,
+        SemanticTokensFullRequest, SemanticTokensRangeRequest, SignatureHelpRequest,
+        TypeHierarchyPrepare, WillCreateFiles, WillDeleteFiles, WillRenameFiles,
+        WorkspaceDiagnosticRequest, WorkspaceSymbolRequest, WorkspaceSymbolResolve,
     },
 };
 
 use crate::{
-    get_root_test_path, receive_response_num,
+    APPLY_EDIT_COMMAND, APPLY_EDIT_RESPONSE_NUM, CHANGE_FILES_RESPONSE_NUM, ERROR_RESPONSE_NUM,
+    PUBLISH_DIAGNOSTICS_WAVES_RESPONSE_NUM, REQUEST_LOG_MESSAGE_RESPONSE_NUM,
+    SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM, STDOUT_NOISE_RESPONSE_NUM, get_root_test_path,
+    notifications_offset, receive_project_root, receive_registrations, receive_response_num,
     responses::{
-        get_code_action_resolve_response, get_code_action_response, get_code_lens_resolve_response,
-        get_code_lens_response, get_color_presentation_response, get_completion_resolve_response,
-        get_completion_response, get_declaration_response, get_definition_response,
-        get_diagnostic_response, get_document_color_response, get_document_highlight_response,
+        CustomRequest, PROGRESS_TOKEN, get_apply_edit_response, get_code_action_resolve_response,
+        get_code_action_response, get_code_lens_resolve_response, get_code_lens_response,
+        get_color_presentation_response, get_completion_resolve_response, get_completion_response,
+        get_completion_trigger_character_response, get_custom_response, get_declaration_response,
+        get_definition_response, get_diagnostic_response, get_diagnostic_unchanged_response,
+        get_document_color_response, get_document_highlight_response,
         get_document_link_resolve_response, get_document_link_response,
         get_document_symbol_response, get_execute_command_response, get_folding_range_response,
         get_formatting_range_response, get_formatting_response, get_hover_response,
         get_implementation_response, get_incoming_calls_response, get_inlay_hint_response,
-        get_linked_editing_range_response, get_moniker_response, get_on_type_formatting_response,
-        get_outgoing_calls_response, get_prepare_call_hierachy_response,
-        get_prepare_rename_response, get_prepare_type_hierachy_response,
-        get_publish_diagnostics_response, get_references_response, get_rename_response,
-        get_selection_range_response, get_semantic_tokens_full_delta_response,
-        get_semantic_tokens_full_response, get_semantic_tokens_range_response,
-        get_signature_help_response, get_type_definition_response,
+        get_linked_editing_range_response, get_log_message_response, get_moniker_response,
+        get_on_type_formatting_response, get_outgoing_calls_response,
+        get_prepare_call_hierachy_response, get_prepare_rename_response,
+        get_prepare_type_hierachy_response, get_progress_response,
+        get_publish_diagnostics_response, get_publish_diagnostics_waves, get_references_response,
+        get_rename_response, get_request_log_message_response, get_selection_range_response,
+        get_semantic_tokens_full_delta_response, get_semantic_tokens_full_response,
+        get_semantic_tokens_range_response, get_show_message_response, get_signature_help_response,
+        get_signature_help_retrigger_response, get_type_definition_response,
         get_workspace_diagnostics_response, get_workspace_symbol_resolve_response,
         get_workspace_symbol_response, get_workspace_will_create_files_response,
         get_workspace_will_delete_files_response,
     },
+    send_completion_context, send_did_change, send_did_change_watched_files, send_inlay_hint_range,
+    send_opened_uri, send_registered,
 };
 
 fn cast_req<R>(req: Request) -> Result<(RequestId, R::Params)>
@@ -93,6 +109,133 @@ where
     Ok(connection.sender.send(Message::Response(result))?)
 }
 
+fn send_req_err(id: RequestId, connection: &Connection) -> Result<()> {
+    info!("Sending error response for request {id}");
+    let result = Response {
+        id,
+        result: None,
+        error: Some(ResponseError {
+            code: ErrorCode::InternalError as i32,
+            message: "Test server error".to_string(),
+            data: None,
+        }),
+    };
+    Ok(connection.sender.send(Message::Response(result))?)
+}
+
+fn send_method_not_found(id: RequestId, method: &str, connection: &Connection) -> Result<()> {
+    info!("Sending method not found response for request {id}");
+    let result = Response {
+        id,
+        result: None,
+        error: Some(ResponseError {
+            code: ErrorCode::MethodNotFound as i32,
+            message: format!("Unhandled method: {method}"),
+            data: None,
+        }),
+    };
+    Ok(connection.sender.send(Message::Response(result))?)
+}
+
+/// Sends a `workspace/applyEdit` request inserting a line at the start of `uri`
+fn send_apply_edit(uri: &Uri, connection: &Connection) -> Result<()> {
+    let params = ApplyWorkspaceEditParams {
+        label: None,
+        edit: get_apply_edit_response(uri),
+    };
+    info!("Sending apply edit request: {params:?}");
+    let req = Request {
+        id: RequestId::from("test-server/applyEdit".to_string()),
+        method: ApplyWorkspaceEdit::METHOD.to_string(),
+        params: serde_json::to_value(&params).unwrap(),
+    };
+    Ok(connection.sender.send(Message::Request(req))?)
+}
+
+/// Sends a `client/registerCapability` request for the registrations sent to the test
+/// case at `root_path`, if there are any
+fn send_registrations(root_path: &Path, connection: &Connection) -> Result<()> {
+    let registrations = receive_registrations(root_path)?;
+    if registrations.is_empty() {
+        return Ok(());
+    }
+    let params = RegistrationParams { registrations };
+    info!("Sending registration request: {params:?}");
+    // The test case's root is encoded in the request id, so that the client's response
+    // can be recorded there
+    let req = Request {
+        id: RequestId::from(format!(
+            "{REGISTER_CAPABILITY_ID_PREFIX}{}",
+            root_path.display()
+        )),
+        method: RegisterCapability::METHOD.to_string(),
+        params: serde_json::to_value(&params).unwrap(),
+    };
+    Ok(connection.sender.send(Message::Request(req))?)
+}
+
+/// Handles `Response`s from the lsp client to the server's requests. Accepted
+/// `client/registerCapability` requests are recorded in the test case's root.
+///
+/// # Errors
+///
+/// Returns `Err` if recording an accepted registration fails.
+pub fn handle_response(resp: &Response) -> Result<()> {
+    let id = serde_json::to_value(&resp.id)?;
+    let Some(root_path) = id
+        .as_str()
+        .and_then(|id| id.strip_prefix(REGISTER_CAPABILITY_ID_PREFIX))
+    else {
+        error!("Unimplemented response received: {resp:?}");
+        return Ok(());
+    };
+    if let Some(err) = &resp.error {
+        error!("Registration rejected: {err:?}");
+        return Ok(());
+    }
+    info!("Registration accepted");
+    Ok(send_registered(Path::new(root_path))?)
+}
+
+/// Writes a message to stdout whose body isn't JSON, corrupting the LSP stream. The
+/// message is framed with a valid header, so the client reliably rejects its body
+/// rather than the following message.
+fn write_stdout_noise() -> Result<()> {
+    let noise = "stray debug output";
+    info!("Writing noise to stdout: {noise}");
+    // The connection's writer thread holds the lock on stdout for its whole lifetime,
+    // so write to a duplicate of the underlying handle instead
+    #[cfg(unix)]
+    let handle = std::os::fd::AsFd::as_fd(&std::io::stdout()).try_clone_to_owned()?;
+    #[cfg(windows)]
+    let handle =
+        std::os::windows::io::AsHandle::as_handle(&std::io::stdout()).try_clone_to_owned()?;
+    let mut stdout = fs::File::from(handle);
+    write!(stdout, "Content-Length: {}\r\n\r\n{noise}", noise.len())?;
+    Ok(stdout.flush()?)
+}
+
+const REGISTER_CAPABILITY_ID_PREFIX: &str = "test-server/registerCapability:";
+
+/// Appends a line to every file in the project root of the test case at `root_path`,
+/// other than `uri`
+fn change_project_files(uri: &Uri, root_path: &Path) -> Result<()> {
+    let project_root = receive_project_root(root_path)?;
+    for entry in fs::read_dir(project_root)? {
+        let path = entry?.path();
+        if !path.is_file() || path.as_os_str() == uri.path().as_str() {
+            continue;
+        }
+        info!("Changing {}", path.display());
+        let mut file = fs::OpenOptions::new().append(true).open(&path)?;
+        writeln!(file, "changed")?;
+    }
+    // Give the client time to report the changes. Its notifications are handled once
+    // the response is sent, before the client shuts us down
+    std::thread::sleep(Duration::from_secs(2));
+    Ok(())
+}
+
 /// Handles `Notification`s from the lsp client.
 ///
 /// # Errors
@@ -112,13 +255,69 @@ pub fn handle_notification(notif: Notification, connection: &Connection) -> Resu
                 "Received `{}` notification: {did_open_params:?}",
                 DidOpenTextDocument::METHOD
             );
+            let uri = &did_open_params.text_document.uri;
+            if let Some(root_path) = get_root_test_path(uri) {
+                send_opened_uri(uri, &root_path)?;
+                send_registrations(&root_path, connection)?;
+            }
             send_diagnostic_resp(&did_open_params.text_document.uri, connection)?;
+            send_messages(&did_open_params.text_document.uri, connection)?;
+            send_progress(&did_open_params.text_document.uri, connection)?;
+        }
+        DidChangeTextDocument::METHOD => {
+            let did_change_params = cast_notif::<DidChangeTextDocument>(notif)?;
+            info!(
+                "Received `{}` notification: {did_change_params:?}",
+                DidChangeTextDocument::METHOD
+            );
+            if let Some(root_path) = get_root_test_path(&did_change_params.text_document.uri) {
+                send_did_change(&did_change_params, &root_path)?;
+            }
+            // Any edit is treated as fixing the source file
+            send_cleared_diagnostics(did_change_params.text_document.uri, connection)?;
+        }
+        DidChangeWatchedFiles::METHOD => {
+            let did_change_params = cast_notif::<DidChangeWatchedFiles>(notif)?;
+            info!(
+                "Received `{}` notification: {did_change_params:?}",
+                DidChangeWatchedFiles::METHOD
+            );
+            let root_path = did_change_params
+                .changes
+                .first()
+                .and_then(|change| get_root_test_path(&change.uri));
+            if let Some(root_path) = root_path {
+                send_did_change_watched_files(&did_change_params, &root_path)?;
+            }
         }
         method => error!("Unimplemented notification method: {method:?}\n{notif:?}"),
     }
     Ok(())
 }
 
+/// Sends a `textDocument/publishDiagnostic` notification with no diagnostics for
+/// `uri` to the client.
+///
+/// # Errors
+///
+/// Returns `Err` if sending the notification fails.
+///
+/// # Panics
+///
+/// Panics if serialization of `PublishDiagnosticsParams` fails.
+pub fn send_cleared_diagnostics(uri: Uri, connection: &Connection) -> Result<()> {
+    let publish_params = PublishDiagnosticsParams::new(uri, Vec::new(), None);
+    info!("Sending diagnostics: {publish_params:?}");
+    let result = serde_json::to_value(&publish_params).unwrap();
+
+    let notif = Notification {
+        method: PublishDiagnostics::METHOD.to_string(),
+        params: result,
+    };
+
+    Ok(connection.sender.send(Message::Notification(notif))?)
+}
+
 /// Sends a `textDocument/publishDiagnostic` notification to the client.
 ///
 /// # Errors
@@ -136,14 +335,43 @@ pub fn send_diagnostic_resp(uri: &Uri, connection: &Connection) -> Result<()> {
         );
         return Ok(());
     };
-    let response_num = receive_response_num(&root_path)?;
+    // Servers prewarmed outside of a test (i.e. by a `ServerPool`) may not have a response
+    // number yet
+    let Ok(response_num) = receive_response_num(&root_path) else {
+        return Ok(());
+    };
     info!("response_num: {response_num}");
+    if response_num == PUBLISH_DIAGNOSTICS_WAVES_RESPONSE_NUM {
+        for (i, publish_params) in get_publish_diagnostics_waves(uri).into_iter().enumerate() {
+            if i > 0 {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+            send_publish_diagnostics(&publish_params, connection)?;
+        }
+        return Ok(());
+    }
     let Some(publish_params) = get_publish_diagnostics_response(response_num, uri) else {
         error!("Invalid response number: {response_num}");
         return Ok(());
     };
+    send_publish_diagnostics(&publish_params, connection)
+}
+
+/// Sends `publish_params` to the client in a `textDocument/publishDiagnostic` notification.
+///
+/// # Errors
+///
+/// Returns `Err` if sending the notification fails.
+///
+/// # Panics
+///
+/// Panics if serialization of `PublishDiagnosticsParams` fails.
+fn send_publish_diagnostics(
+    publish_params: &PublishDiagnosticsParams,
+    connection: &Connection,
+) -> Result<()> {
     info!("Sending diagnostics: {publish_params:?}");
-    let result = serde_json::to_value(&publish_params).unwrap();
+    let result = serde_json::to_value(publish_params).unwrap();
 
     let notif = Notification {
         method: PublishDiagnostics::METHOD.to_string(),
@@ -153,6 +381,103 @@ pub fn send_diagnostic_resp(uri: &Uri, connection: &Connection) -> Result<()> {
     Ok(connection.sender.send(Message::Notification(notif))?)
 }
 
+/// Sends `window/logMessage` and `window/showMessage` notifications to the client.
+///
+/// # Errors
+///
+/// Returns `Err` if sending either notification fails.
+///
+/// # Panics
+///
+/// Panics if serialization of `LogMessageParams` or `ShowMessageParams` fails.
+pub fn send_messages(uri: &Uri, connection: &Connection) -> Result<()> {
+    let Some(root_path) = get_root_test_path(uri) else {
+        error!(
+            "Failed to retrieve root path from provided uri: {}",
+            uri.as_str()
+        );
+        return Ok(());
+    };
+    let Ok(response_num) = receive_response_num(&root_path) else {
+        return Ok(());
+    };
+    let Some(offset) = notifications_offset(response_num) else {
+        return Ok(());
+    };
+    if let Some(log_params) = get_log_message_response(offset, uri) {
+        send_log_message(&log_params, connection)?;
+    }
+    if let Some(show_params) = get_show_message_response(offset, uri) {
+        info!("Sending show message: {show_params:?}");
+        let notif = Notification {
+            method: ShowMessage::METHOD.to_string(),
+            params: serde_json::to_value(&show_params).unwrap(),
+        };
+        connection.sender.send(Message::Notification(notif))?;
+    }
+
+    Ok(())
+}
+
+/// Sends a `window/logMessage` notification to the client.
+///
+/// # Errors
+///
+/// Returns `Err` if sending the notification fails.
+///
+/// # Panics
+///
+/// Panics if serialization of `LogMessageParams` fails.
+pub fn send_log_message(log_params: &LogMessageParams, connection: &Connection) -> Result<()> {
+    info!("Sending log message: {log_params:?}");
+    let notif = Notification {
+        method: LogMessage::METHOD.to_string(),
+        params: serde_json::to_value(log_params).unwrap(),
+    };
+    Ok(connection.sender.send(Message::Notification(notif))?)
+}
+
+/// Sends a series of `$/progress` notifications to the client.
+///
+/// # Errors
+///
+/// Returns `Err` if sending a notification fails.
+///
+/// # Panics
+///
+/// Panics if serialization of `ProgressParams` fails.
+pub fn send_progress(uri: &Uri, connection: &Connection) -> Result<()> {
+    let Some(root_path) = get_root_test_path(uri) else {
+        error!(
+            "Failed to retrieve root path from provided uri: {}",
+            uri.as_str()
+        );
+        return Ok(());
+    };
+    let Ok(response_num) = receive_response_num(&root_path) else {
+        return Ok(());
+    };
+    let Some(progress) =
+        notifications_offset(response_num).and_then(|offset| get_progress_response(offset, uri))
+    else {
+        return Ok(());
+    };
+    for value in progress {
+        let progress_params = ProgressParams {
+            token: NumberOrString::String(PROGRESS_TOKEN.to_string()),
+            value: ProgressParamsValue::WorkDone(value),
+        };
+        info!("Sending progress: {progress_params:?}");
+        let notif = Notification {
+            method: Progress::METHOD.to_string(),
+            params: serde_json::to_value(&progress_params).unwrap(),
+        };
+        connection.sender.send(Message::Notification(notif))?;
+    }
+
+    Ok(())
+}
+
 macro_rules! handle_request {
     ($request_type:ty, $resp_getter:expr, $req:expr, $connection:expr, $extract_uri:expr) => {{
         let (id, params) = cast_req::<$request_type>($req).expect(concat!(
@@ -174,10 +499,75 @@ macro_rules! handle_request {
         };
         let response_num = receive_response_num(&root_path)?;
         info!("response_num: {response_num}");
+        if response_num == ERROR_RESPONSE_NUM {
+            return send_req_err(id, $connection);
+        }
+        if response_num == APPLY_EDIT_RESPONSE_NUM {
+            send_apply_edit(&uri, $connection)?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
+        if response_num == REQUEST_LOG_MESSAGE_RESPONSE_NUM {
+            send_log_message(&get_request_log_message_response(&uri), $connection)?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
+        if response_num == CHANGE_FILES_RESPONSE_NUM {
+            change_project_files(&uri, &root_path)?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
+        if response_num == STDOUT_NOISE_RESPONSE_NUM {
+            write_stdout_noise()?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
 
         let resp = $resp_getter(response_num, &uri);
         send_req_resp(id, resp, $connection)
     }};
+    // Variant for requests whose response depends on more than the uri. `$extract_extra`
+    // pulls additional data from the request's params, which is passed to `$resp_getter`
+    ($request_type:ty, $resp_getter:expr, $req:expr, $connection:expr, $extract_uri:expr, $extract_extra:expr) => {{
+        let (id, params) = cast_req::<$request_type>($req).expect(concat!(
+            "Failed to cast `",
+            stringify!($request_type),
+            "` request"
+        ));
+        info!(
+            "Received `{}` request ({id}): {params:?}",
+            <$request_type>::METHOD
+        );
+        let extra = $extract_extra(&params);
+        let uri = $extract_uri(params);
+        let Some(root_path) = get_root_test_path(&uri) else {
+            error!(
+                "Failed to retrieve root path from provided uri: {}",
+                uri.as_str()
+            );
+            return Ok(());
+        };
+        let response_num = receive_response_num(&root_path)?;
+        info!("response_num: {response_num}");
+        if response_num == ERROR_RESPONSE_NUM {
+            return send_req_err(id, $connection);
+        }
+        if response_num == APPLY_EDIT_RESPONSE_NUM {
+            send_apply_edit(&uri, $connection)?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
+        if response_num == REQUEST_LOG_MESSAGE_RESPONSE_NUM {
+            send_log_message(&get_request_log_message_response(&uri), $connection)?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
+        if response_num == CHANGE_FILES_RESPONSE_NUM {
+            change_project_files(&uri, &root_path)?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
+        if response_num == STDOUT_NOISE_RESPONSE_NUM {
+            write_stdout_noise()?;
+            return send_req_resp(id, None::<()>, $connection);
+        }
+
+        let resp = $resp_getter(response_num, &uri, extra);
+        send_req_resp(id, resp, $connection)
+    }};
 }
 
 /// Handles `Request`s from the lsp client.
@@ -285,11 +675,35 @@ pub fn handle_request(
         Completion::METHOD => {
             handle_request!(
                 Completion,
-                get_completion_response,
+                |response_num, uri: &Uri, trigger_character: Option<String>| {
+                    // Respond to trigger character completions separately to verify
+                    // the client's `context` param
+                    trigger_character.map_or_else(
+                        || get_completion_response(response_num, uri),
+                        |trigger_char| {
+                            get_completion_trigger_character_response(&trigger_char, uri)
+                        },
+                    )
+                },
                 req,
                 conn,
                 |params: CompletionParams| -> Uri {
                     params.text_document_position.text_document.uri
+                },
+                |params: &CompletionParams| -> Option<String> {
+                    // Record the `context` param so tests can verify it's only sent when set
+                    let recorded = get_root_test_path(
+                        &params.text_document_position.text_document.uri,
+                    )
+                    .map(|root_path| send_completion_context(params.context.as_ref(), &root_path));
+                    if let Some(Err(e)) = recorded {
+                        error!("Failed to record completion context -- {e}");
+                    }
+                    params
+                        .context
+                        .as_ref()
+                        .filter(|ctx| ctx.trigger_kind == CompletionTriggerKind::TRIGGER_CHARACTER)
+                        .and_then(|ctx| ctx.trigger_character.clone())
                 }
             )?;
         }
@@ -309,10 +723,20 @@ pub fn handle_request(
         DocumentDiagnosticRequest::METHOD => {
             handle_request!(
                 DocumentDiagnosticRequest,
-                get_diagnostic_response,
+                |response_num, uri: &Uri, previous_result_id: Option<String>| {
+                    get_diagnostic_unchanged_response(
+                        response_num,
+                        previous_result_id.as_deref(),
+                        uri,
+                    )
+                    .or_else(|| get_diagnostic_response(response_num, uri))
+                },
                 req,
                 conn,
-                |params: DocumentDiagnosticParams| -> Uri { params.text_document.uri }
+                |params: DocumentDiagnosticParams| -> Uri { params.text_document.uri },
+                |params: &DocumentDiagnosticParams| -> Option<String> {
+                    params.previous_result_id.clone()
+                }
             )?;
         }
         DocumentColor::METHOD => {
@@ -363,6 +787,15 @@ pub fn handle_request(
             )?;
         }
         ExecuteCommand::METHOD => {
+            if req
+                .params
+                .get("command")
+                .and_then(serde_json::Value::as_str)
+                == Some(APPLY_EDIT_COMMAND)
+            {
+                let raw_uri = req.params["arguments"][0].as_str().unwrap();
+                send_apply_edit(&Uri::from_str(raw_uri).unwrap(), conn)?;
+            }
             handle_request!(
                 ExecuteCommand,
                 get_execute_command_response,
@@ -473,7 +906,15 @@ pub fn handle_request(
                 get_inlay_hint_response,
                 req,
                 conn,
-                |params: InlayHintParams| -> Uri { params.text_document.uri }
+                |params: InlayHintParams| -> Uri {
+                    // Record the range so tests can verify the viewport's visible range
+                    let recorded = get_root_test_path(&params.text_document.uri)
+                        .map(|root_path| send_inlay_hint_range(&params.range, &root_path));
+                    if let Some(Err(e)) = recorded {
+                        error!("Failed to record inlay hint range -- {e}");
+                    }
+                    params.text_document.uri
+                }
             )?;
         }
         LinkedEditingRange::METHOD => {
@@ -566,11 +1007,26 @@ pub fn handle_request(
         SignatureHelpRequest::METHOD => {
             handle_request!(
                 SignatureHelpRequest,
-                get_signature_help_response,
+                |response_num, uri: &Uri, active: Option<SignatureHelp>| {
+                    // Respond to retriggers separately to verify the client's `context` param
+                    match active {
+                        Some(active) if response_num == SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM => {
+                            get_signature_help_retrigger_response(&active, uri)
+                        }
+                        _ => get_signature_help_response(response_num, uri),
+                    }
+                },
                 req,
                 conn,
                 |params: SignatureHelpParams| -> Uri {
                     params.text_document_position_params.text_document.uri
+                },
+                |params: &SignatureHelpParams| -> Option<SignatureHelp> {
+                    params
+                        .context
+                        .as_ref()
+                        .filter(|ctx| ctx.is_retrigger)
+                        .and_then(|ctx| ctx.active_signature_help.clone())
                 }
             )?;
         }
@@ -649,7 +1105,19 @@ pub fn handle_request(
                 }
             )?;
         }
-        method => error!("Unimplemented request method: {method:?}\n{req:?}"),
+        CustomRequest::METHOD => {
+            handle_request!(
+                CustomRequest,
+                get_custom_response,
+                req,
+                conn,
+                |params: TextDocumentPositionParams| -> Uri { params.text_document.uri }
+            )?;
+        }
+        method => {
+            error!("Unimplemented request method: {method:?}\n{req:?}");
+            send_method_not_found(req.id, method, conn)?;
+        }
     }
 
     Ok(())
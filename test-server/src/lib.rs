@@ -5,11 +5,64 @@ use std::{
 
 use anyhow::Result;
 use log::error;
-use lsp_types::{ServerCapabilities, Uri};
+use lsp_types::{
+    CompletionContext, DidChangeTextDocumentParams, DidChangeWatchedFilesParams, Range,
+    Registration, ServerCapabilities, Uri,
+};
 
 pub mod handle;
 pub mod responses;
 
+/// When written as the response number, the server responds to any request with
+/// a `ResponseError` rather than a result
+pub const ERROR_RESPONSE_NUM: u32 = u32::MAX - 1;
+
+/// When written as the response number, the server sends a `workspace/applyEdit`
+/// request modifying the requested document before responding with `null`
+pub const APPLY_EDIT_RESPONSE_NUM: u32 = u32::MAX - 2;
+
+/// The server sends a `workspace/applyEdit` request modifying the requested document
+/// before responding to `workspace/executeCommand` requests for this command
+pub const APPLY_EDIT_COMMAND: &str = "applyEdit";
+
+/// When written as the response number, the server responds to `textDocument/signatureHelp`
+/// with a two-parameter signature, and to retriggers with the context's active signature
+/// help advanced to the next parameter
+pub const SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM: u32 = u32::MAX - 3;
+
+/// When written as the response number, the server sends a `window/logMessage`
+/// notification before responding to any request with `null`
+pub const REQUEST_LOG_MESSAGE_RESPONSE_NUM: u32 = u32::MAX - 4;
+
+/// When written as the response number, the server appends a line to every file in the
+/// project root other than the requested document, and gives the client time to report
+/// the changes via `workspace/didChangeWatchedFiles` before responding with `null`
+pub const CHANGE_FILES_RESPONSE_NUM: u32 = u32::MAX - 5;
+
+/// When written as the response number, the server publishes diagnostics for an opened
+/// document in several waves, 200ms apart. See `responses::get_publish_diagnostics_waves`.
+pub const PUBLISH_DIAGNOSTICS_WAVES_RESPONSE_NUM: u32 = u32::MAX - 6;
+
+/// When written as the response number, the server writes a message that isn't valid
+/// JSON-RPC to stdout (as a stray debug print would) before responding to any request
+/// with `null`
+pub const STDOUT_NOISE_RESPONSE_NUM: u32 = u32::MAX - 7;
+
+/// When written as the response number (or one of the two numbers after it), the server
+/// sends `window/logMessage`, `window/showMessage`, and `$/progress` notifications after
+/// `textDocument/didOpen` is received. The notifications sent are selected by the offset
+/// from this number. Any other response number sends none of them.
+pub const NOTIFICATIONS_RESPONSE_NUM: u32 = u32::MAX - 16;
+
+/// Returns the offset of `response_num` from [`NOTIFICATIONS_RESPONSE_NUM`], if it
+/// selects a set of `textDocument/didOpen` notifications
+#[must_use]
+pub fn notifications_offset(response_num: u32) -> Option<u32> {
+    response_num
+        .checked_sub(NOTIFICATIONS_RESPONSE_NUM)
+        .filter(|offset| *offset < 3)
+}
+
 /// Returns the path to the test server executable
 #[allow(clippy::missing_panics_doc)]
 #[must_use]
@@ -85,6 +138,278 @@ pub fn send_capabiltiies(capabilities: &ServerCapabilities, path: &Path) -> std:
     fs::write(path, capabilities_json)
 }
 
+/// Serializes `registrations` to JSON and writes them to `path/registrations.json`. The
+/// server registers them via `client/registerCapability` after receiving the
+/// `textDocument/didOpen` notification.
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+///
+/// # Panics
+///
+/// Will panic if serialization of `registrations` fails
+pub fn send_registrations(registrations: &[Registration], path: &Path) -> std::io::Result<()> {
+    let mut path = path.to_path_buf();
+    path.push("registrations.json");
+    let registrations_json =
+        serde_json::to_string_pretty(registrations).expect("Failed to serialize registrations");
+
+    fs::write(path, registrations_json)
+}
+
+/// Reads the registrations to send to the client from `path/registrations.json`. Returns
+/// an empty `Vec` if none were sent.
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if reading the file fails or it contains invalid JSON
+pub fn receive_registrations(path: &Path) -> std::io::Result<Vec<Registration>> {
+    let mut path = path.to_path_buf();
+    path.push("registrations.json");
+    match fs::read_to_string(path) {
+        Ok(registrations) => serde_json::from_str(&registrations).map_err(std::io::Error::other),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes `path/REGISTERED.txt`, recording that the client accepted the server's
+/// `client/registerCapability` request
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+pub fn send_registered(path: &Path) -> std::io::Result<()> {
+    let mut path = path.to_path_buf();
+    path.push("REGISTERED.txt");
+    fs::write(path, "")
+}
+
+/// Returns whether the client accepted the server's `client/registerCapability` request,
+/// as recorded in `path/REGISTERED.txt`
+#[must_use]
+pub fn receive_registered(path: &Path) -> bool {
+    path.join("REGISTERED.txt").is_file()
+}
+
+/// Writes the project root the server detected to `path/PROJECT_ROOT.txt`
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+pub fn send_project_root(root: &Path, path: &Path) -> std::io::Result<()> {
+    let mut path = path.to_path_buf();
+    path.push("PROJECT_ROOT.txt");
+
+    fs::write(path, root.as_os_str().as_encoded_bytes())
+}
+
+/// Reads the project root the server detected from `path/PROJECT_ROOT.txt`
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if reading the file fails
+pub fn receive_project_root(path: &Path) -> std::io::Result<PathBuf> {
+    let mut path = path.to_path_buf();
+    path.push("PROJECT_ROOT.txt");
+
+    Ok(PathBuf::from(fs::read_to_string(path)?))
+}
+
+/// Appends `uri` to `path/OPENED.txt`, recording that the server received a
+/// `textDocument/didOpen` notification for it
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+pub fn send_opened_uri(uri: &Uri, path: &Path) -> std::io::Result<()> {
+    use std::io::Write as _;
+
+    let mut path = path.to_path_buf();
+    path.push("OPENED.txt");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", uri.as_str())
+}
+
+/// Reads the uris the server received `textDocument/didOpen` notifications for from
+/// `path/OPENED.txt`, in the order they were opened. Returns an empty `Vec` if none
+/// were recorded.
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if reading the file fails
+pub fn receive_opened_uris(path: &Path) -> std::io::Result<Vec<String>> {
+    let mut path = path.to_path_buf();
+    path.push("OPENED.txt");
+    match fs::read_to_string(path) {
+        Ok(opened) => Ok(opened.lines().map(str::to_string).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Appends `params` to `path/DID_CHANGE.jsonl`, recording a `textDocument/didChange`
+/// notification the server received
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+///
+/// # Panics
+///
+/// Will panic if serialization of `params` fails
+pub fn send_did_change(params: &DidChangeTextDocumentParams, path: &Path) -> std::io::Result<()> {
+    use std::io::Write as _;
+
+    let mut path = path.to_path_buf();
+    path.push("DID_CHANGE.jsonl");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let params_json = serde_json::to_string(params).expect("Failed to serialize didChange params");
+    writeln!(file, "{params_json}")
+}
+
+/// Reads the `textDocument/didChange` notifications the server received from
+/// `path/DID_CHANGE.jsonl`, in the order they were received. Returns an empty `Vec` if
+/// none were recorded.
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if reading the file fails or it contains invalid JSON
+pub fn receive_did_changes(path: &Path) -> std::io::Result<Vec<DidChangeTextDocumentParams>> {
+    let mut path = path.to_path_buf();
+    path.push("DID_CHANGE.jsonl");
+    match fs::read_to_string(path) {
+        Ok(changes) => changes
+            .lines()
+            .map(|line| serde_json::from_str(line).map_err(std::io::Error::other))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Appends `params` to `path/DID_CHANGE_WATCHED_FILES.jsonl`, recording a
+/// `workspace/didChangeWatchedFiles` notification the server received
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+///
+/// # Panics
+///
+/// Will panic if serialization of `params` fails
+pub fn send_did_change_watched_files(
+    params: &DidChangeWatchedFilesParams,
+    path: &Path,
+) -> std::io::Result<()> {
+    use std::io::Write as _;
+
+    let mut path = path.to_path_buf();
+    path.push("DID_CHANGE_WATCHED_FILES.jsonl");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    let params_json =
+        serde_json::to_string(params).expect("Failed to serialize didChangeWatchedFiles params");
+    writeln!(file, "{params_json}")
+}
+
+/// Reads the `workspace/didChangeWatchedFiles` notifications the server received from
+/// `path/DID_CHANGE_WATCHED_FILES.jsonl`, in the order they were received. Returns an
+/// empty `Vec` if none were recorded.
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if reading the file fails or it contains invalid JSON
+pub fn receive_did_change_watched_files(
+    path: &Path,
+) -> std::io::Result<Vec<DidChangeWatchedFilesParams>> {
+    let mut path = path.to_path_buf();
+    path.push("DID_CHANGE_WATCHED_FILES.jsonl");
+    match fs::read_to_string(path) {
+        Ok(changes) => changes
+            .lines()
+            .map(|line| serde_json::from_str(line).map_err(std::io::Error::other))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the `context` param of a `textDocument/completion` request the server received
+/// to `path/COMPLETION_CONTEXT.json`, or `null` if it was omitted
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+///
+/// # Panics
+///
+/// Will panic if serialization of `context` fails
+pub fn send_completion_context(
+    context: Option<&CompletionContext>,
+    path: &Path,
+) -> std::io::Result<()> {
+    let mut path = path.to_path_buf();
+    path.push("COMPLETION_CONTEXT.json");
+    let context_json =
+        serde_json::to_string(&context).expect("Failed to serialize completion context");
+    fs::write(path, context_json)
+}
+
+/// Reads the `context` param of the last `textDocument/completion` request the server
+/// received from `path/COMPLETION_CONTEXT.json`. Returns `None` if the param was omitted.
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if reading the file fails (i.e. no completion request
+/// was received) or it contains invalid JSON
+pub fn receive_completion_context(path: &Path) -> std::io::Result<Option<CompletionContext>> {
+    let mut path = path.to_path_buf();
+    path.push("COMPLETION_CONTEXT.json");
+    let context_json = fs::read_to_string(path)?;
+    serde_json::from_str(&context_json).map_err(std::io::Error::other)
+}
+
+/// Writes the `range` param of a `textDocument/inlayHint` request the server received
+/// to `path/INLAY_HINT_RANGE.json`
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if writing the file fails
+///
+/// # Panics
+///
+/// Will panic if serialization of `range` fails
+pub fn send_inlay_hint_range(range: &Range, path: &Path) -> std::io::Result<()> {
+    let mut path = path.to_path_buf();
+    path.push("INLAY_HINT_RANGE.json");
+    let range_json = serde_json::to_string(range).expect("Failed to serialize inlay hint range");
+    fs::write(path, range_json)
+}
+
+/// Reads the `range` param of the last `textDocument/inlayHint` request the server
+/// received from `path/INLAY_HINT_RANGE.json`
+///
+/// # Errors
+///
+/// Will return `std::io::Error` if reading the file fails (i.e. no inlay hint request
+/// was received) or it contains invalid JSON
+pub fn receive_inlay_hint_range(path: &Path) -> std::io::Result<Range> {
+    let mut path = path.to_path_buf();
+    path.push("INLAY_HINT_RANGE.json");
+    let range_json = fs::read_to_string(path)?;
+    serde_json::from_str(&range_json).map_err(std::io::Error::other)
+}
+
 /// Reads a response number from `path/RESPONSE_NUM.txt`
 ///
 /// # Errors
@@ -3,7 +3,10 @@ use std::{
     str::FromStr as _,
 };
 
-use test_server::handle::{handle_notification, handle_request};
+use test_server::{
+    handle::{handle_notification, handle_request, handle_response},
+    send_project_root,
+};
 
 use anyhow::{Result, anyhow};
 use log::{error, info};
@@ -62,6 +65,13 @@ fn get_project_root(params: &InitializeParams) -> Option<PathBuf> {
     None
 }
 
+/// Returns the lspresso-shot test case directory containing `root`, i.e.
+/// `/tmp/lspresso-shot/<test_id>`
+fn get_test_root(root: &Path) -> Option<&Path> {
+    root.ancestors()
+        .find(|dir| dir.parent().and_then(Path::file_name) == Some("lspresso-shot".as_ref()))
+}
+
 /// Entry point of the lsp server. Connects to the client and enters the main loop
 ///
 /// # Errors
@@ -83,11 +93,22 @@ pub fn main() -> Result<()> {
     let Some(root_path) = get_project_root(&init_params) else {
         return Err(anyhow!("Failed to detect project root"));
     };
-    // Invariant: The `src` directory passed to the test server as the root path
-    // should always be contained within an lspresso-shot test case directory
-    let mut capabilities_path = root_path.parent().unwrap().to_path_buf();
+    // Invariant: The root path passed to the test server should always be contained
+    // within an lspresso-shot test case directory. It's usually the `src` directory,
+    // but may be nested further within it (i.e. via `TestCase::root_markers`)
+    let Some(test_root) = get_test_root(&root_path) else {
+        return Err(anyhow!("Project root is outside of a test case directory"));
+    };
+    send_project_root(&root_path, test_root)?;
+    let mut capabilities_path = test_root.to_path_buf();
     capabilities_path.push("capabilities.json");
-    let server_capabilities = get_capabilities(&capabilities_path)?;
+    // Servers spawned outside of a test (i.e. by a `ServerPool`) have no capabilities sent
+    let server_capabilities = if capabilities_path.exists() {
+        get_capabilities(&capabilities_path)?
+    } else {
+        info!("No capabilities sent, using the defaults");
+        ServerCapabilities::default()
+    };
     info!("Server capabilities: {server_capabilities:?}");
     let initialize_data = serde_json::json!({
         "capabilities": server_capabilities,
@@ -122,7 +143,7 @@ fn main_loop(connection: &Connection, capabilities: &ServerCapabilities) -> Resu
                 handle_request(req, capabilities, connection)?;
             }
             Message::Notification(notif) => handle_notification(notif, connection)?,
-            Message::Response(resp) => error!("Unimplemented response received: {resp:?}"),
+            Message::Response(resp) => handle_response(&resp)?,
         }
     }
     Ok(())
@@ -9,26 +9,75 @@ use lsp_types::{
     DocumentDiagnosticReportKind, DocumentHighlight, DocumentHighlightKind, DocumentLink,
     DocumentSymbol, DocumentSymbolResponse, Documentation, FoldingRange, FoldingRangeKind,
     FullDocumentDiagnosticReport, GotoDefinitionResponse, Hover, HoverContents, InlayHint,
-    InlayHintKind, InlayHintLabel, InlayHintTooltip, LanguageString, LinkedEditingRanges, Location,
-    LocationLink, MarkedString, MarkupContent, MarkupKind, Moniker, MonikerKind, OneOf,
-    ParameterInformation, ParameterLabel, Position, PrepareRenameResponse,
-    PublishDiagnosticsParams, Range, RelatedFullDocumentDiagnosticReport, SelectionRange,
+    InlayHintKind, InlayHintLabel, InlayHintLabelPart, InlayHintLabelPartTooltip, InlayHintTooltip,
+    LanguageString, LinkedEditingRanges, Location, LocationLink, LogMessageParams, MarkedString,
+    MarkupContent, MarkupKind, MessageType, Moniker, MonikerKind, OneOf, ParameterInformation,
+    ParameterLabel, Position, PrepareRenameResponse, PublishDiagnosticsParams, Range,
+    RelatedFullDocumentDiagnosticReport, RelatedUnchangedDocumentDiagnosticReport, SelectionRange,
     SemanticToken, SemanticTokens, SemanticTokensDelta, SemanticTokensEdit,
     SemanticTokensFullDeltaResult, SemanticTokensPartialResult, SemanticTokensRangeResult,
-    SemanticTokensResult, SignatureHelp, SignatureInformation, SymbolInformation, SymbolKind,
-    SymbolTag, TextDocumentEdit, TextEdit, TypeHierarchyItem, UnchangedDocumentDiagnosticReport,
-    UniquenessLevel, Uri, WorkspaceDiagnosticReport, WorkspaceDocumentDiagnosticReport,
-    WorkspaceEdit, WorkspaceFullDocumentDiagnosticReport, WorkspaceLocation, WorkspaceSymbol,
+    SemanticTokensResult, ShowMessageParams, SignatureHelp, SignatureInformation,
+    SymbolInformation, SymbolKind, SymbolTag, TextDocumentEdit, TextDocumentPositionParams,
+    TextEdit, TypeHierarchyItem, UnchangedDocumentDiagnosticReport, UniquenessLevel, Uri,
+    WorkDoneProgress, WorkDoneProgressBegin, WorkDoneProgressEnd, WorkDoneProgressReport,
+    WorkspaceDiagnosticReport, WorkspaceDocumentDiagnosticReport, WorkspaceEdit,
+    WorkspaceFullDocumentDiagnosticReport, WorkspaceLocation, WorkspaceSymbol,
     WorkspaceSymbolResponse, WorkspaceUnchangedDocumentDiagnosticReport,
     request::{GotoDeclarationResponse, GotoImplementationResponse, GotoTypeDefinitionResponse},
 };
 use serde_json::Value;
 
-use crate::get_dummy_source_path;
+use crate::{APPLY_EDIT_COMMAND, SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM, get_dummy_source_path};
+
+/// Builds a `CodeAction` with an embedded `WorkspaceEdit` and diagnostic, both of
+/// which reference `uri`
+fn code_action_with_edit(title: &str, uri: &Uri) -> CodeAction {
+    let mut changes = HashMap::new();
+    changes.insert(
+        uri.clone(),
+        vec![TextEdit {
+            range: Range {
+                start: Position::new(1, 2),
+                end: Position::new(3, 4),
+            },
+            new_text: "new_text".to_string(),
+        }],
+    );
+    CodeAction {
+        title: title.to_string(),
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![Diagnostic {
+            range: Range {
+                start: Position::new(1, 2),
+                end: Position::new(3, 4),
+            },
+            message: "diagnostic message".to_string(),
+            related_information: Some(vec![DiagnosticRelatedInformation {
+                location: Location {
+                    uri: uri.clone(),
+                    range: Range {
+                        start: Position::new(5, 6),
+                        end: Position::new(7, 8),
+                    },
+                },
+                message: "related message".to_string(),
+            }]),
+            ..Default::default()
+        }]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            document_changes: None,
+            change_annotations: None,
+        }),
+        command: None,
+        is_preferred: Some(true),
+        disabled: None,
+        data: None,
+    }
+}
 
 /// For use with `test_code_action`.
 pub fn get_code_action_response(response_num: u32, uri: &Uri) -> Option<CodeActionResponse> {
-    _ = uri;
     let cmd = CodeActionOrCommand::Command(Command {
         title: "command title".to_string(),
         command: "command command".to_string(),
@@ -44,19 +93,35 @@ pub fn get_code_action_response(response_num: u32, uri: &Uri) -> Option<CodeActi
         disabled: None,
         data: None,
     });
-    // TODO: More test cases later...
+    let action_with_edit =
+        CodeActionOrCommand::CodeAction(code_action_with_edit("edit title", uri));
+    let action_with_command = CodeActionOrCommand::CodeAction(CodeAction {
+        title: "action with command title".to_string(),
+        kind: Some(CodeActionKind::REFACTOR),
+        diagnostics: None,
+        edit: None,
+        command: Some(Command {
+            title: "command title".to_string(),
+            command: "command command".to_string(),
+            arguments: Some(vec![Value::String("argument".to_string())]),
+        }),
+        is_preferred: None,
+        disabled: None,
+        data: None,
+    });
     match response_num {
         0 => Some(vec![]),
         1 => Some(vec![cmd]),
         2 => Some(vec![action]),
         3 => Some(vec![cmd, action]),
+        4 => Some(vec![action_with_edit]),
+        5 => Some(vec![cmd, action_with_command, action_with_edit]),
         _ => None,
     }
 }
 
 /// For use with `test_code_action_resolve`.
 pub fn get_code_action_resolve_response(response_num: u32, uri: &Uri) -> Option<CodeAction> {
-    _ = uri;
     let action1 = CodeAction {
         title: "action title 1".to_string(),
         kind: Some(CodeActionKind::QUICKFIX),
@@ -79,10 +144,10 @@ pub fn get_code_action_resolve_response(response_num: u32, uri: &Uri) -> Option<
         disabled: None,
         data: None,
     };
-    // TODO: More test cases later...
     match response_num {
         0 => Some(action1),
         1 => Some(action2),
+        2 => Some(code_action_with_edit("resolved title", uri)),
         _ => None,
     }
 }
@@ -223,12 +288,23 @@ pub fn get_document_link_response(response_num: u32, uri: &Uri) -> Option<Vec<Do
         tooltip: Some("tooltip".to_string()),
         data: None,
     };
+    // An external target, which shouldn't be cleaned
+    let item4 = DocumentLink {
+        range: Range {
+            start: Position::new(1, 2),
+            end: Position::new(3, 4),
+        },
+        target: Some(Uri::from_str("https://example.com/docs").unwrap()),
+        tooltip: None,
+        data: None,
+    };
     match response_num {
         0 => Some(vec![]),
         1 => Some(vec![item1]),
         2 => Some(vec![item2]),
         3 => Some(vec![item3]),
         4 => Some(vec![item1, item2, item3]),
+        5 => Some(vec![item4]),
         _ => None,
     }
 }
@@ -265,10 +341,21 @@ pub fn get_document_link_resolve_response(response_num: u32, uri: &Uri) -> Optio
         tooltip: Some("tooltip".to_string()),
         data: None,
     };
+    // An external target, which shouldn't be cleaned
+    let item4 = DocumentLink {
+        range: Range {
+            start: Position::new(1, 2),
+            end: Position::new(3, 4),
+        },
+        target: Some(Uri::from_str("https://example.com/docs").unwrap()),
+        tooltip: None,
+        data: None,
+    };
     match response_num {
         0 => Some(item1),
         1 => Some(item2),
         2 => Some(item3),
+        3 => Some(item4),
         _ => None,
     }
 }
@@ -332,6 +419,30 @@ pub fn get_execute_command_response(response_num: u32, uri: &Uri) -> Option<Valu
             Value::String("string".to_string()),
             Value::Number(serde_json::Number::from(42)),
         ])),
+        4 | 5 => Some(Value::String("executed".to_string())),
+        _ => None,
+    }
+}
+
+/// A request whose method isn't modeled by `lsp_types`, for use with `test_custom_request`
+pub enum CustomRequest {}
+
+impl lsp_types::request::Request for CustomRequest {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<Value>;
+    const METHOD: &'static str = "test-server/custom";
+}
+
+/// For use with `test_custom_request`.
+#[must_use]
+pub fn get_custom_response(response_num: u32, uri: &Uri) -> Option<Value> {
+    match response_num {
+        0 => Some(serde_json::json!({ "uri": uri.as_str(), "kind": "custom" })),
+        1 => Some(serde_json::json!([
+            { "uri": uri.as_str(), "line": 1 },
+            { "uri": get_dummy_source_path(), "line": 2 },
+        ])),
+        2 => Some(Value::String("custom".to_string())),
         _ => None,
     }
 }
@@ -339,7 +450,6 @@ pub fn get_execute_command_response(response_num: u32, uri: &Uri) -> Option<Valu
 /// For use with `test_code_lens`.
 #[must_use]
 pub fn get_code_lens_response(response_num: u32, uri: &Uri) -> Option<Vec<CodeLens>> {
-    _ = uri;
     let item1 = CodeLens {
         range: Range {
             start: Position::new(1, 2),
@@ -360,11 +470,35 @@ pub fn get_code_lens_response(response_num: u32, uri: &Uri) -> Option<Vec<CodeLe
         }),
         data: None,
     };
+    // An executable lens, for use with `test_code_lens_then_execute`
+    let item3 = CodeLens {
+        range: Range {
+            start: Position::new(0, 0),
+            end: Position::new(0, 1),
+        },
+        command: Some(lsp_types::Command {
+            title: "Run".to_string(),
+            command: "command".to_string(),
+            arguments: Some(vec![Value::String(uri.as_str().to_string())]),
+        }),
+        data: None,
+    };
+    // A lens whose command makes the server apply an edit, see `APPLY_EDIT_COMMAND`
+    let item4 = CodeLens {
+        command: Some(lsp_types::Command {
+            title: "Fix".to_string(),
+            command: APPLY_EDIT_COMMAND.to_string(),
+            arguments: Some(vec![Value::String(uri.as_str().to_string())]),
+        }),
+        ..item3.clone()
+    };
     match response_num {
         0 => Some(vec![]),
         1 => Some(vec![item1]),
         2 => Some(vec![item2]),
         3 => Some(vec![item1, item2]),
+        4 => Some(vec![item1, item3]),
+        5 => Some(vec![item1, item4]),
         _ => None,
     }
 }
@@ -400,6 +534,21 @@ pub fn get_code_lens_resolve_response(response_num: u32, uri: &Uri) -> Option<Co
     }
 }
 
+/// For use with `test_completion`, when the request's context specifies a trigger
+/// character. The response contains a single item labeled with `trigger_character`.
+#[must_use]
+pub fn get_completion_trigger_character_response(
+    trigger_character: &str,
+    uri: &Uri,
+) -> Option<CompletionResponse> {
+    _ = uri;
+    Some(CompletionResponse::Array(vec![CompletionItem {
+        label: format!("triggered by {trigger_character}"),
+        kind: Some(CompletionItemKind::FIELD),
+        ..Default::default()
+    }]))
+}
+
 /// For use with `test_completion`.
 #[must_use]
 pub fn get_completion_response(response_num: u32, uri: &Uri) -> Option<CompletionResponse> {
@@ -578,17 +727,6 @@ pub fn get_hover_response(response_num: u32, uri: &Uri) -> Option<Hover> {
                 end: Position::new(15, 16),
             }),
         }),
-        // BUG: https://github.com/serde-rs/json/issues/1244
-        // 4 => Some(Hover {
-        //     contents: HoverContents::Array(vec![
-        //         MarkedString::String("Array Marked String 1".to_string()),
-        //         MarkedString::String("Array Marked String 2".to_string()),
-        //     ]),
-        //     range: Some(Range {
-        //         start: Position::new(13, 14),
-        //         end: Position::new(15, 16),
-        //     }),
-        // }),
         4 => Some(Hover {
             contents: HoverContents::Array(vec![]),
             range: Some(Range {
@@ -623,6 +761,16 @@ pub fn get_hover_response(response_num: u32, uri: &Uri) -> Option<Hover> {
                 end: Position::new(15, 16),
             }),
         }),
+        7 => Some(Hover {
+            contents: HoverContents::Array(vec![
+                MarkedString::String("Array Marked String 1".to_string()),
+                MarkedString::String("Array Marked String 2".to_string()),
+            ]),
+            range: Some(Range {
+                start: Position::new(13, 14),
+                end: Position::new(15, 16),
+            }),
+        }),
         _ => None,
     }
 }
@@ -630,7 +778,6 @@ pub fn get_hover_response(response_num: u32, uri: &Uri) -> Option<Hover> {
 /// For use with `test_inlay_hint`.
 #[must_use]
 pub fn get_inlay_hint_response(response_num: u32, uri: &Uri) -> Option<Vec<InlayHint>> {
-    _ = uri;
     let hint1 = InlayHint {
         kind: Some(InlayHintKind::TYPE),
         label: InlayHintLabel::String("label1".to_string()),
@@ -654,11 +801,54 @@ pub fn get_inlay_hint_response(response_num: u32, uri: &Uri) -> Option<Vec<Inlay
         text_edits: None,
         data: None,
     };
+    let hint3 = InlayHint {
+        kind: Some(InlayHintKind::PARAMETER),
+        label: InlayHintLabel::LabelParts(vec![
+            InlayHintLabelPart {
+                value: "part1".to_string(),
+                tooltip: Some(InlayHintLabelPartTooltip::String(
+                    "part tooltip".to_string(),
+                )),
+                location: Some(Location {
+                    uri: uri.clone(),
+                    range: Range {
+                        start: Position::new(5, 6),
+                        end: Position::new(7, 8),
+                    },
+                }),
+                command: None,
+            },
+            InlayHintLabelPart {
+                value: "part2".to_string(),
+                tooltip: None,
+                location: None,
+                command: Some(Command {
+                    title: "title".to_string(),
+                    command: "command".to_string(),
+                    arguments: None,
+                }),
+            },
+        ]),
+        padding_left: None,
+        padding_right: None,
+        tooltip: None,
+        position: Position::new(5, 6),
+        text_edits: Some(vec![TextEdit {
+            range: Range {
+                start: Position::new(5, 6),
+                end: Position::new(5, 6),
+            },
+            new_text: "part1part2".to_string(),
+        }]),
+        data: Some(serde_json::json!({ "id": 3, "uri": uri.as_str() })),
+    };
     match response_num {
         0 => Some(vec![]),
         1 => Some(vec![hint1]),
         2 => Some(vec![hint2]),
         3 => Some(vec![hint1, hint2]),
+        4 => Some(vec![hint3]),
+        5 => Some(vec![hint1, hint2, hint3]),
         _ => None,
     }
 }
@@ -1086,10 +1276,44 @@ pub fn get_diagnostic_response(response_num: u32, uri: &Uri) -> Option<DocumentD
                 },
             },
         )),
+        5 => Some(DocumentDiagnosticReport::Full(
+            RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport {
+                    result_id: Some("result_id_5".to_string()),
+                    items: vec![item1],
+                },
+            },
+        )),
         _ => None,
     }
 }
 
+/// For use with `test_diagnostic_unchanged`. Reports the document as unchanged only
+/// when `previous_result_id` matches the `result_id` of the full report for
+/// `response_num`, otherwise returns `None`.
+#[must_use]
+pub fn get_diagnostic_unchanged_response(
+    response_num: u32,
+    previous_result_id: Option<&str>,
+    uri: &Uri,
+) -> Option<DocumentDiagnosticReport> {
+    let Some(DocumentDiagnosticReport::Full(full)) = get_diagnostic_response(response_num, uri)
+    else {
+        return None;
+    };
+    let result_id = full.full_document_diagnostic_report.result_id?;
+    if previous_result_id != Some(result_id.as_str()) {
+        return None;
+    }
+    Some(DocumentDiagnosticReport::Unchanged(
+        RelatedUnchangedDocumentDiagnosticReport {
+            related_documents: None,
+            unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id },
+        },
+    ))
+}
+
 /// For use with `test_publish_diagnostics`.
 #[must_use]
 #[allow(clippy::missing_panics_doc)]
@@ -1149,6 +1373,102 @@ pub fn get_workspace_diagnostics_response(
     }
 }
 
+/// For use with `test_expect_log_message`. Sent via `window/logMessage` after
+/// `textDocument/didOpen` is received, keyed by the response number's offset from
+/// [`NOTIFICATIONS_RESPONSE_NUM`](crate::NOTIFICATIONS_RESPONSE_NUM).
+#[must_use]
+pub fn get_log_message_response(response_num: u32, uri: &Uri) -> Option<LogMessageParams> {
+    _ = uri;
+    match response_num {
+        0 => Some(LogMessageParams {
+            typ: MessageType::WARNING,
+            message: "Malformed config, falling back to defaults".to_string(),
+        }),
+        1 => Some(LogMessageParams {
+            typ: MessageType::INFO,
+            message: "Indexing workspace".to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// For use with `test_expect_log_message`. Sent via `window/showMessage` after
+/// `textDocument/didOpen` is received, keyed by the response number's offset from
+/// [`NOTIFICATIONS_RESPONSE_NUM`](crate::NOTIFICATIONS_RESPONSE_NUM).
+#[must_use]
+pub fn get_show_message_response(response_num: u32, uri: &Uri) -> Option<ShowMessageParams> {
+    _ = uri;
+    match response_num {
+        1 => Some(ShowMessageParams {
+            typ: MessageType::ERROR,
+            message: "Failed to index workspace".to_string(),
+        }),
+        _ => None,
+    }
+}
+
+/// For use with `test_expect_log_message`. Sent via `window/logMessage` in place of
+/// handling a request when the response number is
+/// [`REQUEST_LOG_MESSAGE_RESPONSE_NUM`](crate::REQUEST_LOG_MESSAGE_RESPONSE_NUM).
+#[must_use]
+pub fn get_request_log_message_response(uri: &Uri) -> LogMessageParams {
+    _ = uri;
+    LogMessageParams {
+        typ: MessageType::INFO,
+        message: "Handled request".to_string(),
+    }
+}
+
+/// The edit sent via `workspace/applyEdit` when the response number is
+/// [`APPLY_EDIT_RESPONSE_NUM`](crate::APPLY_EDIT_RESPONSE_NUM), or when executing
+/// [`APPLY_EDIT_COMMAND`](crate::APPLY_EDIT_COMMAND). Inserts a line at the start of `uri`.
+#[must_use]
+pub fn get_apply_edit_response(uri: &Uri) -> WorkspaceEdit {
+    let mut changes = HashMap::new();
+    changes.insert(
+        uri.clone(),
+        vec![TextEdit {
+            range: Range::default(),
+            new_text: "stray edit\n".to_string(),
+        }],
+    );
+    WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }
+}
+
+/// The token used for the `$/progress` notifications returned by [`get_progress_response`]
+pub const PROGRESS_TOKEN: &str = "test-server/indexing";
+
+/// For use with `test_indexing_progress`. Sent via `$/progress` with [`PROGRESS_TOKEN`]
+/// after `textDocument/didOpen` is received, keyed by the response number's offset from
+/// [`NOTIFICATIONS_RESPONSE_NUM`](crate::NOTIFICATIONS_RESPONSE_NUM).
+#[must_use]
+pub fn get_progress_response(response_num: u32, uri: &Uri) -> Option<Vec<WorkDoneProgress>> {
+    _ = uri;
+    let begin = WorkDoneProgress::Begin(WorkDoneProgressBegin {
+        title: "Indexing".to_string(),
+        percentage: Some(0),
+        ..Default::default()
+    });
+    let report = |percentage: u32| {
+        WorkDoneProgress::Report(WorkDoneProgressReport {
+            percentage: Some(percentage),
+            ..Default::default()
+        })
+    };
+    let end = WorkDoneProgress::End(WorkDoneProgressEnd::default());
+    match response_num {
+        0 => Some(vec![begin, report(50), report(100), end]),
+        // Percentage decreases
+        1 => Some(vec![begin, report(50), report(25), report(100), end]),
+        // Ends before reaching 100%
+        2 => Some(vec![begin, report(50), end]),
+        _ => None,
+    }
+}
+
 /// For use with `test_publish_diagnostics`.
 #[must_use]
 #[allow(clippy::missing_panics_doc)]
@@ -1201,6 +1521,18 @@ pub fn get_publish_diagnostics_response(
     }
 }
 
+/// For use with `test_publish_diagnostics` and `PUBLISH_DIAGNOSTICS_WAVES_RESPONSE_NUM`.
+///
+/// The waves published, in order: one diagnostic, none, and finally two diagnostics
+/// (the same as response number 2).
+#[must_use]
+pub fn get_publish_diagnostics_waves(uri: &Uri) -> Vec<PublishDiagnosticsParams> {
+    [1, 0, 2]
+        .into_iter()
+        .filter_map(|response_num| get_publish_diagnostics_response(response_num, uri))
+        .collect()
+}
+
 /// For use with `test_declaration`.
 ///
 /// Since `textDocument/definition` and `textDocument/declaration` have the same response,
@@ -1766,10 +2098,49 @@ pub fn get_signature_help_response(response_num: u32, uri: &Uri) -> Option<Signa
             active_signature: None,
             active_parameter: None,
         }),
+        SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM => Some(SignatureHelp {
+            signatures: vec![SignatureInformation {
+                label: "fn label4(first, second)".to_string(),
+                documentation: None,
+                parameters: Some(vec![
+                    ParameterInformation {
+                        label: ParameterLabel::Simple("first".to_string()),
+                        documentation: None,
+                    },
+                    ParameterInformation {
+                        label: ParameterLabel::Simple("second".to_string()),
+                        documentation: None,
+                    },
+                ]),
+                active_parameter: None,
+            }],
+            active_signature: None,
+            active_parameter: Some(0),
+        }),
         _ => None,
     }
 }
 
+/// For use with `test_signature_help_retrigger`, when the request's context carries an
+/// active signature help. The response is `active` with the active parameter of its
+/// active signature advanced by one.
+#[must_use]
+pub fn get_signature_help_retrigger_response(
+    active: &SignatureHelp,
+    uri: &Uri,
+) -> Option<SignatureHelp> {
+    _ = uri;
+    let mut help = active.clone();
+    let sig_idx = help.active_signature.unwrap_or(0) as usize;
+    let help_param = help.active_parameter;
+    if let Some(sig) = help.signatures.get_mut(sig_idx) {
+        // The signature's active parameter takes precedence over the response's
+        let param = sig.active_parameter.take().or(help_param).unwrap_or(0);
+        help.active_parameter = Some(param + 1);
+    }
+    Some(help)
+}
+
 /// For use with `test_declaration`.
 ///
 /// Since `textDocument/definition` and `textDocument/typeDefinition` have the same
@@ -1960,6 +2331,21 @@ pub fn get_workspace_symbol_response(
             workspace_sym_1,
             workspace_sym_2,
         ])),
+        // Serializes to the same JSON as `Flat(vec![sym_info_1])`
+        8 => Some(WorkspaceSymbolResponse::Nested(vec![WorkspaceSymbol {
+            name: "name1".to_string(),
+            kind: SymbolKind::FILE,
+            tags: None,
+            container_name: None,
+            data: None,
+            location: OneOf::Left(Location {
+                uri: uri.clone(),
+                range: Range {
+                    start: Position::new(1, 2),
+                    end: Position::new(3, 4),
+                },
+            }),
+        }])),
         _ => None,
     }
 }
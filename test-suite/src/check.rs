@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod test {
+    use std::{path::Path, time::Duration};
+
+    use lsp_types::{Position, Range, ServerCapabilities};
+    use lspresso_shot::{
+        test_inlay_hint,
+        types::{TestCase, TestError, TestFile, TestSetupError},
+    };
+    use test_server::{get_dummy_server_path, send_capabiltiies};
+
+    #[test]
+    fn check_valid() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "foo\nbar\n");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .cursor_pos(Some(Position::new(1, 3)));
+
+        assert_eq!(Ok(()), test_case.check());
+    }
+
+    #[test]
+    fn check_cursor_out_of_bounds() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "foo\nbar\n");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .cursor_pos(Some(Position::new(1, 4)));
+
+        assert_eq!(
+            Err(TestSetupError::CursorOutOfBounds {
+                line: 1,
+                character: 4
+            }),
+            test_case.check()
+        );
+    }
+
+    #[test]
+    fn validate_range() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "foo\nbar");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let max = Position::new(1, 3);
+
+        assert_eq!(
+            Ok(()),
+            test_case.validate_range(Range::new(Position::new(0, 0), max))
+        );
+        let past_end = Range::new(Position::new(0, 0), Position::new(1, 4));
+        assert_eq!(
+            Err(TestSetupError::RangeOutOfBounds {
+                range: past_end,
+                max
+            }),
+            test_case.validate_range(past_end)
+        );
+        let inverted = Range::new(Position::new(1, 0), Position::new(0, 1));
+        assert_eq!(
+            Err(TestSetupError::RangeOutOfBounds {
+                range: inverted,
+                max
+            }),
+            test_case.validate_range(inverted)
+        );
+    }
+
+    #[test]
+    fn validate_range_utf16() {
+        // The emoji is a single `char`, but two UTF-16 code units
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "let 😀 = 1;");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let max = Position::new(0, 11);
+
+        assert_eq!(
+            Ok(()),
+            test_case.validate_range(Range::new(Position::new(0, 4), max))
+        );
+        let past_end = Range::new(Position::new(0, 0), Position::new(0, 12));
+        assert_eq!(
+            Err(TestSetupError::RangeOutOfBounds {
+                range: past_end,
+                max
+            }),
+            test_case.validate_range(past_end)
+        );
+    }
+
+    #[test]
+    fn range_out_of_bounds_before_run() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "foo\nbar\n");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let range = Range::new(Position::new(0, 0), Position::new(5, 0));
+
+        assert_eq!(
+            Err(TestError::TestSetup(TestSetupError::RangeOutOfBounds {
+                range,
+                max: Position::new(2, 0),
+            })),
+            test_inlay_hint(&test_case, range, None, None)
+        );
+    }
+
+    #[test]
+    fn check_cursor_utf16() {
+        // The emoji is a single `char`, but two UTF-16 code units
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "let 😀 = 1;");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .cursor_pos(Some(Position::new(0, 11)));
+
+        assert_eq!(Ok(()), test_case.check());
+    }
+
+    #[test]
+    fn check_invalid_filetype() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .attach_filetypes(vec!["rust".to_string(), "not a filetype".to_string()]);
+
+        assert_eq!(
+            Err(TestSetupError::InvalidFiletype(
+                "not a filetype".to_string()
+            )),
+            test_case.check()
+        );
+    }
+
+    #[test]
+    fn check_missing_extension() {
+        let source_file = TestFile::new("main", "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        assert_eq!(
+            Err(TestSetupError::MissingFileExtension("main".to_string())),
+            test_case.check()
+        );
+    }
+
+    #[test]
+    fn check_invalid_nvim_runtime() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .nvim_runtime("/lspresso-shot/nonexistent/runtime");
+
+        assert_eq!(
+            Err(TestSetupError::InvalidNeovimRuntime(
+                "/lspresso-shot/nonexistent/runtime".into()
+            )),
+            test_case.check()
+        );
+    }
+
+    #[test]
+    fn check_missing_reused_source_file() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let reuse_dir = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        let test_case = test_case.reuse_dir(&reuse_dir);
+
+        assert_eq!(
+            Err(TestSetupError::MissingReusedSourceFile(
+                reuse_dir.join(test_server::get_dummy_source_path())
+            )),
+            test_case.check()
+        );
+    }
+
+    #[test]
+    fn check_relative_reuse_dir() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).reuse_dir("fixtures/project");
+
+        assert_eq!(
+            Err(TestSetupError::RelativeReuseDir("fixtures/project".into())),
+            test_case.check()
+        );
+    }
+
+    #[test]
+    fn probe_server() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).probe_server(true);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        assert_eq!(Ok(()), test_case.check());
+    }
+
+    #[test]
+    fn probe_not_an_lsp_server() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        // `cat` echoes the `initialize` request back rather than responding to it
+        let test_case = TestCase::new("cat", source_file)
+            .timeout(Duration::from_millis(250))
+            .probe_server(true);
+
+        assert!(matches!(
+            test_case.check(),
+            Err(TestSetupError::NotAnLspServer { path, .. }) if path == Path::new("cat")
+        ));
+    }
+
+    #[test]
+    fn artifacts_outside_source_dir() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let source_dir = test_case.get_source_file_path("").unwrap();
+        let artifacts_dir = test_case.get_artifacts_dir().unwrap();
+
+        assert!(!artifacts_dir.starts_with(&source_dir));
+        for path in [
+            test_case.get_results_file_path().unwrap(),
+            test_case.get_empty_file_path().unwrap(),
+            test_case.get_init_lua_file_path().unwrap(),
+            test_case.get_error_file_path().unwrap(),
+            test_case.get_log_file_path().unwrap(),
+        ] {
+            assert!(path.starts_with(&artifacts_dir), "{}", path.display());
+        }
+    }
+}
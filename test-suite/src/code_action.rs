@@ -8,7 +8,10 @@ mod test {
     };
     use lspresso_shot::{
         lspresso_shot, test_code_action, test_code_action_resolve,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        types::{
+            ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile,
+            compare::Comparator,
+        },
     };
     use std::{collections::HashMap, num::NonZeroU32, str::FromStr as _, time::Duration};
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
@@ -56,8 +59,53 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_capture_outgoing() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).capture_outgoing(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&code_action_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let context = CodeActionContext {
+            only: Some(vec![CodeActionKind::QUICKFIX]),
+            ..Default::default()
+        };
+        lspresso_shot!(test_code_action(
+            &test_case,
+            Range::default(),
+            &context,
+            None,
+            None
+        ));
+        let outgoing = test_case
+            .read_outgoing()
+            .expect("Failed to read outgoing requests")
+            .expect("No requests were captured");
+        let request = outgoing
+            .iter()
+            .find(|request| request.method == "textDocument/codeAction")
+            .expect("No code action request was captured");
+        assert_eq!(
+            serde_json::json!(["quickfix"]),
+            request.params["context"]["only"]
+        );
+
+        // The params built for the request match what the client sent
+        let built = test_case
+            .read_request_params()
+            .expect("Failed to read request params")
+            .expect("No request params were recorded");
+        assert_eq!(built["range"], request.params["range"]);
+        assert_eq!(built["context"]["only"], request.params["context"]["only"]);
+    }
+
     #[rstest]
-    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
+    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2, 3, 4, 5)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp = test_server::responses::get_code_action_response(response_num, &uri).unwrap();
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -81,12 +129,13 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
 
     #[rstest]
-    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
+    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3, 4, 5)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp = test_server::responses::get_code_action_response(response_num, &uri).unwrap();
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -109,7 +158,7 @@ mod test {
     }
 
     #[rstest]
-    fn test_server_resolve_simple_expect_some_got_some(#[values(0, 1)] response_num: u32) {
+    fn test_server_resolve_simple_expect_some_got_some(#[values(0, 1, 2)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp =
             test_server::responses::get_code_action_resolve_response(response_num, &uri).unwrap();
@@ -255,7 +304,7 @@ mod test {
             &test_case,
             range,
             &CodeActionContext::default(),
-            Some(cmp),
+            Some(Comparator::new(cmp)),
             Some(&vec![
                 CodeActionOrCommand::CodeAction(CodeAction {
                     title: "Insert explicit type `i32`".to_string(),
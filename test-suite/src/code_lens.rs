@@ -4,12 +4,17 @@ mod test {
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
-        lspresso_shot, test_code_lens,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        lspresso_shot, test_code_lens, test_code_lens_then_execute,
+        types::{
+            ResponseMismatchError, ServerStartType, TestCase, TestError, TestExecutionError,
+            TestFile, code_lens::CodeLensExecution, compare::Comparator,
+        },
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
-    use lsp_types::{CodeLens, CodeLensOptions, Position, Range, ServerCapabilities, Uri};
+    use lsp_types::{
+        CodeLens, CodeLensOptions, ExecuteCommandOptions, Position, Range, ServerCapabilities, Uri,
+    };
     use rstest::rstest;
 
     fn code_lens_capabilities_simple() -> ServerCapabilities {
@@ -55,6 +60,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -147,8 +153,107 @@ mod test {
         lspresso_shot!(test_code_lens(
             &test_case,
             Some(&commands),
-            Some(cmp),
+            Some(Comparator::new(cmp)),
             Some(&expected)
         ));
     }
+
+    fn code_lens_execute_capabilities(commands: &[String]) -> ServerCapabilities {
+        ServerCapabilities {
+            execute_command_provider: Some(ExecuteCommandOptions {
+                commands: commands.to_vec(),
+                ..Default::default()
+            }),
+            ..code_lens_capabilities_simple()
+        }
+    }
+
+    #[test]
+    fn test_server_then_execute_expect_some_got_some() {
+        let response_num = 4;
+        let commands = vec!["command".to_string()];
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp =
+            test_server::responses::get_execute_command_response(response_num, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&code_lens_execute_capabilities(&commands), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let expected = CodeLensExecution {
+            response: Some(resp),
+            applied_edits: vec![],
+            state: String::new(),
+        };
+        lspresso_shot!(test_code_lens_then_execute(
+            &test_case,
+            Some(&commands),
+            |lens| lens.command.is_some(),
+            None,
+            Some(&expected)
+        ));
+    }
+
+    #[test]
+    fn test_server_then_execute_applies_edit() {
+        let response_num = 5;
+        let commands = vec![test_server::APPLY_EDIT_COMMAND.to_string()];
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp =
+            test_server::responses::get_execute_command_response(response_num, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&code_lens_execute_capabilities(&commands), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let expected = CodeLensExecution {
+            response: Some(resp),
+            applied_edits: vec![test_server::responses::get_apply_edit_response(&uri)],
+            state: "stray edit\n".to_string(),
+        };
+        lspresso_shot!(test_code_lens_then_execute(
+            &test_case,
+            Some(&commands),
+            |lens| {
+                lens.command
+                    .as_ref()
+                    .is_some_and(|cmd| cmd.command == test_server::APPLY_EDIT_COMMAND)
+            },
+            None,
+            Some(&expected)
+        ));
+    }
+
+    #[test]
+    fn test_server_then_execute_no_matching_lens() {
+        let commands = vec!["command".to_string()];
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&code_lens_execute_capabilities(&commands), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result =
+            test_code_lens_then_execute(&test_case, Some(&commands), |_| true, None, None);
+        assert_eq!(
+            Err(TestError::TestExecution(
+                TestExecutionError::NoMatchingCodeLens(test_case.test_id.clone())
+            )),
+            test_result
+        );
+    }
 }
@@ -4,16 +4,21 @@ mod test {
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
-        lspresso_shot, test_completion,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        lspresso_shot, test_completion, test_trigger_characters,
+        types::{
+            ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile, TriggerKind,
+            compare::Comparator,
+        },
+    };
+    use test_server::{
+        get_dummy_server_path, receive_completion_context, send_capabiltiies, send_response_num,
     };
-    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
     use lsp_types::{
-        CompletionItem, CompletionItemKind, CompletionList, CompletionOptions,
-        CompletionOptionsCompletionItem, CompletionResponse, CompletionTextEdit, Documentation,
-        InsertTextFormat, MarkupContent, Position, Range, ServerCapabilities, TextEdit, Uri,
-        WorkDoneProgressOptions,
+        CompletionContext, CompletionItem, CompletionItemKind, CompletionList, CompletionOptions,
+        CompletionOptionsCompletionItem, CompletionResponse, CompletionTextEdit,
+        CompletionTriggerKind, Documentation, InsertTextFormat, MarkupContent, Position, Range,
+        ServerCapabilities, TextEdit, Uri, WorkDoneProgressOptions,
     };
     use rstest::rstest;
 
@@ -34,6 +39,64 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_server_trigger_characters() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        let mut capabilities = completion_capabilities_simple();
+        if let Some(provider) = capabilities.completion_provider.as_mut() {
+            provider.trigger_characters = Some(vec![".".to_string(), ":".to_string()]);
+        }
+        send_capabiltiies(&capabilities, &test_case_root).expect("Failed to send capabilities");
+
+        lspresso_shot!(test_trigger_characters(
+            &test_case,
+            TriggerKind::Completion,
+            [':', '.']
+        ));
+        let Err(TestError::ResponseMismatch(mismatch)) =
+            test_trigger_characters(&test_case, TriggerKind::Completion, ['.', '('])
+        else {
+            panic!("Expected a response mismatch");
+        };
+        assert_eq!(
+            Some("Missing: [\"(\"], unexpected: [\":\"]"),
+            mismatch.reason.as_deref()
+        );
+        // Signature help isn't advertised at all
+        assert!(test_trigger_characters(&test_case, TriggerKind::SignatureHelp, []).is_err());
+    }
+
+    #[test]
+    fn test_server_at_marker() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_completion_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "let x = 1;\nfoo.");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).at_marker("foo.", 4);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&completion_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let cursor_pos = test_case.cursor_pos.unwrap();
+        assert_eq!(Position::new(1, 4), cursor_pos);
+        lspresso_shot!(test_completion(
+            &test_case,
+            cursor_pos,
+            Some(&CompletionContext {
+                trigger_kind: CompletionTriggerKind::TRIGGER_CHARACTER,
+                trigger_character: Some(".".to_string()),
+            }),
+            None,
+            Some(&resp)
+        ));
+    }
+
     #[test]
     fn test_server_simple_expect_none_got_none() {
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -45,7 +108,13 @@ mod test {
         send_capabiltiies(&completion_capabilities_simple(), &test_case_root)
             .expect("Failed to send capabilities");
 
-        lspresso_shot!(test_completion(&test_case, Position::default(), None, None));
+        lspresso_shot!(test_completion(
+            &test_case,
+            Position::default(),
+            None,
+            None,
+            None
+        ));
     }
 
     #[rstest]
@@ -61,11 +130,12 @@ mod test {
         send_capabiltiies(&completion_capabilities_simple(), &test_case_root)
             .expect("Failed to send capabilities");
 
-        let test_result = test_completion(&test_case, Position::default(), None, None);
+        let test_result = test_completion(&test_case, Position::default(), None, None, None);
         let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -87,6 +157,67 @@ mod test {
             &test_case,
             Position::default(),
             None,
+            None,
+            Some(&resp)
+        ));
+    }
+
+    #[rstest]
+    fn test_server_context_sent_only_when_set(
+        #[values(
+            None,
+            Some(CompletionContext {
+                trigger_kind: CompletionTriggerKind::INVOKED,
+                trigger_character: None,
+            })
+        )]
+        context: Option<CompletionContext>,
+    ) {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_completion_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&completion_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_completion(
+            &test_case,
+            Position::default(),
+            context.as_ref(),
+            None,
+            Some(&resp)
+        ));
+        let received = receive_completion_context(&test_case_root)
+            .expect("Failed to receive completion context");
+        assert_eq!(context, received);
+    }
+
+    #[test]
+    fn test_server_trigger_character_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp =
+            test_server::responses::get_completion_trigger_character_response(".", &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&completion_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_completion(
+            &test_case,
+            Position::default(),
+            Some(&CompletionContext {
+                trigger_kind: CompletionTriggerKind::TRIGGER_CHARACTER,
+                trigger_character: Some(".".to_string()),
+            }),
+            None,
             Some(&resp)
         ));
     }
@@ -216,7 +347,8 @@ println!("format {local_variable} arguments");
         lspresso_shot!(test_completion(
             &test_case,
             Position::new(1, 9),
-            Some(cmp),
+            None,
+            Some(Comparator::new(cmp)),
             Some(&expected_item)
         ));
     }
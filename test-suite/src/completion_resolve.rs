@@ -75,6 +75,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
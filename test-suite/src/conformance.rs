@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod test {
+    use std::str::FromStr as _;
+
+    use lspresso_shot::{
+        test_completion, test_hover,
+        types::{
+            TestCase, TestFile, TestType,
+            report::{ConformanceRunner, ConformanceStatus, SkipReason, TestStatus},
+        },
+    };
+    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
+
+    use lsp_types::{HoverProviderCapability, Position, ServerCapabilities, Uri};
+
+    #[test]
+    fn test_server_conformance() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let hover = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let completion = test_server::responses::get_completion_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(
+            &ServerCapabilities {
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                ..ServerCapabilities::default()
+            },
+            &test_case_root,
+        )
+        .expect("Failed to send capabilities");
+
+        let report = ConformanceRunner::new(&test_case)
+            .expect(TestType::Hover, |test_case| {
+                test_hover(test_case, Position::default(), None, Some(&hover))
+            })
+            .expect(TestType::Completion, |test_case| {
+                test_completion(
+                    test_case,
+                    Position::default(),
+                    None,
+                    None,
+                    Some(&completion),
+                )
+            })
+            .run();
+
+        assert!(report.all_passed(), "{report}");
+        let status = |test_type| report.get(test_type).unwrap().status;
+        assert_eq!(
+            ConformanceStatus::Ran(TestStatus::Passed),
+            status(TestType::Hover)
+        );
+        assert_eq!(
+            ConformanceStatus::Skipped(SkipReason::NotAdvertised),
+            status(TestType::Completion)
+        );
+        assert_eq!(
+            ConformanceStatus::Skipped(SkipReason::NoExpectation),
+            status(TestType::Definition)
+        );
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use lsp_types::{Position, TextDocumentSyncKind};
+    use lspresso_shot::types::{TestCase, TestFile, WriteOrder};
+    use test_server::get_dummy_server_path;
+
+    fn test_case() -> TestCase {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "foo\nbar\n");
+        TestCase::new(get_dummy_server_path(), source_file)
+            .cursor_pos(Some(Position::new(1, 2)))
+            .other_file(TestFile::new("other.dummy", "baz"))
+    }
+
+    #[test]
+    fn content_hash_ignores_non_logical_fields() {
+        let base = test_case();
+        // `test_id` is randomly generated for each instance
+        assert_ne!(base.test_id, test_case().test_id);
+        assert_eq!(base.content_hash(), test_case().content_hash());
+
+        let other = test_case()
+            .nvim_path("other-nvim")
+            .cleanup(true)
+            .trace(true)
+            .golden("golden");
+        assert_eq!(base.content_hash(), other.content_hash());
+    }
+
+    #[test]
+    fn content_hash_tracks_logical_fields() {
+        let hash = test_case().content_hash();
+
+        let other_source =
+            test_case().source_file(TestFile::new(test_server::get_dummy_source_path(), "foo\n"));
+        assert_ne!(hash, other_source.content_hash());
+        let other_cursor = test_case().cursor_pos(Some(Position::new(0, 0)));
+        assert_ne!(hash, other_cursor.content_hash());
+        let other_timeout = test_case().timeout(Duration::from_secs(5));
+        assert_ne!(hash, other_timeout.content_hash());
+        let other_exe = test_case().exeutable_path("other-server");
+        assert_ne!(hash, other_exe.content_hash());
+        let other_watch_globs = test_case().watch_globs(vec!["*.dummy".to_string()]);
+        assert_ne!(hash, other_watch_globs.content_hash());
+        let full_sync = test_case().sync_kind(TextDocumentSyncKind::FULL);
+        assert_ne!(hash, full_sync.content_hash());
+        let incremental_sync = test_case().sync_kind(TextDocumentSyncKind::INCREMENTAL);
+        assert_ne!(full_sync.content_hash(), incremental_sync.content_hash());
+        let sorted = test_case().write_order(WriteOrder::Sorted);
+        assert_ne!(hash, sorted.content_hash());
+        let other_initial_cursor = test_case().initial_cursor(Position::new(0, 0));
+        assert_ne!(hash, other_initial_cursor.content_hash());
+    }
+}
@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod test {
+    use std::str::FromStr as _;
+
+    use crate::test_helpers::NON_RESPONSE_NUM;
+    use lspresso_shot::{
+        lspresso_shot, test_custom_request,
+        types::{ResponseMismatchError, TestCase, TestError, TestFile, compare::Comparator},
+    };
+    use test_server::{
+        get_dummy_server_path, responses::CustomRequest, send_capabiltiies, send_response_num,
+    };
+
+    use lsp_types::{ServerCapabilities, Uri, request::Request as _};
+    use rstest::rstest;
+    use serde_json::{Value, json};
+
+    fn custom_params(test_case: &TestCase) -> Value {
+        let uri = Uri::from_str(&format!(
+            "file://{}",
+            test_case
+                .get_source_file_path(test_server::get_dummy_source_path())
+                .unwrap()
+                .to_str()
+                .unwrap(),
+        ))
+        .unwrap();
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 0 },
+        })
+    }
+
+    #[test]
+    fn test_server_simple_expect_none_got_none() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_custom_request(
+            &test_case,
+            CustomRequest::METHOD,
+            &custom_params(&test_case),
+            None,
+            None,
+        ));
+    }
+
+    #[rstest]
+    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2)] response_num: u32) {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_custom_response(response_num, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_custom_request(
+            &test_case,
+            CustomRequest::METHOD,
+            &custom_params(&test_case),
+            None,
+            Some(&resp),
+        ));
+    }
+
+    #[test]
+    fn test_server_simple_expect_some_got_none() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let expected = test_server::responses::get_custom_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_custom_request(
+            &test_case,
+            CustomRequest::METHOD,
+            &custom_params(&test_case),
+            None,
+            Some(&expected),
+        );
+        let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id.clone(),
+            expected: Some(expected),
+            actual: None,
+            reason: None,
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
+    #[test]
+    fn test_server_simple_cmp() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // Only check the number of returned items
+        let cmp = |expected: &Value, actual: &Value, _: &TestCase| {
+            expected.as_array().map(Vec::len) == actual.as_array().map(Vec::len)
+        };
+        lspresso_shot!(test_custom_request(
+            &test_case,
+            CustomRequest::METHOD,
+            &custom_params(&test_case),
+            Some(Comparator::new(cmp)),
+            Some(&json!([{}, {}])),
+        ));
+    }
+}
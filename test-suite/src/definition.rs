@@ -1,13 +1,18 @@
 #[cfg(test)]
 mod test {
-    use std::{num::NonZeroU32, str::FromStr as _, time::Duration};
+    use std::{num::NonZeroU32, path::PathBuf, str::FromStr as _, time::Duration};
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
         lspresso_shot, test_definition,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        types::{
+            Expected, ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile,
+            TestSetupError,
+        },
+    };
+    use test_server::{
+        get_dummy_server_path, receive_opened_uris, send_capabiltiies, send_response_num,
     };
-    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
     use lsp_types::{
         GotoDefinitionResponse, LocationLink, OneOf, Position, Range, ServerCapabilities, Uri,
@@ -53,6 +58,7 @@ mod test {
             test_id: test_case.test_id.clone(),
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         if response_num == 3 {
             // HACK: Because of the deserialization issues with empty vector results,
@@ -64,12 +70,14 @@ mod test {
                     test_id: test_case.test_id.clone(),
                     expected: None,
                     actual: Some(GotoDefinitionResponse::Link(vec![])),
+                    reason: None,
                 })
             );
             expected_err = TestError::ResponseMismatch(ResponseMismatchError {
                 test_id: test_case.test_id,
                 expected: None,
                 actual: Some(GotoDefinitionResponse::Array(vec![])),
+                reason: None,
             });
         }
         assert_eq!(Err(expected_err), test_result);
@@ -96,6 +104,109 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_preopen() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_definition_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .other_file(TestFile::new("helper.dummy", "fn helper() {}"))
+            .preopen(vec![PathBuf::from("helper.dummy")]);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&definition_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_definition(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        let opened = receive_opened_uris(&test_case_root).expect("Failed to receive opened uris");
+        assert!(
+            opened.iter().any(|uri| uri.ends_with("/src/helper.dummy")),
+            "helper.dummy wasn't opened: {opened:?}"
+        );
+    }
+
+    #[test]
+    fn test_server_preopen_unknown_file() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .preopen(vec![PathBuf::from("helper.dummy")]);
+
+        assert_eq!(
+            Err(TestError::TestSetup(TestSetupError::UnknownFile(
+                PathBuf::from("helper.dummy")
+            ))),
+            test_definition(&test_case, Position::default(), None, None)
+        );
+    }
+
+    #[test]
+    fn test_server_any_of_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let alternatives: Vec<GotoDefinitionResponse> = [4, 6]
+            .into_iter()
+            .map(|num| test_server::responses::get_definition_response(num, &uri).unwrap())
+            .collect();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(6, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&definition_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_definition(
+            &test_case,
+            Position::default(),
+            None,
+            Expected::AnyOf(&alternatives)
+        ));
+    }
+
+    #[test]
+    fn test_server_any_of_none_match() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let alternatives: Vec<GotoDefinitionResponse> = [4, 5]
+            .into_iter()
+            .map(|num| test_server::responses::get_definition_response(num, &uri).unwrap())
+            .collect();
+        let resp = test_server::responses::get_definition_response(6, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(6, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&definition_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_definition(
+            &test_case,
+            Position::default(),
+            None,
+            Expected::AnyOf(&alternatives),
+        );
+        let Err(TestError::ResponseMismatch(ResponseMismatchError {
+            expected,
+            actual,
+            reason: Some(reason),
+            ..
+        })) = test_result
+        else {
+            panic!("Expected a response mismatch");
+        };
+        assert_eq!(alternatives.first(), expected.as_ref());
+        assert_eq!(Some(resp), actual);
+        assert!(reason.contains("[0]") && reason.contains("[1]"));
+    }
+
     #[test]
     fn rust_analyzer() {
         let source_file = TestFile::new(
@@ -1,14 +1,19 @@
 #[cfg(test)]
 mod tests {
-    use std::{num::NonZeroU32, str::FromStr as _, time::Duration};
+    use std::{num::NonZeroU32, path::Path, str::FromStr as _, time::Duration};
 
     use crate::test_helpers::cargo_dot_toml;
     use lspresso_shot::{
-        lspresso_shot, test_diagnostic, test_publish_diagnostics, test_workspace_diagnostic,
-        types::{ServerStartType, TestCase, TestFile},
+        lspresso_shot, test_diagnostic, test_diagnostic_unchanged, test_diagnostics_cleared,
+        test_diagnostics_latency, test_publish_diagnostics, test_publish_diagnostics_for_file,
+        test_workspace_diagnostic,
+        types::{
+            ServerStartType, TestCase, TestError, TestExecutionError, TestFile, TestSetupError,
+        },
     };
     use test_server::{
-        get_dummy_server_path, get_dummy_source_path, send_capabiltiies, send_response_num,
+        PUBLISH_DIAGNOSTICS_WAVES_RESPONSE_NUM, get_dummy_server_path, get_dummy_source_path,
+        receive_did_changes, send_capabiltiies, send_response_num,
     };
 
     use lsp_types::{
@@ -16,7 +21,7 @@ mod tests {
         DiagnosticServerCapabilities, DiagnosticSeverity, DiagnosticTag, DocumentDiagnosticReport,
         FullDocumentDiagnosticReport, Location, NumberOrString, Position, Range,
         RelatedFullDocumentDiagnosticReport, ServerCapabilities, TextDocumentSyncCapability,
-        TextDocumentSyncKind, Uri, WorkDoneProgressOptions,
+        TextDocumentSyncKind, TextEdit, Uri, WorkDoneProgressOptions,
     };
     use rstest::rstest;
     use serde_json::Map;
@@ -52,7 +57,7 @@ mod tests {
 
     #[rstest]
     fn test_server_diagnostic_simple_expect_some_got_some(
-        #[values(0, 1, 2, 3, 4)] response_num: u32,
+        #[values(0, 1, 2, 3, 4, 5)] response_num: u32,
     ) {
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
         let test_case = TestCase::new(get_dummy_server_path(), source_file);
@@ -68,6 +73,45 @@ mod tests {
         lspresso_shot!(test_diagnostic(&test_case, None, None, None, &resp));
     }
 
+    #[test]
+    fn test_server_diagnostic_unchanged() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(5, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_diagnostic_unchanged(&test_case, "result_id_5"));
+        let test_result = test_diagnostic_unchanged(&test_case, "result_id_6");
+        assert!(matches!(test_result, Err(TestError::ResponseMismatch(_))));
+    }
+
+    #[test]
+    fn test_server_diagnostic_stale_result_id() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        let uri = Uri::from_str(&get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_diagnostic_response(5, &uri).unwrap();
+        send_response_num(5, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // A result id the server didn't hand out gets a full report
+        lspresso_shot!(test_diagnostic(
+            &test_case,
+            None,
+            Some("result_id_6"),
+            None,
+            &resp
+        ));
+    }
+
     #[rstest]
     fn test_server_publish_diagnostics_simple_expect_some_got_some(
         #[values(0, 1, 2)] response_num: u32,
@@ -92,6 +136,252 @@ mod tests {
         ));
     }
 
+    #[rstest]
+    fn test_server_publish_diagnostics_settle_expect_some_got_some(
+        #[values(0, 1, 2)] response_num: u32,
+    ) {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp =
+            test_server::responses::get_publish_diagnostics_response(response_num, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).settle(Duration::from_millis(100));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_publish_diagnostics(
+            &test_case,
+            None,
+            &resp.diagnostics
+        ));
+    }
+
+    #[rstest]
+    // The waves are published 200ms apart: `[item]`, `[]`, `[item, item]`
+    #[case::first_wave(1, None, 0)]
+    #[case::settle_from_first_wave(1, Some(300), 1)]
+    #[case::threshold_only(2, None, 1)]
+    #[case::settle_from_threshold(2, Some(300), 2)]
+    fn test_server_publish_diagnostics_settle_with_progress(
+        #[case] threshold: u32,
+        #[case] settle_ms: Option<u64>,
+        #[case] expected_wave: usize,
+    ) {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let mut test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .start_type(ServerStartType::Progress(
+                NonZeroU32::new(threshold).unwrap(),
+                String::new(),
+            ))
+            .timeout(Duration::from_secs(2));
+        if let Some(settle_ms) = settle_ms {
+            test_case = test_case.settle(Duration::from_millis(settle_ms));
+        }
+        let waves = test_server::responses::get_publish_diagnostics_waves(&uri);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(PUBLISH_DIAGNOSTICS_WAVES_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_publish_diagnostics(
+            &test_case,
+            None,
+            &waves[expected_wave].diagnostics
+        ));
+    }
+
+    #[test]
+    fn test_server_publish_diagnostics_settle_exceeds_timeout() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .timeout(Duration::from_millis(500))
+            .settle(Duration::from_millis(500));
+
+        assert!(matches!(
+            test_publish_diagnostics(&test_case, None, &Vec::new()),
+            Err(TestError::TestSetup(
+                TestSetupError::SettleExceedsTimeout { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_server_publish_diagnostics_for_file() {
+        let uri = Uri::from_str("other.dummy").unwrap();
+        let resp = test_server::responses::get_publish_diagnostics_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .other_file(TestFile::new("other.dummy", ""));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_publish_diagnostics_for_file(
+            &test_case,
+            Path::new("other.dummy"),
+            None,
+            &resp.diagnostics
+        ));
+    }
+
+    #[test]
+    fn test_server_publish_diagnostics_for_unknown_file() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_result = test_publish_diagnostics_for_file(
+            &test_case,
+            Path::new("missing.dummy"),
+            None,
+            &vec![],
+        );
+        assert_eq!(
+            Err(TestError::TestSetup(TestSetupError::UnknownFile(
+                "missing.dummy".into()
+            ))),
+            test_result
+        );
+    }
+
+    #[test]
+    fn test_server_diagnostics_cleared() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_publish_diagnostics_response(1, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // The test server clears its diagnostics on any change
+        let fix = TextEdit {
+            range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+            new_text: "fixed".to_string(),
+        };
+        lspresso_shot!(test_diagnostics_cleared(
+            &test_case,
+            &resp.diagnostics,
+            &[fix],
+            Duration::from_millis(500)
+        ));
+    }
+
+    #[rstest]
+    fn test_server_sync_kind_override(
+        #[values(None, Some(TextDocumentSyncKind::INCREMENTAL))] sync_kind: Option<
+            TextDocumentSyncKind,
+        >,
+    ) {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_publish_diagnostics_response(1, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let mut test_case = TestCase::new(get_dummy_server_path(), source_file);
+        if let Some(sync_kind) = sync_kind {
+            test_case = test_case.sync_kind(sync_kind);
+        }
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        // The server itself advertises full syncing
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let fix = TextEdit {
+            range: Range::new(Position::new(0, 0), Position::new(0, 0)),
+            new_text: "fixed".to_string(),
+        };
+        lspresso_shot!(test_diagnostics_cleared(
+            &test_case,
+            &resp.diagnostics,
+            &[fix],
+            Duration::from_millis(500)
+        ));
+        let changes = receive_did_changes(&test_case_root).expect("Failed to receive changes");
+        let content_changes: Vec<_> = changes
+            .iter()
+            .flat_map(|change| &change.content_changes)
+            .collect();
+        assert!(!content_changes.is_empty(), "No didChange was received");
+        // Incremental changes carry the changed range, full ones replace the whole text
+        let incremental = sync_kind == Some(TextDocumentSyncKind::INCREMENTAL);
+        for change in content_changes {
+            assert_eq!(incremental, change.range.is_some(), "{change:?}");
+        }
+    }
+
+    #[test]
+    fn test_server_publish_diagnostics_within_latency() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_publish_diagnostics_response(1, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_diagnostics_latency(
+            &test_case,
+            Duration::from_secs(1),
+            None,
+            &resp.diagnostics
+        ));
+        // Latency samples are kept out of the benchmark results
+        assert!(
+            test_case
+                .get_benchmark_results()
+                .unwrap_or_default()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_server_publish_diagnostics_latency_exceeded() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_publish_diagnostics_response(1, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&diagnostic_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result =
+            test_diagnostics_latency(&test_case, Duration::ZERO, None, &resp.diagnostics);
+        assert!(matches!(
+            test_result,
+            Err(TestError::TestExecution(
+                TestExecutionError::LatencyExceeded { .. }
+            ))
+        ));
+    }
+
     #[rstest]
     fn test_server_workspace_diagnostic_simple_expect_some_got_some(
         #[values(0, 1, 2, 3)] response_num: u32,
@@ -2,7 +2,7 @@
 mod test {
     use lspresso_shot::{
         lspresso_shot, test_document_color,
-        types::{TestCase, TestFile},
+        types::{TestCase, TestError, TestFile},
     };
     use std::str::FromStr as _;
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
@@ -34,5 +34,29 @@ mod test {
         lspresso_shot!(test_document_color(&test_case, None, &resp));
     }
 
+    #[test]
+    fn test_server_float_tolerance() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let mut resp = test_server::responses::get_document_color_response(1, &uri).unwrap();
+        resp[0].color.red += 0.0005;
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&document_color_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // Exact matching by default
+        assert!(matches!(
+            test_document_color(&test_case, None, &resp),
+            Err(TestError::ResponseMismatch(_))
+        ));
+        let test_case = test_case.float_tolerance(1e-3);
+        lspresso_shot!(test_document_color(&test_case, None, &resp));
+    }
+
     // NOTE: rust-analyzer doesn't support `textDocument/documentColor`
 }
@@ -5,11 +5,13 @@ mod test {
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
         lspresso_shot, test_document_highlight,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile, compare},
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
-    use lsp_types::{DocumentHighlight, OneOf, Position, Range, ServerCapabilities, Uri};
+    use lsp_types::{
+        DocumentHighlight, DocumentHighlightKind, OneOf, Position, Range, ServerCapabilities, Uri,
+    };
     use rstest::rstest;
 
     fn document_highlight_capabilities_simple() -> ServerCapabilities {
@@ -59,6 +61,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -86,6 +89,36 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_highlights_by_kind() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(3, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&document_highlight_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // Listed in the reverse order of the server's response
+        lspresso_shot!(test_document_highlight(
+            &test_case,
+            Position::default(),
+            Some(compare::highlights_by_kind()),
+            Some(&vec![
+                DocumentHighlight {
+                    range: Range::new(Position::new(5, 6), Position::new(7, 8)),
+                    kind: Some(DocumentHighlightKind::TEXT),
+                },
+                DocumentHighlight {
+                    range: Range::new(Position::new(1, 2), Position::new(3, 4)),
+                    kind: None,
+                },
+            ])
+        ));
+    }
+
     #[test]
     fn rust_analyzer() {
         let source_file = TestFile::new(
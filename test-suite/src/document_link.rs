@@ -9,7 +9,10 @@ mod test {
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
-    use lsp_types::{DocumentLinkOptions, ServerCapabilities, Uri, WorkDoneProgressOptions};
+    use lsp_types::{
+        DocumentLink, DocumentLinkOptions, Position, Range, ServerCapabilities, Uri,
+        WorkDoneProgressOptions,
+    };
     use rstest::rstest;
 
     fn document_link_capabilities_simple() -> ServerCapabilities {
@@ -40,7 +43,7 @@ mod test {
     }
 
     #[rstest]
-    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2, 3, 4)] response_num: u32) {
+    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2, 3, 4, 5)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp = test_server::responses::get_document_link_response(response_num, &uri).unwrap();
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -58,12 +61,13 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
 
     #[rstest]
-    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3, 4)] response_num: u32) {
+    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3, 4, 5)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp = test_server::responses::get_document_link_response(response_num, &uri).unwrap();
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -79,5 +83,27 @@ mod test {
         lspresso_shot!(test_document_link(&test_case, None, Some(&resp)));
     }
 
+    #[test]
+    fn test_server_external_target() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(5, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&document_link_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // Only `file://` targets are cleaned, so the url is passed through unchanged
+        let expected = vec![DocumentLink {
+            range: Range::new(Position::new(1, 2), Position::new(3, 4)),
+            target: Some(Uri::from_str("https://example.com/docs").unwrap()),
+            tooltip: None,
+            data: None,
+        }];
+        lspresso_shot!(test_document_link(&test_case, None, Some(&expected)));
+    }
+
     // NOTE: rust-analyzer doesn't support `textDocument/documentLink`
 }
@@ -61,7 +61,7 @@ mod test {
     }
 
     #[rstest]
-    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2)] response_num: u32) {
+    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp =
             test_server::responses::get_document_link_resolve_response(response_num, &uri).unwrap();
@@ -99,12 +99,13 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
 
     #[rstest]
-    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2)] response_num: u32) {
+    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp =
             test_server::responses::get_document_link_resolve_response(response_num, &uri).unwrap();
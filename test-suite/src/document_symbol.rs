@@ -55,6 +55,7 @@ mod test {
             test_id: test_case.test_id.clone(),
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         if response_num == 1 {
             // HACK: Because of the deserialization issues with empty vector results,
@@ -66,12 +67,14 @@ mod test {
                     test_id: test_case.test_id.clone(),
                     expected: None,
                     actual: Some(DocumentSymbolResponse::Nested(vec![])),
+                    reason: None,
                 })
             );
             expected_err = TestError::ResponseMismatch(ResponseMismatchError {
                 test_id: test_case.test_id,
                 expected: None,
                 actual: Some(DocumentSymbolResponse::Flat(vec![])),
+                reason: None,
             });
         }
         assert_eq!(Err(expected_err), test_result);
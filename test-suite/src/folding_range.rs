@@ -4,8 +4,8 @@ mod test {
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
-        lspresso_shot, test_folding_range,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        FoldingRangeComparator, lspresso_shot, test_folding_range,
+        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile, compare},
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
@@ -53,6 +53,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -74,6 +75,60 @@ mod test {
         lspresso_shot!(test_folding_range(&test_case, None, Some(&resp)));
     }
 
+    #[rstest]
+    fn test_server_simple_well_formed(#[values(0, 1, 2, 3, 4)] response_num: u32) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&folding_range_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_folding_range(
+            &test_case,
+            Some(compare::folding_well_formed()),
+            Some(&vec![])
+        ));
+    }
+
+    #[test]
+    fn test_server_folding_covers() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(4, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&folding_range_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_folding_range(
+            &test_case,
+            Some(FoldingRangeComparator::explained(|_, actual, _| {
+                compare::folding_covers(&[(0, 1), (2, 4), (6, 8)], actual)
+            })),
+            Some(&vec![])
+        ));
+        let test_result = test_folding_range(
+            &test_case,
+            Some(FoldingRangeComparator::explained(|_, actual, _| {
+                compare::folding_covers(&[(2, 5)], actual)
+            })),
+            Some(&vec![]),
+        );
+        let Err(TestError::ResponseMismatch(mismatch)) = test_result else {
+            panic!("Expected a response mismatch, got {test_result:?}");
+        };
+        assert_eq!(
+            Some("no folding range covers lines 2-5".to_string()),
+            mismatch.reason
+        );
+    }
+
     #[test]
     fn rust_analyzer() {
         let source_file = TestFile::new(
@@ -4,9 +4,11 @@ mod test {
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
-        lspresso_shot, test_formatting, test_on_type_formatting, test_range_formatting,
+        FormattingOptionsBuilder, lspresso_shot, test_formatting, test_on_type_formatting,
+        test_range_formatting,
         types::{
             ResponseMismatchError, ServerStartType, StateOrResponse, TestCase, TestError, TestFile,
+            compare,
         },
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
@@ -40,6 +42,28 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_server_state_text_normalized() {
+        let contents = "Some source contents";
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), contents);
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&formatting_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let expected = StateOrResponse::State(format!("\n{contents}  \r\n\n"));
+        assert!(test_formatting(&test_case, None, None, Some(&expected)).is_err());
+        lspresso_shot!(test_formatting(
+            &test_case,
+            None,
+            Some(compare::text_normalized_trim_blank_lines()),
+            Some(&expected)
+        ));
+    }
+
     #[test]
     fn test_server_state_simple_expect_some_got_some() {
         let contents = "Some source contents";
@@ -138,6 +162,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -163,10 +188,40 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn check_range_formatting_unexpected(response_num: u32, edits: Vec<TextEdit>) {
+        let source_file =
+            TestFile::new(test_server::get_dummy_source_path(), "Some source contents");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&range_formatting_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_range_formatting(&test_case, Range::default(), None, None, None);
+        let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id,
+            expected: None,
+            actual: Some(edits),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
 
+    crate::lspresso_matrix!(
+        test_server_range_simple_expect_none_got_some,
+        super::check_range_formatting_unexpected,
+        test_server::responses::get_formatting_range_response,
+        0..4
+    );
+
     #[rstest]
     fn test_server_response_simple_expect_some_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
@@ -189,6 +244,70 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_both_simple_expect_some_got_some() {
+        let contents = "Some source contents";
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), contents);
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        // NOTE: Response 0 is an empty edit response, so the start and end states
+        // of the source file are the same
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&formatting_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_formatting(
+            &test_case,
+            None,
+            None,
+            Some(&StateOrResponse::Both {
+                edits: vec![],
+                state: contents.to_string()
+            })
+        ));
+        let test_result = test_formatting(
+            &test_case,
+            None,
+            None,
+            Some(&StateOrResponse::Both {
+                edits: vec![],
+                state: "Some other contents".to_string(),
+            }),
+        );
+        assert!(matches!(test_result, Err(TestError::ResponseMismatch(_))));
+    }
+
+    #[test]
+    fn test_server_response_options_builder_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let edits = test_server::responses::get_formatting_response(1, &uri).unwrap();
+        let source_file =
+            TestFile::new(test_server::get_dummy_source_path(), "Some source contents");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&formatting_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+        let options = FormattingOptionsBuilder::new()
+            .tab_size(2)
+            .insert_spaces(false)
+            .property_bool("bool_prop", true)
+            .property_number("number_prop", 42)
+            .property_string("string_prop", "value")
+            .build();
+
+        lspresso_shot!(test_formatting(
+            &test_case,
+            Some(&options),
+            None,
+            Some(&StateOrResponse::Response(edits))
+        ));
+    }
+
     #[rstest]
     fn test_server_range_simple_expect_some_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
@@ -213,6 +332,48 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_range_edits_within() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let edits = test_server::responses::get_formatting_range_response(2, &uri).unwrap();
+        let source_file = TestFile::new(
+            test_server::get_dummy_source_path(),
+            "Some\nsource\ncontents\nspanning\nseveral\nlines\n",
+        );
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(2, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&range_formatting_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // Only the first edit falls within the range, the second is ignored
+        let range = Range::new(Position::new(1, 0), Position::new(4, 0));
+        let expected = vec![edits[0].clone()];
+        lspresso_shot!(test_range_formatting(
+            &test_case,
+            range,
+            None,
+            Some(compare::edits_within(range)),
+            Some(&expected)
+        ));
+
+        // The second edit falls outside of the range
+        let test_result = test_range_formatting(
+            &test_case,
+            range,
+            None,
+            Some(compare::edits_exactly_within(range)),
+            Some(&expected),
+        );
+        let Err(TestError::ResponseMismatch(mismatch)) = test_result else {
+            panic!("Expected a response mismatch, got {test_result:?}");
+        };
+        assert_eq!(Some(edits), mismatch.actual);
+        assert!(mismatch.reason.is_some());
+    }
+
     #[rstest]
     fn test_server_on_type_simple_expect_some_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
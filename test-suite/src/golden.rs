@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        str::FromStr as _,
+    };
+
+    use lspresso_shot::{
+        lspresso_shot, test_hover, test_no_error,
+        types::{ResponseMismatchError, TestCase, TestError, TestFile, TestSetupError, TestType},
+    };
+    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
+
+    use lsp_types::{HoverProviderCapability, Position, ServerCapabilities, Uri};
+
+    fn hover_capabilities_simple() -> ServerCapabilities {
+        ServerCapabilities {
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    fn hover_test_case(response_num: u32, golden_dir: &Path) -> TestCase {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).golden(golden_dir);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+        test_case
+    }
+
+    fn golden_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join("lspresso-shot-golden")
+            .join(format!("{name}-{}", std::process::id()));
+        _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_server_golden_records_then_passes() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(2, &uri).unwrap();
+        let golden_dir = golden_dir("records");
+
+        // The golden file is missing, so the response is recorded regardless of `expected`
+        let test_case = hover_test_case(2, &golden_dir);
+        lspresso_shot!(test_hover(&test_case, Position::default(), None, None));
+        let golden_path = test_case.get_golden_file_path(TestType::Hover).unwrap();
+        assert_eq!(golden_path, golden_dir.join("textDocument_hover.json"));
+        let recorded: lsp_types::Hover =
+            serde_json::from_str(&fs::read_to_string(&golden_path).unwrap()).unwrap();
+        assert_eq!(resp, recorded);
+
+        // Subsequent runs compare against the recorded response
+        let test_case = hover_test_case(2, &golden_dir);
+        lspresso_shot!(test_hover(&test_case, Position::default(), None, None));
+    }
+
+    #[test]
+    fn test_server_golden_mismatch() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let golden_resp = test_server::responses::get_hover_response(2, &uri).unwrap();
+        let resp = test_server::responses::get_hover_response(3, &uri).unwrap();
+        let golden_dir = golden_dir("mismatch");
+
+        let test_case = hover_test_case(2, &golden_dir);
+        lspresso_shot!(test_hover(&test_case, Position::default(), None, None));
+
+        let test_case = hover_test_case(3, &golden_dir);
+        let test_result = test_hover(&test_case, Position::default(), None, None);
+        let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id,
+            expected: Some(golden_resp),
+            actual: Some(resp),
+            reason: None,
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
+    #[test]
+    fn test_server_golden_unsupported() {
+        let golden_dir = golden_dir("unsupported");
+        let test_case = hover_test_case(2, &golden_dir);
+        let test_result = test_no_error(&test_case, TestType::Hover, &serde_json::Value::Null);
+        assert_eq!(
+            Err(TestError::TestSetup(TestSetupError::GoldenUnsupported(
+                "test_no_error"
+            ))),
+            test_result
+        );
+    }
+}
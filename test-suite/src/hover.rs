@@ -1,17 +1,28 @@
 #[cfg(test)]
 mod test {
-    use std::{num::NonZeroU32, str::FromStr as _, time::Duration};
+    use std::{num::NonZeroU32, path::PathBuf, str::FromStr as _, time::Duration};
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
+        benchmark_hover_positions,
+        lspresso_async::test_hover_async,
         lspresso_shot, test_hover,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        types::{
+            BenchmarkConfig, BenchmarkError, EndCondition, Expected, ResponseMismatchError,
+            ServerStartType, TestCase, TestError, TestExecutionError, TestFile, TestSetupError,
+            compare::Cardinality,
+        },
+    };
+    use test_server::{
+        APPLY_EDIT_RESPONSE_NUM, CHANGE_FILES_RESPONSE_NUM, STDOUT_NOISE_RESPONSE_NUM,
+        get_dummy_server_path, receive_did_change_watched_files, receive_project_root,
+        receive_registered, send_capabiltiies, send_registrations, send_response_num,
     };
-    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
     use lsp_types::{
-        Hover, HoverContents, HoverOptions, HoverProviderCapability, MarkupContent, MarkupKind,
-        Position, Range, ServerCapabilities, Uri, WorkDoneProgressOptions,
+        DidChangeWatchedFilesRegistrationOptions, FileSystemWatcher, GlobPattern, Hover,
+        HoverContents, HoverOptions, HoverProviderCapability, MarkupContent, MarkupKind, Position,
+        Range, Registration, ServerCapabilities, Uri, WorkDoneProgressOptions,
     };
     use rstest::rstest;
 
@@ -70,12 +81,13 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
 
     #[rstest]
-    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3, 4, 5)] response_num: u32) {
+    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3, 4, 5, 7)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp = test_server::responses::get_hover_response(response_num, &uri).unwrap();
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -96,6 +108,825 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_initial_cursor() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        // The character offset is in UTF-16 code units, while `é` takes two bytes
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "foo\néé baz\n");
+        // The request is still issued at the origin, regardless of the cursor's location
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).initial_cursor(Position::new(1, 3));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        assert_eq!(
+            Some(Position::new(1, 3)),
+            test_case
+                .read_initial_cursor()
+                .expect("Failed to read initial cursor")
+        );
+    }
+
+    #[test]
+    fn test_server_root_markers() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new("nested/main.dummy", "");
+        // Without the marker, the root would be the test case's `src` directory
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .other_file(TestFile::new("nested/marker.dummy", ""))
+            .root_markers(vec!["marker.dummy".to_string()]);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        let root = receive_project_root(&test_case_root).expect("Failed to receive project root");
+        let expected_root = test_case
+            .get_source_file_path("nested")
+            .and_then(|path| path.canonicalize())
+            .unwrap();
+        assert_eq!(expected_root, root);
+    }
+
+    #[test]
+    fn test_server_root_uri() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new("nested/main.dummy", "");
+        // Takes precedence over the markers
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .other_file(TestFile::new("marker.dummy", ""))
+            .root_markers(vec!["marker.dummy".to_string()])
+            .root_uri(PathBuf::from("nested"));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        let root = receive_project_root(&test_case_root).expect("Failed to receive project root");
+        let expected_root = test_case
+            .get_source_file_path("nested")
+            .and_then(|path| path.canonicalize())
+            .unwrap();
+        assert_eq!(expected_root, root);
+    }
+
+    #[test]
+    fn test_server_normalize() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        // Dropping the range on both sides means a bogus expected range still matches
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).normalize(|value| {
+            if let Some(hover) = value.as_object_mut() {
+                hover.remove("range");
+            }
+        });
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&Hover {
+                range: Some(Range::default()),
+                ..resp
+            })
+        ));
+    }
+
+    #[test]
+    fn test_server_record_timing() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).record_timing(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        let timing = test_case
+            .get_timing()
+            .expect("Failed to read timing")
+            .expect("No timing recorded");
+        assert!(timing.ready.is_some());
+        assert!(timing.request.is_some());
+        assert!(timing.parse.is_some());
+        assert!(timing.spawn <= timing.total);
+    }
+
+    #[tokio::test]
+    async fn test_server_simple_async_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover_async(&test_case, Position::default(), None, Some(&resp)).await);
+    }
+
+    #[tokio::test]
+    async fn test_server_simple_async_expect_none_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_hover_async(&test_case, Position::default(), None, None).await;
+        let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id,
+            expected: None,
+            actual: Some(resp),
+            reason: None,
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
+    #[test]
+    fn test_server_after_did_open_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .start_type(ServerStartType::AfterDidOpen);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        // Hover isn't advertised up front, only registered after `textDocument/didOpen`
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+        let registration = Registration {
+            id: "hover".to_string(),
+            method: "textDocument/hover".to_string(),
+            register_options: None,
+        };
+        send_registrations(&[registration], &test_case_root).expect("Failed to send registrations");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        assert!(receive_registered(&test_case_root));
+    }
+
+    #[test]
+    fn test_server_nvim_runtime() {
+        // Ask neovim where its own runtime lives, and pin the test to it
+        let output = std::process::Command::new("nvim")
+            .args([
+                "--clean",
+                "--headless",
+                "-c",
+                "lua io.stdout:write(vim.env.VIMRUNTIME)",
+                "-c",
+                "qa!",
+            ])
+            .output()
+            .expect("Failed to run neovim");
+        let runtime = PathBuf::from(String::from_utf8(output.stdout).unwrap().trim());
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).nvim_runtime(runtime);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+    }
+
+    #[test]
+    fn test_server_simple_with_delay_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        // The delay doesn't count against the timeout
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .start_type(ServerStartType::SimpleWithDelay(Duration::from_millis(
+                1500,
+            )))
+            .timeout(Duration::from_secs(1));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+    }
+
+    #[test]
+    fn test_server_reuse_dir_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        // The contents are ignored, the source file is read from the reused directory
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        let fixture_dir = test_case_root.join("fixture");
+        std::fs::create_dir_all(&fixture_dir).expect("Failed to create fixture directory");
+        std::fs::write(fixture_dir.join(test_server::get_dummy_source_path()), "")
+            .expect("Failed to write fixture source file");
+        let test_case = test_case.reuse_dir(fixture_dir);
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+    }
+
+    #[test]
+    fn test_server_attach_filetypes_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new("main.json", "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .attach_filetypes(vec!["json".to_string()]);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+    }
+
+    #[test]
+    fn test_server_stdout_noise() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).detect_stdout_noise(true);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(STDOUT_NOISE_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let Err(TestError::TestExecution(TestExecutionError::ProtocolNoise(test_id, noise))) =
+            test_hover(&test_case, Position::default(), None, None)
+        else {
+            panic!("Expected protocol noise to be detected");
+        };
+        assert_eq!(test_case.test_id, test_id);
+        assert!(noise.contains("INVALID_SERVER_JSON"), "{noise}");
+    }
+
+    #[test]
+    fn test_server_benchmark_positions() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "foo\nbar\nbaz");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+        let positions = [
+            Position::new(0, 1),
+            Position::new(1, 1),
+            Position::new(2, 1),
+        ];
+        let config = BenchmarkConfig {
+            end_condition: EndCondition::Count(6),
+            ..Default::default()
+        };
+
+        let stats =
+            benchmark_hover_positions(&test_case, config, &positions).expect("Benchmark failed");
+        // Iterations cycle through the positions, so each is measured equally often
+        assert_eq!(6, stats.aggregate.count);
+        assert_eq!(
+            positions.to_vec(),
+            stats
+                .positions
+                .iter()
+                .map(|(pos, _)| *pos)
+                .collect::<Vec<_>>()
+        );
+        for (pos, pos_stats) in &stats.positions {
+            let pos_stats = pos_stats.unwrap_or_else(|| panic!("No measurements for {pos:?}"));
+            assert_eq!(2, pos_stats.count);
+        }
+
+        assert!(matches!(
+            benchmark_hover_positions(&test_case, config, &[]),
+            Err(BenchmarkError::NoResults)
+        ));
+    }
+
+    #[test]
+    fn test_server_watch_globs() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .other_file(TestFile::new("watched.txt", ""))
+            .other_file(TestFile::new("unwatched.txt", ""))
+            .watch_globs(vec!["watched.txt".to_string()])
+            .start_type(ServerStartType::AfterDidOpen)
+            .timeout(Duration::from_secs(10));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(CHANGE_FILES_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+        // Hover is registered alongside the file watchers, so the request is only issued
+        // once the client is watching every file
+        let watchers = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![FileSystemWatcher {
+                glob_pattern: GlobPattern::String("**/*".to_string()),
+                kind: None,
+            }],
+        };
+        let registrations = [
+            Registration {
+                id: "hover".to_string(),
+                method: "textDocument/hover".to_string(),
+                register_options: None,
+            },
+            Registration {
+                id: "watched-files".to_string(),
+                method: "workspace/didChangeWatchedFiles".to_string(),
+                register_options: Some(serde_json::to_value(watchers).unwrap()),
+            },
+        ];
+        send_registrations(&registrations, &test_case_root).expect("Failed to send registrations");
+
+        lspresso_shot!(test_hover(&test_case, Position::default(), None, None));
+        let changed: Vec<_> = receive_did_change_watched_files(&test_case_root)
+            .expect("Failed to receive watched file changes")
+            .into_iter()
+            .flat_map(|params| params.changes)
+            .map(|change| {
+                let path = PathBuf::from(change.uri.path().as_str());
+                path.file_name().unwrap().to_string_lossy().to_string()
+            })
+            .collect();
+        assert!(!changed.is_empty());
+        assert!(changed.iter().all(|file_name| file_name == "watched.txt"));
+    }
+
+    #[test]
+    fn test_server_nvim_paths() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case = test_case
+            .clone()
+            .nvim_paths(vec![test_case.nvim_path.clone(); 2]);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        // Every version fails, so each is reported
+        let mut other = resp.clone();
+        other.range = Some(Range::new(Position::new(9, 9), Position::new(9, 9)));
+        let Err(TestError::NvimVersions(versions)) =
+            test_hover(&test_case, Position::default(), None, Some(&other))
+        else {
+            panic!("Expected a per-version failure");
+        };
+        assert_eq!(2, versions.results.len());
+        assert!(
+            versions
+                .results
+                .iter()
+                .all(|(_, result)| matches!(result, Err(TestError::ResponseMismatch(_))))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_server_nvim_paths_async() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case = test_case
+            .clone()
+            .nvim_paths(vec![test_case.nvim_path.clone(); 2]);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover_async(&test_case, Position::default(), None, Some(&resp)).await);
+        // Each version is run, and each failure is reported
+        let mut other = resp.clone();
+        other.range = Some(Range::new(Position::new(9, 9), Position::new(9, 9)));
+        let Err(TestError::NvimVersions(versions)) =
+            test_hover_async(&test_case, Position::default(), None, Some(&other)).await
+        else {
+            panic!("Expected a per-version failure");
+        };
+        assert_eq!(2, versions.results.len());
+    }
+
+    #[test]
+    fn test_server_nvim_paths_per_version() {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        // A "version" of Neovim that exits immediately, without running the test
+        let broken_nvim = std::env::temp_dir().join(format!("broken-nvim-{}", test_case.test_id));
+        std::fs::write(&broken_nvim, "#!/bin/sh\nexit 1\n").expect("Failed to write script");
+        std::fs::set_permissions(&broken_nvim, std::fs::Permissions::from_mode(0o755))
+            .expect("Failed to set script permissions");
+        let test_case = test_case
+            .clone()
+            .nvim_paths(vec![test_case.nvim_path.clone(), broken_nvim.clone()]);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_hover(&test_case, Position::default(), None, Some(&resp));
+        std::fs::remove_file(&broken_nvim).expect("Failed to remove script");
+        let Err(TestError::NvimVersions(versions)) = test_result else {
+            panic!("Expected a per-version failure");
+        };
+        // Only the broken version fails, and each result is paired with its executable
+        assert_eq!(2, versions.results.len());
+        assert_eq!(test_case.nvim_paths[0], versions.results[0].0);
+        assert!(versions.results[0].1.is_ok());
+        assert_eq!(broken_nvim, versions.results[1].0);
+        assert!(versions.results[1].1.is_err());
+    }
+
+    #[test]
+    fn test_server_json() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let expected = serde_json::to_value(&resp).unwrap();
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Expected::Json(&expected)
+        ));
+        let mut other = expected.clone();
+        other["range"] = serde_json::Value::Null;
+        let Err(TestError::JsonMismatch(mismatch)) = test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Expected::Json(&other),
+        ) else {
+            panic!("Expected a JSON mismatch");
+        };
+        assert_eq!(Some(expected), mismatch.actual);
+    }
+
+    #[test]
+    fn test_server_predicate() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Expected::Predicate(|hover: &Hover| {
+                hover
+                    .range
+                    .map(|_| ())
+                    .ok_or_else(|| "Missing range".to_string())
+            })
+        ));
+        let test_result = test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Expected::Predicate(|_| Err("Always rejected".to_string())),
+        );
+        let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id.clone(),
+            expected: None,
+            actual: Some(resp),
+            reason: Some("Always rejected".to_string()),
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
+    #[test]
+    fn test_server_target() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .other_file(TestFile::new("other.dummy", "other"))
+            .target(PathBuf::from("other.dummy"))
+            .capture_outgoing(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+        let outgoing = test_case
+            .read_outgoing()
+            .expect("Failed to read outgoing requests")
+            .expect("No requests were captured");
+        let request = outgoing
+            .iter()
+            .find(|request| request.method == "textDocument/hover")
+            .expect("No hover request was captured");
+        assert!(
+            request.params["textDocument"]["uri"]
+                .as_str()
+                .is_some_and(|uri| uri.ends_with("/other.dummy"))
+        );
+
+        // The target must be one of the test case's files
+        let test_case = test_case.target(PathBuf::from("missing.dummy"));
+        assert_eq!(
+            Err(TestError::TestSetup(TestSetupError::UnknownFile(
+                PathBuf::from("missing.dummy")
+            ))),
+            test_hover(&test_case, Position::default(), None, None)
+        );
+    }
+
+    #[test]
+    fn test_server_attach_filetypes_no_match() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .attach_filetypes(vec!["json".to_string()]);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // The server never attaches to the source file, so the request is never issued
+        let test_result = test_hover(&test_case, Position::default(), None, None);
+        assert_eq!(
+            Err(TestError::TestExecution(
+                TestExecutionError::ServerNeverAttached(test_case.test_id.clone())
+            )),
+            test_result
+        );
+    }
+
+    #[test]
+    fn test_server_assert_buffer_unchanged_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_hover_response(0, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "source");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).assert_buffer_unchanged(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(
+            &test_case,
+            Position::default(),
+            None,
+            Some(&resp)
+        ));
+    }
+
+    #[test]
+    fn test_server_assert_buffer_unchanged_got_edit() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "source");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).assert_buffer_unchanged(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(APPLY_EDIT_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // The server sends a `workspace/applyEdit` request while handling the hover
+        let test_result = test_hover(&test_case, Position::default(), None, None);
+        let expected_err = TestError::TestExecution(TestExecutionError::BufferChanged {
+            test_id: test_case.test_id.clone(),
+            before: "source".to_string(),
+            after: "stray edit\nsource".to_string(),
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
+    #[test]
+    fn test_server_expect_server_requests() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "source");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .expect_server_requests("workspace/applyEdit", Cardinality::Exactly(1))
+            .expect_server_requests("workspace/configuration", Cardinality::Exactly(0));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(APPLY_EDIT_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_hover(&test_case, Position::default(), None, None));
+    }
+
+    #[test]
+    fn test_server_expect_server_requests_mismatch() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "source");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .expect_server_requests("workspace/applyEdit", Cardinality::AtLeast(2));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(APPLY_EDIT_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_hover(&test_case, Position::default(), None, None);
+        let expected_err = TestError::TestExecution(TestExecutionError::UnexpectedServerRequests {
+            test_id: test_case.test_id.clone(),
+            method: "workspace/applyEdit".to_string(),
+            expected: Cardinality::AtLeast(2),
+            actual: 1,
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
+    #[test]
+    fn test_server_server_request_handler_override() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "source");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .server_request_handler(
+                "workspace/applyEdit",
+                serde_json::json!({ "applied": false }),
+            )
+            .expect_server_requests("workspace/applyEdit", Cardinality::Exactly(1))
+            .assert_buffer_unchanged(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(APPLY_EDIT_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // The overridden handler declines the edit rather than applying it
+        lspresso_shot!(test_hover(&test_case, Position::default(), None, None));
+    }
+
     #[test]
     fn rust_analyzer() {
         let source_file = TestFile::new(
@@ -59,6 +59,7 @@ mod test {
             test_id: test_case.test_id.clone(),
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         if response_num == 3 {
             // HACK: Because of the deserialization issues with empty vector results,
@@ -70,12 +71,14 @@ mod test {
                     test_id: test_case.test_id.clone(),
                     expected: None,
                     actual: Some(GotoDefinitionResponse::Link(vec![])),
+                    reason: None,
                 })
             );
             expected_err = TestError::ResponseMismatch(ResponseMismatchError {
                 test_id: test_case.test_id,
                 expected: None,
                 actual: Some(GotoDefinitionResponse::Array(vec![])),
+                reason: None,
             });
         }
         assert_eq!(Err(expected_err), test_result);
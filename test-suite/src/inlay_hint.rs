@@ -4,10 +4,15 @@ mod test {
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
-        lspresso_shot, test_inlay_hint,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        lspresso_shot, test_inlay_hint, test_inlay_hint_visible,
+        types::{
+            ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile,
+            compare::Comparator,
+        },
+    };
+    use test_server::{
+        get_dummy_server_path, receive_inlay_hint_range, send_capabiltiies, send_response_num,
     };
-    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
     use lsp_types::{
         InlayHint, InlayHintKind, InlayHintLabel, OneOf, Position, Range, ServerCapabilities, Uri,
@@ -35,8 +40,37 @@ mod test {
         lspresso_shot!(test_inlay_hint(&test_case, Range::default(), None, None));
     }
 
+    #[test]
+    fn test_server_visible_range() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_inlay_hint_response(0, &uri).unwrap();
+        let contents: String = (0..100).map(|i| format!("line {i}\n")).collect();
+        let mut visible_lines = Vec::new();
+        for rows in [10, 20] {
+            let source_file = TestFile::new(test_server::get_dummy_source_path(), &contents);
+            let test_case = TestCase::new(get_dummy_server_path(), source_file).viewport(rows, 80);
+            let test_case_root = test_case
+                .get_lspresso_dir()
+                .expect("Failed to get test case's root directory");
+            send_response_num(0, &test_case_root).expect("Failed to send response num");
+            send_capabiltiies(&inlay_hint_capabilities_simple(), &test_case_root)
+                .expect("Failed to send capabilities");
+
+            lspresso_shot!(test_inlay_hint_visible(&test_case, None, Some(&resp)));
+            let range = receive_inlay_hint_range(&test_case_root)
+                .expect("Failed to receive inlay hint range");
+            // The window starts at the top of the buffer, and leaves room for the
+            // status and command lines
+            assert_eq!(Position::new(0, 0), range.start);
+            assert_eq!(0, range.end.character);
+            assert!(0 < range.end.line && range.end.line < u32::from(rows));
+            visible_lines.push(range.end.line);
+        }
+        assert!(visible_lines[0] < visible_lines[1]);
+    }
+
     #[rstest]
-    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
+    fn test_server_simple_expect_none_got_some(#[values(0, 1, 2, 3, 4, 5)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp = test_server::responses::get_inlay_hint_response(response_num, &uri).unwrap();
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -53,12 +87,13 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
 
     #[rstest]
-    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3)] response_num: u32) {
+    fn test_server_simple_expect_some_got_some(#[values(0, 1, 2, 3, 4, 5)] response_num: u32) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp = test_server::responses::get_inlay_hint_response(response_num, &uri).unwrap();
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -138,7 +173,7 @@ mod test {
                     character: 1,
                 },
             ),
-            Some(cmp),
+            Some(Comparator::new(cmp)),
             Some(&vec![InlayHint {
                 position: Position {
                     line: 1,
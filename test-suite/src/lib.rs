@@ -1,11 +1,15 @@
 pub mod test_helpers;
 
+mod check;
 mod code_action;
 mod code_lens;
 mod code_lens_resolve;
 mod color_presentation;
 mod completion;
 mod completion_resolve;
+mod conformance;
+mod content_hash;
+mod custom_request;
 mod declaration;
 mod definition;
 mod diagnostics;
@@ -17,20 +21,28 @@ mod document_symbol;
 mod execute_command;
 mod folding_range;
 mod formatting;
+mod golden;
 mod hover;
 mod implementation;
 mod incoming_calls;
 mod inlay_hint;
 mod linked_editing_range;
+mod log_message;
+mod method_not_found;
 mod moniker;
+mod no_error;
 mod outgoing_calls;
+#[cfg(all(feature = "pool", unix))]
+mod pool;
 mod prepare_call_hierarchy;
+mod progress;
 mod references;
 mod rename;
 mod selection_range;
 mod semantic_tokens_full;
 mod semantic_tokens_full_delta;
 mod semantic_tokens_range;
+mod server_info;
 mod signature_help;
 mod type_definition;
 mod type_hierarchy;
@@ -0,0 +1,189 @@
+#[cfg(test)]
+mod test {
+    use std::{str::FromStr as _, time::Duration};
+
+    use crate::test_helpers::NON_RESPONSE_NUM;
+    use lspresso_shot::{
+        lspresso_shot, test_expect_log_message,
+        types::{
+            TestCase, TestError, TestExecutionError, TestFile, TestSetupError, TestType,
+            log_message::{LogMatcher, LogTrigger},
+        },
+    };
+    use test_server::{
+        NOTIFICATIONS_RESPONSE_NUM, REQUEST_LOG_MESSAGE_RESPONSE_NUM, get_dummy_server_path,
+        send_capabiltiies, send_response_num,
+    };
+
+    use lsp_types::{
+        HoverProviderCapability, LogMessageParams, MessageType, ServerCapabilities, Uri,
+    };
+    use rstest::rstest;
+    use serde_json::{Value, json};
+
+    fn hover_capabilities_simple() -> ServerCapabilities {
+        ServerCapabilities {
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    fn hover_params(test_case: &TestCase) -> Value {
+        let uri = Uri::from_str(&format!(
+            "file://{}",
+            test_case
+                .get_source_file_path(test_server::get_dummy_source_path())
+                .unwrap()
+                .to_str()
+                .unwrap(),
+        ))
+        .unwrap();
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 0 },
+        })
+    }
+
+    #[rstest]
+    #[case(0, LogMatcher::new(Some(MessageType::WARNING), "Malformed config"))]
+    #[case(1, LogMatcher::new(None, "Indexing"))]
+    #[case(1, LogMatcher::new(Some(MessageType::ERROR), "Failed to index"))]
+    fn test_server_open_got_message(#[case] response_num: u32, #[case] matcher: LogMatcher) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).settle(Duration::from_millis(100));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NOTIFICATIONS_RESPONSE_NUM + response_num, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_expect_log_message(
+            &test_case,
+            LogTrigger::Open,
+            matcher
+        ));
+    }
+
+    #[test]
+    fn test_server_request_got_message() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).settle(Duration::from_millis(100));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(REQUEST_LOG_MESSAGE_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_expect_log_message(
+            &test_case,
+            LogTrigger::Request(TestType::Hover, &hover_params(&test_case)),
+            LogMatcher::new(Some(MessageType::INFO), "Handled request")
+        ));
+    }
+
+    #[test]
+    fn test_server_open_no_request_message() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).settle(Duration::from_millis(100));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(REQUEST_LOG_MESSAGE_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // The message is only sent once a request is received
+        let test_result = test_expect_log_message(
+            &test_case,
+            LogTrigger::Open,
+            LogMatcher::new(None, "Handled request"),
+        );
+        assert!(matches!(
+            test_result,
+            Err(TestError::TestExecution(
+                TestExecutionError::MissingLogMessage { .. }
+            ))
+        ));
+    }
+
+    #[rstest]
+    #[case(0, LogMatcher::new(Some(MessageType::ERROR), "Malformed config"))]
+    #[case(1, LogMatcher::new(None, "Malformed config"))]
+    fn test_server_open_no_match(#[case] response_num: u32, #[case] matcher: LogMatcher) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).settle(Duration::from_millis(100));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NOTIFICATIONS_RESPONSE_NUM + response_num, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_expect_log_message(&test_case, LogTrigger::Open, matcher);
+        assert!(matches!(
+            test_result,
+            Err(TestError::TestExecution(
+                TestExecutionError::MissingLogMessage { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_server_open_no_messages() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case =
+            TestCase::new(get_dummy_server_path(), source_file).settle(Duration::from_millis(100));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let matcher = LogMatcher::new(None, "");
+        let test_result = test_expect_log_message(&test_case, LogTrigger::Open, matcher.clone());
+        assert_eq!(
+            Err(TestError::TestExecution(
+                TestExecutionError::MissingLogMessage {
+                    test_id: test_case.test_id.clone(),
+                    matcher,
+                    received: Vec::<LogMessageParams>::new(),
+                }
+            )),
+            test_result
+        );
+    }
+
+    #[test]
+    fn unsupported_trigger() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_result = test_expect_log_message(
+            &test_case,
+            LogTrigger::Request(TestType::PublishDiagnostics, &json!({})),
+            LogMatcher::new(None, ""),
+        );
+        assert_eq!(
+            Err(TestError::TestSetup(TestSetupError::UnsupportedTestType(
+                TestType::PublishDiagnostics
+            ))),
+            test_result
+        );
+    }
+}
@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod test {
+    use lspresso_shot::{
+        lspresso_shot, test_method_not_found,
+        types::{TestCase, TestError, TestExecutionError, TestFile},
+    };
+    use test_server::{
+        ERROR_RESPONSE_NUM, get_dummy_server_path, responses::CustomRequest, send_capabiltiies,
+        send_response_num,
+    };
+
+    use lsp_types::{ServerCapabilities, request::Request as _};
+    use rstest::rstest;
+
+    #[test]
+    fn test_server_unknown_method() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_method_not_found(&test_case, "lspresso/notARealMethod"));
+    }
+
+    #[rstest]
+    fn test_server_known_method(#[values(0, ERROR_RESPONSE_NUM)] response_num: u32) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_method_not_found(&test_case, CustomRequest::METHOD);
+        assert!(matches!(
+            test_result,
+            Err(TestError::TestExecution(
+                TestExecutionError::MethodFound { .. }
+            ))
+        ));
+    }
+}
@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod test {
+    use std::str::FromStr as _;
+
+    use crate::test_helpers::NON_RESPONSE_NUM;
+    use lspresso_shot::{
+        lspresso_shot, test_no_error,
+        types::{TestCase, TestError, TestExecutionError, TestFile, TestSetupError, TestType},
+    };
+    use test_server::{
+        ERROR_RESPONSE_NUM, get_dummy_server_path, send_capabiltiies, send_response_num,
+    };
+
+    use lsp_types::{HoverProviderCapability, ServerCapabilities, Uri};
+    use rstest::rstest;
+    use serde_json::{Value, json};
+
+    fn hover_capabilities_simple() -> ServerCapabilities {
+        ServerCapabilities {
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            ..ServerCapabilities::default()
+        }
+    }
+
+    fn hover_params(test_case: &TestCase) -> Value {
+        let uri = Uri::from_str(&format!(
+            "file://{}",
+            test_case
+                .get_source_file_path(test_server::get_dummy_source_path())
+                .unwrap()
+                .to_str()
+                .unwrap(),
+        ))
+        .unwrap();
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 0 },
+        })
+    }
+
+    #[rstest]
+    fn test_server_simple_got_response(
+        #[values(0, 1, 2, 3, 4, 5, NON_RESPONSE_NUM)] response_num: u32,
+    ) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_no_error(
+            &test_case,
+            TestType::Hover,
+            &hover_params(&test_case)
+        ));
+    }
+
+    #[test]
+    fn test_server_simple_got_error() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(ERROR_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&hover_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_no_error(&test_case, TestType::Hover, &hover_params(&test_case));
+        assert!(matches!(
+            test_result,
+            Err(TestError::TestExecution(TestExecutionError::ResponseError(
+                _,
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn unsupported_test_type() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_result = test_no_error(&test_case, TestType::PublishDiagnostics, &json!({}));
+        assert_eq!(
+            Err(TestError::TestSetup(TestSetupError::UnsupportedTestType(
+                TestType::PublishDiagnostics
+            ))),
+            test_result
+        );
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod test {
+    use std::{num::NonZeroUsize, path::PathBuf, str::FromStr as _};
+
+    use lspresso_shot::{
+        lspresso_shot,
+        pool::ServerPool,
+        test_custom_request,
+        types::{TestCase, TestFile},
+    };
+    use test_server::{get_dummy_server_path, responses::CustomRequest, send_response_num};
+
+    use lsp_types::{Uri, request::Request as _};
+    use serde_json::{Value, json};
+
+    fn custom_params(test_case: &TestCase) -> Value {
+        let uri = Uri::from_str(&format!(
+            "file://{}",
+            test_case
+                .get_source_file_path(test_server::get_dummy_source_path())
+                .unwrap()
+                .to_str()
+                .unwrap(),
+        ))
+        .unwrap();
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 0, "character": 0 },
+        })
+    }
+
+    #[test]
+    fn test_server_pool_reuses_instance() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let template = TestCase::new(get_dummy_server_path(), source_file);
+        let pool = ServerPool::new(&template, NonZeroUsize::new(1).unwrap())
+            .expect("Failed to create pool");
+
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        // Each test is run against the pool's only instance
+        for response_num in [0, 1] {
+            let resp = test_server::responses::get_custom_response(response_num, &uri).unwrap();
+            let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+            let test_case = TestCase::new(get_dummy_server_path(), source_file)
+                .other_file(TestFile::new("extra.dummy", ""));
+
+            let mut extra_path = PathBuf::new();
+            lspresso_shot!(pool.run(&test_case, |pooled| {
+                extra_path = pooled.get_source_file_path("extra.dummy").unwrap();
+                assert!(extra_path.exists());
+                // The server was spawned in the instance's directory, not `test_case`'s
+                let instance_root = pooled
+                    .reuse_dir
+                    .as_ref()
+                    .and_then(|dir| dir.parent())
+                    .expect("Pooled test case has no project directory");
+                send_response_num(response_num, instance_root)
+                    .expect("Failed to send response num");
+
+                test_custom_request(
+                    pooled,
+                    CustomRequest::METHOD,
+                    &custom_params(pooled),
+                    None,
+                    Some(&resp),
+                )
+            }));
+            // Files that aren't part of the template are removed after the test
+            assert!(!extra_path.exists());
+        }
+    }
+}
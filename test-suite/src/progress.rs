@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod test {
+    use std::{num::NonZeroU32, time::Duration};
+
+    use lspresso_shot::{
+        lspresso_shot, test_hover, test_indexing_progress,
+        types::{ServerStartType, TestCase, TestError, TestExecutionError, TestFile},
+    };
+    use test_server::{
+        NOTIFICATIONS_RESPONSE_NUM, get_dummy_server_path, responses::PROGRESS_TOKEN,
+        send_capabiltiies, send_response_num,
+    };
+
+    use lsp_types::{Position, ServerCapabilities};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, true)]
+    #[case(0, false)]
+    #[case(1, false)]
+    #[case(2, false)]
+    fn test_server_progress_ok(#[case] response_num: u32, #[case] expected_monotonic: bool) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NOTIFICATIONS_RESPONSE_NUM + response_num, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_indexing_progress(
+            &test_case,
+            PROGRESS_TOKEN,
+            expected_monotonic
+        ));
+    }
+
+    #[rstest]
+    fn test_server_progress_invalid(#[values(1, 2)] response_num: u32) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NOTIFICATIONS_RESPONSE_NUM + response_num, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_indexing_progress(&test_case, PROGRESS_TOKEN, true);
+        assert!(matches!(
+            test_result,
+            Err(TestError::TestExecution(
+                TestExecutionError::InvalidProgress { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_server_progress_start_type_wrong_token() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .start_type(ServerStartType::Progress(
+                NonZeroU32::new(1).unwrap(),
+                "test-server/indexin".to_string(),
+            ))
+            .timeout(Duration::from_secs(1));
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NOTIFICATIONS_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_hover(&test_case, Position::default(), None, None);
+        let Err(TestError::TestExecution(TestExecutionError::TimeoutExceeded(timeout_error))) =
+            test_result
+        else {
+            panic!("Expected a timeout, got {test_result:?}");
+        };
+        assert_eq!(
+            Some(vec![PROGRESS_TOKEN.to_string()]),
+            timeout_error.observed_progress_tokens
+        );
+    }
+}
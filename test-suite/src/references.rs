@@ -5,7 +5,10 @@ mod test {
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
         lspresso_shot, test_references,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        types::{
+            Expected, ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile,
+            compare::{Cardinality, Comparator},
+        },
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
@@ -57,6 +60,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -83,6 +87,98 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_simple_cardinality() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(3, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&references_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_references(
+            &test_case,
+            Position::default(),
+            true,
+            Some(Comparator::new(|_, actual, _| {
+                Cardinality::Between(2, 3).matches(actual)
+            })),
+            Some(&vec![])
+        ));
+        let test_result = test_references(
+            &test_case,
+            Position::default(),
+            true,
+            Some(Comparator::new(|_, actual, _| {
+                Cardinality::AtMost(2).matches(actual)
+            })),
+            Some(&vec![]),
+        );
+        assert!(matches!(test_result, Err(TestError::ResponseMismatch(_))));
+    }
+
+    #[rstest]
+    fn test_server_simple_expect_empty_list(#[values(0, 1)] response_num: u32) {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_references_response(response_num, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&references_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_references(
+            &test_case,
+            Position::default(),
+            true,
+            None,
+            Expected::EmptyList,
+        );
+        if resp.is_empty() {
+            assert_eq!(Ok(()), test_result);
+        } else {
+            let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+                test_id: test_case.test_id,
+                expected: Some(vec![]),
+                actual: Some(resp),
+                reason: None,
+            });
+            assert_eq!(Err(expected_err), test_result);
+        }
+    }
+
+    #[test]
+    fn test_server_simple_expect_empty_list_got_none() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&references_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_references(
+            &test_case,
+            Position::default(),
+            true,
+            None,
+            Expected::EmptyList,
+        );
+        let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id,
+            expected: Some(vec![]),
+            actual: None,
+            reason: None,
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+
     #[test]
     fn rust_analyzer() {
         let source_file = TestFile::new(
@@ -5,7 +5,7 @@ mod test {
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
         lspresso_shot, test_selection_range,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile, compare},
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
@@ -55,6 +55,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -82,6 +83,38 @@ mod test {
         ));
     }
 
+    #[rstest]
+    fn test_server_simple_nested(#[values(0, 1, 2)] response_num: u32) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&selection_range_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+        let positions = vec![Position::default(); 3];
+
+        let test_result = test_selection_range(
+            &test_case,
+            &positions,
+            Some(compare::selection_range_nested()),
+            Some(&vec![]),
+        );
+        // The parent of response 2's only item doesn't contain it
+        if response_num == 2 {
+            assert!(matches!(
+                test_result,
+                Err(TestError::ResponseMismatch(ResponseMismatchError {
+                    reason: Some(_),
+                    ..
+                }))
+            ));
+        } else {
+            assert_eq!(Ok(()), test_result);
+        }
+    }
+
     #[test]
     fn rust_analyzer() {
         let source_file = TestFile::new(
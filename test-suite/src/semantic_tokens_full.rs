@@ -5,7 +5,7 @@ mod test {
     use crate::test_helpers::NON_RESPONSE_NUM;
     use lspresso_shot::{
         lspresso_shot, test_semantic_tokens_full,
-        types::{ResponseMismatchError, TestCase, TestError, TestFile},
+        types::{ResponseMismatchError, TestCase, TestError, TestFile, compare},
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
@@ -71,6 +71,7 @@ mod test {
             test_id: test_case.test_id.clone(),
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         // HACK: Because of the serialization issues with `SemanticTokensResult`, we have
         // to work around
@@ -82,6 +83,7 @@ mod test {
                     result_id: None,
                     data: resp_data,
                 })),
+                reason: None,
             });
         }
         assert_eq!(Err(expected_err), test_result);
@@ -105,4 +107,43 @@ mod test {
 
         lspresso_shot!(test_semantic_tokens_full(&test_case, None, Some(&resp)));
     }
+
+    #[rstest]
+    fn test_server_result_id_presence(#[values(1, 3, 5, 7)] response_num: u32) {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let SemanticTokensResult::Tokens(tokens) =
+            test_server::responses::get_semantic_tokens_full_response(response_num, &uri).unwrap()
+        else {
+            unreachable!("Response has a `result_id`");
+        };
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(response_num, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&semantic_tokens_full_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // Only the presence of the `result_id` is compared...
+        let other_id = SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: Some("some_other_id".to_string()),
+            data: tokens.data.clone(),
+        });
+        lspresso_shot!(test_semantic_tokens_full(&test_case, None, Some(&other_id)));
+        let no_id = SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data: tokens.data,
+        });
+        assert!(matches!(
+            test_semantic_tokens_full(&test_case, None, Some(&no_id)),
+            Err(TestError::ResponseMismatch(_))
+        ));
+        // ...unless it's ignored entirely
+        lspresso_shot!(test_semantic_tokens_full(
+            &test_case,
+            Some(compare::ignore_result_id()),
+            Some(&no_id)
+        ));
+    }
 }
@@ -84,6 +84,7 @@ mod test {
             test_id: test_case.test_id.clone(),
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
 
         // HACK: Because of the serialization issues with `SemanticTokensRangeResult`,
@@ -96,6 +97,7 @@ mod test {
                     result_id: None,
                     data: resp_data,
                 })),
+                reason: None,
             });
         }
         assert_eq!(Err(expected_err), test_result);
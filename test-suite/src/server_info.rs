@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod test {
+    use lspresso_shot::{
+        lspresso_shot, test_server_info,
+        types::{ResponseMismatchError, TestCase, TestError, TestFile},
+    };
+    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
+
+    use lsp_types::{ServerCapabilities, ServerInfo};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(None, None)]
+    #[case(Some("test-server"), None)]
+    #[case(None, Some("0.1.0"))]
+    #[case(Some("test-server"), Some("0.1.0"))]
+    fn test_server_info_match(#[case] name: Option<&str>, #[case] version: Option<&str>) {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        lspresso_shot!(test_server_info(&test_case, name, version));
+    }
+
+    #[test]
+    fn test_server_info_version_mismatch() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(0, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&ServerCapabilities::default(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let test_result = test_server_info(&test_case, None, Some("0.2.0"));
+        // Only the version is pinned, so the name is taken from the actual response
+        let expected_err = TestError::ResponseMismatch(ResponseMismatchError {
+            test_id: test_case.test_id.clone(),
+            expected: Some(ServerInfo {
+                name: "test-server".to_string(),
+                version: Some("0.2.0".to_string()),
+            }),
+            actual: Some(ServerInfo {
+                name: "test-server".to_string(),
+                version: Some("0.1.0".to_string()),
+            }),
+            reason: None,
+        });
+        assert_eq!(Err(expected_err), test_result);
+    }
+}
@@ -6,10 +6,14 @@ mod test {
 
     use crate::test_helpers::{NON_RESPONSE_NUM, cargo_dot_toml};
     use lspresso_shot::{
-        lspresso_shot, test_signature_help,
-        types::{ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+        SignatureHelpContextBuilder, lspresso_shot, test_signature_help,
+        test_signature_help_retrigger,
+        types::{Expected, ResponseMismatchError, ServerStartType, TestCase, TestError, TestFile},
+    };
+    use test_server::{
+        SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM, get_dummy_server_path, send_capabiltiies,
+        send_response_num,
     };
-    use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
 
     use lsp_types::{
         ParameterInformation, Position, ServerCapabilities, SignatureHelp, SignatureHelpOptions,
@@ -70,6 +74,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -97,6 +102,121 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_retrigger_expect_some_got_some() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let previous = test_server::responses::get_signature_help_response(0, &uri).unwrap();
+        let resp = test_server::responses::get_signature_help_response(1, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(1, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&signature_help_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        let context = SignatureHelpContextBuilder::new()
+            .trigger_character(",")
+            .retrigger_from(&previous)
+            .build();
+        assert!(context.is_retrigger);
+        assert_eq!(Some(previous), context.active_signature_help);
+
+        lspresso_shot!(test_signature_help(
+            &test_case,
+            Position::default(),
+            Some(&context),
+            None,
+            Some(&resp),
+        ));
+    }
+
+    #[test]
+    fn test_server_retrigger_advances() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let initial = test_server::responses::get_signature_help_response(
+            SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM,
+            &uri,
+        )
+        .unwrap();
+        let resp =
+            test_server::responses::get_signature_help_retrigger_response(&initial, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(SIGNATURE_HELP_RETRIGGER_RESPONSE_NUM, &test_case_root)
+            .expect("Failed to send response num");
+        send_capabiltiies(&signature_help_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        assert_eq!(Some(1), resp.active_parameter);
+        lspresso_shot!(test_signature_help_retrigger(
+            &test_case,
+            Position::default(),
+            Position::new(0, 1),
+            Some(&resp),
+        ));
+    }
+
+    #[test]
+    fn test_server_retrigger_not_advanced() {
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let resp = test_server::responses::get_signature_help_response(3, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(3, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&signature_help_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // The server responds identically to both requests
+        let test_result = test_signature_help_retrigger(
+            &test_case,
+            Position::default(),
+            Position::new(0, 1),
+            Some(&resp),
+        );
+        let Err(TestError::ResponseMismatch(mismatch)) = test_result else {
+            panic!("Expected a response mismatch, got {test_result:?}");
+        };
+        assert_eq!(Some(resp), mismatch.actual);
+        assert_eq!(None, mismatch.expected);
+        assert!(mismatch.reason.is_some());
+    }
+
+    #[test]
+    fn test_server_retrigger_not_advanced_predicate() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(3, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&signature_help_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+
+        // The advance is checked even when the expectation itself is satisfied
+        let test_result = test_signature_help_retrigger(
+            &test_case,
+            Position::default(),
+            Position::new(0, 1),
+            Expected::Predicate(|_| Ok(())),
+        );
+        let Err(TestError::ResponseMismatch(mismatch)) = test_result else {
+            panic!("Expected a response mismatch, got {test_result:?}");
+        };
+        assert!(mismatch.reason.is_some());
+    }
+
     #[test]
     fn rust_analyzer() {
         let source_file = TestFile::new(
@@ -19,3 +19,40 @@ name = "test"
 path = "src/main.rs""#,
     )
 }
+
+/// Generates one `#[test]` per dummy server response number in the range `$start..$end`,
+/// grouped in a module named `$mod_name`, with the tests named `response_0`,
+/// `response_1`, etc. Each generated test fetches the corresponding response from
+/// `$generator` (one of `test_server::responses::get_*_response`) and passes the
+/// response number and the response itself to `$test_fn`. Each test is independent,
+/// so they can be run in parallel. The range's bounds must be integer literals.
+///
+/// ```ignore
+/// fn check_hover(response_num: u32, resp: Hover) { /* ... */ }
+///
+/// lspresso_matrix!(
+///     hover_expect_some_got_some,
+///     super::check_hover,
+///     test_server::responses::get_hover_response,
+///     0..4
+/// );
+/// ```
+#[macro_export]
+macro_rules! lspresso_matrix {
+    ($mod_name:ident, $test_fn:path, $generator:path, $start:tt..$end:tt) => {
+        mod $mod_name {
+            seq_macro::seq!(N in $start..$end {
+                #[test]
+                fn response_~N() {
+                    let uri = <lsp_types::Uri as std::str::FromStr>::from_str(
+                        &test_server::get_dummy_source_path(),
+                    )
+                    .unwrap();
+                    let resp = $generator(N, &uri)
+                        .unwrap_or_else(|| panic!("No response for response number {}", N));
+                    $test_fn(N, resp);
+                }
+            });
+        }
+    };
+}
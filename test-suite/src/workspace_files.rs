@@ -89,6 +89,65 @@ mod test {
         ));
     }
 
+    #[test]
+    fn test_server_create_on_disk() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file).create_on_disk(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        let created = test_case.get_source_file_path("new/created.dummy").unwrap();
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(
+            &workspace_will_create_files_capabilities_simple(),
+            &test_case_root,
+        )
+        .expect("Failed to send capabilities");
+        let params = CreateFilesParams {
+            files: vec![FileCreate {
+                uri: created.to_str().unwrap().to_string(),
+            }],
+        };
+
+        lspresso_shot!(test_workspace_will_create_files(
+            &test_case, &params, None, None
+        ));
+        assert!(created.is_file());
+    }
+
+    #[test]
+    fn test_server_rename_on_disk() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file)
+            .other_file(TestFile::new("old.dummy", "contents"))
+            .rename_on_disk(true);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        let old_path = test_case.get_source_file_path("old.dummy").unwrap();
+        let new_path = test_case.get_source_file_path("renamed/new.dummy").unwrap();
+        send_response_num(NON_RESPONSE_NUM, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(
+            &workspace_will_rename_files_capabilities_simple(),
+            &test_case_root,
+        )
+        .expect("Failed to send capabilities");
+        let params = RenameFilesParams {
+            files: vec![FileRename {
+                old_uri: old_path.to_str().unwrap().to_string(),
+                new_uri: new_path.to_str().unwrap().to_string(),
+            }],
+        };
+
+        lspresso_shot!(test_workspace_will_rename_files(
+            &test_case, &params, None, None
+        ));
+        assert!(!old_path.exists());
+        assert_eq!("contents", std::fs::read_to_string(new_path).unwrap());
+    }
+
     #[test]
     fn test_server_delete_simple_expect_none_got_none() {
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
@@ -175,6 +234,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -213,6 +273,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
@@ -254,6 +315,7 @@ mod test {
             test_id: test_case.test_id,
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         assert_eq!(Err(expected_err), test_result);
     }
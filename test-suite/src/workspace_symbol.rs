@@ -11,7 +11,7 @@ mod test {
         lspresso_shot, test_workspace_symbol, test_workspace_symbol_resolve,
         types::{
             CleanResponse as _, ResponseMismatchError, ServerStartType, TestCase, TestError,
-            TestFile,
+            TestFile, compare::Comparator,
         },
     };
     use test_server::{get_dummy_server_path, send_capabiltiies, send_response_num};
@@ -94,6 +94,7 @@ mod test {
             test_id: test_case.test_id.clone(),
             expected: None,
             actual: Some(resp),
+            reason: None,
         });
         match response_num {
             // HACK: Because of the serialization issues with `WorkspaceSymbolResponse`, we have
@@ -103,6 +104,7 @@ mod test {
                     test_id: test_case.test_id,
                     expected: None,
                     actual: Some(WorkspaceSymbolResponse::Flat(vec![])),
+                    reason: None,
                 });
             }
             #[allow(deprecated)]
@@ -121,6 +123,7 @@ mod test {
                         },
                         deprecated: None,
                     }])),
+                    reason: None,
                 });
             }
             _ => {}
@@ -130,7 +133,7 @@ mod test {
 
     #[rstest]
     fn test_server_simple_expect_some_got_some(
-        #[values(0, 1, 2, 3, 4, 5, 6, 7)] response_num: u32,
+        #[values(0, 1, 2, 3, 4, 5, 6, 7, 8)] response_num: u32,
     ) {
         let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
         let resp =
@@ -149,6 +152,63 @@ mod test {
         lspresso_shot!(test_workspace_symbol(&test_case, &uri, None, Some(&resp)));
     }
 
+    #[test]
+    fn test_server_flat_nested_ambiguity() {
+        // The server sends a `Nested` response that is indistinguishable from a `Flat`
+        // one over JSON, so the client deserializes it as `Flat`
+        let uri = Uri::from_str(&test_server::get_dummy_source_path()).unwrap();
+        let nested = test_server::responses::get_workspace_symbol_response(8, &uri).unwrap();
+        let flat = test_server::responses::get_workspace_symbol_response(2, &uri).unwrap();
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(8, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&workspace_symbol_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+        let uri = get_dummy_uri(&test_case);
+
+        // Either representation is accepted by the default comparator
+        lspresso_shot!(test_workspace_symbol(&test_case, &uri, None, Some(&nested)));
+        lspresso_shot!(test_workspace_symbol(&test_case, &uri, None, Some(&flat)));
+    }
+
+    #[test]
+    fn test_server_location_uris_cleaned() {
+        let source_file = TestFile::new(test_server::get_dummy_source_path(), "");
+        let test_case = TestCase::new(get_dummy_server_path(), source_file);
+
+        let test_case_root = test_case
+            .get_lspresso_dir()
+            .expect("Failed to get test case's root directory");
+        send_response_num(7, &test_case_root).expect("Failed to send response num");
+        send_capabiltiies(&workspace_symbol_capabilities_simple(), &test_case_root)
+            .expect("Failed to send capabilities");
+        let uri = get_dummy_uri(&test_case);
+
+        // The server responds with absolute uris inside of the test case's directory,
+        // which should be cleaned to paths relative to it
+        lspresso_shot!(test_workspace_symbol(
+            &test_case,
+            &uri,
+            Some(Comparator::new(|_, actual, _| {
+                let WorkspaceSymbolResponse::Nested(symbols) = actual else {
+                    return false;
+                };
+                symbols.iter().all(|sym| {
+                    let uri = match &sym.location {
+                        OneOf::Left(location) => &location.uri,
+                        OneOf::Right(location) => &location.uri,
+                    };
+                    uri.as_str() == test_server::get_dummy_source_path()
+                })
+            })),
+            Some(&WorkspaceSymbolResponse::Nested(vec![]))
+        ));
+    }
+
     #[rstest]
     fn test_server_resolve_simple_expect_some_got_some(#[values(0, 1)] response_num: u32) {
         let source_file = TestFile::new(test_server::get_dummy_source_path(), "");